@@ -1,9 +1,10 @@
-// src/examples/airfoil
+// examples/airfoil.rs
 
-use crate::solver;
-use solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+use cappusim::solver;
+use solver::flags::CellType;
 use solver::lbm::LBM;
 use solver::precision::PrecisionMode;
+use solver::velocity_sets::VelocitySet;
 
 // 2D NACA Airfoil Flow Example
 pub fn airfoil_2d_example() {
@@ -15,7 +16,13 @@ pub fn airfoil_2d_example() {
     let angle_of_attack = 10.0; // Degrees
     let chord_length = nx as f32 * 0.3; // 30% of domain width
 
-    let mut lbm = LBM::new(nx, ny, nz, "D2Q9".to_string(), viscosity, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(VelocitySet::D2Q9)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     let thickness = 0.12; // 12% thickness
     let cx = nx as f32 * 0.3; // Airfoil center x
@@ -23,7 +30,7 @@ pub fn airfoil_2d_example() {
 
     let angle_rad = angle_of_attack * std::f32::consts::PI / 180.0;
 
-    lbm.set_conditions(|lbm, x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         let xf = x as f32;
         let yf = y as f32;
 
@@ -44,32 +51,32 @@ pub fn airfoil_2d_example() {
                 - 0.1015 * x_c.powi(4));
 
             if (y_rot).abs() <= yt * chord_length {
-                lbm.flags[n] = FLAG_SOLID;
+                lbm.set_cell(x, y, z, CellType::Solid);
                 return;
             }
         }
 
         // Inlet
         if x == 0 {
-            lbm.flags[n] = FLAG_EQ;
+            lbm.set_cell(x, y, z, CellType::Equilibrium);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
         }
         // Outlet
         else if x == nx - 1 {
-            lbm.flags[n] = FLAG_EQ;
+            lbm.set_cell(x, y, z, CellType::Equilibrium);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
         }
         // Top and bottom walls
         else if y == 0 || y == ny - 1 {
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
         }
         // Fluid region
         else {
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
@@ -101,11 +108,13 @@ pub fn airfoil_3d_example() {
     // Force magnitude for pressure gradient
     let force_magnitude = 5e-6 * viscosity * target_velocity;
 
-    let mut lbm = LBM::new(nx, ny, nz,
-        "D3Q19".to_string(),
-        viscosity,
-        PrecisionMode::FP32
-    );
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(VelocitySet::D3Q19)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Apply constant force in X direction
     lbm.set_constant_force(vec![force_magnitude, 0.0, 0.0]);
@@ -144,14 +153,14 @@ pub fn airfoil_3d_example() {
                 - 0.1015 * x_c.powi(4));
 
             if y_rot.abs() <= yt * chord_length {
-                lbm.flags[n] = FLAG_SOLID;
+                lbm.set_cell(x, y, z, CellType::Solid);
                 return;
             }
         }
 
         // Inlet
         if x == 0 {
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = target_velocity;
             lbm.velocity[n].y = 0.0;
             lbm.velocity[n].z = 0.0;
@@ -159,7 +168,7 @@ pub fn airfoil_3d_example() {
         }
         // Outlet
         else if x == nx - 1 {
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = target_velocity;
             lbm.velocity[n].y = 0.0;
             lbm.velocity[n].z = 0.0;
@@ -167,15 +176,15 @@ pub fn airfoil_3d_example() {
         }
         // Top and bottom walls
         else if y == 0 || y == ny - 1 {
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
         }
         // Front and back periodic boundaries
         else if z == 0 || z == nz - 1 {
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
         }
         // Fluid region
         else {
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = target_velocity;
             lbm.velocity[n].y = 0.0;
             lbm.velocity[n].z = 0.0;
@@ -193,3 +202,12 @@ pub fn airfoil_3d_example() {
     let re = target_velocity * chord_length / viscosity;
     println!("Reynolds number: {}", re);
 }
+
+// cargo run --release --example airfoil [3d]
+fn main() {
+    if std::env::args().nth(1).as_deref() == Some("3d") {
+        airfoil_3d_example();
+    } else {
+        airfoil_2d_example();
+    }
+}
@@ -1,10 +1,11 @@
-// src/examples/couette
+// examples/couette.rs
 
 // Import
-use crate::solver;
-use solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+use cappusim::solver;
+use solver::flags::CellType;
 use solver::lbm::LBM;
 use solver::precision::PrecisionMode; 
+use solver::velocity_sets::VelocitySet;
 
 pub fn couette_2d_example() {
     let nx = 128;
@@ -15,23 +16,29 @@ pub fn couette_2d_example() {
     let steps = 100000;
 
     // Initialize LBM simulation
-    let mut lbm = LBM::new(nx, ny, nz, "D2Q9".to_string(), viscosity, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(VelocitySet::D2Q9)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set boundary and initial conditions for Couette flow
-    lbm.set_conditions(|lbm, _x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         if y == 0 {
             // Bottom wall: stationary
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
             lbm.velocity[n].x = 0.0;
             lbm.velocity[n].y = 0.0;
         } else if y == ny - 1 {
             // Top wall: moving with velocity u0
-            lbm.flags[n] = FLAG_EQ;
+            lbm.set_cell(x, y, z, CellType::Equilibrium);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
         } else {
             // Interior: fluid
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = 0.0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
@@ -54,25 +61,31 @@ pub fn couette_3d_example() {
     let steps = 20000;
 
     // Initialize LBM simulation for 3D
-    let mut lbm = LBM::new(nx, ny, nz, "D3Q19".to_string(), viscosity, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(VelocitySet::D3Q19)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set boundary and initial conditions for 3D Couette flow
-    lbm.set_conditions(|lbm, _x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         if y == 0 {
             // Bottom wall: stationary
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
             lbm.velocity[n].x = 0.0;
             lbm.velocity[n].y = 0.0;
             lbm.velocity[n].z = 0.0;
         } else if y == ny - 1 {
             // Top wall: moving with velocity u0 in x-direction
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
             lbm.velocity[n].z = 0.0;
         } else {
             // Interior: fluid
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = 0.0;
             lbm.velocity[n].y = 0.0;
             lbm.velocity[n].z = 0.0;
@@ -83,4 +96,13 @@ pub fn couette_3d_example() {
     // Run the simulation
     lbm.run(steps);
     lbm.export_to_vtk("output/couette3d.vtk").unwrap();
-}
\ No newline at end of file
+}
+
+// cargo run --release --example couette [3d]
+fn main() {
+    if std::env::args().nth(1).as_deref() == Some("3d") {
+        couette_3d_example();
+    } else {
+        couette_2d_example();
+    }
+}
@@ -0,0 +1,151 @@
+// examples/flapping_foil.rs
+
+// Import
+use cappusim::solver;
+use solver::flags::CellType;
+use solver::ibm::closed_curve_spacing;
+use solver::kinematics::{naca_markers, Kinematics};
+use solver::lbm::LBM;
+use solver::precision::PrecisionMode;
+use solver::velocity_sets::VelocitySet;
+
+use std::fs::{self, File};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PropulsionResult {
+    pub strouhal: f32,
+    pub thrust_coefficient: f32,
+    pub power_coefficient: f32,
+    pub efficiency: f32,
+}
+
+// Single pitching-plunging NACA 0012 run at Strouhal number St = 2 f h0 / U
+pub fn flapping_foil_2d(strouhal: f32) -> PropulsionResult {
+    let nx = 640;
+    let ny = 320;
+    let chord = 64.0f32;
+    let u0 = 0.04f32;
+    let reynolds = 400.0f32;
+    let viscosity = u0 * chord / reynolds;
+
+    let heave_amplitude = 0.75 * chord;
+    let pitch_amplitude = 30.0; // Degrees
+    let phase = 90.0; // Pitch leads heave
+    let frequency = strouhal * u0 / (2.0 * heave_amplitude);
+    let period = (1.0 / frequency).round() as usize;
+    let cycles = 4;
+    let averaged_cycles = 2;
+
+    let mut lbm = LBM::builder()
+        .size(nx, ny, 1)
+        .model(VelocitySet::D2Q9)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
+
+    // Free stream everywhere, prescribed on the domain boundary
+    lbm.set_conditions(|lbm, x, y, z, n| {
+        let cell = if x == 0 || x == nx - 1 || y == 0 || y == ny - 1 {
+            CellType::Equilibrium
+        } else {
+            CellType::Fluid
+        };
+        lbm.set_cell(x, y, z, cell);
+        lbm.velocity[n].x = u0;
+        lbm.density[n] = 1.0;
+    });
+
+    // Pivot at the quarter chord
+    let leading_edge = [nx as f32 * 0.25, ny as f32 * 0.5, 0.0];
+    let pivot = [leading_edge[0] + 0.25 * chord, leading_edge[1], 0.0];
+    let markers = naca_markers(leading_edge, chord, 0.12, chord as usize);
+    let spacing = closed_curve_spacing(&markers);
+    lbm.add_kinematic_body(
+        "foil",
+        markers,
+        spacing,
+        Kinematics::heave_pitch(pivot, heave_amplitude, pitch_amplitude, frequency, phase),
+    );
+
+    lbm.run(cycles * period);
+
+    // Cycle-averaged thrust and input power over the last cycles
+    let foil = &lbm.immersed_boundaries[0];
+    let window = (averaged_cycles * period).min(foil.force_history.len());
+    if window == 0 {
+        // No loads were recorded (the run stopped early), so there is nothing to average
+        println!("St = {}: no force samples recorded, skipping the averages", strouhal);
+        return PropulsionResult {
+            strouhal,
+            thrust_coefficient: 0.0,
+            power_coefficient: 0.0,
+            efficiency: 0.0,
+        };
+    }
+    let start = foil.force_history.len() - window;
+    let thrust: f32 = foil.force_history[start..]
+        .iter()
+        .map(|f| -f[0])
+        .sum::<f32>()
+        / window as f32;
+    let power: f32 = foil.power_history[start..].iter().sum::<f32>() / window as f32;
+
+    let dynamic_pressure = 0.5 * u0 * u0 * chord;
+    let thrust_coefficient = thrust / dynamic_pressure;
+    let power_coefficient = power / (dynamic_pressure * u0);
+    let efficiency = if power_coefficient.abs() > 1e-12 {
+        thrust_coefficient / power_coefficient
+    } else {
+        0.0
+    };
+
+    PropulsionResult {
+        strouhal,
+        thrust_coefficient,
+        power_coefficient,
+        efficiency,
+    }
+}
+
+// Sweep of propulsive performance versus Strouhal number
+pub fn flapping_foil_strouhal_sweep_example() {
+    let strouhal_numbers = [0.1, 0.2, 0.3, 0.4, 0.5];
+    let results: Vec<PropulsionResult> = strouhal_numbers
+        .iter()
+        .map(|&st| flapping_foil_2d(st))
+        .collect();
+
+    println!("{}", "-".repeat(72));
+    println!("{:>8} {:>12} {:>12} {:>12}", "St", "C_T", "C_P", "eta");
+    for r in &results {
+        println!(
+            "{:>8.3} {:>12.5} {:>12.5} {:>12.4}",
+            r.strouhal, r.thrust_coefficient, r.power_coefficient, r.efficiency
+        );
+    }
+
+    // run() recreates output/, so the table goes to results/
+    fs::create_dir_all("results").expect("Failed to create results directory.");
+    let mut file =
+        File::create("results/flapping_foil_strouhal.csv").expect("Failed to create CSV file.");
+    writeln!(
+        file,
+        "strouhal,thrust_coefficient,power_coefficient,efficiency"
+    )
+    .unwrap();
+    for r in &results {
+        writeln!(
+            file,
+            "{:.4},{:.6},{:.6},{:.6}",
+            r.strouhal, r.thrust_coefficient, r.power_coefficient, r.efficiency
+        )
+        .unwrap();
+    }
+}
+
+// cargo run --release --example flapping_foil
+fn main() {
+    flapping_foil_strouhal_sweep_example();
+}
@@ -1,10 +1,11 @@
-// src/examples/liddriven_cavity
+// examples/liddriven_cavity.rs
 
 // Import
-use crate::solver;
-use solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+use cappusim::solver;
+use solver::flags::CellType;
 use solver::lbm::LBM;
 use solver::precision::PrecisionMode; 
+use solver::velocity_sets::VelocitySet;
 
 // 2D Lid-driven Cavity Example
 pub fn liddriven_cavity_2d_example() {
@@ -15,23 +16,29 @@ pub fn liddriven_cavity_2d_example() {
     println!("Reynolds number: {}", re);
     
     // Initialize LBM simulation
-    let mut lbm = LBM::new(nx, nx, 1, "D2Q9".to_string(), viscosity, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, nx, 1)
+        .model(VelocitySet::D2Q9)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set initial conditions
-    lbm.set_conditions(|lbm, x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         lbm.velocity[n].x = 0.0;
         lbm.velocity[n].y = 0.0;
         lbm.velocity[n].z = 0.0;
         lbm.density[n] = 1.0;
         
-        lbm.flags[n] = FLAG_FLUID;
+        lbm.set_cell(x, y, z, CellType::Fluid);
         
         if y == 0 || x == 0 || x == nx - 1 {
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
         }
         
         else if y == nx - 1 {
-            lbm.flags[n] = FLAG_EQ;
+            lbm.set_cell(x, y, z, CellType::Equilibrium);
             lbm.velocity[n].x = lid_velocity;
             lbm.velocity[n].y = 0.0;
         }
@@ -40,8 +47,8 @@ pub fn liddriven_cavity_2d_example() {
     // Configure output
     // lbm.set_output_interval(10);
 
-    // Run the simulation
-    lbm.run(1000000);
+    // Run until the velocity field stops changing
+    lbm.run_until_steady(1e-6, 1000);
     
     lbm.export_to_vtk(&format!("output/liddriven_cavity_re{}.vtk", re as i32)).unwrap();
 }
@@ -51,7 +58,13 @@ pub fn liddriven_cavity_3d_example() {
     let nx = 200;
 
     // Initialize LBM simulation for 3D
-    let mut lbm = LBM::new(nx, nx, nx, "D3Q19".to_string(), 0.1, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, nx, nx)
+        .model(VelocitySet::D3Q19)
+        .viscosity(0.1)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set initial conditions
     lbm.set_conditions(|lbm, x, y, z, n| {
@@ -60,14 +73,14 @@ pub fn liddriven_cavity_3d_example() {
         lbm.velocity[n].z = 0.0;
         lbm.density[n] = 1.0f32;
 
-        // Set FLAG_SOLID for the walls
+        // Set solid cells for the walls
         if x == 0 || x == nx - 1 || y == 0 || y == nx - 1 || z == 0 || z == nx - 1 {
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
         }
 
-        // Set FLAG_EQ for the top lid (z = nx - 1) with a constant velocity
+        // Set equilibrium cells for the top lid (z = nx - 1) with a constant velocity
         if z == nx - 1 {
-            lbm.flags[n] = FLAG_EQ;
+            lbm.set_cell(x, y, z, CellType::Equilibrium);
             lbm.velocity[n].x = -0.1; // Lid moving in x-direction
             lbm.velocity[n].y = 0.0;
             lbm.velocity[n].z = 0.0;
@@ -81,4 +94,13 @@ pub fn liddriven_cavity_3d_example() {
     lbm.run(10000);
 
     lbm.export_to_vtk("output/liddriven_cavity_3d.vtk").expect("Failed to write output file.");
-}
\ No newline at end of file
+}
+
+// cargo run --release --example liddriven_cavity [3d]
+fn main() {
+    if std::env::args().nth(1).as_deref() == Some("3d") {
+        liddriven_cavity_3d_example();
+    } else {
+        liddriven_cavity_2d_example();
+    }
+}
@@ -1,10 +1,11 @@
-// src/examples/poiseuille
+// examples/poiseuille.rs
 
 // Import
-use crate::solver;
-use solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+use cappusim::solver;
+use solver::flags::CellType;
 use solver::lbm::LBM;
 use solver::precision::PrecisionMode; 
+use solver::velocity_sets::VelocitySet;
 
 pub fn poiseuille_2d_example() {
     let nx = 512;
@@ -15,17 +16,23 @@ pub fn poiseuille_2d_example() {
     let steps = 100000;
 
     // Initialize LBM simulation
-    let mut lbm = LBM::new(nx, ny, nz, "D2Q9".to_string(), viscosity, PrecisionMode::FP32);
-    lbm.set_constant_force(vec![fx, 0.0, 0.0]);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(VelocitySet::D2Q9)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .constant_force([fx, 0.0, 0.0])
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set boundary and initial conditions
-    lbm.set_conditions(|lbm, _x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         if y == 0 || y == ny - 1 {
             // Top and bottom walls
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
         } else {
             // Interior: fluid
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = 0.0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
@@ -51,14 +58,20 @@ pub fn poiseuille_3d_example() {
     let fz = 1e-6; // Small body force in z-direction
 
     // Initialize LBM simulation
-    let mut lbm = LBM::new(nx, ny, nz, "D3Q19".to_string(), viscosity, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(VelocitySet::D3Q19)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set boundary and initial conditions
-    lbm.set_conditions(|lbm, _x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         if y == 0 || y == ny - 1 {
-            lbm.flags[n] = FLAG_SOLID; // No-slip top and bottom walls
+            lbm.set_cell(x, y, z, CellType::Solid); // No-slip top and bottom walls
         } else {
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
 
             // Approximate initial velocity profile (optional)
             let y_f = y as f32;
@@ -78,4 +91,13 @@ pub fn poiseuille_3d_example() {
 
     // Run the simulation
     lbm.run(100);
-}
\ No newline at end of file
+}
+
+// cargo run --release --example poiseuille [3d]
+fn main() {
+    if std::env::args().nth(1).as_deref() == Some("3d") {
+        poiseuille_3d_example();
+    } else {
+        poiseuille_2d_example();
+    }
+}
@@ -1,11 +1,12 @@
-// src/examples/taylor_green
+// examples/taylor_green.rs
 #![allow(dead_code)]
 #![allow(unused_imports)]
 // Import
-use crate::solver;
-use solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+use cappusim::solver;
+use solver::flags::CellType;
 use solver::lbm::LBM;
 use solver::precision::PrecisionMode; 
+use solver::velocity_sets::VelocitySet;
 
 // 2D Taylor-Green Vortex Example
 pub fn taylor_green_2d_example() {
@@ -14,13 +15,19 @@ pub fn taylor_green_2d_example() {
     let nz = 1;
     let viscosity = 0.01;
     let u0 = 0.1;
-    let model = "D2Q9".to_string();
+    let model = VelocitySet::D2Q9;
 
     // Initialize LBM simulation
-    let mut lbm = LBM::new(nx, ny, nz, model, viscosity, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(model)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set initial conditions for Taylor-Green vortex
-    lbm.set_conditions(|lbm, x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         let pi = std::f32::consts::PI;
         let lx = nx as f32;
         let ly = ny as f32;
@@ -28,7 +35,7 @@ pub fn taylor_green_2d_example() {
         let fx = x as f32 / lx;
         let fy = y as f32 / ly;
 
-        lbm.flags[n] = FLAG_FLUID;
+        lbm.set_cell(x, y, z, CellType::Fluid);
         lbm.density[n] = 1.0;
 
         lbm.velocity[n].x = -u0 * (2.0 * pi * fx).cos() * (2.0 * pi * fy).sin();
@@ -57,10 +64,16 @@ pub fn taylor_green_3d_example() {
     let a_amp = 0.25;
     let pi = std::f32::consts::PI;
 
-    let model = "D3Q19".to_string();
+    let model = VelocitySet::D3Q19;
 
     // Initialize LBM simulation
-    let mut lbm = LBM::new(nx, ny, nz, model, viscosity, PrecisionMode::FP16C);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, nz)
+        .model(model)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP16C)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Set initial conditions for Taylor-Green vortex in 3D (FluidX3D style)
     lbm.set_conditions(|lbm, x, y, z, n| {
@@ -68,7 +81,7 @@ pub fn taylor_green_3d_example() {
         let fy = y as f32 + 0.5 - 0.5 * ny as f32;
         let fz = z as f32 + 0.5 - 0.5 * nz as f32;
 
-        lbm.flags[n] = FLAG_FLUID;
+        lbm.set_cell(x, y, z, CellType::Fluid);
 
         lbm.velocity[n].x =  a_amp * (2.0 * pi * fx / a).cos() * (2.0 * pi * fy / b).sin() * (2.0 * pi * fz / c).sin();
         lbm.velocity[n].y = -a_amp * (2.0 * pi * fx / a).sin() * (2.0 * pi * fy / b).cos() * (2.0 * pi * fz / c).sin();
@@ -86,4 +99,13 @@ pub fn taylor_green_3d_example() {
     // Run the simulation
     lbm.run(10000);
     // lbm.export_to_vtk("vtk_output/taylor_green_3d_final.vtk").unwrap();
-}
\ No newline at end of file
+}
+
+// cargo run --release --example taylor_green [3d]
+fn main() {
+    if std::env::args().nth(1).as_deref() == Some("3d") {
+        taylor_green_3d_example();
+    } else {
+        taylor_green_2d_example();
+    }
+}
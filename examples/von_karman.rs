@@ -1,11 +1,12 @@
-// src/examples/von_karman
+// examples/von_karman.rs
 #![allow(dead_code)]
 #![allow(unused_imports)]
 // Import
-use crate::solver;
-use solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+use cappusim::solver;
+use solver::flags::CellType;
 use solver::lbm::LBM;
 use solver::precision::PrecisionMode; 
+use solver::velocity_sets::VelocitySet;
 
 // 2D Von-Kármán Vortex Street Example
 pub fn von_karman_vortex_2d_example() {
@@ -15,7 +16,13 @@ pub fn von_karman_vortex_2d_example() {
     let u0 = 0.1;
 
     // Initialize LBM simulation
-    let mut lbm = LBM::new(nx, ny, 1, "D2Q9".to_string(), viscosity, PrecisionMode::FP32);
+    let mut lbm = LBM::builder()
+        .size(nx, ny, 1)
+        .model(VelocitySet::D2Q9)
+        .viscosity(viscosity)
+        .precision(PrecisionMode::FP32)
+        .build()
+        .expect("Invalid simulation setup");
 
     // Cylinder parameters
     let radius = nx as f32 * 0.08;
@@ -23,31 +30,31 @@ pub fn von_karman_vortex_2d_example() {
     let cy = ny as i32 / 2;
 
     // Set boundary and initial conditions
-    lbm.set_conditions(|lbm, x, y, _z, n| {
+    lbm.set_conditions(|lbm, x, y, z, n| {
         let dx = x as i32 - cx;
         let dy = y as i32 - cy;
         let dist = ((dx * dx + dy * dy) as f32).sqrt();
 
         if dist <= radius {
-            lbm.flags[n] = FLAG_SOLID; // Cylinder obstacle
+            lbm.set_cell(x, y, z, CellType::Solid); // Cylinder obstacle
         } else if x == 0 {
             // Inlet with prescribed velocity
-            lbm.flags[n] = FLAG_EQ;
+            lbm.set_cell(x, y, z, CellType::Equilibrium);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
         } else if x == nx - 1 {
-            // Outflow: still FLAG_EQ for now, but zero-velocity to reduce reflection
-            lbm.flags[n] = FLAG_EQ;
+            // Outflow: still an equilibrium cell for now, but zero-velocity to reduce reflection
+            lbm.set_cell(x, y, z, CellType::Equilibrium);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
         } else if y == 0 || y == ny - 1 {
             // Top and bottom walls
-            lbm.flags[n] = FLAG_SOLID;
+            lbm.set_cell(x, y, z, CellType::Solid);
         } else {
             // Normal fluid region
-            lbm.flags[n] = FLAG_FLUID;
+            lbm.set_cell(x, y, z, CellType::Fluid);
             lbm.velocity[n].x = u0;
             lbm.velocity[n].y = 0.0;
             lbm.density[n] = 1.0;
@@ -60,4 +67,9 @@ pub fn von_karman_vortex_2d_example() {
 
     // Run the simulation
     lbm.run(10000);
-}
\ No newline at end of file
+}
+
+// cargo run --release --example von_karman
+fn main() {
+    von_karman_vortex_2d_example();
+}
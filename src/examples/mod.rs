@@ -1,6 +0,0 @@
-pub mod airfoil;
-pub mod couette;
-pub mod liddriven_cavity;
-pub mod poiseuille;
-pub mod taylor_green;
-pub mod von_karman;
\ No newline at end of file
@@ -0,0 +1,28 @@
+// ============================================================
+// AVERAGES - running time averages for turbulence statistics
+// ============================================================
+// Per cell, 10 running means: rho, ux, uy, uz, ux*ux, uy*uy, uz*uz, ux*uy,
+// ux*uz, uy*uz. Each sample moves the means by weight = 1 / sample count, which
+// keeps them bounded instead of summing thousands of samples in float.
+#define AVERAGE_FIELDS 10
+
+__kernel void accumulate_averages_kernel(
+    __global float* rho,      // Density
+    __global float* u,        // Velocity (3 components per cell)
+    __global uchar* flags,    // Flag array: FLUID, SOLID, EQ
+    __global float* means,    // Running means, AVERAGE_FIELDS per cell
+    float weight              // 1 / number of samples including this one
+) {
+    int n = get_global_id(0);
+    if (n >= N) return;
+    if (flags[n] == FLAG_SOLID) return;
+
+    float ux = u[n * 3], uy = u[n * 3 + 1], uz = u[n * 3 + 2];
+    float sample[AVERAGE_FIELDS] = {
+        rho[n], ux, uy, uz, ux * ux, uy * uy, uz * uz, ux * uy, ux * uz, uy * uz
+    };
+    __global float* m = means + n * AVERAGE_FIELDS;
+    for (int k = 0; k < AVERAGE_FIELDS; k++) {
+        m[k] += (sample[k] - m[k]) * weight;
+    }
+}
@@ -0,0 +1,35 @@
+// ============================================================
+// CELLS - transfers restricted to listed cells
+// ============================================================
+// Host-side models only need the fields and forces at a few stencil cells,
+// so these gather and update those cells instead of the full arrays.
+
+__kernel void gather_cells_kernel(
+    __global float* rho,        // Density array
+    __global float* u,          // Velocity array
+    __global uint* cells,       // Linear indices of the cells
+    int count,                  // Number of cells
+    __global float* out         // rho, ux, uy, uz per cell
+) {
+    int i = get_global_id(0);
+    if (i >= count) return;
+    int n = cells[i];
+    out[i * 4 + 0] = rho[n];
+    out[i * 4 + 1] = u[n * 3 + 0];
+    out[i * 4 + 2] = u[n * 3 + 1];
+    out[i * 4 + 3] = u[n * 3 + 2];
+}
+
+__kernel void add_cell_forces_kernel(
+    __global float* force,      // Per-cell force density
+    __global uint* cells,       // Linear indices of the cells, distinct
+    int count,                  // Number of cells
+    __global float* values      // Force density added per cell (3 per cell)
+) {
+    int i = get_global_id(0);
+    if (i >= count) return;
+    int n = cells[i];
+    force[n * 3 + 0] += values[i * 3 + 0];
+    force[n * 3 + 1] += values[i * 3 + 1];
+    force[n * 3 + 2] += values[i * 3 + 2];
+}
@@ -0,0 +1,36 @@
+// ============================================================
+// CONSERVATION - global mass correction for reduced precision
+// ============================================================
+// Scales the populations the next step will read by a uniform factor, which
+// restores the total mass without changing the velocity field.
+#if defined(USE_FP32)
+#define RESCALE_STORAGE float
+#elif defined(USE_FP16S)
+#define RESCALE_STORAGE STORAGE_TYPE
+#else
+#define RESCALE_STORAGE half
+#endif
+
+__kernel void rescale_kernel(
+    __global RESCALE_STORAGE* f,     // Distribution function (ping-pong)
+    __global RESCALE_STORAGE* f_new, // Distribution function (ping-pong)
+    __global uchar* flags,           // Flag array: FLUID, SOLID, EQ
+    float scale,                     // Mass correction factor
+    int timestep                     // Next time step to be computed
+) {
+    int n = get_global_id(0);
+    if (n >= N) return;
+    if (flags[n] == FLAG_SOLID) return;
+
+    __global RESCALE_STORAGE* read_buf = (timestep % 2 == 0) ? f : f_new;
+
+    for (int q = 0; q < Q; q++) {
+#ifdef USE_FP16S
+        store_ddf(load_ddf(q * N + n, read_buf) * scale, q * N + n, read_buf);
+#elif defined(USE_FP16C)
+        read_buf[q * N + n] = ddf_encode(q, ddf_decode(q, read_buf[q * N + n]) * scale);
+#else
+        read_buf[q * N + n] = (RESCALE_STORAGE)((float)read_buf[q * N + n] * scale);
+#endif
+    }
+}
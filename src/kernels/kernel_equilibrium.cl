@@ -12,10 +12,10 @@ __kernel void equilibrium(
     if (n >= N) return; // Prevent out-of-bounds access
 
     // Retrieve velocity components for the current node
-    int offset = n * 3;
-    float ux = u[offset];
-    float uy = u[offset + 1];
-    float uz = u[offset + 2];
+    float3 u_cell = vload3(n, u);
+    float ux = u_cell.x;
+    float uy = u_cell.y;
+    float uz = u_cell.z;
     
     // Compute the squared velocity magnitude
     float u2 = ux * ux + uy * uy + uz * uz;
@@ -36,10 +36,10 @@ __kernel void equilibrium(
 
 #elif defined(USE_FP16S)
 // ============================================================
-// FP16S - STORAGE MODE (FP16 storage, FP32 computation)
+// FP16S / BF16S - STORAGE MODE (16-bit storage, FP32 computation)
 // ============================================================
 __kernel void equilibrium(
-    __global half* f,         // Distribution function array (FP16)
+    __global STORAGE_TYPE* f, // Distribution function array (16-bit)
     __global float* rho,      // Density array
     __global float* u         // Velocity array
 ) {
@@ -48,9 +48,10 @@ __kernel void equilibrium(
     if (n >= N) return; // Prevent out-of-bounds access
 
     // Retrieve velocity components for the current node
-    float ux = u[n * 3];
-    float uy = u[n * 3 + 1];
-    float uz = u[n * 3 + 2];
+    float3 u_cell = vload3(n, u);
+    float ux = u_cell.x;
+    float uy = u_cell.y;
+    float uz = u_cell.z;
     
     // Compute the squared velocity magnitude
     float u2 = ux * ux + uy * uy + uz * uz;
@@ -67,8 +68,8 @@ __kernel void equilibrium(
         float feq = local_rho * w[q] * (FLOAT_ONE + FLOAT_THREE * cu + 
                     FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
             
-        // Store result in FP16 format
-        vstore_half(feq, q * N + n, f);
+        // Store result in 16-bit format
+        store_ddf(feq, q * N + n, f);
     }
 }
 
@@ -85,26 +86,19 @@ __kernel void equilibrium(
     int n = get_global_id(0);
     if (n >= N) return; // Prevent out-of-bounds access
 
-    // Retrieve velocity components and convert to half
-    half ux = (half)u[n * 3];
-    half uy = (half)u[n * 3 + 1];
-    half uz = (half)u[n * 3 + 2];
-    
-    // Compute the squared velocity magnitude in half precision
-    half u2 = ux * ux + uy * uy + uz * uz;
-
-    // Retrieve the local density and convert to half
-    half local_rho = (half)rho[n];
+    // Equilibrium in float, stored as shifted halves (see ddf_encode)
+    float3 u_cell = vload3(n, u);
+    float ux = u_cell.x;
+    float uy = u_cell.y;
+    float uz = u_cell.z;
+    float u2 = ux * ux + uy * uy + uz * uz;
+    float local_rho = rho[n];
 
     // Loop over all velocity directions
     for (int q = 0; q < Q; q++) {
-        half cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
-
-        // Compute the equilibrium distribution function in half precision
-        half feq = local_rho * w[q] * (FLOAT_ONE + FLOAT_THREE * cu + 
-                  FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
-        
-        f[q * N + n] = feq;
+        float cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
+        float feq = local_rho * w[q] * (1.0f + 3.0f * cu + 4.5f * cu * cu - 1.5f * u2);
+        f[q * N + n] = ddf_encode(q, feq);
     }
 }
 #endif
\ No newline at end of file
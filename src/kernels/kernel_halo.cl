@@ -0,0 +1,48 @@
+// ============================================================
+// HALO PACKING (multi-device domain decomposition)
+// ============================================================
+// Slabs are cut along the slowest axis: z in 3D, y in 2D. Only the
+// populations that cross a slab face are exchanged: those leaving through the
+// upper face have c[q][SLAB_AXIS] == 1, through the lower face -1. They are
+// packed direction-major, LAYER_CELLS floats per direction.
+#if NZ > 1
+#define SLAB_AXIS 2
+#define LAYER_CELLS (NX * NY)
+#else
+#define SLAB_AXIS 1
+#define LAYER_CELLS NX
+#endif
+
+// Gather the populations of `layer` moving along `sign` into `packed`
+__kernel void pack_halo_kernel(
+    __global const float* f,
+    __global float* packed,
+    int layer,
+    int sign
+) {
+    int i = get_global_id(0);
+    if (i >= LAYER_CELLS) return;
+    int k = 0;
+    for (int q = 0; q < Q; q++) {
+        if (c[q][SLAB_AXIS] != sign) continue;
+        packed[k * LAYER_CELLS + i] = f[q * N + layer * LAYER_CELLS + i];
+        k++;
+    }
+}
+
+// Scatter populations packed by pack_halo_kernel into halo layer `layer`
+__kernel void unpack_halo_kernel(
+    __global const float* packed,
+    __global float* f,
+    int layer,
+    int sign
+) {
+    int i = get_global_id(0);
+    if (i >= LAYER_CELLS) return;
+    int k = 0;
+    for (int q = 0; q < Q; q++) {
+        if (c[q][SLAB_AXIS] != sign) continue;
+        f[q * N + layer * LAYER_CELLS + i] = packed[k * LAYER_CELLS + i];
+        k++;
+    }
+}
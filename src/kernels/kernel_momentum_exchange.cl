@@ -0,0 +1,236 @@
+// ============================================================
+// MOMENTUM EXCHANGE - hydrodynamic force and torque on solid bodies
+// ============================================================
+// For every solid cell of a body, sums the momentum carried across its links
+// to non-solid neighbours by the latest post-collision populations, including
+// the moving-wall correction of the bounce-back kernel. Writes Fx, Fy, Fz,
+// Tx, Ty, Tz per cell; torque is taken about (cx, cy, cz) at link midpoints.
+// The wall-layer kernels below visit the same links from the fluid side, one
+// work item per cell of the wall layer (see compute_wall_layer).
+#if defined(USE_FP32)
+#define EXCHANGE_STORAGE float
+#elif defined(USE_FP16S)
+#define EXCHANGE_STORAGE STORAGE_TYPE
+#else
+#define EXCHANGE_STORAGE half
+#endif
+
+inline float exchange_load(__global EXCHANGE_STORAGE* buf, int q, int i) {
+#ifdef USE_FP16S
+    return load_ddf(i, buf);
+#elif defined(USE_FP16C)
+    return ddf_decode(q, buf[i]);
+#else
+    return (float)buf[i];
+#endif
+}
+
+__kernel void momentum_exchange_kernel(
+    __global EXCHANGE_STORAGE* f,     // Distribution function (ping-pong)
+    __global EXCHANGE_STORAGE* f_new, // Distribution function (ping-pong)
+    __global float* u,                // Velocity array (wall velocity in solid cells)
+    __global uchar* flags,            // Flag array: FLUID, SOLID, EQ
+    __global uint* cells,             // Solid cells of the body
+    int count,                        // Number of cells
+    int timestep,                     // Next time step to be computed
+    float cx,                         // Torque reference point
+    float cy,
+    float cz,
+    __global float* out               // 6 values per cell
+) {
+    int i = get_global_id(0);
+    if (i >= count) return;
+    int s = cells[i];
+
+    // Latest post-collision populations are in the buffer the next step reads
+    __global EXCHANGE_STORAGE* buf = (timestep % 2 == 0) ? f : f_new;
+
+    int x = s % NX;
+    int y = (s / NX) % NY;
+    int z = s / (NX * NY);
+    float uwx = u[s * 3 + 0];
+    float uwy = u[s * 3 + 1];
+    float uwz = u[s * 3 + 2];
+
+    float F[3] = {0.0f, 0.0f, 0.0f};
+    float T[3] = {0.0f, 0.0f, 0.0f};
+    for (int q = 1; q < Q; q++) {
+        int xn = (x + c[q][0] + NX) % NX;
+        int yn = (y + c[q][1] + NY) % NY;
+        int zn = (z + c[q][2] + NZ) % NZ;
+        int nf = zn * (NX * NY) + yn * NX + xn;
+        if (flags[nf] == FLAG_SOLID) continue;
+
+        // Population of nf heading into the solid along e = -c[q]
+        int k = opposite[q];
+        float fk = exchange_load(buf, k, wall_slot(k, nf));
+        float eu = -(c[q][0] * uwx + c[q][1] * uwy + c[q][2] * uwz);
+        float m = 2.0f * fk - 6.0f * (float)w[k] * eu;
+        float fl[3] = {-c[q][0] * m, -c[q][1] * m, -c[q][2] * m};
+
+        float r[3] = {
+            x + 0.5f * c[q][0] - cx,
+            y + 0.5f * c[q][1] - cy,
+            z + 0.5f * c[q][2] - cz
+        };
+        F[0] += fl[0];
+        F[1] += fl[1];
+        F[2] += fl[2];
+        T[0] += r[1] * fl[2] - r[2] * fl[1];
+        T[1] += r[2] * fl[0] - r[0] * fl[2];
+        T[2] += r[0] * fl[1] - r[1] * fl[0];
+    }
+
+    for (int d = 0; d < 3; d++) {
+        out[i * 6 + d] = F[d];
+        out[i * 6 + 3 + d] = T[d];
+    }
+}
+
+// Force and torque on all solids from wall-layer cell cells[i]: the links of
+// the previous kernel, seen from the fluid end. Writes 6 values per cell.
+__kernel void wall_momentum_exchange_kernel(
+    __global EXCHANGE_STORAGE* f,     // Distribution function (ping-pong)
+    __global EXCHANGE_STORAGE* f_new, // Distribution function (ping-pong)
+    __global float* u,                // Velocity array (wall velocity in solid cells)
+    __global uchar* flags,            // Flag array: FLUID, SOLID, EQ
+    __global uint* cells,             // Wall layer
+    int count,                        // Number of cells
+    int timestep,                     // Next time step to be computed
+    float cx,                         // Torque reference point
+    float cy,
+    float cz,
+    __global float* out               // 6 values per cell
+) {
+    int i = get_global_id(0);
+    if (i >= count) return;
+    int n = cells[i];
+    __global EXCHANGE_STORAGE* buf = (timestep % 2 == 0) ? f : f_new;
+
+    int x = n % NX;
+    int y = (n / NX) % NY;
+    int z = n / (NX * NY);
+
+    float F[3] = {0.0f, 0.0f, 0.0f};
+    float T[3] = {0.0f, 0.0f, 0.0f};
+    for (int k = 1; k < Q; k++) {
+        int xs = (x + c[k][0] + NX) % NX;
+        int ys = (y + c[k][1] + NY) % NY;
+        int zs = (z + c[k][2] + NZ) % NZ;
+        int s = zs * (NX * NY) + ys * NX + xs;
+        if (flags[s] != FLAG_SOLID) continue;
+
+        // Population of n heading into the solid along c[k]
+        float fk = exchange_load(buf, k, wall_slot(k, n));
+        float eu = c[k][0] * u[s * 3 + 0] + c[k][1] * u[s * 3 + 1] + c[k][2] * u[s * 3 + 2];
+        float m = 2.0f * fk - 6.0f * (float)w[k] * eu;
+        float fl[3] = {c[k][0] * m, c[k][1] * m, c[k][2] * m};
+
+        float r[3] = {
+            x + 0.5f * c[k][0] - cx,
+            y + 0.5f * c[k][1] - cy,
+            z + 0.5f * c[k][2] - cz
+        };
+        F[0] += fl[0];
+        F[1] += fl[1];
+        F[2] += fl[2];
+        T[0] += r[1] * fl[2] - r[2] * fl[1];
+        T[1] += r[2] * fl[0] - r[0] * fl[2];
+        T[2] += r[0] * fl[1] - r[1] * fl[0];
+    }
+
+    for (int d = 0; d < 3; d++) {
+        out[i * 6 + d] = F[d];
+        out[i * 6 + 3 + d] = T[d];
+    }
+}
+
+// Wall shear stress magnitude of wall-layer cell cells[i]: the tangential part
+// of sigma . n, with the deviatoric stress
+//   sigma_ab = prefactor * sum_q (f_q - feq_q) c_qa c_qb
+// from the populations the next step pulls, and the wall normal n pointing
+// from the solid neighbours into the fluid.
+__kernel void wall_shear_kernel(
+    __global EXCHANGE_STORAGE* f,     // Distribution function (ping-pong)
+    __global EXCHANGE_STORAGE* f_new, // Distribution function (ping-pong)
+    __global uchar* flags,            // Flag array: FLUID, SOLID, EQ
+    __global uint* cells,             // Wall layer
+    int count,                        // Number of cells
+    int timestep,                     // Next time step to be computed
+    float prefactor,                  // -(1 - omega / 2)
+    __global float* out               // 1 value per cell
+) {
+    int i = get_global_id(0);
+    if (i >= count) return;
+    int n = cells[i];
+    __global EXCHANGE_STORAGE* buf = (timestep % 2 == 0) ? f : f_new;
+
+    int x = n % NX;
+    int y = (n / NX) % NY;
+    int z = n / (NX * NY);
+
+    float f_pop[Q];
+    float rho = 0.0f;
+    float ux = 0.0f, uy = 0.0f, uz = 0.0f;
+    float normal[3] = {0.0f, 0.0f, 0.0f};
+    for (int q = 0; q < Q; q++) {
+        int xp = (x - c[q][0] + NX) % NX;
+        int yp = (y - c[q][1] + NY) % NY;
+        int zp = (z - c[q][2] + NZ) % NZ;
+        int np = zp * (NX * NY) + yp * NX + xp;
+        if (flags[np] == FLAG_SOLID) {
+            f_pop[q] = exchange_load(buf, opposite[q], bounce_slot(q, n, timestep));
+            // The solid lies along -c[q], so c[q] points into the fluid
+            normal[0] += c[q][0];
+            normal[1] += c[q][1];
+            normal[2] += c[q][2];
+        } else {
+            f_pop[q] = exchange_load(buf, q, pull_slot(q, q, n, np, timestep));
+        }
+        rho += f_pop[q];
+        ux += c[q][0] * f_pop[q];
+        uy += c[q][1] * f_pop[q];
+        uz += c[q][2] * f_pop[q];
+    }
+
+    float length = sqrt(normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]);
+    if (rho <= 1e-10f || length == 0.0f) {
+        out[i] = 0.0f;
+        return;
+    }
+    ux /= rho;
+    uy /= rho;
+    uz /= rho;
+    float u2 = ux * ux + uy * uy + uz * uz;
+    for (int d = 0; d < 3; d++) normal[d] /= length;
+
+    // Non-equilibrium momentum flux [xx, yy, zz, xy, xz, yz]
+    float pi[6] = {0.0f, 0.0f, 0.0f, 0.0f, 0.0f, 0.0f};
+    for (int q = 0; q < Q; q++) {
+        float cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
+        float feq = rho * (float)w[q] * (1.0f + 3.0f * cu + 4.5f * cu * cu - 1.5f * u2);
+        float fneq = f_pop[q] - feq;
+        pi[0] += fneq * c[q][0] * c[q][0];
+        pi[1] += fneq * c[q][1] * c[q][1];
+        pi[2] += fneq * c[q][2] * c[q][2];
+        pi[3] += fneq * c[q][0] * c[q][1];
+        pi[4] += fneq * c[q][0] * c[q][2];
+        pi[5] += fneq * c[q][1] * c[q][2];
+    }
+    float sigma[3][3] = {
+        {pi[0], pi[3], pi[4]},
+        {pi[3], pi[1], pi[5]},
+        {pi[4], pi[5], pi[2]}
+    };
+    float traction[3];
+    for (int a = 0; a < 3; a++) {
+        traction[a] = prefactor * (sigma[a][0] * normal[0] + sigma[a][1] * normal[1] + sigma[a][2] * normal[2]);
+    }
+    float tn = traction[0] * normal[0] + traction[1] * normal[1] + traction[2] * normal[2];
+    float tau = 0.0f;
+    for (int d = 0; d < 3; d++) {
+        float t = traction[d] - tn * normal[d];
+        tau += t * t;
+    }
+    out[i] = sqrt(tau);
+}
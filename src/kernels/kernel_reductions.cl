@@ -0,0 +1,121 @@
+// ============================================================
+// REDUCTIONS - global monitors and field sums, maxima and norms
+// ============================================================
+// Each work-group sums its cells in local memory and writes one partial
+// (energy, enstrophy) pair; the host adds the partials. Velocity gradients
+// are central differences with periodic wrap, like the streaming step.
+#define REDUCE_GROUP 256
+
+__kernel void energy_enstrophy_kernel(
+    __global float* rho,      // Density
+    __global float* u,        // Velocity (3 components per cell)
+    __global uchar* flags,    // Flag array: FLUID, SOLID, EQ
+    __global float* partial   // Two sums per work-group
+) {
+    __local float energy[REDUCE_GROUP];
+    __local float enstrophy[REDUCE_GROUP];
+    int n = get_global_id(0);
+    int l = get_local_id(0);
+
+    float e = 0.0f;
+    float w = 0.0f;
+    if (n < N && flags[n] != FLAG_SOLID) {
+        int x = n % NX;
+        int y = (n / NX) % NY;
+        int z = n / (NX * NY);
+        int xp = ((x + 1) % NX) + (y + z * NY) * NX;
+        int xm = ((x + NX - 1) % NX) + (y + z * NY) * NX;
+        int yp = x + (((y + 1) % NY) + z * NY) * NX;
+        int ym = x + (((y + NY - 1) % NY) + z * NY) * NX;
+        int zp = x + (y + ((z + 1) % NZ) * NY) * NX;
+        int zm = x + (y + ((z + NZ - 1) % NZ) * NY) * NX;
+
+        float ux = u[n * 3], uy = u[n * 3 + 1], uz = u[n * 3 + 2];
+        e = 0.5f * rho[n] * (ux * ux + uy * uy + uz * uz);
+
+        float wx = 0.5f * ((u[yp * 3 + 2] - u[ym * 3 + 2]) - (u[zp * 3 + 1] - u[zm * 3 + 1]));
+        float wy = 0.5f * ((u[zp * 3] - u[zm * 3]) - (u[xp * 3 + 2] - u[xm * 3 + 2]));
+        float wz = 0.5f * ((u[xp * 3 + 1] - u[xm * 3 + 1]) - (u[yp * 3] - u[ym * 3]));
+        w = 0.5f * (wx * wx + wy * wy + wz * wz);
+    }
+    energy[l] = e;
+    enstrophy[l] = w;
+    barrier(CLK_LOCAL_MEM_FENCE);
+
+    for (int s = get_local_size(0) / 2; s > 0; s >>= 1) {
+        if (l < s) {
+            energy[l] += energy[l + s];
+            enstrophy[l] += enstrophy[l + s];
+        }
+        barrier(CLK_LOCAL_MEM_FENCE);
+    }
+    if (l == 0) {
+        partial[get_group_id(0) * 2] = energy[0];
+        partial[get_group_id(0) * 2 + 1] = enstrophy[0];
+    }
+}
+
+// Generic reduction over the non-solid cells of a field with `components`
+// floats per cell. The per-cell value is optionally the difference to a
+// second field (REDUCE_DIFFERENCE) and scaled by a per-cell weight
+// (REDUCE_WEIGHTED). REDUCE_SUM writes one partial per component and group;
+// REDUCE_MAX and REDUCE_SUM_SQUARES write one per group, taken over the value
+// of scalars and the magnitude of vectors.
+#define REDUCE_SUM 0
+#define REDUCE_MAX 1
+#define REDUCE_SUM_SQUARES 2
+#define REDUCE_DIFFERENCE 4
+#define REDUCE_WEIGHTED 8
+#define REDUCE_MAX_COMPONENTS 4
+
+__kernel void reduce_field_kernel(
+    __global float* a,        // Field to reduce
+    __global float* b,        // Subtracted field (REDUCE_DIFFERENCE only)
+    __global float* weight,   // Per-cell weight (REDUCE_WEIGHTED only)
+    __global uchar* flags,    // Flag array: FLUID, SOLID, EQ
+    int components,           // Floats per cell, at most REDUCE_MAX_COMPONENTS
+    int mode,                 // Operation plus modifier bits
+    __global float* partial   // Partials per work-group
+) {
+    __local float values[REDUCE_MAX_COMPONENTS * REDUCE_GROUP];
+    int n = get_global_id(0);
+    int l = get_local_id(0);
+    int op = mode & 3;
+    int outputs = (op == REDUCE_SUM) ? components : 1;
+
+    float v[REDUCE_MAX_COMPONENTS] = {0.0f, 0.0f, 0.0f, 0.0f};
+    if (n < N && flags[n] != FLAG_SOLID) {
+        float scale = (mode & REDUCE_WEIGHTED) ? weight[n] : 1.0f;
+        float square = 0.0f;
+        for (int k = 0; k < components; k++) {
+            float x = a[n * components + k];
+            if (mode & REDUCE_DIFFERENCE) x -= b[n * components + k];
+            x *= scale;
+            v[k] = x;
+            square += x * x;
+        }
+        if (op == REDUCE_MAX && components > 1) v[0] = sqrt(square);
+        if (op == REDUCE_SUM_SQUARES) v[0] = square;
+    } else if (op == REDUCE_MAX) {
+        v[0] = -INFINITY;
+    }
+    for (int k = 0; k < outputs; k++) {
+        values[k * REDUCE_GROUP + l] = v[k];
+    }
+    barrier(CLK_LOCAL_MEM_FENCE);
+
+    for (int s = get_local_size(0) / 2; s > 0; s >>= 1) {
+        if (l < s) {
+            for (int k = 0; k < outputs; k++) {
+                int i = k * REDUCE_GROUP + l;
+                values[i] = (op == REDUCE_MAX) ? fmax(values[i], values[i + s]) : values[i] + values[i + s];
+            }
+        }
+        barrier(CLK_LOCAL_MEM_FENCE);
+    }
+    if (l == 0) {
+        for (int k = 0; k < outputs; k++) {
+            partial[get_group_id(0) * outputs + k] = values[k * REDUCE_GROUP];
+        }
+    }
+}
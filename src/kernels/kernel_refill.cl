@@ -0,0 +1,111 @@
+// ============================================================
+// REFILL - cells uncovered by moving solids
+// ============================================================
+// Fresh fluid cells (FLAG_FRESH) hold stale populations from before they were
+// covered. They are rebuilt as equilibrium at the mean density of their valid
+// neighbours and the wall velocity the host left in u, plus the
+// non-equilibrium part extrapolated from the neighbour lying furthest
+// opposite to the wall motion, i.e. away from the receding body.
+#if defined(USE_FP32)
+#define REFILL_STORAGE float
+#elif defined(USE_FP16S)
+#define REFILL_STORAGE STORAGE_TYPE
+#else
+#define REFILL_STORAGE half
+#endif
+
+inline float refill_load(__global REFILL_STORAGE* buf, int q, int i) {
+#ifdef USE_FP16S
+    return load_ddf(i, buf);
+#elif defined(USE_FP16C)
+    return ddf_decode(q, buf[i]);
+#else
+    return (float)buf[i];
+#endif
+}
+
+inline float refill_feq(int q, float rho, float ux, float uy, float uz) {
+    float cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
+    float u2 = ux * ux + uy * uy + uz * uz;
+    return rho * (float)w[q] * (1.0f + 3.0f * cu + 4.5f * cu * cu - 1.5f * u2);
+}
+
+__kernel void refill_kernel(
+    __global REFILL_STORAGE* f,     // Distribution function (ping-pong)
+    __global REFILL_STORAGE* f_new, // Distribution function (ping-pong)
+    __global float* rho,            // Density array
+    __global float* u,              // Velocity array
+    __global uchar* flags,          // Flag array: FLUID, SOLID, EQ, FRESH
+    __global uint* cells,           // Linear indices of the fresh cells
+    int count,                      // Number of fresh cells
+    int timestep                    // Next time step to be computed
+) {
+    int i = get_global_id(0);
+    if (i >= count) return;
+    int n = cells[i];
+
+    // Populations for the next step are read from this buffer
+    __global REFILL_STORAGE* read_buf = (timestep % 2 == 0) ? f : f_new;
+
+    int x = n % NX;
+    int y = (n / NX) % NY;
+    int z = n / (NX * NY);
+
+    float ux = u[n * 3 + 0];
+    float uy = u[n * 3 + 1];
+    float uz = u[n * 3 + 2];
+
+    float rho_sum = 0.0f;
+    int rho_count = 0;
+    int source = -1;
+    float best = -1.0e30f;
+    for (int q = 1; q < Q; q++) {
+        int xp = (x + c[q][0] + NX) % NX;
+        int yp = (y + c[q][1] + NY) % NY;
+        int zp = (z + c[q][2] + NZ) % NZ;
+        int np = zp * (NX * NY) + yp * NX + xp;
+        if (flags[np] == FLAG_SOLID || flags[np] == FLAG_FRESH) continue;
+        rho_sum += rho[np];
+        rho_count++;
+        float score = -(c[q][0] * ux + c[q][1] * uy + c[q][2] * uz);
+        if (score > best) {
+            best = score;
+            source = np;
+        }
+    }
+    float local_rho = (rho_count > 0) ? rho_sum / (float)rho_count : 1.0f;
+    rho[n] = local_rho;
+
+    float rho_s = 0.0f, usx = 0.0f, usy = 0.0f, usz = 0.0f;
+    if (source >= 0) {
+        rho_s = rho[source];
+        usx = u[source * 3 + 0];
+        usy = u[source * 3 + 1];
+        usz = u[source * 3 + 2];
+    }
+
+    for (int q = 0; q < Q; q++) {
+        float value = refill_feq(q, local_rho, ux, uy, uz);
+        if (source >= 0) {
+            value += refill_load(read_buf, q, cell_slot(q, source, timestep)) - refill_feq(q, rho_s, usx, usy, usz);
+        }
+#ifdef USE_FP16S
+        store_ddf(value, cell_slot(q, n, timestep), read_buf);
+#elif defined(USE_FP16C)
+        read_buf[cell_slot(q, n, timestep)] = ddf_encode(q, value);
+#else
+        read_buf[cell_slot(q, n, timestep)] = (REFILL_STORAGE)value;
+#endif
+    }
+}
+
+// Return refilled cells to the fluid once every refill_kernel item has finished
+__kernel void refill_finish_kernel(
+    __global uchar* flags,
+    __global uint* cells,
+    int count
+) {
+    int i = get_global_id(0);
+    if (i >= count) return;
+    flags[cells[i]] = FLAG_FLUID;
+}
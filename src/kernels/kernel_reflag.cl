@@ -0,0 +1,112 @@
+// ============================================================
+// REFLAG - on-device rasterization of moving bodies
+// ============================================================
+// Each body is described by REFLAG_STRIDE floats:
+//   [0] shape (0 sphere, 1 box, 2 cylinder along the body z axis, 3 sampled SDF)
+//   [1..3] shape parameters (radius | half extents | radius, half length |
+//          offset into sdf and half width of the sample grid, as int bits)
+//   [4..6] current center, [7] body ID
+//   [8..10] linear velocity, [11..13] angular velocity
+//   [16..24] rotation from the body frame to the world frame, row major
+// Cells inside a body become solid with the local wall velocity and are
+// appended to `covered`. Cells a listed body owned before but no longer covers
+// become FLAG_FRESH and are appended to `fresh` for refill_kernel. A body only
+// claims fluid cells and its own, so static solids, equilibrium (inlet/outlet)
+// cells and cells of other bodies, listed or not, are never taken over.
+#define REFLAG_STRIDE 32
+
+// Trilinear interpolation of an SDF sampled on the unit grid of
+// (2 h + 1)^3 points centered on the body origin; points off the grid are outside
+inline bool reflag_sampled_inside(__global float* sdf, int offset, int h, float lx, float ly, float lz) {
+    int s = 2 * h + 1;
+    float gx = lx + (float)h;
+    float gy = ly + (float)h;
+    float gz = lz + (float)h;
+    if (gx < 0.0f || gy < 0.0f || gz < 0.0f) return false;
+    if (gx > (float)(s - 1) || gy > (float)(s - 1) || gz > (float)(s - 1)) return false;
+    int ix = min((int)gx, s - 2);
+    int iy = min((int)gy, s - 2);
+    int iz = min((int)gz, s - 2);
+    float tx = gx - (float)ix;
+    float ty = gy - (float)iy;
+    float tz = gz - (float)iz;
+    __global float* p = sdf + offset + (iz * s + iy) * s + ix;
+    float c00 = mix(p[0], p[1], tx);
+    float c10 = mix(p[s], p[s + 1], tx);
+    float c01 = mix(p[s * s], p[s * s + 1], tx);
+    float c11 = mix(p[s * s + s], p[s * s + s + 1], tx);
+    return mix(mix(c00, c10, ty), mix(c01, c11, ty), tz) <= 0.0f;
+}
+
+inline bool reflag_inside(__global float* body, __global float* sdf, float px, float py, float pz) {
+    float dx = px - body[4];
+    float dy = py - body[5];
+    float dz = pz - body[6];
+    // Body frame coordinates, R^T (p - center)
+    float lx = body[16] * dx + body[19] * dy + body[22] * dz;
+    float ly = body[17] * dx + body[20] * dy + body[23] * dz;
+    float lz = body[18] * dx + body[21] * dy + body[24] * dz;
+    int shape = (int)body[0];
+    if (shape == 0) {
+        return lx * lx + ly * ly + lz * lz <= body[1] * body[1];
+    } else if (shape == 1) {
+        return fabs(lx) <= body[1] && fabs(ly) <= body[2] && fabs(lz) <= body[3];
+    } else if (shape == 2) {
+        return lx * lx + ly * ly <= body[1] * body[1] && fabs(lz) <= body[2];
+    } else {
+        return reflag_sampled_inside(sdf, as_int(body[1]), as_int(body[2]), lx, ly, lz);
+    }
+}
+
+__kernel void reflag_kernel(
+    __global uchar* flags,          // Flag array
+    __global float* u,              // Velocity array
+    __global ushort* owner,         // Body ID owning each device-rasterized cell
+    __global float* bodies,         // Body descriptors
+    __global float* sdf,            // Samples of the SDF shapes
+    int body_count,
+    int x0, int y0, int z0,         // Region origin
+    int sx, int sy, int sz,         // Region size
+    __global uint* fresh,           // Output: uncovered cells
+    __global uint* covered,         // Output: covered cells
+    volatile __global int* counts   // Output: number of fresh and covered cells
+) {
+    int i = get_global_id(0);
+    if (i >= sx * sy * sz) return;
+    int x = x0 + i % sx;
+    int y = y0 + (i / sx) % sy;
+    int z = z0 + i / (sx * sy);
+    int n = z * (NX * NY) + y * NX + x;
+
+    ushort current = owner[n];
+    if (current == 0 && (flags[n] == FLAG_SOLID || flags[n] == FLAG_EQ)) return;
+
+    float px = (float)x;
+    float py = (float)y;
+    float pz = (float)z;
+    for (int b = 0; b < body_count; b++) {
+        __global float* body = bodies + b * REFLAG_STRIDE;
+        ushort id = (ushort)body[7];
+        if (current != 0 && current != id) continue;
+        if (!reflag_inside(body, sdf, px, py, pz)) continue;
+        float rx = px - body[4];
+        float ry = py - body[5];
+        float rz = pz - body[6];
+        flags[n] = FLAG_SOLID;
+        owner[n] = id;
+        u[n * 3 + 0] = body[8] + body[12] * rz - body[13] * ry;
+        u[n * 3 + 1] = body[9] + body[13] * rx - body[11] * rz;
+        u[n * 3 + 2] = body[10] + body[11] * ry - body[12] * rx;
+        covered[atomic_inc(&counts[1])] = (uint)n;
+        return;
+    }
+
+    if (current == 0) return;
+    for (int b = 0; b < body_count; b++) {
+        if ((ushort)bodies[b * REFLAG_STRIDE + 7] != current) continue;
+        owner[n] = 0;
+        flags[n] = FLAG_FRESH;
+        fresh[atomic_inc(&counts[0])] = (uint)n;
+        return;
+    }
+}
@@ -1,3 +1,177 @@
+#ifdef SYMMETRY_PLANES
+// ============================================================
+// SYMMETRY PLANES (free-slip mirror boundaries at the domain faces)
+// ============================================================
+// SYMMETRY_PLANES bits: 1 = x min, 2 = x max, 4 = y min, 8 = y max, 16 = z min, 32 = z max.
+// Returns the axes (bit 0: x, 1: y, 2: z) on which source cell (xs, ys, zs)
+// lies beyond a symmetry plane.
+inline int symmetry_flip(int xs, int ys, int zs) {
+    int flip = 0;
+    if (((SYMMETRY_PLANES & 1) && xs < 0) || ((SYMMETRY_PLANES & 2) && xs >= NX)) flip |= 1;
+    if (((SYMMETRY_PLANES & 4) && ys < 0) || ((SYMMETRY_PLANES & 8) && ys >= NY)) flip |= 2;
+    if (((SYMMETRY_PLANES & 16) && zs < 0) || ((SYMMETRY_PLANES & 32) && zs >= NZ)) flip |= 4;
+    return flip;
+}
+
+// Direction q with the velocity components selected by `flip` reversed
+inline int reflected_direction(int q, int flip) {
+    int rx = (flip & 1) ? -c[q][0] : c[q][0];
+    int ry = (flip & 2) ? -c[q][1] : c[q][1];
+    int rz = (flip & 4) ? -c[q][2] : c[q][2];
+    for (int k = 0; k < Q; k++) {
+        if (c[k][0] == rx && c[k][1] == ry && c[k][2] == rz) return k;
+    }
+    return q;
+}
+#endif
+
+// ============================================================
+// NEIGHBOURS
+// ============================================================
+// Index of the cell a population arriving at n along q comes from, i.e.
+// (x, y, z) - c[q] on the periodic lattice. The default wraps with modulo;
+// NEIGHBOR_WRAP replaces the modulo by compare-and-select and NEIGHBOR_TABLE
+// reads the index from a table of Q ints per cell filled once at startup.
+inline int source_cell(int q, int n, int x, int y, int z, __global const int* neighbors) {
+#if defined(NEIGHBOR_TABLE)
+    return neighbors[q * N + n];
+#elif defined(NEIGHBOR_WRAP)
+    int xp = x - c[q][0];
+    int yp = y - c[q][1];
+    int zp = z - c[q][2];
+    xp += (xp < 0) ? NX : ((xp >= NX) ? -NX : 0);
+    yp += (yp < 0) ? NY : ((yp >= NY) ? -NY : 0);
+    zp += (zp < 0) ? NZ : ((zp >= NZ) ? -NZ : 0);
+    return zp * (NX * NY) + yp * NX + xp;
+#else
+    int xp = (x - c[q][0] + NX) % NX;
+    int yp = (y - c[q][1] + NY) % NY;
+    int zp = (z - c[q][2] + NZ) % NZ;
+    return zp * (NX * NY) + yp * NX + xp;
+#endif
+}
+
+#ifdef NEIGHBOR_TABLE
+__kernel void neighbor_table_kernel(__global int* neighbors) {
+    int n = get_global_id(0);
+    if (n >= N) return;
+    int x = n % NX;
+    int y = (n / NX) % NY;
+    int z = n / (NX * NY);
+    for (int q = 0; q < Q; q++) {
+        int xp = (x - c[q][0] + NX) % NX;
+        int yp = (y - c[q][1] + NY) % NY;
+        int zp = (z - c[q][2] + NZ) % NZ;
+        neighbors[q * N + n] = zp * (NX * NY) + yp * NX + xp;
+    }
+}
+#endif
+
+// ============================================================
+// STREAMING SLOTS
+// ============================================================
+// Index of each population in the DDF buffer. The default two-lattice scheme
+// pulls from f and writes the other buffer. With IN_PLACE_STREAMING (AA
+// pattern) the host binds one buffer as both f and f_new: even steps read and
+// write the cell's own slots, storing direction q in the opposite slot; odd
+// steps read the neighbours' opposite slots and push to the neighbours.
+// Links to solid cells bounce back into the cell's own slots in both cases.
+
+// Slot of the bounced-back population arriving at n along q
+inline int bounce_slot(int q, int n, int timestep) {
+#ifdef IN_PLACE_STREAMING
+    return q * N + n;
+#else
+    return opposite[q] * N + n;
+#endif
+}
+
+// Slot of the population arriving at n along q from non-solid neighbour np
+// (direction qs at np)
+inline int pull_slot(int q, int qs, int n, int np, int timestep) {
+#ifdef IN_PLACE_STREAMING
+    return (timestep % 2 == 0) ? q * N + n : opposite[q] * N + np;
+#else
+    return qs * N + np;
+#endif
+}
+
+// Slot receiving the post-collision population q of cell n at (x, y, z)
+inline int push_slot(int q, int n, int x, int y, int z, __global uchar* flags,
+                     __global const int* neighbors, int timestep) {
+#ifdef IN_PLACE_STREAMING
+    if (timestep % 2 == 0) return opposite[q] * N + n;
+    int m = source_cell(opposite[q], n, x, y, z, neighbors);
+    return (flags[m] == FLAG_SOLID) ? opposite[q] * N + n : q * N + m;
+#else
+    return q * N + n;
+#endif
+}
+
+// u and force hold three floats per cell and are accessed with vload3 and
+// vstore3; the direction-major DDFs are coalesced across work items already
+// and stay scalar.
+// Projection of the wall velocity stored in solid cell np onto c[q]
+inline float wall_velocity_dot(int q, int np, __global float* u) {
+    return dot(convert_float3(vload3(0, c[q])), vload3(np, u));
+}
+
+// Slot holding population q of cell n between steps, before `timestep` runs
+inline int cell_slot(int q, int n, int timestep) {
+#ifdef IN_PLACE_STREAMING
+    return (timestep % 2 == 1) ? opposite[q] * N + n : q * N + n;
+#else
+    return q * N + n;
+#endif
+}
+
+// Slot of the latest post-collision population q of non-solid cell n whose
+// link along q ends in a solid cell
+inline int wall_slot(int q, int n) {
+#ifdef IN_PLACE_STREAMING
+    return opposite[q] * N + n;
+#else
+    return q * N + n;
+#endif
+}
+
+// ============================================================
+// COLLISION
+// ============================================================
+// BGK relaxes every population with omega. With COLLISION_TRT the parts of
+// f_q even and odd under q -> opposite[q] relax separately, the even part with
+// omega (which sets the viscosity) and the odd part with omega_a, fixed by the
+// magic parameter (1/omega - 1/2)(1/omega_a - 1/2) = 3/16.
+inline float antisymmetric_omega(float omega) {
+#ifdef COLLISION_TRT
+    return 1.0f / (0.1875f / (1.0f / omega - 0.5f) + 0.5f);
+#else
+    return omega;
+#endif
+}
+
+// Post-collision population q from fq = f_q, fo = f_opposite[q] and the even
+// and odd parts of the equilibrium, feq_e = rho w (1 + 4.5 (c.u)^2 - 1.5 u^2)
+// and feq_o = 3 rho w (c.u)
+inline float relax(float fq, float fo, float feq_e, float feq_o, float omega, float omega_a) {
+#ifdef COLLISION_TRT
+    return fq - omega * (0.5f * (fq + fo) - feq_e) - omega_a * (0.5f * (fq - fo) - feq_o);
+#else
+    return (1.0f - omega) * fq + omega * (feq_e + feq_o);
+#endif
+}
+
+// Guo forcing term of population q with force density (Fx, Fy, Fz); its even
+// part relaxes with omega and its odd part, 3 w c.F, with omega_a
+inline float guo_force(int q, float ux, float uy, float uz, float Fx, float Fy, float Fz,
+                       float cu, float rho, float omega, float omega_a) {
+    if (rho <= FLOAT_EPSILON) return 0.0f;
+    float cF = c[q][0] * Fx + c[q][1] * Fy + c[q][2] * Fz;
+    float even = 9.0f * cF * cu - 3.0f * (ux * Fx + uy * Fy + uz * Fz);
+    float odd = 3.0f * cF;
+    return w[q] * ((1.0f - 0.5f * omega) * even + (1.0f - 0.5f * omega_a) * odd) / rho;
+}
+
 // ============================================================
 // FP32 - FULL PRECISION MODE
 // ============================================================
@@ -9,7 +183,10 @@ __kernel void stream_collide_kernel(
     __global float* u,        // Velocity array (output)
     __global uchar* flags,    // Flag array: FLUID, SOLID, EQ
     float omega,              // Relaxation parameter
-    int timestep              // Current time step
+    int timestep,             // Current time step
+    __global float* force,    // Per-cell force density (USE_FORCE_FIELD only)
+    float4 body_force,        // Uniform force density (USE_CONSTANT_FORCE only)
+    __global const int* neighbors // Source cell of each link (NEIGHBOR_TABLE only)
 ) {
     int n = get_global_id(0);
     if (n >= N) return;
@@ -34,18 +211,32 @@ __kernel void stream_collide_kernel(
         int dy = c[q][1];
         int dz = c[q][2];
 
-        int xp = (x - dx + NX) % NX;
-        int yp = (y - dy + NY) % NY;
-        int zp = (z - dz + NZ) % NZ;
-
-        int np = zp * (NX * NY) + yp * NX + xp;
+        int np = source_cell(q, n, x, y, z, neighbors);
+        int qs = q; // Source direction, differs from q at symmetry planes
+
+        #ifdef SYMMETRY_PLANES
+        // The image cell beyond a symmetry plane holds the boundary cell's
+        // populations with the normal component reversed (specular reflection)
+        int flip = symmetry_flip(x - dx, y - dy, z - dz);
+        if (flip) {
+            qs = reflected_direction(q, flip);
+            int xp = (flip & 1) ? x : (x - dx + NX) % NX;
+            int yp = (flip & 2) ? y : (y - dy + NY) % NY;
+            int zp = (flip & 4) ? z : (z - dz + NZ) % NZ;
+            np = zp * (NX * NY) + yp * NX + xp;
+        }
+        #endif
         uchar neighbor_flag = flags[np];
 
         if (neighbor_flag == FLAG_SOLID) {
             // Bounce-back
-            f_pop[q] = read_buf[opposite[q] * N + n];
+            f_pop[q] = read_buf[bounce_slot(q, n, timestep)];
+            #ifdef USE_MOVING_WALLS
+            // Moving-wall correction; the wall velocity is stored in u of the solid cell
+            f_pop[q] += FLOAT_CONST(6.0) * w[q] * wall_velocity_dot(q, np, u);
+            #endif
         } else {
-            f_pop[q] = read_buf[q * N + np];
+            f_pop[q] = read_buf[pull_slot(q, qs, n, np, timestep)];
         }
 
         // Accumulate for macroscopic variables
@@ -65,72 +256,77 @@ __kernel void stream_collide_kernel(
     // --- Collision ---
     if (flags[n] == FLAG_EQ) {
         // Use prescribed velocity and density from host
-        int offset = n * 3;
-        ux = u[offset + 0];
-        uy = u[offset + 1];
-        uz = u[offset + 2];
+        float3 u_eq = vload3(n, u);
+        ux = u_eq.x;
+        uy = u_eq.y;
+        uz = u_eq.z;
         local_rho = rho[n];
         u2 = ux * ux + uy * uy + uz * uz;
         for (int q = 0; q < Q; q++) {
             float cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
-            write_buf[q * N + n] = local_rho * w[q] * (1.0f + 3.0f * cu + 4.5f * cu * cu - 1.5f * u2);
+            write_buf[push_slot(q, n, x, y, z, flags, neighbors, timestep)] = local_rho * w[q] * (1.0f + 3.0f * cu + 4.5f * cu * cu - 1.5f * u2);
         }
     } else {
-        // Standard BGK collision for fluid cells
+        // Collision for fluid cells (BGK, or TRT with COLLISION_TRT)
         rho[n] = local_rho;
         
-        int offset = n * 3;
-        u[offset + 0] = ux;
-        u[offset + 1] = uy;
-        u[offset + 2] = uz;
+        vstore3((float3)(ux, uy, uz), n, u);
+
+        #if defined(USE_CONSTANT_FORCE) || defined(USE_FORCE_FIELD)
+        float Fx = 0.0f, Fy = 0.0f, Fz = 0.0f;
+        #ifdef USE_CONSTANT_FORCE
+        Fx += body_force.x;
+        Fy += body_force.y;
+        Fz += body_force.z;
+        #endif
+        #ifdef USE_FORCE_FIELD
+        float3 force_cell = vload3(n, force);
+        Fx += force_cell.x;
+        Fy += force_cell.y;
+        Fz += force_cell.z;
+        #endif
+        #endif
         
+        float omega_a = antisymmetric_omega(omega);
         for (int q = 0; q < Q; q++) {
             float cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
-            float feq = local_rho * w[q] * (FLOAT_ONE + FLOAT_THREE * cu + 
-                FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
-            float f_new_val = (1.0f - omega) * f_pop[q] + omega * feq;
+            float feq_e = local_rho * w[q] * (FLOAT_ONE + FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
+            float feq_o = local_rho * w[q] * FLOAT_THREE * cu;
+            float f_new_val = relax(f_pop[q], f_pop[opposite[q]], feq_e, feq_o, omega, omega_a);
             
-            #ifdef USE_CONSTANT_FORCE
+            #if defined(USE_CONSTANT_FORCE) || defined(USE_FORCE_FIELD)
             // Guo Force term
-            float cF = c[q][0] * FX + c[q][1] * FY + c[q][2] * FZ;
-            
-            // Proteção contra divisão por zero
-            float force_term = 0.0f;
-            if (local_rho > FLOAT_EPSILON) {
-                force_term = w[q] * (FLOAT_ONE - FLOAT_CONST(0.5) * omega) * (
-                    FLOAT_THREE * ((c[q][0] - ux) * FX + (c[q][1] - uy) * FY + (c[q][2] - uz) * FZ) +
-                    FLOAT_CONST(9.0) * cF * cu
-                ) / local_rho;
-            }
-            
-            f_new_val += force_term;
+            f_new_val += guo_force(q, ux, uy, uz, Fx, Fy, Fz, cu, local_rho, omega, omega_a);
             #endif
             
-            write_buf[q * N + n] = f_new_val;
+            write_buf[push_slot(q, n, x, y, z, flags, neighbors, timestep)] = f_new_val;
         }
     }
 }
 
 // ============================================================
-// FP16S - STORAGE MODE (FP16 storage, FP32 computation)
+// FP16S / BF16S - STORAGE MODE (16-bit storage, FP32 computation)
 // ============================================================
 #elif defined(USE_FP16S)
 __kernel void stream_collide_kernel(
-    __global half* f,         // FP16 distribution function (input/output, ping-pong)
-    __global half* f_new,     // FP16 output buffer (ping-pong)
+    __global STORAGE_TYPE* f,         // FP16 distribution function (input/output, ping-pong)
+    __global STORAGE_TYPE* f_new,     // FP16 output buffer (ping-pong)
     __global float* rho,      // Density array (output)
     __global float* u,        // Velocity array (output)
     __global uchar* flags,    // Flag array: FLUID, SOLID, EQ
     float omega,              // Relaxation parameter
-    int timestep              // Current time step
+    int timestep,             // Current time step
+    __global float* force,    // Per-cell force density (USE_FORCE_FIELD only)
+    float4 body_force,        // Uniform force density (USE_CONSTANT_FORCE only)
+    __global const int* neighbors // Source cell of each link (NEIGHBOR_TABLE only)
 ) {
     int n = get_global_id(0);
     if (n >= N) return;
     if (flags[n] == FLAG_SOLID) return;
 
     // Determine which buffer to read from and write to based on timestep
-    __global half* read_buf_fp16 = (timestep % 2 == 0) ? f : f_new;
-    __global half* write_buf_fp16 = (timestep % 2 == 0) ? f_new : f;
+    __global STORAGE_TYPE* read_buf_fp16 = (timestep % 2 == 0) ? f : f_new;
+    __global STORAGE_TYPE* write_buf_fp16 = (timestep % 2 == 0) ? f_new : f;
 
     int x = n % NX;
     int y = (n / NX) % NY;
@@ -146,17 +342,30 @@ __kernel void stream_collide_kernel(
         int dy = c[q][1];
         int dz = c[q][2];
 
-        int xp = (x - dx + NX) % NX;
-        int yp = (y - dy + NY) % NY;
-        int zp = (z - dz + NZ) % NZ;
-
-        int np = zp * (NX * NY) + yp * NX + xp;
+        int np = source_cell(q, n, x, y, z, neighbors);
+        int qs = q; // Source direction, differs from q at symmetry planes
+
+        #ifdef SYMMETRY_PLANES
+        // The image cell beyond a symmetry plane holds the boundary cell's
+        // populations with the normal component reversed (specular reflection)
+        int flip = symmetry_flip(x - dx, y - dy, z - dz);
+        if (flip) {
+            qs = reflected_direction(q, flip);
+            int xp = (flip & 1) ? x : (x - dx + NX) % NX;
+            int yp = (flip & 2) ? y : (y - dy + NY) % NY;
+            int zp = (flip & 4) ? z : (z - dz + NZ) % NZ;
+            np = zp * (NX * NY) + yp * NX + xp;
+        }
+        #endif
         uchar neighbor_flag = flags[np];
 
         if (neighbor_flag == FLAG_SOLID) {
-            f_pop[q] = vload_half(opposite[q] * N + n, read_buf_fp16);
+            f_pop[q] = load_ddf(bounce_slot(q, n, timestep), read_buf_fp16);
+            #ifdef USE_MOVING_WALLS
+            f_pop[q] += 6.0f * w[q] * wall_velocity_dot(q, np, u);
+            #endif
         } else {
-            f_pop[q] = vload_half(q * N + np, read_buf_fp16);
+            f_pop[q] = load_ddf(pull_slot(q, qs, n, np, timestep), read_buf_fp16);
         }
 
         // Accumulate for macroscopic variables
@@ -176,51 +385,53 @@ __kernel void stream_collide_kernel(
     // --- Collision ---
     if (flags[n] == FLAG_EQ) {
         // Use prescribed velocity and density from host
-        int offset = n * 3;
-        ux = u[offset + 0];
-        uy = u[offset + 1];
-        uz = u[offset + 2];
+        float3 u_eq = vload3(n, u);
+        ux = u_eq.x;
+        uy = u_eq.y;
+        uz = u_eq.z;
         local_rho = rho[n];
         u2 = ux * ux + uy * uy + uz * uz;
         
         for (int q = 0; q < Q; q++) {
             float cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
             float feq = local_rho * w[q] * (1.0f + 3.0f * cu + 4.5f * cu * cu - 1.5f * u2);
-            vstore_half(feq, q * N + n, write_buf_fp16);
+            store_ddf(feq, push_slot(q, n, x, y, z, flags, neighbors, timestep), write_buf_fp16);
         }
     } else {
-        // Standard BGK collision for fluid cells
+        // Collision for fluid cells (BGK, or TRT with COLLISION_TRT)
         rho[n] = local_rho;
         
         // Offset
-        int offset = n * 3;
-        u[offset + 0] = ux;
-        u[offset + 1] = uy;
-        u[offset + 2] = uz;
+        vstore3((float3)(ux, uy, uz), n, u);
+
+        #if defined(USE_CONSTANT_FORCE) || defined(USE_FORCE_FIELD)
+        float Fx = 0.0f, Fy = 0.0f, Fz = 0.0f;
+        #ifdef USE_CONSTANT_FORCE
+        Fx += body_force.x;
+        Fy += body_force.y;
+        Fz += body_force.z;
+        #endif
+        #ifdef USE_FORCE_FIELD
+        float3 force_cell = vload3(n, force);
+        Fx += force_cell.x;
+        Fy += force_cell.y;
+        Fz += force_cell.z;
+        #endif
+        #endif
         
+        float omega_a = antisymmetric_omega(omega);
         for (int q = 0; q < Q; q++) {
             float cu = c[q][0] * ux + c[q][1] * uy + c[q][2] * uz;
-            float feq = local_rho * w[q] * (FLOAT_ONE + FLOAT_THREE * cu + 
-                FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
-            float f_new_val = (1.0f - omega) * f_pop[q] + omega * feq;
+            float feq_e = local_rho * w[q] * (FLOAT_ONE + FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
+            float feq_o = local_rho * w[q] * FLOAT_THREE * cu;
+            float f_new_val = relax(f_pop[q], f_pop[opposite[q]], feq_e, feq_o, omega, omega_a);
             
-            #ifdef USE_CONSTANT_FORCE
+            #if defined(USE_CONSTANT_FORCE) || defined(USE_FORCE_FIELD)
             // Guo Force term
-            float cF = c[q][0] * FX + c[q][1] * FY + c[q][2] * FZ;
-            
-            // Proteção contra divisão por zero
-            float force_term = 0.0f;
-            if (local_rho > FLOAT_EPSILON) {
-                force_term = w[q] * (FLOAT_ONE - FLOAT_CONST(0.5) * omega) * (
-                    FLOAT_THREE * ((c[q][0] - ux) * FX + (c[q][1] - uy) * FY + (c[q][2] - uz) * FZ) +
-                    FLOAT_CONST(9.0) * cF * cu
-                ) / local_rho;
-            }
-            
-            f_new_val += force_term;
+            f_new_val += guo_force(q, ux, uy, uz, Fx, Fy, Fz, cu, local_rho, omega, omega_a);
             #endif
             
-            vstore_half(f_new_val, q * N + n, write_buf_fp16);
+            store_ddf(f_new_val, push_slot(q, n, x, y, z, flags, neighbors, timestep), write_buf_fp16);
         }
     }
 }
@@ -236,7 +447,10 @@ __kernel void stream_collide_kernel(
     __global float* u,        // Velocity array (output) - keep in FP32
     __global uchar* flags,    // Flag array: FLUID, SOLID, EQ
     float omega,              // Relaxation parameter
-    int timestep              // Current time step
+    int timestep,             // Current time step
+    __global float* force,    // Per-cell force density (USE_FORCE_FIELD only)
+    float4 body_force,        // Uniform force density (USE_CONSTANT_FORCE only)
+    __global const int* neighbors // Source cell of each link (NEIGHBOR_TABLE only)
 ) {
     int n = get_global_id(0);
     if (n >= N) return;
@@ -252,7 +466,7 @@ __kernel void stream_collide_kernel(
     
     half omega_h = (half)omega;
 
-    // Accumulate macroscopic variables in float for higher accuracy
+    // Shifted DDFs (see ddf_encode); moments are accumulated in float
     half f_pop[Q];
     float local_rho = 0.0f;
     float ux = 0.0f, uy = 0.0f, uz = 0.0f;
@@ -263,21 +477,34 @@ __kernel void stream_collide_kernel(
         int dy = c[q][1];
         int dz = c[q][2];
 
-        int xp = (x - dx + NX) % NX;
-        int yp = (y - dy + NY) % NY;
-        int zp = (z - dz + NZ) % NZ;
-
-        int np = zp * (NX * NY) + yp * NX + xp;
+        int np = source_cell(q, n, x, y, z, neighbors);
+        int qs = q; // Source direction, differs from q at symmetry planes
+
+        #ifdef SYMMETRY_PLANES
+        // The image cell beyond a symmetry plane holds the boundary cell's
+        // populations with the normal component reversed (specular reflection)
+        int flip = symmetry_flip(x - dx, y - dy, z - dz);
+        if (flip) {
+            qs = reflected_direction(q, flip);
+            int xp = (flip & 1) ? x : (x - dx + NX) % NX;
+            int yp = (flip & 2) ? y : (y - dy + NY) % NY;
+            int zp = (flip & 4) ? z : (z - dz + NZ) % NZ;
+            np = zp * (NX * NY) + yp * NX + xp;
+        }
+        #endif
         uchar neighbor_flag = flags[np];
 
         if (neighbor_flag == FLAG_SOLID) {
-            f_pop[q] = read_buf[opposite[q] * N + n];
+            f_pop[q] = read_buf[bounce_slot(q, n, timestep)];
+            #ifdef USE_MOVING_WALLS
+            f_pop[q] += (half)(6.0f * wall_velocity_dot(q, np, u)); // 6 w (c.u) / w
+            #endif
         } else {
-            f_pop[q] = read_buf[q * N + np];
+            f_pop[q] = read_buf[pull_slot(q, qs, n, np, timestep)];
         }
 
         // Accumulate for macroscopic variables (in float)
-        float f_pop_f = (float)f_pop[q];
+        float f_pop_f = ddf_decode(q, f_pop[q]);
         local_rho += f_pop_f;
         ux += (float)c[q][0] * f_pop_f;
         uy += (float)c[q][1] * f_pop_f;
@@ -294,45 +521,60 @@ __kernel void stream_collide_kernel(
     // --- Collision ---
     if (flags[n] == FLAG_EQ) {
         // Use prescribed velocity and density from host (convert to float for computation)
-        int offset = n * 3;
-        ux = u[offset + 0];
-        uy = u[offset + 1];
-        uz = u[offset + 2];
+        float3 u_eq = vload3(n, u);
+        ux = u_eq.x;
+        uy = u_eq.y;
+        uz = u_eq.z;
         local_rho = rho[n];
         u2 = ux * ux + uy * uy + uz * uz;
         for (int q = 0; q < Q; q++) {
             float cu = (float)c[q][0] * ux + (float)c[q][1] * uy + (float)c[q][2] * uz;
             float feq = local_rho * w[q] * (FLOAT_ONE + FLOAT_THREE * cu + FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
-            write_buf[q * N + n] = (half)feq;
+            write_buf[push_slot(q, n, x, y, z, flags, neighbors, timestep)] = ddf_encode(q, feq);
         }
     } else {
-        // Standard BGK collision for fluid cells
+        // Collision for fluid cells (BGK, or TRT with COLLISION_TRT)
         rho[n] = local_rho;  // Output as float
-        int offset = n * 3;
-        u[offset + 0] = ux;
-        u[offset + 1] = uy;
-        u[offset + 2] = uz;
+        vstore3((float3)(ux, uy, uz), n, u);
+
+        #if defined(USE_CONSTANT_FORCE) || defined(USE_FORCE_FIELD)
+        float Fx = 0.0f, Fy = 0.0f, Fz = 0.0f;
+        #ifdef USE_CONSTANT_FORCE
+        Fx += body_force.x;
+        Fy += body_force.y;
+        Fz += body_force.z;
+        #endif
+        #ifdef USE_FORCE_FIELD
+        float3 force_cell = vload3(n, force);
+        Fx += force_cell.x;
+        Fy += force_cell.y;
+        Fz += force_cell.z;
+        #endif
+        #endif
+        float omega_a = antisymmetric_omega((float)omega_h);
         for (int q = 0; q < Q; q++) {
             float cu = (float)c[q][0] * ux + (float)c[q][1] * uy + (float)c[q][2] * uz;
+            #ifdef COLLISION_TRT
+            // TRT mixes q and opposite[q], so decode both, relax in float and
+            // re-encode
+            float feq_e = local_rho * w[q] * (FLOAT_ONE + FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
+            float feq_o = local_rho * w[q] * FLOAT_THREE * cu;
+            half h_new = ddf_encode(q, relax(ddf_decode(q, f_pop[q]), ddf_decode(opposite[q], f_pop[opposite[q]]),
+                                             feq_e, feq_o, (float)omega_h, omega_a));
+            #else
             float feq = local_rho * w[q] * (FLOAT_ONE + FLOAT_THREE * cu + FLOAT_FOUR_POINT_FIVE * cu * cu - FLOAT_ONE_POINT_FIVE * u2);
-            float f_new_val = (1.0f - omega) * (float)f_pop[q] + omega * feq;
+            // The shift is affine, so BGK relaxes the shifted DDFs in half
+            // precision exactly as it would the DDFs themselves
+            half h_new = f_pop[q] + omega_h * (ddf_encode(q, feq) - f_pop[q]);
+            #endif
             
-            #ifdef USE_CONSTANT_FORCE
+            #if defined(USE_CONSTANT_FORCE) || defined(USE_FORCE_FIELD)
             // Guo Force term
-            float cF = c[q][0] * FX + c[q][1] * FY + c[q][2] * FZ;  // c é int, conversão automática
-            
-            float force_term = 0.0f;
-            if (local_rho > FLOAT_EPSILON) {
-                force_term = (float)w[q] * (FLOAT_ONE - FLOAT_CONST(0.5) * omega_h) * (  // Usar (float)w[q] e omega_h
-                    FLOAT_THREE * ((c[q][0] - ux) * FX + (c[q][1] - uy) * FY + (c[q][2] - uz) * FZ) +
-                    FLOAT_CONST(9.0) * cF * cu
-                ) / local_rho;
-            }
-            
-            f_new_val += force_term;
+            float force_term = guo_force(q, ux, uy, uz, Fx, Fy, Fz, cu, local_rho, (float)omega_h, omega_a);
+            h_new += (half)(force_term / w[q]);
             #endif
             
-            write_buf[q * N + n] = (half)f_new_val;
+            write_buf[push_slot(q, n, x, y, z, flags, neighbors, timestep)] = h_new;
         }
     }
 }
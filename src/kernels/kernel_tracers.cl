@@ -0,0 +1,117 @@
+// ============================================================
+// TRACERS - passive and inertial Lagrangian particles
+// ============================================================
+// Positions are in lattice units (cell centers at integer coordinates) and
+// stored as x, y, z triplets. The domain is periodic except along the axes
+// set in `open_faces`; solid cells contribute zero velocity to the
+// interpolation. A particle leaving through an open face or entering a
+// FLAG_EQ cell is marked exited (status -1); one entering a solid cell is
+// deposited there (status cell + 1). Inactive particles are not moved.
+// Particles with a positive diameter are inertial point particles (one-way
+// coupled): dv/dt = f (u - v) / tau_p + (1 - 1 / rho_p) g with the Stokes
+// response time tau_p = rho_p d^2 / (18 nu) and drag correction f = 1
+// (Stokes) or 1 + 0.15 Re_p^0.687 (Schiller-Naumann).
+
+inline float3 tracer_velocity(__global float* u, __global uchar* flags, float3 p) {
+    float3 base = floor(p);
+    float3 s = p - base;
+    int x0 = (int)base.x;
+    int y0 = (int)base.y;
+    int z0 = (int)base.z;
+    float3 v = (float3)(0.0f, 0.0f, 0.0f);
+    for (int k = 0; k < 8; k++) {
+        int dx = k & 1;
+        int dy = (k >> 1) & 1;
+        int dz = (k >> 2) & 1;
+        int x = ((x0 + dx) % NX + NX) % NX;
+        int y = ((y0 + dy) % NY + NY) % NY;
+        int z = ((z0 + dz) % NZ + NZ) % NZ;
+        int n = z * (NX * NY) + y * NX + x;
+        if (flags[n] == FLAG_SOLID) continue;
+        float weight = (dx ? s.x : 1.0f - s.x) * (dy ? s.y : 1.0f - s.y) * (dz ? s.z : 1.0f - s.z);
+        v += weight * (float3)(u[n * 3 + 0], u[n * 3 + 1], u[n * 3 + 2]);
+    }
+    return v;
+}
+
+inline float3 tracer_wrap(float3 p) {
+    float3 size = (float3)((float)NX, (float)NY, (float)NZ);
+    return p - size * floor(p / size);
+}
+
+// Midpoint (RK2) advection of passive tracers, exponential integration of
+// the particle momentum equation for inertial particles, over one time step
+__kernel void advect_tracers_kernel(
+    __global float* u,          // Velocity array
+    __global uchar* flags,      // Flag array
+    __global float* positions,  // Tracer positions (3 per tracer)
+    __global float* velocities, // Tracer velocities (3 per tracer)
+    __global float* travel,     // Unwrapped displacement since release (3 per tracer)
+    __global float* properties, // Diameter and relative density (2 per tracer)
+    int count,                  // Number of tracers
+    float nu,                   // Kinematic viscosity
+    float gx, float gy, float gz,
+    int drag_model,             // 0: Stokes, 1: Schiller-Naumann
+    __global int* status,       // 0 active, -1 exited, cell + 1 deposited
+    int open_faces,             // Bit d: particles leave through faces normal to axis d
+    volatile __global int* removed // Number of particles deactivated so far
+) {
+    int i = get_global_id(0);
+    if (i >= count || status[i] != 0) return;
+    float3 p = (float3)(positions[i * 3 + 0], positions[i * 3 + 1], positions[i * 3 + 2]);
+    float3 v;
+    float diameter = properties[i * 2 + 0];
+    if (diameter <= 0.0f) {
+        float3 k1 = tracer_velocity(u, flags, p);
+        v = tracer_velocity(u, flags, tracer_wrap(p + 0.5f * k1));
+        p = p + v;
+        travel[i * 3 + 0] += v.x;
+        travel[i * 3 + 1] += v.y;
+        travel[i * 3 + 2] += v.z;
+    } else {
+        float density = properties[i * 2 + 1];
+        float3 v0 = (float3)(velocities[i * 3 + 0], velocities[i * 3 + 1], velocities[i * 3 + 2]);
+        float3 uf = tracer_velocity(u, flags, p);
+        float tau = density * diameter * diameter / (18.0f * nu);
+        float f = 1.0f;
+        if (drag_model == 1) {
+            float re = length(uf - v0) * diameter / nu;
+            f += 0.15f * pow(re, 0.687f);
+        }
+        float3 g = (1.0f - 1.0f / density) * (float3)(gx, gy, gz);
+        float decay = exp(-f / tau);
+        // Terminal velocity relative to the fluid is g tau / f
+        float3 drift = g * tau / f;
+        v = uf + drift + (v0 - uf - drift) * decay;
+        float3 step = 0.5f * (v0 + v);
+        p = p + step;
+        travel[i * 3 + 0] += step.x;
+        travel[i * 3 + 1] += step.y;
+        travel[i * 3 + 2] += step.z;
+    }
+
+    float3 size = (float3)((float)NX, (float)NY, (float)NZ);
+    bool outside_x = (open_faces & 1) && (p.x < -0.5f || p.x >= size.x - 0.5f);
+    bool outside_y = (open_faces & 2) && (p.y < -0.5f || p.y >= size.y - 0.5f);
+    bool outside_z = (open_faces & 4) && (p.z < -0.5f || p.z >= size.z - 0.5f);
+    if (outside_x || outside_y || outside_z) {
+        status[i] = -1;
+        atomic_inc(removed);
+    } else {
+        p = tracer_wrap(p);
+        int x = ((int)round(p.x)) % NX;
+        int y = ((int)round(p.y)) % NY;
+        int z = ((int)round(p.z)) % NZ;
+        int n = z * (NX * NY) + y * NX + x;
+        if (flags[n] == FLAG_SOLID || flags[n] == FLAG_EQ) {
+            status[i] = (flags[n] == FLAG_SOLID) ? n + 1 : -1;
+            atomic_inc(removed);
+        }
+    }
+    positions[i * 3 + 0] = p.x;
+    positions[i * 3 + 1] = p.y;
+    positions[i * 3 + 2] = p.z;
+    velocities[i * 3 + 0] = v.x;
+    velocities[i * 3 + 1] = v.y;
+    velocities[i * 3 + 2] = v.z;
+}
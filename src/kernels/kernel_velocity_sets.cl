@@ -8,6 +8,27 @@
     #define FLOAT_CONST(x) x##f  // Example: FLOAT_CONST(1.0) becomes 1.0f
 #endif
 
+// 16-bit DDF storage with float arithmetic: IEEE half for FP16S, bfloat16
+// (the upper half of a float, kept as ushort) for BF16S, which trades
+// mantissa bits for the full float exponent range
+#if defined(USE_BF16S)
+inline float load_ddf(uint i, const __global ushort* p) {
+    return as_float((uint)p[i] << 16);
+}
+
+inline void store_ddf(float value, uint i, __global ushort* p) {
+    uint bits = as_uint(value);
+    // Round to nearest even; NaN keeps a mantissa bit so it stays NaN
+    bits = isnan(value) ? (bits | 0x00400000u) : bits + 0x7fffu + ((bits >> 16) & 1u);
+    p[i] = (ushort)(bits >> 16);
+}
+#elif defined(USE_FP16S)
+#define load_ddf(i, p) vload_half(i, p)
+#define store_ddf(value, i, p) vstore_half(value, i, p)
+#endif
+
+#define WEIGHT_CONST(x) x##f  // Weight literals, float in every mode
+
 // Velocity vectors (same for all modes)
 constant int c[Q][3] = {
 #if defined(D2Q9)
@@ -52,56 +73,69 @@ constant int opposite[Q] = {
 #endif
 };
 
-// Weights with the appropriate type automatically
-constant FLOAT_TYPE w[Q] = {
+// Weights, in float in every mode: half weights do not sum to one
+constant float w[Q] = {
 #if defined(D2Q9)
-    FLOAT_CONST(4.0)/FLOAT_CONST(9.0), FLOAT_CONST(1.0)/FLOAT_CONST(9.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(9.0), FLOAT_CONST(1.0)/FLOAT_CONST(9.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(9.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0)
+    WEIGHT_CONST(4.0)/WEIGHT_CONST(9.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0)
 #elif defined(D3Q7)
-    FLOAT_CONST(1.0)/FLOAT_CONST(4.0), FLOAT_CONST(1.0)/FLOAT_CONST(8.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(8.0), FLOAT_CONST(1.0)/FLOAT_CONST(8.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(8.0), FLOAT_CONST(1.0)/FLOAT_CONST(8.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(8.0)
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(4.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(8.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(8.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(8.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(8.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(8.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(8.0)
 #elif defined(D3Q15)
-    FLOAT_CONST(2.0)/FLOAT_CONST(9.0), FLOAT_CONST(1.0)/FLOAT_CONST(9.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(9.0), FLOAT_CONST(1.0)/FLOAT_CONST(9.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(9.0), FLOAT_CONST(1.0)/FLOAT_CONST(9.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(9.0), FLOAT_CONST(1.0)/FLOAT_CONST(72.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(72.0), FLOAT_CONST(1.0)/FLOAT_CONST(72.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(72.0), FLOAT_CONST(1.0)/FLOAT_CONST(72.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(72.0), FLOAT_CONST(1.0)/FLOAT_CONST(72.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(72.0)
+    WEIGHT_CONST(2.0)/WEIGHT_CONST(9.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(9.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(72.0)
 #elif defined(D3Q19)
-    FLOAT_CONST(1.0)/FLOAT_CONST(3.0), FLOAT_CONST(1.0)/FLOAT_CONST(18.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(18.0), FLOAT_CONST(1.0)/FLOAT_CONST(18.0), 
-    FLOAT_CONST(1.0)/FLOAT_CONST(18.0), FLOAT_CONST(1.0)/FLOAT_CONST(18.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(18.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0), FLOAT_CONST(1.0)/FLOAT_CONST(36.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(36.0)
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(3.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(18.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(18.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(18.0), 
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(18.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(18.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(18.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(36.0)
 #elif defined(D3Q27)
-    FLOAT_CONST(8.0)/FLOAT_CONST(27.0), FLOAT_CONST(2.0)/FLOAT_CONST(27.0), 
-    FLOAT_CONST(2.0)/FLOAT_CONST(27.0), FLOAT_CONST(2.0)/FLOAT_CONST(27.0), 
-    FLOAT_CONST(2.0)/FLOAT_CONST(27.0), FLOAT_CONST(2.0)/FLOAT_CONST(27.0),
-    FLOAT_CONST(2.0)/FLOAT_CONST(27.0), FLOAT_CONST(1.0)/FLOAT_CONST(54.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(54.0), FLOAT_CONST(1.0)/FLOAT_CONST(54.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(54.0), FLOAT_CONST(1.0)/FLOAT_CONST(54.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(54.0), FLOAT_CONST(1.0)/FLOAT_CONST(54.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(54.0), FLOAT_CONST(1.0)/FLOAT_CONST(54.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(54.0), FLOAT_CONST(1.0)/FLOAT_CONST(54.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(216.0), FLOAT_CONST(1.0)/FLOAT_CONST(216.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(216.0), FLOAT_CONST(1.0)/FLOAT_CONST(216.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(216.0), FLOAT_CONST(1.0)/FLOAT_CONST(216.0),
-    FLOAT_CONST(1.0)/FLOAT_CONST(216.0), FLOAT_CONST(1.0)/FLOAT_CONST(216.0)
+    WEIGHT_CONST(8.0)/WEIGHT_CONST(27.0), WEIGHT_CONST(2.0)/WEIGHT_CONST(27.0), 
+    WEIGHT_CONST(2.0)/WEIGHT_CONST(27.0), WEIGHT_CONST(2.0)/WEIGHT_CONST(27.0), 
+    WEIGHT_CONST(2.0)/WEIGHT_CONST(27.0), WEIGHT_CONST(2.0)/WEIGHT_CONST(27.0),
+    WEIGHT_CONST(2.0)/WEIGHT_CONST(27.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(54.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0),
+    WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0), WEIGHT_CONST(1.0)/WEIGHT_CONST(216.0)
 #endif
 };
 
+#ifdef USE_FP16C
+// FP16C stores each DDF shifted by its rest value and scaled by its weight,
+// h = f / w[q] - 1, so the half mantissa resolves the deviation from rest
+// instead of the weight itself, and small deviations stay clear of subnormals
+inline half ddf_encode(int q, float f) {
+    return (half)(f / w[q] - 1.0f);
+}
+
+inline float ddf_decode(int q, half h) {
+    return w[q] * (1.0f + (float)h);
+}
+#endif
+
 // Additional pre-defined constants for efficient calculation
 constant FLOAT_TYPE FLOAT_ONE = FLOAT_CONST(1.0);
 constant FLOAT_TYPE FLOAT_THREE = FLOAT_CONST(3.0);
@@ -0,0 +1,17 @@
+// src/lib.rs
+
+// CappuSim: a lattice Boltzmann CFD solver on OpenCL. The main types are
+// re-exported at the crate root; everything else lives under `solver`.
+// See examples/ for complete setups (`cargo run --release --example <name>`).
+
+pub mod solver;
+pub mod utils;
+
+pub use solver::config::SimulationConfig;
+pub use solver::diagnostics::VelocityField;
+pub use solver::flags::CellType;
+pub use solver::lbm::LBM;
+pub use solver::precision::PrecisionMode;
+pub use solver::presets::Preset;
+pub use solver::velocity_sets::VelocitySet;
+pub use utils::terminal_utils::Verbosity;
@@ -1,40 +1,82 @@
 // src/main.rs
 
-#![allow(dead_code)]
-#![allow(unused_imports)]
-
 // Import
-mod solver;
-mod utils;
-mod examples;
-use solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
-use solver::lbm::LBM;
-use solver::benchmark;
-use solver::precision::PrecisionMode;
-use examples::{poiseuille, von_karman, taylor_green, liddriven_cavity, airfoil};
-
-use crate::examples::poiseuille::poiseuille_2d_example;
-use crate::examples::von_karman::von_karman_vortex_2d_example;
-use crate::examples::taylor_green::taylor_green_2d_example;
-use crate::examples::liddriven_cavity::{liddriven_cavity_2d_example, liddriven_cavity_3d_example};
-use crate::examples::airfoil::{airfoil_2d_example, airfoil_3d_example};
-use crate::examples::couette::{couette_2d_example, couette_3d_example};
+use cappusim::utils;
+use cappusim::utils::terminal_utils::Verbosity;
+use cappusim::LBM;
 
 // =============================================================================
 // Comprehensive Benchmark Suite
 fn main() {
-    // To run an example, uncomment the corresponding function call below:
-    // or set your own setup. Check /examples for inspiration.
+    // Subcommands: `cappusim post <run_dir>` post-processes stored outputs
+    let mut args: Vec<String> = std::env::args().collect();
+    // `--quiet` keeps only errors, `--verbose` adds details; both may appear anywhere
+    if args.iter().any(|a| a == "--quiet" || a == "-q") {
+        utils::terminal_utils::set_verbosity(Verbosity::Silent);
+    } else if args.iter().any(|a| a == "--verbose" || a == "-v") {
+        utils::terminal_utils::set_verbosity(Verbosity::Verbose);
+    }
+    args.retain(|a| !matches!(a.as_str(), "--quiet" | "-q" | "--verbose" | "-v"));
+    if args.len() > 1 && args[1] == "post" {
+        let Some(run_dir) = args.get(2) else {
+            utils::terminal_utils::print_error("Usage: cappusim post <run_dir>");
+            std::process::exit(1);
+        };
+        if let Err(err) = LBM::post_process(run_dir) {
+            utils::terminal_utils::print_error(&format!("Error: {}", err));
+            std::process::exit(1);
+        }
+        return;
+    }
+    // `cappusim run <case.toml>` runs a case file, `cappusim run --preset <name>
+    // [--nx N --re R ...]` a built-in scenario with overridden parameters
+    if args.len() > 1 && args[1] == "run" {
+        let usage = "Usage: cappusim run <case.toml> | cappusim run --preset <name> [options]";
+        let Some(path) = args.get(2) else {
+            utils::terminal_utils::print_error(usage);
+            std::process::exit(1);
+        };
+        let result = if path == "--preset" {
+            match args.get(3) {
+                Some(name) => LBM::run_preset(name, &args[4..]),
+                None => Err(usage.into()),
+            }
+        } else {
+            LBM::run_config_file(path)
+        };
+        if let Err(err) = result {
+            utils::terminal_utils::print_error(&format!("Error: {}", err));
+            std::process::exit(1);
+        }
+        return;
+    }
+    // `cappusim compare <baseline> [current]` compares benchmark results files,
+    // or runs the suite against the baseline when no current file is given
+    if args.len() > 1 && args[1] == "compare" {
+        let Some(baseline) = args.get(2) else {
+            utils::terminal_utils::print_error("Usage: cappusim compare <baseline> [current]");
+            std::process::exit(1);
+        };
+        match args.get(3) {
+            Some(current) => {
+                if let Err(err) = LBM::compare_benchmark_files(baseline, current) {
+                    utils::terminal_utils::print_error(&format!("Error: {}", err));
+                    std::process::exit(1);
+                }
+            }
+            None => LBM::benchmark_compare(baseline),
+        }
+        return;
+    }
 
-    LBM::benchmark();
+    // `cappusim benchmark --all-devices` runs the suite on every OpenCL device
+    if args.len() > 2 && args[1] == "benchmark" && args[2] == "--all-devices" {
+        LBM::benchmark_all_devices();
+        return;
+    }
 
-    // airfoil_2d_example();
-    // airfoil_3d_example();
-    // couette_2d_example();
-    // couette_3d_example();
-    // liddriven_cavity_2d_example();
-    // liddriven_cavity_3d_example();
-    // poiseuille_2d_example();
-    // von_karman_vortex_2d_example
+    // Simulations are set up with the library; see examples/ and run one
+    // with `cargo run --release --example <name>`.
 
+    LBM::benchmark();
 }
@@ -1,3 +0,0 @@
-pub mod solver;
-pub mod utils;
-pub mod examples;
\ No newline at end of file
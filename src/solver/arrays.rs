@@ -0,0 +1,44 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// ndarray views of the host copies of the fields, valid after read_from_gpu().
+// Arrays are indexed [z, y, x] (and [z, y, x, c] for velocity), matching the
+// memory layout, so views borrow the Vecs without copying. Only built with the
+// "ndarray" feature.
+
+use super::lbm::LBM;
+
+use ndarray::{ArrayView3, ArrayView4, ArrayViewMut3, ArrayViewMut4};
+
+impl LBM {
+    fn shape3(&self) -> (usize, usize, usize) {
+        (self.Nz, self.Ny, self.Nx)
+    }
+
+    fn shape4(&self) -> (usize, usize, usize, usize) {
+        (self.Nz, self.Ny, self.Nx, 3)
+    }
+
+    pub fn density_view(&self) -> ArrayView3<'_, f32> {
+        ArrayView3::from_shape(self.shape3(), &self.density).expect("Density has incorrect length")
+    }
+
+    pub fn density_view_mut(&mut self) -> ArrayViewMut3<'_, f32> {
+        let shape = self.shape3();
+        ArrayViewMut3::from_shape(shape, &mut self.density).expect("Density has incorrect length")
+    }
+
+    pub fn velocity_view(&self) -> ArrayView4<'_, f32> {
+        ArrayView4::from_shape(self.shape4(), &self.u).expect("Velocity has incorrect length")
+    }
+
+    // Changes reach the device with write_u_to_gpu()
+    pub fn velocity_view_mut(&mut self) -> ArrayViewMut4<'_, f32> {
+        let shape = self.shape4();
+        ArrayViewMut4::from_shape(shape, &mut self.u).expect("Velocity has incorrect length")
+    }
+
+    pub fn flags_view(&self) -> ArrayView3<'_, u8> {
+        ArrayView3::from_shape(self.shape3(), &self.flags).expect("Flags have incorrect length")
+    }
+}
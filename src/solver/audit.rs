@@ -0,0 +1,153 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Precision audit: runs the current setup for a number of steps in FP32 and
+// in a reduced precision mode on the selected device and reports how far the
+// density and velocity fields drift apart, to judge the accuracy cost of an
+// FP16 mode before a long run.
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::parameters::TIMESTEP_ARG;
+use crate::solver::precision::PrecisionMode;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FieldError {
+    pub l2: f32,   // L2 norm of the difference relative to the FP32 field
+    pub linf: f32, // Largest absolute difference of a cell
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct PrecisionAudit {
+    pub precision: PrecisionMode,
+    pub time_steps: usize,
+    pub density: FieldError,
+    pub velocity: FieldError, // Of the velocity vector per cell
+}
+
+impl LBM {
+    // Compare `time_steps` steps of FP32 and this run's precision before run()
+    // starts (0 = off); ignored for FP32 runs
+    pub fn set_precision_audit(&mut self, time_steps: usize) {
+        self.precision_audit = time_steps;
+    }
+
+    // Fresh lattice with this setup's host fields in another precision
+    fn audit_lattice(&self, precision: PrecisionMode) -> LBM {
+        let mut lattice = LBM::new_silent(
+            self.Nx,
+            self.Ny,
+            self.Nz,
+            self.model,
+            self.viscosity,
+            precision,
+        );
+        lattice.flags = self.flags.clone();
+        lattice.density = self.density.clone();
+        lattice.u = self.u.clone();
+        if let Some(force) = &self.constant_force {
+            lattice.set_constant_force(force.clone());
+        }
+        lattice.use_force_field = self.use_force_field;
+        lattice.force_field = self.force_field.clone();
+        lattice.use_moving_walls = self.use_moving_walls;
+        lattice.symmetry_planes = self.symmetry_planes;
+        lattice.in_place_streaming = self.in_place_streaming;
+        lattice.neighbor_indexing = self.neighbor_indexing;
+        lattice.collision = self.collision;
+        lattice.assigned_device = self.assigned_device;
+        lattice
+    }
+
+    // Initialize in equilibrium, run `time_steps` and read rho and u back
+    fn advance_audit_lattice(&mut self, time_steps: usize) -> Result<(), Box<dyn Error>> {
+        self.initialize();
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        unsafe {
+            self.equilibrium_kernel
+                .as_ref()
+                .ok_or("equilibrium_kernel not initialized")?
+                .enq()?;
+            let kernel = self
+                .stream_collide_kernel
+                .as_ref()
+                .ok_or("stream_collide_kernel not initialized")?;
+            for t in 0..time_steps {
+                kernel.set_arg(TIMESTEP_ARG, t as i32)?;
+                kernel.enq()?;
+            }
+        }
+        queue.finish()?;
+        self.time_step = time_steps;
+        self.read_from_gpu()?;
+        Ok(())
+    }
+
+    // Run this setup in FP32 and in `precision` for `time_steps` steps and
+    // compare density and velocity over the non-solid cells
+    pub fn audit_precision(
+        &self,
+        precision: PrecisionMode,
+        time_steps: usize,
+    ) -> Result<PrecisionAudit, Box<dyn Error>> {
+        let mut reference = self.audit_lattice(PrecisionMode::FP32);
+        reference.advance_audit_lattice(time_steps)?;
+        let mut reduced = self.audit_lattice(precision);
+        reduced.advance_audit_lattice(time_steps)?;
+
+        let (mut rho_error, mut rho_norm) = (0.0f64, 0.0f64);
+        let (mut u_error, mut u_norm) = (0.0f64, 0.0f64);
+        let mut density = FieldError::default();
+        let mut velocity = FieldError::default();
+        for n in (0..self.N).filter(|&n| self.flags[n] != FLAG_SOLID) {
+            let d = reduced.density[n] - reference.density[n];
+            rho_error += (d * d) as f64;
+            rho_norm += (reference.density[n] * reference.density[n]) as f64;
+            density.linf = density.linf.max(d.abs());
+
+            let mut du2 = 0.0f32;
+            for i in 0..3 {
+                let du = reduced.u[n * 3 + i] - reference.u[n * 3 + i];
+                du2 += du * du;
+                u_norm += (reference.u[n * 3 + i] * reference.u[n * 3 + i]) as f64;
+            }
+            u_error += du2 as f64;
+            // NaN propagates so a diverged run cannot look accurate
+            velocity.linf = if du2.is_nan() {
+                f32::NAN
+            } else {
+                velocity.linf.max(du2.sqrt())
+            };
+        }
+        let relative = |error: f64, norm: f64| {
+            if norm > 0.0 {
+                (error / norm).sqrt() as f32
+            } else {
+                error.sqrt() as f32
+            }
+        };
+        density.l2 = relative(rho_error, rho_norm);
+        velocity.l2 = relative(u_error, u_norm);
+        Ok(PrecisionAudit {
+            precision: reduced.precision_mode, // After a possible FP16C fallback
+            time_steps,
+            density,
+            velocity,
+        })
+    }
+
+    pub fn print_precision_audit(report: &PrecisionAudit) {
+        terminal_utils::print_log(&format!(
+            "Precision audit ({:?} vs FP32, {} steps): density L2 {:.3e}, L∞ {:.3e}; velocity L2 {:.3e}, L∞ {:.3e}",
+            report.precision,
+            report.time_steps,
+            report.density.l2,
+            report.density.linf,
+            report.velocity.l2,
+            report.velocity.linf
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Time-averaged fields for turbulent statistics. After a warm-up the device
+// keeps running means of rho, u and the products u_i u_j; the host derives
+// the mean fields and Reynolds stresses from them at output time.
+
+use super::lbm::LBM;
+
+use ocl::{flags::MEM_READ_WRITE, Buffer, Kernel};
+use std::error::Error;
+
+// Running means per cell: rho, ux, uy, uz, xx, yy, zz, xy, xz, yz
+pub const AVERAGE_FIELDS: usize = 10;
+
+impl LBM {
+    // Start averaging after `warmup` steps, sampling every `every` steps
+    pub fn enable_time_averages(&mut self, warmup: usize, every: usize) {
+        self.averages_start = Some(warmup);
+        self.averages_every = every.max(1);
+    }
+
+    // Whether the averages take a sample at the current step
+    pub fn averages_due(&self) -> bool {
+        match self.averages_start {
+            Some(warmup) => {
+                self.time_step >= warmup && (self.time_step - warmup) % self.averages_every == 0
+            }
+            None => false,
+        }
+    }
+
+    // Fold the current density and velocity into the running means
+    pub fn accumulate_averages(&mut self) -> Result<(), Box<dyn Error>> {
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        if self.averages_buffer.is_none() {
+            let buffer = Buffer::<f32>::builder()
+                .queue(queue.clone())
+                .flags(MEM_READ_WRITE)
+                .len(self.N * AVERAGE_FIELDS)
+                .fill_val(0.0f32)
+                .build()
+                .map_err(|e| format!("Failed to build 'averages' buffer: {}", e))?;
+            self.averages_buffer = Some(buffer);
+            self.averages_samples = 0;
+        }
+        self.averages_samples += 1;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("accumulate_averages_kernel")
+            .queue(queue.clone())
+            .global_work_size(self.N)
+            .arg(self.density_buffer.as_ref().ok_or("Density buffer is None")?)
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(self.averages_buffer.as_ref().ok_or("Averages buffer is None")?)
+            .arg(1.0f32 / self.averages_samples as f32)
+            .build()
+            .map_err(|e| format!("Failed to build 'accumulate_averages_kernel': {}", e))?;
+        unsafe {
+            kernel
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'accumulate_averages_kernel': {}", e))?;
+        }
+        self.enqueue_barrier()
+    }
+
+    // Copy the running means to the host (empty until the first sample)
+    pub fn read_averages_from_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(buffer) = self.averages_buffer.as_ref() else {
+            return Ok(());
+        };
+        let mut means = vec![0.0f32; self.N * AVERAGE_FIELDS];
+        buffer
+            .read(&mut means)
+            .enq()
+            .map_err(|e| format!("Failed to read 'averages' buffer: {}", e))?;
+        self.averages = means;
+        Ok(())
+    }
+
+    pub fn has_averages(&self) -> bool {
+        self.averages.len() == self.N * AVERAGE_FIELDS
+    }
+
+    // Time-averaged density; the mean pressure is cs^2 times this
+    pub fn mean_density(&self) -> Vec<f32> {
+        self.averages
+            .chunks_exact(AVERAGE_FIELDS)
+            .map(|m| m[0])
+            .collect()
+    }
+
+    // Time-averaged velocity, 3 components per cell
+    pub fn mean_velocity(&self) -> Vec<f32> {
+        self.averages
+            .chunks_exact(AVERAGE_FIELDS)
+            .flat_map(|m| [m[1], m[2], m[3]])
+            .collect()
+    }
+
+    // Reynolds stresses <u_i' u_j'> = <u_i u_j> - <u_i><u_j> per cell as
+    // [xx, yy, zz, xy, xz, yz]
+    pub fn reynolds_stress(&self) -> Vec<[f32; 6]> {
+        self.averages
+            .chunks_exact(AVERAGE_FIELDS)
+            .map(|m| {
+                let (u, v, w) = (m[1], m[2], m[3]);
+                [
+                    m[4] - u * u,
+                    m[5] - v * v,
+                    m[6] - w * w,
+                    m[7] - u * v,
+                    m[8] - u * w,
+                    m[9] - v * w,
+                ]
+            })
+            .collect()
+    }
+}
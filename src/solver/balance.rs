@@ -0,0 +1,200 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Load balancing of MultiLBM across unequal devices. Before the run every
+// device times a few steps of a small slab, and the layers are split in
+// proportion to the measured MLUps. During the run the kernel timestamps of
+// one step are sampled at intervals; when the slowest device stays well
+// above the mean, the populations are gathered on the host and the domain is
+// split again by the layers per second each device achieved.
+
+use super::lbm::LBM;
+use crate::solver::multi::MultiLBM;
+use crate::solver::parameters::TIMESTEP_ARG;
+use crate::solver::profiling::event_seconds;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+use std::time::Instant;
+
+const PROBE_LAYERS: usize = 8; // Layers of the probe slab
+const PROBE_STEPS: usize = 20;
+pub const BALANCE_INTERVAL: usize = 100; // Steps between load samples
+const BALANCE_TOLERANCE: f64 = 1.1; // Slowest over mean device time counted as lagging
+const BALANCE_PATIENCE: usize = 3; // Lagging samples in a row before rebalancing
+
+impl LBM {
+    // (first layer, depth) of slabs sized in proportion to `weights`, at
+    // least one layer each; equal slabs when the weights are unusable
+    pub fn weighted_decomposition(&self, weights: &[f64]) -> Vec<(usize, usize)> {
+        let (layers, _) = self.slab_layers();
+        let total: f64 = weights.iter().sum();
+        if !total.is_finite() || total <= 0.0 || weights.iter().any(|w| *w <= 0.0) {
+            return self.decomposition(weights.len());
+        }
+        let ideal: Vec<f64> = weights.iter().map(|w| layers as f64 * w / total).collect();
+        let mut depths: Vec<usize> = ideal.iter().map(|d| (d.floor() as usize).max(1)).collect();
+        // Largest remainders get the layers left over by rounding down
+        while depths.iter().sum::<usize>() < layers {
+            let i = (0..depths.len())
+                .max_by(|&a, &b| {
+                    (ideal[a] - depths[a] as f64).total_cmp(&(ideal[b] - depths[b] as f64))
+                })
+                .unwrap_or(0);
+            depths[i] += 1;
+        }
+        while depths.iter().sum::<usize>() > layers {
+            let i = (0..depths.len()).max_by_key(|&i| depths[i]).unwrap_or(0);
+            depths[i] -= 1;
+        }
+        let mut start = 0;
+        depths
+            .into_iter()
+            .map(|depth| {
+                start += depth;
+                (start - depth, depth)
+            })
+            .collect()
+    }
+}
+
+impl MultiLBM {
+    // Size slabs by device speed and rebalance during the run (on by default)
+    pub fn set_load_balancing(&mut self, enabled: bool) {
+        self.balance = enabled;
+    }
+
+    // MLUps of every device on a small slab of this domain
+    pub fn probe_devices(&self) -> Result<Vec<f64>, Box<dyn Error>> {
+        let (layers, _) = self.lbm.slab_layers();
+        let mut speeds = Vec::with_capacity(self.devices.len());
+        for device in &self.devices {
+            let lattice = self.lbm.slab_lattice(
+                PROBE_LAYERS.min(layers),
+                Some((device.platform_index, device.device_index)),
+            );
+            let queue = lattice.queue.as_ref().ok_or("OpenCL queue is None")?;
+            let kernel = lattice
+                .stream_collide_kernel
+                .as_ref()
+                .ok_or("Stream-collide kernel is None")?;
+            unsafe {
+                lattice
+                    .equilibrium_kernel
+                    .as_ref()
+                    .ok_or("Equilibrium kernel is None")?
+                    .enq()?;
+            }
+            queue.finish()?;
+            let start_time = Instant::now();
+            for t in 0..PROBE_STEPS {
+                kernel.set_arg(TIMESTEP_ARG, t as i32)?;
+                unsafe {
+                    kernel.enq()?;
+                }
+            }
+            queue.finish()?;
+            let mlups = (lattice.N * PROBE_STEPS) as f64 / start_time.elapsed().as_secs_f64() / 1e6;
+            terminal_utils::print_log(&format!(
+                "{}: {:.1} MLUps",
+                device.device_name.trim(),
+                mlups
+            ));
+            speeds.push(mlups);
+        }
+        Ok(speeds)
+    }
+
+    // Sample the device times of step t and rebalance once a device has
+    // lagged for BALANCE_PATIENCE samples
+    pub fn check_balance(&mut self, t: usize) -> Result<(), Box<dyn Error>> {
+        self.finish()?;
+        let times: Vec<f64> = self
+            .subdomains
+            .iter()
+            .map(|s| s.kernel_events.iter().filter_map(event_seconds).sum())
+            .collect();
+        if times.iter().any(|&time| time <= 0.0) {
+            return Ok(()); // No timestamps from this platform
+        }
+        let mean = times.iter().sum::<f64>() / times.len() as f64;
+        let slowest = times.iter().cloned().fold(0.0, f64::max);
+        self.lagging = if slowest > BALANCE_TOLERANCE * mean {
+            self.lagging + 1
+        } else {
+            0
+        };
+        if self.lagging < BALANCE_PATIENCE {
+            return Ok(());
+        }
+        self.lagging = 0;
+
+        let speeds: Vec<f64> = self
+            .subdomains
+            .iter()
+            .zip(&times)
+            .map(|(s, time)| s.depth as f64 / time)
+            .collect();
+        let slabs = self.lbm.weighted_decomposition(&speeds);
+        let current: Vec<(usize, usize)> =
+            self.subdomains.iter().map(|s| (s.start, s.depth)).collect();
+        if slabs == current {
+            return Ok(());
+        }
+        terminal_utils::print_log(&format!(
+            "Rebalancing at step {}: slab depths {:?} -> {:?}",
+            t + 1,
+            current.iter().map(|s| s.1).collect::<Vec<_>>(),
+            slabs.iter().map(|s| s.1).collect::<Vec<_>>()
+        ));
+        self.redistribute(&slabs, t)
+    }
+
+    // Move the state after step t onto slabs `slabs` through host memory
+    fn redistribute(&mut self, slabs: &[(usize, usize)], t: usize) -> Result<(), Box<dyn Error>> {
+        self.read_from_gpu()?;
+        let (layers, len) = self.lbm.slab_layers();
+        let (n, q_count) = (self.lbm.N, self.lbm.Q);
+        // Step t + 1 reads f on even steps and f_new on odd ones
+        let next = |lattice: &LBM| {
+            if (t + 1) % 2 == 0 {
+                lattice.f_buffer.clone()
+            } else {
+                lattice.f_new_buffer.clone()
+            }
+        };
+
+        let mut f = vec![0.0f32; n * q_count];
+        for s in &self.subdomains {
+            let mut slab_f = vec![0.0f32; s.lattice.N * q_count];
+            next(&s.lattice)
+                .ok_or("f buffer is None")?
+                .read(&mut slab_f)
+                .enq()?;
+            for q in 0..q_count {
+                let source = q * s.lattice.N + len;
+                let target = q * n + s.start * len;
+                f[target..target + s.depth * len]
+                    .copy_from_slice(&slab_f[source..source + s.depth * len]);
+            }
+        }
+
+        self.decompose(slabs)?;
+        for s in &self.subdomains {
+            let mut slab_f = vec![0.0f32; s.lattice.N * q_count];
+            for l in 0..s.depth + 2 {
+                let z = (s.start + layers + l - 1) % layers;
+                for q in 0..q_count {
+                    let target = q * s.lattice.N + l * len;
+                    let source = q * n + z * len;
+                    slab_f[target..target + len].copy_from_slice(&f[source..source + len]);
+                }
+            }
+            next(&s.lattice)
+                .ok_or("f buffer is None")?
+                .write(&slab_f)
+                .enq()?;
+        }
+        self.finish()
+    }
+}
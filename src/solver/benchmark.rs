@@ -1,10 +1,25 @@
 use super::lbm::LBM;
 use crate::utils::terminal_utils;
 use std::fs::File;
+use std::collections::HashMap;
 use std::io::Write;
+use std::path::Path;
 use std::time::Instant;
 use ocl;
+use crate::solver::collision::CollisionOperator;
+use crate::solver::metadata::{json_number, json_string};
+use crate::solver::neighbors::NeighborIndexing;
+use crate::solver::opencl::{list_devices, select_device, DeviceListing};
+use crate::solver::parameters::TIMESTEP_ARG;
 use crate::solver::precision::PrecisionMode;
+use crate::solver::velocity_sets::VelocitySet;
+
+/// Fraction of the device memory a configuration may use; the rest is left
+/// for the driver and other applications
+const VRAM_HEADROOM: f64 = 0.9;
+
+/// Buffer copies timed to measure the attainable device bandwidth
+const BANDWIDTH_COPIES: usize = 10;
 
 #[derive(Debug, Clone)]
 pub struct BenchmarkResult {
@@ -25,6 +40,10 @@ pub struct BenchmarkResult {
     pub local_memory_kb: f64,
     pub cell_memory_bytes: f64,
     pub precision: String, // Add precision field to result
+    pub collision: String,
+    pub bytes_per_lup: f64,
+    pub bandwidth_gbs: f64,      // Achieved: bytes per LUP × MLUps
+    pub peak_bandwidth_gbs: f64, // CAPPUSIM_PEAK_BANDWIDTH or measured copy bandwidth
 }
 
 #[derive(Debug, Clone)]
@@ -37,25 +56,207 @@ pub struct DeviceInfo {
     pub local_memory_kb: f64,
 }
 
+/// MLUps of one configuration as stored in a results file
+#[derive(Debug, Clone)]
+pub struct BenchmarkRecord {
+    pub model: String,
+    pub precision: String,
+    pub collision: String,
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+    pub mlups: f64,
+}
+
+/// Reads the flat objects of the "results" array written by
+/// save_results_to_json (string, number and null values only)
+fn parse_json_results(text: &str) -> Result<Vec<HashMap<String, String>>, Box<dyn std::error::Error>> {
+    let start = text.find("\"results\"").ok_or("No \"results\" array in the file")?;
+    let mut chars = text[start..].chars().skip_while(|&c| c != '[').skip(1).peekable();
+    let mut objects = Vec::new();
+    let mut current: Option<HashMap<String, String>> = None;
+    let mut key: Option<String> = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' => current = Some(HashMap::new()),
+            '}' => objects.extend(current.take()),
+            ']' if current.is_none() => break,
+            '"' => {
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('u') => {
+                                let code: String = chars.by_ref().take(4).collect();
+                                let c = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32);
+                                value.push(c.unwrap_or('?'));
+                            }
+                            Some(c) => value.push(c),
+                            None => break,
+                        },
+                        c => value.push(c),
+                    }
+                }
+                match (key.take(), current.as_mut()) {
+                    (Some(k), Some(object)) => {
+                        object.insert(k, value);
+                    }
+                    (None, _) => key = Some(value),
+                    _ => {}
+                }
+            }
+            ':' | ',' => {}
+            c if c.is_whitespace() => {}
+            c => {
+                // Bare number or literal up to the next delimiter
+                let mut value = c.to_string();
+                while let Some(&c) = chars.peek() {
+                    if c == ',' || c == '}' || c.is_whitespace() {
+                        break;
+                    }
+                    value.push(c);
+                    chars.next();
+                }
+                if let (Some(k), Some(object)) = (key.take(), current.as_mut()) {
+                    object.insert(k, value);
+                }
+            }
+        }
+    }
+    Ok(objects)
+}
+
 impl LBM {
     /// Runs comprehensive benchmarks for different models and grid sizes
     pub fn benchmark() {
-        println!("{}", "=".repeat(80));
+        Self::run_benchmark_suite(None);
+    }
+
+    /// Runs the benchmark suite on every OpenCL device in turn and prints
+    /// the MLUps of each next to the others, to choose the device to run on
+    pub fn benchmark_all_devices() {
+        let devices = match list_devices() {
+            Ok(devices) => devices,
+            Err(e) => {
+                terminal_utils::print_error(&format!("Failed to list OpenCL devices: {}", e));
+                return;
+            }
+        };
+        let mut runs = Vec::new();
+        for (i, device) in devices.iter().enumerate() {
+            terminal_utils::print_success(&format!("Device [{}/{}]: {} ({})",
+                i + 1, devices.len(), device.device_name.trim(), device.platform_name.trim()));
+            let results = Self::run_benchmark_suite(Some(device));
+            runs.push((device, results));
+        }
+        Self::print_device_comparison(&runs);
+    }
+
+    /// Runs the benchmark suite and compares it with a previous results file
+    /// (.json or .csv)
+    pub fn benchmark_compare(baseline: &str) {
+        let baseline_records = match Self::load_benchmark_records(baseline) {
+            Ok(records) => records,
+            Err(e) => {
+                terminal_utils::print_error(&format!("Failed to load {}: {}", baseline, e));
+                return;
+            }
+        };
+        let results = Self::run_benchmark_suite(None);
+        let current: Vec<BenchmarkRecord> = results.iter().map(BenchmarkRecord::from).collect();
+        Self::print_benchmark_comparison(&baseline_records, &current);
+    }
+
+    /// Compares two stored results files (.json or .csv)
+    pub fn compare_benchmark_files(baseline: &str, current: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let baseline_records = Self::load_benchmark_records(baseline)?;
+        let current_records = Self::load_benchmark_records(current)?;
+        Self::print_benchmark_comparison(&baseline_records, &current_records);
+        Ok(())
+    }
+
+    /// Times every neighbour indexing mode on a 2D and a 3D grid and prints
+    /// the fastest, to choose the mode passed to set_neighbor_indexing()
+    pub fn benchmark_neighbor_indexing() {
+        terminal_utils::print_info(&"=".repeat(80));
+        terminal_utils::print_success("Neighbour Indexing Benchmark");
+        terminal_utils::print_info(&"=".repeat(80));
+
+        let modes = [NeighborIndexing::Modulo, NeighborIndexing::Wrap, NeighborIndexing::Table];
+        let grids = [(VelocitySet::D2Q9, (1024, 1024, 1), 500), (VelocitySet::D3Q19, (128, 128, 128), 250)];
+        for (model, (nx, ny, nz), time_steps) in grids {
+            let mut timings = Vec::new();
+            for mode in modes {
+                let config = BenchmarkConfig {
+                    model,
+                    nx, ny, nz,
+                    time_steps,
+                    viscosity: 0.1,
+                    precision: PrecisionMode::FP32,
+                    collision: CollisionOperator::BGK,
+                    neighbor_indexing: mode,
+                    device: None,
+                };
+                match Self::run_single_benchmark(&config) {
+                    Ok(result) => {
+                        terminal_utils::print_info(&format!("  {} {}×{}×{} {:?}: {:.2} MLUps", model, nx, ny, nz, mode, result.mlups));
+                        timings.push((mode, result.mlups));
+                    }
+                    Err(e) => terminal_utils::print_error(&format!("Failed to run {} with {:?}: {}", model, mode, e)),
+                }
+            }
+            if let Some((mode, mlups)) = timings.iter().max_by(|a, b| a.1.total_cmp(&b.1)) {
+                terminal_utils::print_success(&format!("Fastest for {}: {:?} ({:.2} MLUps)", model, mode, mlups));
+            }
+        }
+        terminal_utils::print_info(&"=".repeat(80));
+    }
+
+    fn run_benchmark_suite(device: Option<&DeviceListing>) -> Vec<BenchmarkResult> {
+        terminal_utils::print_info(&"=".repeat(80));
         terminal_utils::print_success("Starting CappuSim Benchmark Suite");
-        println!("{}", "=".repeat(80));
+        terminal_utils::print_info(&"=".repeat(80));
         
         let mut results = Vec::new();
         
         // Define benchmark configurations
-        let configs = Self::get_benchmark_configs();
+        let mut configs = Self::get_benchmark_configs();
+        let assigned = device.map(|d| (d.platform_index, d.device_index));
+        for config in &mut configs {
+            config.device = assigned;
+        }
         
         let total_tests = configs.len();
-        println!("Running {} benchmark configurations...\n", total_tests);
+        terminal_utils::print_info(&format!("Running {} benchmark configurations...\n", total_tests));
+
+        // Memory of the device the runs will select, to skip oversized grids
+        let available_memory = match device {
+            Some(d) => Some(d.global_memory_bytes),
+            None => Self::available_device_memory(),
+        };
+        let mut skipped = 0;
         
         // Update progress display to show precision
         for (i, config) in configs.iter().enumerate() {
-            println!("Progress: [{}/{}] Testing {} {}×{}×{} ({:?})", 
-                i + 1, total_tests, config.model, config.nx, config.ny, config.nz, config.precision);
+            terminal_utils::print_info(&format!("Progress: [{}/{}] Testing {} {}×{}×{} ({:?}, {})", 
+                i + 1, total_tests, config.model, config.nx, config.ny, config.nz, config.precision, config.collision));
+
+            let required = Self::required_device_bytes(config);
+            if let Some(available) = available_memory {
+                if required as f64 > available as f64 * VRAM_HEADROOM {
+                    terminal_utils::print_warning(&format!(
+                        "Skipping {} {}x{}x{}: needs {:.0} MB of {:.0} MB device memory",
+                        config.model, config.nx, config.ny, config.nz,
+                        required as f64 / (1024.0 * 1024.0),
+                        available as f64 / (1024.0 * 1024.0)
+                    ));
+                    skipped += 1;
+                    terminal_utils::print_info(&"-".repeat(80));
+                    continue;
+                }
+            }
             
             match Self::run_single_benchmark(config) {
                 Ok(result) => {
@@ -67,13 +268,18 @@ impl LBM {
                         config.model, config.nx, config.ny, config.nz, e));
                 }
             }
-            println!("{}", "-".repeat(80));
+            terminal_utils::print_info(&"-".repeat(80));
         }
         
-        // Save results to CSV
+        // Save results to CSV, and as JSON next to it
         match Self::save_results_to_csv(&results) {
             Ok(filename) => {
                 terminal_utils::print_success(&format!("Benchmark results saved to: {}", filename));
+                let json = Path::new(&filename).with_extension("json");
+                match Self::save_results_to_json(&results, &json) {
+                    Ok(()) => terminal_utils::print_success(&format!("Benchmark results saved to: {}", json.display())),
+                    Err(e) => terminal_utils::print_error(&format!("Failed to save JSON: {}", e)),
+                }
             }
             Err(e) => {
                 terminal_utils::print_error(&format!("Failed to save CSV: {}", e));
@@ -82,6 +288,147 @@ impl LBM {
         
         // Print summary
         Self::print_benchmark_summary(&results);
+        if skipped > 0 {
+            terminal_utils::print_info(&format!("  {} configurations skipped for lack of device memory", skipped));
+        }
+        results
+    }
+
+    /// Saves benchmark results as JSON, one object per configuration
+    fn save_results_to_json(results: &[BenchmarkResult], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{{")?;
+        writeln!(file, "  \"results\": [")?;
+        for (i, r) in results.iter().enumerate() {
+            let fields = [
+                ("model", json_string(&r.model)),
+                ("precision", json_string(&r.precision)),
+                ("collision", json_string(&r.collision)),
+                ("nx", r.nx.to_string()),
+                ("ny", r.ny.to_string()),
+                ("nz", r.nz.to_string()),
+                ("grid_size", r.grid_size.to_string()),
+                ("time_steps", r.time_steps.to_string()),
+                ("elapsed_time", json_number(r.elapsed_time)),
+                ("mlups", json_number(r.mlups)),
+                ("memory_usage_mb", json_number(r.memory_usage_mb)),
+                ("cell_memory_bytes", json_number(r.cell_memory_bytes)),
+                ("device_name", json_string(&r.device_name)),
+                ("platform_name", json_string(&r.platform_name)),
+                ("compute_units", r.compute_units.to_string()),
+                ("max_work_group_size", r.max_work_group_size.to_string()),
+                ("global_memory_gb", json_number(r.global_memory_gb)),
+                ("local_memory_kb", json_number(r.local_memory_kb)),
+                ("bytes_per_lup", json_number(r.bytes_per_lup)),
+                ("bandwidth_gbs", json_number(r.bandwidth_gbs)),
+                ("peak_bandwidth_gbs", json_number(r.peak_bandwidth_gbs)),
+            ];
+            let body: Vec<String> = fields.iter().map(|(k, v)| format!("\"{}\": {}", k, v)).collect();
+            let separator = if i + 1 < results.len() { "," } else { "" };
+            writeln!(file, "    {{{}}}{}", body.join(", "), separator)?;
+        }
+        writeln!(file, "  ]")?;
+        writeln!(file, "}}")?;
+        Ok(())
+    }
+
+    /// Loads the per-configuration MLUps of a .json or .csv results file
+    pub fn load_benchmark_records(path: &str) -> Result<Vec<BenchmarkRecord>, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)?;
+        let rows: Vec<HashMap<String, String>> = if path.ends_with(".json") {
+            parse_json_results(&text)?
+        } else {
+            // CSV columns are matched by header name, lower-cased like the JSON keys
+            let mut lines = text.lines();
+            let header: Vec<String> = lines
+                .next()
+                .ok_or("Empty results file")?
+                .split(',')
+                .map(|h| h.trim().to_lowercase())
+                .collect();
+            lines
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| header.iter().cloned().zip(line.split(',').map(|v| v.trim().to_string())).collect())
+                .collect()
+        };
+        let field = |row: &HashMap<String, String>, key: &str| -> Result<String, Box<dyn std::error::Error>> {
+            Ok(row.get(key).ok_or(format!("Missing '{}' in {}", key, path))?.clone())
+        };
+        rows.iter()
+            .map(|row| {
+                Ok(BenchmarkRecord {
+                    model: field(row, "model")?,
+                    precision: field(row, "precision")?,
+                    // Files from before the collision sweep are all BGK
+                    collision: field(row, "collision").unwrap_or_else(|_| "BGK".to_string()),
+                    nx: field(row, "nx")?.parse()?,
+                    ny: field(row, "ny")?.parse()?,
+                    nz: field(row, "nz")?.parse()?,
+                    mlups: field(row, "mlups")?.parse()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Prints the MLUps change of every configuration present in both sets
+    fn print_benchmark_comparison(baseline: &[BenchmarkRecord], current: &[BenchmarkRecord]) {
+        terminal_utils::print_info(&format!("\n{}", "=".repeat(80)));
+        terminal_utils::print_success("Benchmark Comparison");
+        terminal_utils::print_info(&"=".repeat(80));
+        terminal_utils::print_info(&format!("{:<8}{:<8}{:>18}{:>14}{:>14}{:>12}", "Model", "Prec/Op", "Grid", "Before", "After", "Change"));
+
+        let mut log_ratio_sum = 0.0;
+        let mut matched = 0;
+        for c in current {
+            let Some(b) = baseline.iter().find(|b| {
+                b.model == c.model && b.precision == c.precision && b.collision == c.collision
+                    && (b.nx, b.ny, b.nz) == (c.nx, c.ny, c.nz)
+            }) else {
+                continue;
+            };
+            if b.mlups <= 0.0 || c.mlups <= 0.0 {
+                continue;
+            }
+            let change = 100.0 * (c.mlups / b.mlups - 1.0);
+            let line = format!("{:<8}{:<8}{:>18}{:>14.2}{:>14.2}{:>+11.1}%",
+                c.model, format!("{}/{}", c.precision, c.collision), format!("{}x{}x{}", c.nx, c.ny, c.nz), b.mlups, c.mlups, change);
+            // Changes within a few percent are run-to-run noise
+            if change < -3.0 {
+                terminal_utils::print_warning(&line);
+            } else {
+                terminal_utils::print_info(&line);
+            }
+            log_ratio_sum += (c.mlups / b.mlups).ln();
+            matched += 1;
+        }
+
+        if matched == 0 {
+            terminal_utils::print_warning("No configurations in common with the baseline.");
+        } else {
+            let speedup = (log_ratio_sum / matched as f64).exp();
+            terminal_utils::print_info(&format!("\n{} configurations compared, geometric mean speedup {:.3}x ({:+.1}%)",
+                matched, speedup, 100.0 * (speedup - 1.0)));
+        }
+        terminal_utils::print_info(&"=".repeat(80));
+    }
+
+    /// Global memory in bytes of the device LBM::initialize() will select
+    fn available_device_memory() -> Option<u64> {
+        let listings = list_devices().ok()?;
+        select_device(&listings).ok().map(|d| d.global_memory_bytes)
+    }
+
+    /// Device memory in bytes allocated by initialize() for a configuration
+    fn required_device_bytes(config: &BenchmarkConfig) -> u64 {
+        let n = (config.nx * config.ny * config.nz) as u64;
+        let q = config.model.q() as u64;
+        let f32_bytes = std::mem::size_of::<f32>() as u64;
+        // f and f_new hold N * Q floats, packed as halves in the FP16 modes
+        let populations = 2 * config.precision.f_buffer_len((n * q) as usize) as u64 * f32_bytes;
+        let density = n * f32_bytes;
+        let velocity = 3 * n * f32_bytes;
+        let flags = n;
+        populations + density + velocity + flags
     }
     
     /// Defines all benchmark configurations to test
@@ -99,20 +446,25 @@ impl LBM {
             (2048, 2048, 1),
         ];
         
-        for precision in &precision_modes {
-            for &(nx, ny, nz) in &grid_sizes_2d {
-                configs.push(BenchmarkConfig {
-                    model: "D2Q9".to_string(),
-                    nx, ny, nz,
-                    time_steps: 500,
-                    viscosity: 0.1,
-                    precision: precision.clone(),
-                });
+        for collision in CollisionOperator::ALL {
+            for precision in &precision_modes {
+                for &(nx, ny, nz) in &grid_sizes_2d {
+                    configs.push(BenchmarkConfig {
+                        model: VelocitySet::D2Q9,
+                        nx, ny, nz,
+                        time_steps: 500,
+                        viscosity: 0.1,
+                        precision: precision.clone(),
+                        collision,
+                        neighbor_indexing: NeighborIndexing::default(),
+                        device: None,
+                    });
+                }
             }
         }
         
         // 3D Models
-        let models_3d = vec!["D3Q7", "D3Q15", "D3Q19", "D3Q27"];
+        let models_3d = vec![VelocitySet::D3Q7, VelocitySet::D3Q15, VelocitySet::D3Q19, VelocitySet::D3Q27];
         let grid_sizes_3d = vec![
             (32, 32, 32),
             (64, 64, 64),
@@ -121,16 +473,21 @@ impl LBM {
             (256, 256, 256),
         ];
         
-        for precision in &precision_modes {
-            for model in &models_3d {
-                for &(nx, ny, nz) in &grid_sizes_3d {
-                    configs.push(BenchmarkConfig {
-                        model: model.to_string(),
-                        nx, ny, nz,
-                        time_steps: 250,
-                        viscosity: 0.1,
-                        precision: precision.clone(),
-                    });
+        for collision in CollisionOperator::ALL {
+            for precision in &precision_modes {
+                for model in &models_3d {
+                    for &(nx, ny, nz) in &grid_sizes_3d {
+                        configs.push(BenchmarkConfig {
+                            model: *model,
+                            nx, ny, nz,
+                            time_steps: 250,
+                            viscosity: 0.1,
+                            precision: precision.clone(),
+                            collision,
+                            neighbor_indexing: NeighborIndexing::default(),
+                            device: None,
+                        });
+                    }
                 }
             }
         }
@@ -143,11 +500,15 @@ impl LBM {
         // Create LBM instance with precision mode
         let mut lbm = LBM::new(
             config.nx, config.ny, config.nz, 
-            config.model.clone(), 
+            config.model,
             config.viscosity,
             config.precision.clone()
         );
         
+        lbm.set_collision(config.collision);
+        lbm.set_neighbor_indexing(config.neighbor_indexing);
+        lbm.assigned_device = config.device;
+
         // Set simple initial conditions (fluid everywhere)
         lbm.set_conditions(|lbm, _x, _y, _z, n| {
             lbm.flags[n] = 0; // FLAG_FLUID
@@ -187,7 +548,7 @@ impl LBM {
         for t in 0..config.time_steps {
             unsafe {
                 let kernel = lbm.stream_collide_kernel.as_ref().unwrap();
-                kernel.set_arg(6, &(t as i32))
+                kernel.set_arg(TIMESTEP_ARG, &(t as i32))
                     .expect("Failed to set kernel argument");
                 kernel.enq()
                     .expect("Failed to enqueue stream-collide kernel");
@@ -207,10 +568,19 @@ impl LBM {
 
         // Calculate cell memory usage
         let cell_memory_bytes = Self::calculate_cell_memory_usage(&lbm, &config.precision);
+
+        // Achieved bandwidth against the device limit
+        let bytes_per_lup = Self::calculate_bytes_per_lup(&lbm, &config.precision);
+        let bandwidth_gbs = bytes_per_lup * mlups / 1000.0;
+        let peak_bandwidth_gbs = match Self::peak_bandwidth_override() {
+            Some(peak) => peak,
+            None => Self::measure_copy_bandwidth(&lbm)?,
+        };
         
         Ok(BenchmarkResult {
-            model: config.model.clone(),
+            model: config.model.to_string(),
             precision: format!("{:?}", config.precision),
+            collision: config.collision.to_string(),
             nx: config.nx,
             ny: config.ny,
             nz: config.nz,
@@ -226,8 +596,44 @@ impl LBM {
             global_memory_gb: device_info.global_memory_gb,
             local_memory_kb: device_info.local_memory_kb,
             cell_memory_bytes,
+            bytes_per_lup,
+            bandwidth_gbs,
+            peak_bandwidth_gbs,
         })
     }
+
+    /// Theoretical device bandwidth in GB/s from CAPPUSIM_PEAK_BANDWIDTH, e.g.
+    /// the datasheet value, which OpenCL cannot query
+    fn peak_bandwidth_override() -> Option<f64> {
+        std::env::var("CAPPUSIM_PEAK_BANDWIDTH")
+            .ok()
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|&v| v > 0.0)
+    }
+
+    /// Measures the device bandwidth in GB/s with f to f_new buffer copies,
+    /// each of which reads and writes the whole buffer
+    fn measure_copy_bandwidth(lbm: &LBM) -> Result<f64, Box<dyn std::error::Error>> {
+        let queue = lbm.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let f = lbm.f_buffer.as_ref().ok_or("f buffer is None")?;
+        let f_new = lbm.f_new_buffer.as_ref().ok_or("f_new buffer is None")?;
+        if lbm.in_place_streaming {
+            return Err("Copy bandwidth needs two population buffers".into());
+        }
+
+        // Warm-up copy outside the timing
+        f.copy(f_new, None, None).enq()?;
+        queue.finish()?;
+        let start_time = Instant::now();
+        for _ in 0..BANDWIDTH_COPIES {
+            f.copy(f_new, None, None).enq()?;
+        }
+        queue.finish()?;
+        let elapsed_seconds = start_time.elapsed().as_secs_f64();
+
+        let bytes = 2.0 * (f.len() * std::mem::size_of::<f32>()) as f64 * BANDWIDTH_COPIES as f64;
+        Ok(bytes / elapsed_seconds / 1e9)
+    }
     
     /// Gets device information from the LBM instance
     fn get_device_info(lbm: &LBM) -> Result<DeviceInfo, Box<dyn std::error::Error>> {
@@ -289,7 +695,7 @@ impl LBM {
     fn calculate_cell_memory_usage(lbm: &LBM, precision: &PrecisionMode) -> f64 {
         let (bytes_per_distribution, bytes_per_f32, bytes_per_uchar) = match precision {
             PrecisionMode::FP32 => (4, 4, 1),   // 32-bit float, 32-bit float, 8-bit uchar
-            PrecisionMode::FP16S | PrecisionMode::FP16C => (2, 4, 1), // 16-bit half, 32-bit float, 8-bit uchar
+            PrecisionMode::FP16S | PrecisionMode::FP16C | PrecisionMode::BF16S => (2, 4, 1), // 16-bit half, 32-bit float, 8-bit uchar
         };
 
         // Memory usage for one cell
@@ -303,15 +709,38 @@ impl LBM {
         cell_memory_bytes
     }
     
+    /// Global memory traffic of one lattice update in bytes: every population
+    /// is read and written once, density and velocity are written and the
+    /// flag is read (neighbour flags are assumed to hit the cache)
+    fn calculate_bytes_per_lup(lbm: &LBM, precision: &PrecisionMode) -> f64 {
+        let bytes_per_distribution = match precision {
+            PrecisionMode::FP32 => 4,
+            PrecisionMode::FP16S | PrecisionMode::FP16C | PrecisionMode::BF16S => 2,
+        };
+        let bytes_per_f32 = 4;
+        let bytes_per_uchar = 1;
+
+        (
+            lbm.Q * 2 * bytes_per_distribution + // populations read and written
+            bytes_per_f32 +                      // density written
+            3 * bytes_per_f32 +                  // velocity written
+            bytes_per_uchar                      // flag read
+        ) as f64
+    }
+
     /// Prints result for a single benchmark
     fn print_benchmark_result(result: &BenchmarkResult) {
-        println!("  Model: {}", result.model);
-        println!("  Grid: {}×{}×{} ({} cells)", result.nx, result.ny, result.nz, result.grid_size);
-        println!("  Time steps: {}", result.time_steps);
-        println!("  Elapsed time: {:.3}s", result.elapsed_time);
-        println!("  Performance: {:.2} MLUps", result.mlups);
-        println!("  Memory usage: {:.1} MB", result.memory_usage_mb);
-        println!("  Device: {} ({} CUs)", result.device_name, result.compute_units);
+        terminal_utils::print_info(&format!("  Model: {}", result.model));
+        terminal_utils::print_info(&format!("  Grid: {}×{}×{} ({} cells)", result.nx, result.ny, result.nz, result.grid_size));
+        terminal_utils::print_info(&format!("  Time steps: {}", result.time_steps));
+        terminal_utils::print_info(&format!("  Elapsed time: {:.3}s", result.elapsed_time));
+        terminal_utils::print_info(&format!("  Precision: {}, collision: {}", result.precision, result.collision));
+        terminal_utils::print_info(&format!("  Performance: {:.2} MLUps", result.mlups));
+        terminal_utils::print_info(&format!("  Memory usage: {:.1} MB", result.memory_usage_mb));
+        terminal_utils::print_info(&format!("  Bandwidth: {:.1} GB/s of {:.1} GB/s ({:.0}%, {:.0} bytes/LUP)",
+            result.bandwidth_gbs, result.peak_bandwidth_gbs,
+            Self::bandwidth_utilization(result), result.bytes_per_lup));
+        terminal_utils::print_info(&format!("  Device: {} ({} CUs)", result.device_name, result.compute_units));
     }
     
     /// Saves benchmark results to CSV file
@@ -333,13 +762,14 @@ impl LBM {
         let mut file = File::create(&filename)?;
         
         // Write CSV header
-        writeln!(file, "Model,Precision,Nx,Ny,Nz,GridSize,TimeSteps,ElapsedTime,MLUps,MemoryUsageMB,CellMemoryBytes,DeviceName,PlatformName,ComputeUnits,MaxWorkGroupSize,GlobalMemoryGB,LocalMemoryKB")?;
+        writeln!(file, "Model,Precision,Collision,Nx,Ny,Nz,GridSize,TimeSteps,ElapsedTime,MLUps,MemoryUsageMB,CellMemoryBytes,DeviceName,PlatformName,ComputeUnits,MaxWorkGroupSize,GlobalMemoryGB,LocalMemoryKB,BytesPerLUP,BandwidthGBs,PeakBandwidthGBs")?;
         
         // Write data rows
         for result in results {
-            writeln!(file, "{},{},{},{},{},{},{},{:.6},{:.6},{:.2},{:.2},{},{},{},{},{:.2},{:.1}",
+            writeln!(file, "{},{},{},{},{},{},{},{},{:.6},{:.6},{:.2},{:.2},{},{},{},{},{:.2},{:.1},{:.0},{:.2},{:.2}",
                 result.model,
                 result.precision,  // Add precision
+                result.collision,
                 result.nx,
                 result.ny, 
                 result.nz,
@@ -355,6 +785,9 @@ impl LBM {
                 result.max_work_group_size,
                 result.global_memory_gb,
                 result.local_memory_kb,
+                result.bytes_per_lup,
+                result.bandwidth_gbs,
+                result.peak_bandwidth_gbs,
             )?;
         }
         
@@ -367,9 +800,9 @@ impl LBM {
             return;
         }
         
-        println!("\n{}", "=".repeat(80));
+        terminal_utils::print_info(&format!("\n{}", "=".repeat(80)));
         terminal_utils::print_success("Benchmark Summary");
-        println!("{}", "=".repeat(80));
+        terminal_utils::print_info(&"=".repeat(80));
         
         // Group results by model
         let mut model_prec_results = std::collections::HashMap::new();
@@ -380,43 +813,185 @@ impl LBM {
                 .push(result);
         }
 
-        println!("Performance by model and precision:");
+        terminal_utils::print_info("Performance by model and precision:");
         for ((model, precision), results_group) in &model_prec_results {
             let max_mlups = results_group.iter().map(|r| r.mlups).fold(0.0f64, f64::max);
             let avg_mlups = results_group.iter().map(|r| r.mlups).sum::<f64>() / results_group.len() as f64;
             
             let best = results_group.iter().max_by(|a, b| a.mlups.partial_cmp(&b.mlups).unwrap()).unwrap();
             
-            println!("  {} ({}): Max {:.2} MLUps ({}×{}×{}), Avg {:.2} MLUps",
-                model, precision, max_mlups, best.nx, best.ny, best.nz, avg_mlups);
+            terminal_utils::print_info(&format!("  {} ({}): Max {:.2} MLUps ({}×{}×{}), Avg {:.2} MLUps",
+                model, precision, max_mlups, best.nx, best.ny, best.nz, avg_mlups));
         }
         
         // Overall best
         let best_overall = results.iter().max_by(|a, b| a.mlups.partial_cmp(&b.mlups).unwrap());
         if let Some(best) = best_overall {
-            println!("\nOverall best performance:");
-            println!("  {}: {:.2} MLUps ({}×{}×{})", 
-                best.model, best.mlups, best.nx, best.ny, best.nz);
+            terminal_utils::print_info("\nOverall best performance:");
+            terminal_utils::print_info(&format!("  {}: {:.2} MLUps ({}×{}×{})", 
+                best.model, best.mlups, best.nx, best.ny, best.nz));
         }
         
         // Overall statistics
         let total_mlups: f64 = results.iter().map(|r| r.mlups).sum();
         let avg_mlups = total_mlups / results.len() as f64;
         
-        println!("\nOverall statistics:");
-        println!("  Total configurations tested: {}", results.len());
-        println!("  Average performance: {:.2} MLUps", avg_mlups);
-        println!("{}", "=".repeat(80));
+        terminal_utils::print_info("\nOverall statistics:");
+        terminal_utils::print_info(&format!("  Total configurations tested: {}", results.len()));
+        terminal_utils::print_info(&format!("  Average performance: {:.2} MLUps", avg_mlups));
+
+        Self::print_sweep_table(results);
+        Self::print_bandwidth_report(results);
+        terminal_utils::print_info(&"=".repeat(80));
+    }
+
+    /// Prints MLUps and memory of every model, precision, collision operator
+    /// and resolution combination that ran
+    fn print_sweep_table(results: &[BenchmarkResult]) {
+        let mut rows: Vec<&BenchmarkResult> = results.iter().collect();
+        rows.sort_by(|a, b| {
+            (&a.model, &a.collision, &a.precision, a.grid_size)
+                .cmp(&(&b.model, &b.collision, &b.precision, b.grid_size))
+        });
+
+        terminal_utils::print_info("\nPrecision and collision sweep:");
+        terminal_utils::print_info(&format!("  {:<8}{:<8}{:<10}{:>18}{:>12}{:>14}{:>10}", "Model", "Op", "Precision", "Grid", "MLUps", "Memory (MB)", "B/cell"));
+        for r in rows {
+            terminal_utils::print_info(&format!("  {:<8}{:<8}{:<10}{:>18}{:>12.2}{:>14.1}{:>10.0}",
+                r.model, r.collision, r.precision, format!("{}x{}x{}", r.nx, r.ny, r.nz),
+                r.mlups, r.memory_usage_mb, r.cell_memory_bytes));
+        }
+    }
+
+    /// Prints the best MLUps of each model and precision on every device,
+    /// and the geometric-mean speed of each device relative to the first
+    fn print_device_comparison(runs: &[(&DeviceListing, Vec<BenchmarkResult>)]) {
+        if runs.is_empty() {
+            return;
+        }
+        terminal_utils::print_info(&format!("\n{}", "=".repeat(80)));
+        terminal_utils::print_success("Device Comparison (best MLUps)");
+        terminal_utils::print_info(&"=".repeat(80));
+
+        let mut groups: Vec<(String, String)> = Vec::new();
+        for (_, results) in runs {
+            for r in results {
+                let key = (r.model.clone(), r.precision.clone());
+                if !groups.contains(&key) {
+                    groups.push(key);
+                }
+            }
+        }
+        let best = |results: &[BenchmarkResult], model: &str, precision: &str| {
+            results.iter()
+                .filter(|r| r.model == model && r.precision == precision)
+                .map(|r| r.mlups)
+                .fold(0.0f64, f64::max)
+        };
+
+        let mut header = format!("  {:<16}", "Model");
+        for (i, (device, _)) in runs.iter().enumerate() {
+            header += &format!("{:>14}", format!("[{}] {}", i, device.device_type));
+        }
+        terminal_utils::print_info(&header);
+        for (model, precision) in &groups {
+            let mut row = format!("  {:<16}", format!("{} {}", model, precision));
+            for (_, results) in runs {
+                match best(results, model, precision) {
+                    mlups if mlups > 0.0 => row += &format!("{:>14.2}", mlups),
+                    _ => row += &format!("{:>14}", "-"),
+                }
+            }
+            terminal_utils::print_info(&row);
+        }
+
+        // Speed relative to the first device over the configurations both ran
+        let reference = &runs[0].1;
+        terminal_utils::print_info("\nDevices:");
+        for (i, (device, results)) in runs.iter().enumerate() {
+            let ratios: Vec<f64> = results.iter()
+                .filter_map(|r| {
+                    reference.iter()
+                        .find(|b| b.model == r.model && b.precision == r.precision
+                            && b.collision == r.collision && b.grid_size == r.grid_size)
+                        .filter(|b| b.mlups > 0.0)
+                        .map(|b| r.mlups / b.mlups)
+                })
+                .collect();
+            let relative = if ratios.is_empty() {
+                "n/a".to_string()
+            } else {
+                let log_mean = ratios.iter().map(|r| r.ln()).sum::<f64>() / ratios.len() as f64;
+                format!("{:.2}x", log_mean.exp())
+            };
+            terminal_utils::print_info(&format!("  [{}] {} ({}, {} CUs): {} configurations, {} of device [0]",
+                i, device.device_name.trim(), device.platform_name.trim(),
+                device.compute_units, results.len(), relative));
+        }
+        terminal_utils::print_info(&"=".repeat(80));
+    }
+
+    /// Achieved share of the peak bandwidth in percent
+    fn bandwidth_utilization(result: &BenchmarkResult) -> f64 {
+        if result.peak_bandwidth_gbs > 0.0 {
+            100.0 * result.bandwidth_gbs / result.peak_bandwidth_gbs
+        } else {
+            0.0
+        }
+    }
+
+    /// Prints the best achieved bandwidth of each model and precision against
+    /// the device peak. LBM is memory bound, so this is its roofline: MLUps
+    /// cannot exceed peak bandwidth / bytes per LUP.
+    fn print_bandwidth_report(results: &[BenchmarkResult]) {
+        // Small grids underestimate the copy bandwidth, so take the largest
+        let peak = results.iter().map(|r| r.peak_bandwidth_gbs).fold(0.0f64, f64::max);
+        if peak <= 0.0 {
+            return;
+        }
+        let source = if Self::peak_bandwidth_override().is_some() { "CAPPUSIM_PEAK_BANDWIDTH" } else { "measured copy" };
+        terminal_utils::print_info(&format!("\nMemory bandwidth (peak {:.1} GB/s, {}):", peak, source));
+
+        let mut best: Vec<&BenchmarkResult> = Vec::new();
+        for result in results {
+            match best.iter_mut().find(|b| b.model == result.model && b.precision == result.precision) {
+                Some(b) if b.bandwidth_gbs < result.bandwidth_gbs => *b = result,
+                Some(_) => {}
+                None => best.push(result),
+            }
+        }
+        for b in best {
+            terminal_utils::print_info(&format!("  {} ({}): {:.1} GB/s, {:.0}% of peak, {:.0} bytes/LUP, roofline {:.0} MLUps",
+                b.model, b.precision, b.bandwidth_gbs, 100.0 * b.bandwidth_gbs / peak,
+                b.bytes_per_lup, peak * 1000.0 / b.bytes_per_lup));
+        }
+    }
+}
+
+impl From<&BenchmarkResult> for BenchmarkRecord {
+    fn from(r: &BenchmarkResult) -> Self {
+        BenchmarkRecord {
+            model: r.model.clone(),
+            precision: r.precision.clone(),
+            collision: r.collision.clone(),
+            nx: r.nx,
+            ny: r.ny,
+            nz: r.nz,
+            mlups: r.mlups,
+        }
     }
 }
 
 #[derive(Debug, Clone)]
 struct BenchmarkConfig {
-    model: String,
+    model: VelocitySet,
     nx: usize,
     ny: usize, 
     nz: usize,
     time_steps: usize,
     viscosity: f32,
     precision: PrecisionMode,  // Add precision field
+    collision: CollisionOperator,
+    neighbor_indexing: NeighborIndexing,
+    device: Option<(usize, usize)>, // (platform, device) index, or the default selection
 }
\ No newline at end of file
@@ -0,0 +1,164 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Named-parameter construction of an LBM:
+//
+//     let mut lbm = LBM::builder()
+//         .size(256, 128, 1)
+//         .model(VelocitySet::D2Q9)
+//         .viscosity(0.01)
+//         .precision(PrecisionMode::FP32)
+//         .constant_force([1e-6, 0.0, 0.0])
+//         .output_interval(1000)
+//         .build()?;
+//
+// Only size and viscosity are required; the model defaults to D2Q9 for a
+// single layer and D3Q19 otherwise. Features without a builder method keep
+// their set_* methods on the built lattice.
+
+use super::lbm::LBM;
+use crate::solver::collision::CollisionOperator;
+use crate::solver::neighbors::NeighborIndexing;
+use crate::solver::precision::PrecisionMode;
+use crate::solver::velocity_sets::VelocitySet;
+
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct LBMBuilder {
+    size: Option<(usize, usize, usize)>,
+    model: Option<VelocitySet>,
+    viscosity: Option<f32>,
+    precision: PrecisionMode,
+    collision: CollisionOperator,
+    constant_force: Option<[f32; 3]>,
+    neighbor_indexing: NeighborIndexing,
+    output_interval: Option<usize>,
+    output_csv: Option<bool>,
+    output_vtk: Option<bool>,
+    output_vti: Option<bool>,
+    self_test: bool,
+}
+
+impl LBM {
+    pub fn builder() -> LBMBuilder {
+        LBMBuilder {
+            size: None,
+            model: None,
+            viscosity: None,
+            precision: PrecisionMode::FP32,
+            collision: CollisionOperator::default(),
+            constant_force: None,
+            neighbor_indexing: NeighborIndexing::default(),
+            output_interval: None,
+            output_csv: None,
+            output_vtk: None,
+            output_vti: None,
+            self_test: false,
+        }
+    }
+}
+
+impl LBMBuilder {
+    // Lattice cells along x, y and z (1 for 2D)
+    pub fn size(mut self, Nx: usize, Ny: usize, Nz: usize) -> Self {
+        self.size = Some((Nx, Ny, Nz));
+        self
+    }
+
+    pub fn model(mut self, model: VelocitySet) -> Self {
+        self.model = Some(model);
+        self
+    }
+
+    // Kinematic viscosity in lattice units
+    pub fn viscosity(mut self, viscosity: f32) -> Self {
+        self.viscosity = Some(viscosity);
+        self
+    }
+
+    pub fn precision(mut self, precision: PrecisionMode) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn collision(mut self, collision: CollisionOperator) -> Self {
+        self.collision = collision;
+        self
+    }
+
+    // Uniform body force density
+    pub fn constant_force(mut self, force: [f32; 3]) -> Self {
+        self.constant_force = Some(force);
+        self
+    }
+
+    pub fn neighbor_indexing(mut self, mode: NeighborIndexing) -> Self {
+        self.neighbor_indexing = mode;
+        self
+    }
+
+    pub fn output_interval(mut self, interval: usize) -> Self {
+        self.output_interval = Some(interval);
+        self
+    }
+
+    pub fn output_csv(mut self, state: bool) -> Self {
+        self.output_csv = Some(state);
+        self
+    }
+
+    pub fn output_vtk(mut self, state: bool) -> Self {
+        self.output_vtk = Some(state);
+        self
+    }
+
+    pub fn output_vti(mut self, state: bool) -> Self {
+        self.output_vti = Some(state);
+        self
+    }
+
+    // Verify the device with a Taylor-Green run before run()
+    pub fn self_test(mut self, state: bool) -> Self {
+        self.self_test = state;
+        self
+    }
+
+    // Create the lattice, checking the parameters up front instead of at run()
+    pub fn build(self) -> Result<LBM, Box<dyn Error>> {
+        let (Nx, Ny, Nz) = self.size.ok_or("LBM::builder() needs size().")?;
+        let viscosity = self.viscosity.ok_or("LBM::builder() needs viscosity().")?;
+        let model = self
+            .model
+            .unwrap_or(if Nz == 1 { VelocitySet::D2Q9 } else { VelocitySet::D3Q19 });
+
+        let mut lbm = LBM::new(
+            Nx,
+            Ny,
+            Nz,
+            model,
+            viscosity,
+            self.precision,
+        );
+        lbm.check_errors_in_input()?;
+        if let Some(force) = self.constant_force {
+            lbm.set_constant_force(force.to_vec());
+        }
+        lbm.set_collision(self.collision);
+        lbm.set_neighbor_indexing(self.neighbor_indexing);
+        if let Some(interval) = self.output_interval {
+            lbm.set_output_interval(interval);
+        }
+        if let Some(state) = self.output_csv {
+            lbm.set_output_csv(state);
+        }
+        if let Some(state) = self.output_vtk {
+            lbm.set_output_vtk(state);
+        }
+        if let Some(state) = self.output_vti {
+            lbm.set_output_vti(state);
+        }
+        lbm.set_self_test(self.self_test);
+        Ok(lbm)
+    }
+}
@@ -0,0 +1,82 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Capabilities of the selected device, queried at initialize() and turned
+// into defines of the generated kernel source. FP16C needs native half
+// arithmetic (cl_khr_fp16); without it the run falls back to FP16S, whose
+// vload_half/vstore_half storage conversions are part of core OpenCL.
+
+use super::lbm::LBM;
+use crate::solver::precision::PrecisionMode;
+use crate::utils::terminal_utils;
+
+use ocl::enums::{DeviceInfo, DeviceInfoResult};
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeviceCapabilities {
+    pub fp16: bool,      // cl_khr_fp16
+    pub subgroups: bool, // cl_khr_subgroups or cl_intel_subgroups
+    pub vector_width_float: u32,
+    pub vector_width_half: u32, // 0 without half support
+}
+
+impl DeviceCapabilities {
+    // Defines prepended to the kernel source
+    pub fn kernel_defines(&self) -> String {
+        let mut defines = String::new();
+        if self.fp16 {
+            defines.push_str("#define HAS_FP16\n");
+        }
+        if self.subgroups {
+            defines.push_str("#define HAS_SUBGROUPS\n");
+        }
+        defines.push_str(&format!(
+            "#define PREFERRED_VECTOR_WIDTH_FLOAT {}\n#define PREFERRED_VECTOR_WIDTH_HALF {}\n",
+            self.vector_width_float.max(1),
+            self.vector_width_half.max(1)
+        ));
+        defines
+    }
+}
+
+impl LBM {
+    // Query the selected device
+    pub fn query_device_capabilities(&self) -> Result<DeviceCapabilities, Box<dyn Error>> {
+        let device = self.device.as_ref().ok_or("OpenCL device is None")?;
+        let extensions = match device.info(DeviceInfo::Extensions)? {
+            DeviceInfoResult::Extensions(extensions) => extensions,
+            _ => String::new(),
+        };
+        let vector_width_float = match device.info(DeviceInfo::PreferredVectorWidthFloat)? {
+            DeviceInfoResult::PreferredVectorWidthFloat(width) => width,
+            _ => 1,
+        };
+        let vector_width_half = match device.info(DeviceInfo::PreferredVectorWidthHalf) {
+            Ok(DeviceInfoResult::PreferredVectorWidthHalf(width)) => width,
+            _ => 0, // Not reported by OpenCL 1.0 devices
+        };
+        Ok(DeviceCapabilities {
+            fp16: extensions.contains("cl_khr_fp16"),
+            subgroups: extensions.contains("cl_khr_subgroups")
+                || extensions.contains("cl_intel_subgroups"),
+            vector_width_float,
+            vector_width_half,
+        })
+    }
+
+    // Store the device capabilities and downgrade the precision mode if the
+    // device cannot run it
+    pub fn apply_device_capabilities(&mut self) -> Result<(), Box<dyn Error>> {
+        let capabilities = self.query_device_capabilities()?;
+        if self.precision_mode == PrecisionMode::FP16C && !capabilities.fp16 {
+            terminal_utils::print_warning(
+                "FP16C requires cl_khr_fp16 (half arithmetic), which this device lacks; \
+                 falling back to FP16S (half storage, float arithmetic, same memory use).",
+            );
+            self.precision_mode = PrecisionMode::FP16S;
+        }
+        self.capabilities = Some(capabilities);
+        Ok(())
+    }
+}
@@ -3,9 +3,23 @@
 #![allow(non_snake_case)] // Allow non-snake_case naming convention
 #![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
 use super::lbm::LBM;
+use crate::solver::flags::{CellType, FLAG_EQ, FLAG_FRESH, FLAG_SOLID};
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+use crate::utils::terminal_utils;
 
+use std::collections::VecDeque;
 use std::error::Error;
 
+#[derive(Debug, Clone, Default)]
+pub struct GeometryReport {
+    // Connected fluid regions with no path to an inlet/outlet (FLAG_EQ) cell.
+    // Without any FLAG_EQ cells, every region except the largest is reported.
+    pub isolated_pockets: Vec<(usize, (usize, usize, usize))>, // (cell count, first cell)
+    // Solid cells with non-solid cells on both sides along some axis
+    pub thin_wall_cells: usize,
+    pub first_thin_wall: Option<(usize, usize, usize)>,
+}
+
 impl LBM {
     pub fn check_errors_in_input(&mut self) -> Result<(), Box<dyn Error>> {
         // Check if the dimensions are positive
@@ -14,17 +28,10 @@ impl LBM {
             return Err("Dimensions Nx, Ny, and Nz must be greater than 0.".into());
         }
 
-        // Check if the model is supported
-        let supported_models = ["D2Q9", "D3Q7", "D3Q15", "D3Q19", "D3Q27"];
-        if !supported_models.contains(&self.model.as_str()) {
-            self.found_errors = true;
-            return Err(format!("Unsupported model: {}.", self.model).into());
-        }
-
-        // Check if D2Q9 model is used with Nz != 1
-        if self.model == "D2Q9" && self.Nz != 1 {
+        // Check if a 2D model is used with Nz != 1
+        if self.model.dimensions() == 2 && self.Nz != 1 {
             self.found_errors = true;
-            return Err("D2Q9 model should have Nz equal to 1.".into());
+            return Err(format!("{} model should have Nz equal to 1.", self.model).into());
         }
 
         // Check if viscosity is positive
@@ -47,6 +54,13 @@ impl LBM {
             self.found_errors = true;
             return Err("Flags vector has incorrect length.".into());
         }
+        // FLAG_FRESH only appears transiently on the device, e.g. in a checkpoint
+        let invalid = |flag: u8| flag != FLAG_FRESH && CellType::try_from(flag).is_err();
+        if let Some((n, &flag)) = self.flags.iter().enumerate().find(|(_, &flag)| invalid(flag)) {
+            self.found_errors = true;
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            return Err(format!("Invalid cell flag {} at ({}, {}, {}).", flag, x, y, z).into());
+        }
 
         // Check if OpenCL queue is available
         if let Some(queue) = &self.queue {
@@ -58,4 +72,105 @@ impl LBM {
 
         Ok(())
     }
+
+    // Connectivity and wall-thickness analysis of the flags array.
+    // Neighbourhoods follow the lattice velocity set with periodic wrap, like the kernel.
+    pub fn analyze_geometry(&self) -> GeometryReport {
+        let c = self.model.vectors();
+        let neighbor = |n: usize, d: &[i32; 3]| -> usize {
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            let xp = (x as i32 + d[0]).rem_euclid(self.Nx as i32) as usize;
+            let yp = (y as i32 + d[1]).rem_euclid(self.Ny as i32) as usize;
+            let zp = (z as i32 + d[2]).rem_euclid(self.Nz as i32) as usize;
+            n_from_xyz(&xp, &yp, &zp, &self.Nx, &self.Ny)
+        };
+
+        // Label connected non-solid regions
+        let mut label = vec![usize::MAX; self.N];
+        let mut regions: Vec<(usize, usize, bool)> = Vec::new(); // (size, seed, touches EQ)
+        let mut queue = VecDeque::new();
+        for seed in 0..self.N {
+            if self.flags[seed] == FLAG_SOLID || label[seed] != usize::MAX {
+                continue;
+            }
+            let id = regions.len();
+            let (mut size, mut connected) = (0usize, false);
+            label[seed] = id;
+            queue.push_back(seed);
+            while let Some(n) = queue.pop_front() {
+                size += 1;
+                connected |= self.flags[n] == FLAG_EQ;
+                for d in c.iter().skip(1) {
+                    let np = neighbor(n, d);
+                    if self.flags[np] != FLAG_SOLID && label[np] == usize::MAX {
+                        label[np] = id;
+                        queue.push_back(np);
+                    }
+                }
+            }
+            regions.push((size, seed, connected));
+        }
+
+        let has_eq = regions.iter().any(|r| r.2);
+        let largest = regions
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, r)| r.0)
+            .map(|(i, _)| i);
+        let isolated_pockets = regions
+            .iter()
+            .enumerate()
+            .filter(|(i, r)| if has_eq { !r.2 } else { Some(*i) != largest })
+            .map(|(_, r)| (r.0, xyz_from_n(&r.1, &self.Nx, &self.Ny)))
+            .collect();
+
+        // One-cell-thick walls along the coordinate axes
+        let axes: &[[i32; 3]] = if self.Nz == 1 {
+            &[[1, 0, 0], [0, 1, 0]]
+        } else {
+            &[[1, 0, 0], [0, 1, 0], [0, 0, 1]]
+        };
+        let mut thin_wall_cells = 0;
+        let mut first_thin_wall = None;
+        for n in 0..self.N {
+            if self.flags[n] != FLAG_SOLID {
+                continue;
+            }
+            let thin = axes.iter().any(|a| {
+                let back = [-a[0], -a[1], -a[2]];
+                self.flags[neighbor(n, a)] != FLAG_SOLID && self.flags[neighbor(n, &back)] != FLAG_SOLID
+            });
+            if thin {
+                thin_wall_cells += 1;
+                first_thin_wall.get_or_insert(xyz_from_n(&n, &self.Nx, &self.Ny));
+            }
+        }
+
+        GeometryReport {
+            isolated_pockets,
+            thin_wall_cells,
+            first_thin_wall,
+        }
+    }
+
+    // Run analyze_geometry and print warnings for anything suspicious
+    pub fn check_geometry(&self) -> GeometryReport {
+        let report = self.analyze_geometry();
+        if !report.isolated_pockets.is_empty() {
+            let cells: usize = report.isolated_pockets.iter().map(|p| p.0).sum();
+            terminal_utils::print_warning(&format!(
+                "Found {} isolated fluid pocket(s) ({} cells) not connected to any inlet/outlet, e.g. at {:?}.",
+                report.isolated_pockets.len(),
+                cells,
+                report.isolated_pockets[0].1
+            ));
+        }
+        if let Some(at) = report.first_thin_wall {
+            terminal_utils::print_warning(&format!(
+                "Found {} solid cells in one-cell-thick walls (e.g. at {:?}); bounce-back may leak through them.",
+                report.thin_wall_cells, at
+            ));
+        }
+        report
+    }
 }
@@ -0,0 +1,283 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Binary checkpoints of the full simulation state: lattice parameters, forces,
+// flags, macroscopic fields and the raw device populations, so a run resumes
+// exactly where it stopped. Outputs, bodies and monitors are not stored and
+// must be configured again after loading. Files may be zstd-compressed as a
+// whole; the loader detects this from the zstd frame magic.
+
+use super::lbm::LBM;
+use crate::solver::precision::PrecisionMode;
+use crate::solver::velocity_sets::VelocitySet;
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::thread;
+
+const CHECKPOINT_MAGIC: &[u8; 8] = b"CAPPUCKP";
+const CHECKPOINT_VERSION: u32 = 2; // 2: streaming mode
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+fn write_u64(w: &mut impl Write, v: usize) -> std::io::Result<()> {
+    w.write_all(&(v as u64).to_le_bytes())
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> std::io::Result<()> {
+    write_u64(w, s.len())?;
+    w.write_all(s.as_bytes())
+}
+
+fn write_f32s(w: &mut impl Write, values: &[f32]) -> std::io::Result<()> {
+    write_u64(w, values.len())?;
+    for v in values {
+        w.write_all(&v.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_u64(r: &mut impl Read) -> std::io::Result<usize> {
+    let mut b = [0u8; 8];
+    r.read_exact(&mut b)?;
+    Ok(u64::from_le_bytes(b) as usize)
+}
+
+fn read_u8(r: &mut impl Read) -> std::io::Result<u8> {
+    let mut b = [0u8; 1];
+    r.read_exact(&mut b)?;
+    Ok(b[0])
+}
+
+fn read_f32(r: &mut impl Read) -> std::io::Result<f32> {
+    let mut b = [0u8; 4];
+    r.read_exact(&mut b)?;
+    Ok(f32::from_le_bytes(b))
+}
+
+fn read_bytes(r: &mut impl Read) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![0u8; read_u64(r)?];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_str(r: &mut impl Read) -> Result<String, Box<dyn Error>> {
+    Ok(String::from_utf8(read_bytes(r)?)?)
+}
+
+fn read_u16s(r: &mut impl Read) -> std::io::Result<Vec<u16>> {
+    let mut bytes = vec![0u8; read_u64(r)? * 2];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes
+        .chunks_exact(2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .collect())
+}
+
+fn read_f32s(r: &mut impl Read) -> std::io::Result<Vec<f32>> {
+    let mut bytes = vec![0u8; read_u64(r)? * 4];
+    r.read_exact(&mut bytes)?;
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+impl LBM {
+    // Write the current state to `path`. During or after run() the fields and
+    // populations are read back from the device first; before initialize()
+    // only the host state is stored and the load starts from equilibrium.
+    pub fn save_checkpoint(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let f = self.read_checkpoint_state()?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        if let Some(level) = self.checkpoint_compression {
+            let mut encoder = zstd::Encoder::new(writer, level)?;
+            self.write_checkpoint(&mut encoder, &f)?;
+            encoder.finish()?.flush()?;
+        } else {
+            self.write_checkpoint(&mut writer, &f)?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    // Compress saved and autosaved checkpoints with zstd at `level` (1-22,
+    // 3 is a good default), or store them uncompressed with None
+    pub fn set_checkpoint_compression(&mut self, level: Option<i32>) {
+        self.checkpoint_compression = level;
+    }
+
+    // Bring the host state up to date and return the raw populations (empty
+    // before initialize())
+    fn read_checkpoint_state(&mut self) -> Result<Vec<f32>, Box<dyn Error>> {
+        if self.f_buffer.is_none() {
+            return Ok(vec![]);
+        }
+        self.read_from_gpu()?;
+        self.read_flags_from_gpu()?;
+        self.read_f_raw_from_gpu()
+    }
+
+    // Keep a rolling set of the last `keep_last` checkpoints, written every
+    // `interval` steps of run() to checkpoints/checkpoint_<step>.ckpt
+    pub fn set_checkpoint_interval(&mut self, interval: usize, keep_last: usize) {
+        self.checkpoint_interval = interval;
+        self.checkpoint_keep = keep_last.max(1);
+    }
+
+    // Snapshot the state and hand the file write to a background thread so the
+    // GPU loop only waits for the readback. At most one write is in flight.
+    pub fn autosave_checkpoint(&mut self) -> Result<(), Box<dyn Error>> {
+        self.finish_autosave()?;
+        let f = self.read_checkpoint_state()?;
+        let mut bytes = Vec::new();
+        self.write_checkpoint(&mut bytes, &f)?;
+        drop(f);
+        let level = self.checkpoint_compression;
+
+        fs::create_dir_all("checkpoints")?;
+        let path = format!("checkpoints/checkpoint_{}.ckpt", self.time_step);
+        self.checkpoint_files.push_back(path.clone());
+        let mut expired = Vec::new();
+        while self.checkpoint_files.len() > self.checkpoint_keep {
+            expired.extend(self.checkpoint_files.pop_front());
+        }
+        self.checkpoint_writer = Some(thread::spawn(move || {
+            // Write beside the target and rename, so a crash mid-write never
+            // leaves a truncated checkpoint behind
+            let partial = format!("{}.partial", path);
+            match level {
+                Some(level) => fs::write(&partial, zstd::encode_all(&bytes[..], level)?)?,
+                None => fs::write(&partial, &bytes)?,
+            }
+            fs::rename(&partial, &path)?;
+            for old in expired {
+                if Path::new(&old).exists() {
+                    fs::remove_file(old)?;
+                }
+            }
+            Ok(())
+        }));
+        Ok(())
+    }
+
+    // Wait for the pending background checkpoint write, if any
+    pub fn finish_autosave(&mut self) -> Result<(), Box<dyn Error>> {
+        if let Some(writer) = self.checkpoint_writer.take() {
+            writer
+                .join()
+                .map_err(|_| "Checkpoint writer thread panicked")??;
+        }
+        Ok(())
+    }
+
+    fn write_checkpoint(&self, w: &mut impl Write, f: &[f32]) -> Result<(), Box<dyn Error>> {
+        w.write_all(CHECKPOINT_MAGIC)?;
+        w.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+
+        // Lattice and parameters
+        write_u64(w, self.Nx)?;
+        write_u64(w, self.Ny)?;
+        write_u64(w, self.Nz)?;
+        write_str(w, self.model.as_str())?;
+        write_str(w, &format!("{:?}", self.precision_mode))?;
+        w.write_all(&self.viscosity.to_le_bytes())?;
+        write_u64(w, self.time_step)?;
+        w.write_all(&[
+            self.use_moving_walls as u8,
+            self.symmetry_planes,
+            self.use_constant_force as u8,
+            self.use_force_field as u8,
+            self.in_place_streaming as u8, // Layout of the raw populations
+        ])?;
+        write_f32s(w, self.constant_force.as_deref().unwrap_or(&[]))?;
+        write_f32s(w, &self.static_force_field())?;
+
+        // Lattice fields
+        write_u64(w, self.flags.len())?;
+        w.write_all(&self.flags)?;
+        write_u64(w, self.body_ids.len())?;
+        for id in &self.body_ids {
+            w.write_all(&id.to_le_bytes())?;
+        }
+        write_u64(w, self.body_names.len())?;
+        for name in &self.body_names {
+            write_str(w, name)?;
+        }
+        write_f32s(w, &self.density)?;
+        write_f32s(w, &self.u)?;
+        write_f32s(w, f)?;
+        Ok(())
+    }
+
+    // Rebuild a simulation from a checkpoint written by save_checkpoint. The
+    // stored populations are uploaded by run(), which continues from the
+    // saved time step.
+    pub fn load_checkpoint(path: &str) -> Result<LBM, Box<dyn Error>> {
+        let mut reader = BufReader::new(File::open(path)?);
+        if reader.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+            LBM::read_checkpoint(&mut zstd::Decoder::with_buffer(reader)?)
+        } else {
+            LBM::read_checkpoint(&mut reader)
+        }
+    }
+
+    fn read_checkpoint(r: &mut impl Read) -> Result<LBM, Box<dyn Error>> {
+        let mut magic = [0u8; 8];
+        r.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err("Not a CappuSim checkpoint".into());
+        }
+        let mut version = [0u8; 4];
+        r.read_exact(&mut version)?;
+        let version = u32::from_le_bytes(version);
+        if version != CHECKPOINT_VERSION {
+            return Err(format!("Unsupported checkpoint version {}", version).into());
+        }
+
+        let (nx, ny, nz) = (read_u64(r)?, read_u64(r)?, read_u64(r)?);
+        let model: VelocitySet = read_str(r)?.parse()?;
+        let precision: PrecisionMode = read_str(r)?.parse()?;
+        let viscosity = read_f32(r)?;
+        let mut lbm = LBM::new(nx, ny, nz, model, viscosity, precision);
+        lbm.time_step = read_u64(r)?;
+        lbm.use_moving_walls = read_u8(r)? != 0;
+        lbm.symmetry_planes = read_u8(r)?;
+        lbm.use_constant_force = read_u8(r)? != 0;
+        lbm.use_force_field = read_u8(r)? != 0;
+        // The raw populations are only valid in the mode they were written in
+        lbm.in_place_streaming = read_u8(r)? != 0;
+        let constant_force = read_f32s(r)?;
+        if !constant_force.is_empty() {
+            lbm.constant_force = Some(constant_force);
+        }
+        lbm.force_field = read_f32s(r)?;
+
+        let n = lbm.N;
+        let flags = read_bytes(r)?;
+        if flags.len() != n {
+            return Err("Checkpoint flags do not match the lattice size".into());
+        }
+        lbm.flags = flags;
+        lbm.body_ids = read_u16s(r)?;
+        let names = read_u64(r)?;
+        for _ in 0..names {
+            lbm.body_names.push(read_str(r)?);
+        }
+        lbm.density = read_f32s(r)?;
+        lbm.u = read_f32s(r)?;
+        lbm.velocity = vec![];
+        if lbm.density.len() != n || lbm.u.len() != n * 3 {
+            return Err("Checkpoint fields do not match the lattice size".into());
+        }
+        let f = read_f32s(r)?;
+        if !f.is_empty() {
+            if f.len() != lbm.precision_mode.f_buffer_len(n * lbm.Q) {
+                return Err("Checkpoint populations do not match the lattice size".into());
+            }
+            lbm.checkpoint_f = Some(f);
+        }
+        Ok(lbm)
+    }
+}
@@ -0,0 +1,126 @@
+// src/solver/conditions.rs
+// Evaluation of the per-cell initial condition closure, serial or on threads
+
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::CellType;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+use crate::utils::velocity::Velocity;
+
+use std::ops::{Index, IndexMut};
+use std::thread;
+
+// Contiguous range of a per-cell field, indexed by global cell index
+pub struct Cells<'a, T> {
+    offset: usize,
+    data: &'a mut [T],
+}
+
+impl<T> Index<usize> for Cells<'_, T> {
+    type Output = T;
+
+    fn index(&self, n: usize) -> &T {
+        &self.data[n - self.offset]
+    }
+}
+
+impl<T> IndexMut<usize> for Cells<'_, T> {
+    fn index_mut(&mut self, n: usize) -> &mut T {
+        &mut self.data[n - self.offset]
+    }
+}
+
+// Fields one worker thread of par_set_conditions may write. Only the cells of
+// the worker's range are reachable, indexed like the LBM fields.
+pub struct CellConditions<'a> {
+    pub Nx: usize,
+    pub Ny: usize,
+    pub Nz: usize,
+    pub N: usize,
+    pub flags: Cells<'a, u8>,
+    pub density: Cells<'a, f32>,
+    pub velocity: Cells<'a, Velocity>,
+}
+
+impl CellConditions<'_> {
+    pub fn set_cell(&mut self, x: usize, y: usize, z: usize, cell: CellType) {
+        let n = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
+        self.flags[n] = cell.into();
+    }
+
+    pub fn cell(&self, x: usize, y: usize, z: usize) -> Option<CellType> {
+        let n = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
+        CellType::try_from(self.flags[n]).ok()
+    }
+}
+
+impl LBM {
+    // The velocity staging array is consumed into u by the first call
+    fn assert_velocity_staged(&self) {
+        assert!(
+            self.velocity.len() == self.N,
+            "set_conditions() was already called; set further velocities with set_region_velocity() or velocity_at_mut()"
+        );
+    }
+
+    pub fn set_conditions<F>(&mut self, f: F)
+    where
+        F: Fn(&mut LBM, usize, usize, usize, usize), // x, y, z, n
+    {
+        self.assert_velocity_staged();
+        for n in 0..self.N {
+            // Get the x, y, z coordinates from the linear index n
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            // Call the user-defined lambda function
+            f(self, x, y, z, n);
+        }
+        self.u = self.velocity_to_u(); // Transform 3D array to Flattened array
+        self.velocity = vec![];
+    }
+
+    // Parallel set_conditions: f(cells, x, y, z, n) is evaluated for contiguous
+    // ranges of cells on separate threads, so it may only touch cell n.
+    pub fn par_set_conditions<F>(&mut self, f: F)
+    where
+        F: Fn(&mut CellConditions, usize, usize, usize, usize) + Sync, // x, y, z, n
+    {
+        self.assert_velocity_staged();
+        let threads = thread::available_parallelism().map_or(1, |t| t.get());
+        let chunk = self.N.div_ceil(threads).max(1);
+        let (Nx, Ny, Nz, N) = (self.Nx, self.Ny, self.Nz, self.N);
+
+        thread::scope(|scope| {
+            let ranges = self
+                .flags
+                .chunks_mut(chunk)
+                .zip(self.density.chunks_mut(chunk))
+                .zip(self.velocity.chunks_mut(chunk))
+                .enumerate();
+            for (i, ((flags, density), velocity)) in ranges {
+                let f = &f;
+                scope.spawn(move || {
+                    let offset = i * chunk;
+                    let len = flags.len();
+                    let mut cells = CellConditions {
+                        Nx,
+                        Ny,
+                        Nz,
+                        N,
+                        flags: Cells { offset, data: flags },
+                        density: Cells { offset, data: density },
+                        velocity: Cells { offset, data: velocity },
+                    };
+                    for n in offset..offset + len {
+                        let (x, y, z) = xyz_from_n(&n, &Nx, &Ny);
+                        f(&mut cells, x, y, z, n);
+                    }
+                });
+            }
+        });
+
+        self.u = self.velocity_to_u(); // Transform 3D array to Flattened array
+        self.velocity = vec![];
+    }
+}
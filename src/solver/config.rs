@@ -0,0 +1,277 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Serializable description of a simulation setup: grid, model, parameters,
+// outputs, uniform initial state, box-shaped boundary conditions and geometry
+// primitives. It converts to an LBM through the builder and back with
+// LBM::config(), as the common format for case files (config_file.rs, which
+// deserializes it directly) and parameter sweeps. Omitted fields take the
+// defaults below. Per-cell geometry set by set_conditions() is not part of
+// it; only the boundaries and primitives listed here are reapplied by build().
+
+use super::lbm::LBM;
+use crate::solver::flags::CellType;
+use crate::solver::precision::PrecisionMode;
+use crate::solver::region::Region;
+use crate::solver::transforms::Axis;
+use crate::solver::velocity_sets::VelocitySet;
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+const AXES: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+fn solid() -> CellType {
+    CellType::Solid
+}
+
+fn z_axis() -> Axis {
+    Axis::Z
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SimulationConfig {
+    pub grid: [usize; 3],
+    #[serde(default)]
+    pub model: Option<VelocitySet>, // Builder default (D2Q9 for Nz = 1, D3Q19 otherwise) when None
+    pub viscosity: f32,
+    #[serde(default)]
+    pub precision: PrecisionMode,
+    #[serde(default)]
+    pub force: Option<[f32; 3]>, // Uniform body force density
+    #[serde(default, rename = "output")]
+    pub outputs: OutputConfig,
+    #[serde(default)]
+    pub boundaries: BoundaryConfig,
+    #[serde(default)]
+    pub initial: InitialConfig,
+    // Applied after the boundary boxes, in order
+    #[serde(default)]
+    pub geometry: Vec<GeometryPrimitive>,
+    #[serde(default)]
+    pub time_steps: usize, // Steps run by run_config_file()
+}
+
+// Uniform initial state of every cell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InitialConfig {
+    pub density: f32,
+    pub velocity: [f32; 3],
+}
+
+impl Default for InitialConfig {
+    fn default() -> Self {
+        InitialConfig {
+            density: 1.0,
+            velocity: [0.0; 3],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub interval: usize, // 0 disables field output
+    pub csv: bool,
+    pub vtk: bool,
+    pub vti: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoundaryConfig {
+    #[serde(default)]
+    pub moving_walls: bool,
+    #[serde(default)]
+    pub symmetry_planes: Vec<SymmetryPlane>,
+    // Applied in order, so later boxes overwrite earlier ones
+    #[serde(default)]
+    pub boxes: Vec<BoundaryBox>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SymmetryPlane {
+    pub axis: Axis,
+    #[serde(default)]
+    pub upper: bool, // Face at the maximum coordinate instead of 0
+}
+
+// Inclusive box of cells set to `cell`, with an optional prescribed velocity
+// (for equilibrium cells, or solid cells with moving walls)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BoundaryBox {
+    pub min: [usize; 3],
+    pub max: [usize; 3],
+    pub cell: CellType,
+    #[serde(default)]
+    pub velocity: Option<[f32; 3]>,
+}
+
+// Cells inside `shape` are set to `cell` (solid by default), with an optional
+// prescribed velocity. The shape fields sit next to these, e.g.
+// { shape = "sphere", center = [...], radius = 4.0, cell = "solid" }. Unknown
+// keys are rejected by Shape, which receives every key not listed here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeometryPrimitive {
+    #[serde(flatten)]
+    pub shape: Shape,
+    #[serde(default = "solid")]
+    pub cell: CellType,
+    #[serde(default)]
+    pub velocity: Option<[f32; 3]>,
+}
+
+// Shapes in lattice coordinates (cell centres at integer positions)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Shape {
+    Sphere {
+        center: [f32; 3],
+        radius: f32,
+    },
+    // Infinite along `axis`; a circle in 2D with axis Z
+    Cylinder {
+        center: [f32; 3],
+        radius: f32,
+        #[serde(default = "z_axis")]
+        axis: Axis,
+    },
+    Cuboid {
+        min: [f32; 3],
+        max: [f32; 3],
+    },
+}
+
+impl Shape {
+    pub fn contains(&self, x: usize, y: usize, z: usize) -> bool {
+        let p = [x as f32, y as f32, z as f32];
+        match self {
+            Shape::Sphere { center, radius } => {
+                (0..3).map(|d| (p[d] - center[d]).powi(2)).sum::<f32>() <= radius * radius
+            }
+            Shape::Cylinder {
+                center,
+                radius,
+                axis,
+            } => {
+                (0..3)
+                    .filter(|&d| d != axis.index())
+                    .map(|d| (p[d] - center[d]).powi(2))
+                    .sum::<f32>()
+                    <= radius * radius
+            }
+            Shape::Cuboid { min, max } => (0..3).all(|d| p[d] >= min[d] && p[d] <= max[d]),
+        }
+    }
+}
+
+impl SimulationConfig {
+    // Create and set up the lattice described by the config
+    pub fn build(&self) -> Result<LBM, Box<dyn Error>> {
+        let [Nx, Ny, Nz] = self.grid;
+        let mut builder = LBM::builder()
+            .size(Nx, Ny, Nz)
+            .viscosity(self.viscosity)
+            .precision(self.precision)
+            .output_interval(self.outputs.interval)
+            .output_csv(self.outputs.csv)
+            .output_vtk(self.outputs.vtk)
+            .output_vti(self.outputs.vti);
+        if let Some(model) = self.model {
+            builder = builder.model(model);
+        }
+        if let Some(force) = self.force {
+            builder = builder.constant_force(force);
+        }
+        let mut lbm = builder.build()?;
+
+        if self.boundaries.moving_walls {
+            lbm.enable_moving_walls();
+        }
+        for plane in &self.boundaries.symmetry_planes {
+            lbm.set_symmetry_plane(plane.axis, plane.upper);
+        }
+        if self.initial != InitialConfig::default() {
+            lbm.density.fill(self.initial.density);
+            let everywhere = Region::bbox((0, 0, 0), (Nx - 1, Ny - 1, Nz - 1));
+            lbm.set_region_velocity(&everywhere, self.initial.velocity)?;
+        }
+        for b in &self.boundaries.boxes {
+            let region = Region::bbox(
+                (b.min[0], b.min[1], b.min[2]),
+                (b.max[0], b.max[1], b.max[2]),
+            );
+            lbm.fill_flags(&region, b.cell.into())?;
+            if let Some(velocity) = b.velocity {
+                lbm.set_region_velocity(&region, velocity)?;
+            }
+        }
+        for primitive in &self.geometry {
+            let shape = primitive.shape.clone();
+            let region = Region::predicate(move |x, y, z| shape.contains(x, y, z));
+            lbm.fill_flags(&region, primitive.cell.into())?;
+            if let Some(velocity) = primitive.velocity {
+                lbm.set_region_velocity(&region, velocity)?;
+            }
+        }
+        Ok(lbm)
+    }
+}
+
+impl LBM {
+    // Config of this lattice; boundaries holds only the global settings, as
+    // the flags array is not reduced back to boxes
+    pub fn config(&self) -> SimulationConfig {
+        let force = match (&self.constant_force, self.use_constant_force) {
+            (Some(f), true) if f.len() == 3 => Some([f[0], f[1], f[2]]),
+            _ => None,
+        };
+        let mut symmetry_planes = Vec::new();
+        for axis in AXES {
+            for upper in [false, true] {
+                if self.symmetry_planes & (1 << (2 * axis.index() + upper as usize)) != 0 {
+                    symmetry_planes.push(SymmetryPlane { axis, upper });
+                }
+            }
+        }
+        SimulationConfig {
+            grid: [self.Nx, self.Ny, self.Nz],
+            model: Some(self.model),
+            viscosity: self.viscosity,
+            precision: self.precision_mode,
+            force,
+            outputs: OutputConfig {
+                interval: self.output_interval,
+                csv: self.output_csv,
+                vtk: self.output_vtk,
+                vti: self.output_vti,
+            },
+            boundaries: BoundaryConfig {
+                moving_walls: self.use_moving_walls,
+                symmetry_planes,
+                boxes: Vec::new(),
+            },
+            initial: InitialConfig::default(),
+            geometry: Vec::new(),
+            time_steps: self.time_steps,
+        }
+    }
+}
+
+impl From<&LBM> for SimulationConfig {
+    fn from(lbm: &LBM) -> Self {
+        lbm.config()
+    }
+}
+
+impl TryFrom<&SimulationConfig> for LBM {
+    type Error = Box<dyn Error>;
+
+    fn try_from(config: &SimulationConfig) -> Result<Self, Self::Error> {
+        config.build()
+    }
+}
@@ -0,0 +1,189 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// TOML case files, deserialized into a SimulationConfig (config.rs holds the
+// schema and defaults) so routine cases run without writing Rust
+// (`cappusim run case.toml`). Only `grid` and `viscosity` are required, and
+// unknown tables or keys are errors rather than being ignored:
+//
+//     grid = [400, 100, 1]
+//     model = "D2Q9"              # D2Q9 for nz = 1, D3Q19 otherwise
+//     viscosity = 0.02
+//     precision = "FP32"
+//     force = [0.0, 0.0, 0.0]     # Uniform body force density
+//     time_steps = 20000
+//
+//     [initial]
+//     density = 1.0
+//     velocity = [0.05, 0.0, 0.0]
+//
+//     [output]
+//     interval = 500
+//     vti = true                  # Also csv, vtk
+//
+//     [boundaries]
+//     moving_walls = false
+//     symmetry_planes = [{ axis = "y", upper = true }]
+//
+//     [[boundaries.boxes]]        # Inclusive cell ranges, applied in order
+//     min = [0, 0, 0]
+//     max = [0, 99, 0]
+//     cell = "equilibrium"        # fluid, solid or equilibrium
+//     velocity = [0.05, 0.0, 0.0]
+//
+//     [[geometry]]                # Applied after the boxes
+//     shape = "cylinder"          # sphere, cylinder (with axis) or cuboid (min, max)
+//     center = [80.0, 50.0, 0.0]
+//     radius = 10.0
+//     axis = "z"
+//     cell = "solid"              # Default
+
+use super::lbm::LBM;
+use crate::solver::config::SimulationConfig;
+
+use std::error::Error;
+use std::path::Path;
+
+impl SimulationConfig {
+    // Parse a TOML case description (see the top of this file)
+    pub fn from_toml(text: &str) -> Result<SimulationConfig, Box<dyn Error>> {
+        Ok(toml::from_str(text)?)
+    }
+
+    // Read a case file; the format follows the extension
+    pub fn from_file(path: &str) -> Result<SimulationConfig, Box<dyn Error>> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        match extension.as_str() {
+            "toml" => {
+                let text = std::fs::read_to_string(path)?;
+                Self::from_toml(&text).map_err(|e| format!("{}: {}", path, e).into())
+            }
+            _ => Err(format!("Unsupported case file format '{}'; use a .toml file.", path).into()),
+        }
+    }
+}
+
+impl LBM {
+    // Lattice described by a case file, ready for set_conditions() or run()
+    pub fn from_config_file(path: &str) -> Result<LBM, Box<dyn Error>> {
+        SimulationConfig::from_file(path)?.build()
+    }
+
+    // Build and run a case file for its `time_steps`
+    pub fn run_config_file(path: &str) -> Result<(), Box<dyn Error>> {
+        let config = SimulationConfig::from_file(path)?;
+        if config.time_steps == 0 {
+            return Err(format!("{}: 'time_steps' must be set to run the case.", path).into());
+        }
+        let mut lbm = config.build()?;
+        lbm.run(config.time_steps);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::config::{Shape, SimulationConfig};
+    use crate::solver::flags::CellType;
+    use crate::solver::precision::PrecisionMode;
+    use crate::solver::transforms::Axis;
+    use crate::solver::velocity_sets::VelocitySet;
+
+    const CYLINDER: &str = include_str!("../../examples/cases/cylinder.toml");
+
+    #[test]
+    fn parses_example_case() {
+        let config = SimulationConfig::from_toml(CYLINDER).unwrap();
+        assert_eq!(config.grid, [400, 100, 1]);
+        assert_eq!(config.model, Some(VelocitySet::D2Q9));
+        assert_eq!(config.viscosity, 0.02);
+        assert_eq!(config.time_steps, 20000);
+        assert_eq!(config.initial.density, 1.0);
+        assert_eq!(config.initial.velocity, [0.05, 0.0, 0.0]);
+        assert_eq!(config.outputs.interval, 500);
+        assert!(config.outputs.vti && !config.outputs.vtk && !config.outputs.csv);
+
+        let boxes = &config.boundaries.boxes;
+        assert_eq!(boxes.len(), 4);
+        assert_eq!(boxes[0].cell, CellType::Equilibrium);
+        assert_eq!(boxes[0].velocity, Some([0.05, 0.0, 0.0]));
+        assert_eq!(boxes[3].min, [0, 99, 0]);
+        assert_eq!(boxes[3].cell, CellType::Solid);
+        assert_eq!(boxes[3].velocity, None);
+
+        assert_eq!(config.geometry.len(), 1);
+        assert_eq!(config.geometry[0].cell, CellType::Solid);
+        assert_eq!(
+            config.geometry[0].shape,
+            Shape::Cylinder {
+                center: [80.0, 50.0, 0.0],
+                radius: 10.0,
+                axis: Axis::Z,
+            }
+        );
+    }
+
+    #[test]
+    fn fills_defaults() {
+        let config = SimulationConfig::from_toml(
+            "grid = [8, 8, 8]\nviscosity = 0.1\n\n[[geometry]]\nshape = \"sphere\"\ncenter = [4, 4, 4]\nradius = 2\n",
+        )
+        .unwrap();
+        assert_eq!(config.model, None);
+        assert_eq!(config.precision, PrecisionMode::FP32);
+        assert_eq!(config.force, None);
+        assert_eq!(config.time_steps, 0);
+        assert_eq!(config.initial.density, 1.0);
+        assert!(config.boundaries.boxes.is_empty());
+        // Integers are accepted for floats and the cell type defaults to solid
+        assert_eq!(
+            config.geometry[0].shape,
+            Shape::Sphere {
+                center: [4.0, 4.0, 4.0],
+                radius: 2.0,
+            }
+        );
+        assert_eq!(config.geometry[0].cell, CellType::Solid);
+    }
+
+    #[test]
+    fn rejects_invalid_cases() {
+        assert!(SimulationConfig::from_toml("grid = [8, 8, 1]\n").is_err());
+        assert!(SimulationConfig::from_toml("grid = [8, 8]\nviscosity = 0.1\n").is_err());
+        assert!(SimulationConfig::from_toml(
+            "grid = [8, 8, 1]\nviscosity = 0.1\n[[geometry]]\nshape = \"torus\"\n"
+        )
+        .is_err());
+        assert!(SimulationConfig::from_toml(
+            "grid = [8, 8, 1]\nviscosity = 0.1\n[[boundaries.boxes]]\nmin = [0, 0, 0]\nmax = [0, 7, 0]\ncell = \"wall\"\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        let base = "grid = [8, 8, 1]\nviscosity = 0.1\n";
+        // Misspelled table, key in a nested table, and key next to a shape
+        for extra in [
+            "[outputs]\ninterval = 10\n",
+            "[output]\ninterval = 10\nvtu = true\n",
+            "[[boundaries.boxes]]\nmin = [0, 0, 0]\nmax = [0, 7, 0]\ncell = \"solid\"\nvelocty = [0.1, 0, 0]\n",
+            "[[geometry]]\nshape = \"sphere\"\ncenter = [4, 4, 0]\nradius = 2\nradus = 3\n",
+        ] {
+            let text = format!("{}{}", base, extra);
+            assert!(SimulationConfig::from_toml(&text).is_err(), "accepted:\n{}", text);
+        }
+        assert!(SimulationConfig::from_toml(&format!("{}timesteps = 10\n", base)).is_err());
+    }
+
+    #[test]
+    fn round_trips_through_toml() {
+        let config = SimulationConfig::from_toml(CYLINDER).unwrap();
+        let text = toml::to_string(&config).unwrap();
+        assert_eq!(SimulationConfig::from_toml(&text).unwrap(), config);
+    }
+}
@@ -0,0 +1,150 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::{FLAG_EQ, FLAG_SOLID};
+use crate::solver::precision::PrecisionMode;
+use crate::solver::reductions::Reduction;
+use crate::utils::terminal_utils;
+
+use ocl::Kernel;
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConservationSample {
+    pub step: usize,
+    pub mass: f64,
+    pub momentum: [f64; 3],
+    pub mass_drift: f64, // Relative to the initial mass
+    pub scale: f32,      // Correction applied at this step (1.0 = none)
+}
+
+impl LBM {
+    // Track global mass and momentum every `interval` steps (0 disables).
+    // With `correct`, FP16C runs in closed domains (no FLAG_EQ cells) get their
+    // populations rescaled so the total mass returns to its initial value.
+    pub fn set_conservation_monitor(&mut self, interval: usize, correct: bool) {
+        self.conservation_interval = interval;
+        self.conservation_correction = correct;
+    }
+
+    // Total mass and momentum of all non-solid cells from the host fields
+    pub fn global_mass_momentum(&self) -> (f64, [f64; 3]) {
+        let mut mass = 0.0f64;
+        let mut momentum = [0.0f64; 3];
+        for n in 0..self.N {
+            if self.flags[n] == FLAG_SOLID {
+                continue;
+            }
+            let rho = self.density[n] as f64;
+            mass += rho;
+            for (d, m) in momentum.iter_mut().enumerate() {
+                *m += rho * self.u[n * 3 + d] as f64;
+            }
+        }
+        (mass, momentum)
+    }
+
+    // Total mass and momentum reduced on the device fields
+    pub fn device_mass_momentum(&self) -> Result<(f64, [f64; 3]), Box<dyn Error>> {
+        let density = self
+            .density_buffer
+            .as_ref()
+            .ok_or("Density buffer is None")?;
+        let velocity = self.u_buffer.as_ref().ok_or("Velocity buffer is None")?;
+        let mass = self.field_sum(density, 1)?[0];
+        let momentum = self.reduce_field(velocity, 3, Reduction::Sum, Some(density), None)?;
+        Ok((mass, [momentum[0], momentum[1], momentum[2]]))
+    }
+
+    // Record the reference totals from the initial condition
+    pub fn start_conservation_monitor(&mut self) {
+        self.conservation_history.clear();
+        let (mass, momentum) = self.global_mass_momentum();
+        self.conservation_history.push(ConservationSample {
+            step: 0,
+            mass,
+            momentum,
+            mass_drift: 0.0,
+            scale: 1.0,
+        });
+
+        let open = self.flags.contains(&FLAG_EQ);
+        if self.conservation_correction && open {
+            terminal_utils::print_warning(
+                "Mass correction disabled: FLAG_EQ cells exchange mass with the outside.",
+            );
+        } else if self.conservation_correction && self.precision_mode != PrecisionMode::FP16C {
+            terminal_utils::print_warning(
+                "Mass correction is only applied in FP16C mode; drift is monitored only.",
+            );
+        }
+    }
+
+    // Sample the totals at the current step and rescale if requested
+    pub fn update_conservation_monitor(&mut self) -> Result<(), Box<dyn Error>> {
+        let Some(reference) = self.conservation_history.first().copied() else {
+            return Ok(());
+        };
+        let (mass, momentum) = self.device_mass_momentum()?;
+        let mass_drift = if reference.mass > 0.0 {
+            (mass - reference.mass) / reference.mass
+        } else {
+            0.0
+        };
+
+        let correct = self.conservation_correction
+            && self.precision_mode == PrecisionMode::FP16C
+            && !self.flags.contains(&FLAG_EQ)
+            && mass > 0.0;
+        let scale = if correct {
+            (reference.mass / mass) as f32
+        } else {
+            1.0
+        };
+        if scale != 1.0 {
+            self.rescale_populations(scale)?;
+        }
+
+        self.conservation_history.push(ConservationSample {
+            step: self.time_step,
+            mass,
+            momentum,
+            mass_drift,
+            scale,
+        });
+        Ok(())
+    }
+
+    // Multiply the populations of all non-solid cells by `scale` on the device
+    pub fn rescale_populations(&mut self, scale: f32) -> Result<(), Box<dyn Error>> {
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("rescale_kernel")
+            .queue(queue.clone())
+            .global_work_size(self.N)
+            .arg(self.f_buffer.as_ref().ok_or("f buffer is None")?)
+            .arg(self.f_new_buffer.as_ref().ok_or("f_new buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(scale)
+            .arg(self.time_step as i32)
+            .build()
+            .map_err(|e| format!("Failed to build 'rescale_kernel': {}", e))?;
+        unsafe {
+            kernel
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'rescale_kernel': {}", e))?;
+        }
+        queue.finish()?;
+        Ok(())
+    }
+
+    // Largest relative mass drift seen so far
+    pub fn max_mass_drift(&self) -> f64 {
+        self.conservation_history
+            .iter()
+            .map(|s| s.mass_drift.abs())
+            .fold(0.0, f64::max)
+    }
+}
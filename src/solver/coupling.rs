@@ -0,0 +1,90 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Two-way momentum coupling of inertial point particles: the drag each
+// particle feels is returned to the fluid as a body force spread with the
+// same trilinear weights used to interpolate the fluid velocity.
+
+use super::lbm::LBM;
+use crate::solver::sparse::{CellForces, CellIndex};
+use crate::solver::tracers::DragModel;
+use crate::solver::transforms::n_from_xyz;
+
+use std::error::Error;
+use std::f32::consts::PI;
+
+impl LBM {
+    // Return particle drag to the fluid. Call before initialize(), since the
+    // kernel is compiled with the force field enabled.
+    pub fn enable_two_way_coupling(&mut self) {
+        self.tracers.two_way = true;
+        self.use_force_field = true;
+    }
+
+    // Periodic trilinear stencil: the 8 surrounding cells and their weights
+    fn trilinear_stencil(&self, p: [f32; 3]) -> [(usize, f32); 8] {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let base = p.map(|c| c.floor());
+        let s = [0, 1, 2].map(|d| p[d] - base[d]);
+        let mut stencil = [(0usize, 0.0f32); 8];
+        for (k, entry) in stencil.iter_mut().enumerate() {
+            let offset = [k & 1, (k >> 1) & 1, (k >> 2) & 1];
+            let c = [0, 1, 2]
+                .map(|d| (base[d] as i64 + offset[d] as i64).rem_euclid(dims[d] as i64) as usize);
+            let weight: f32 = (0..3)
+                .map(|d| if offset[d] == 1 { s[d] } else { 1.0 - s[d] })
+                .product();
+            *entry = (n_from_xyz(&c[0], &c[1], &c[2], &self.Nx, &self.Ny), weight);
+        }
+        stencil
+    }
+
+    // Replace last step's particle reaction in the force field with the
+    // current one. Only the fluid velocity in the particle stencils is read
+    // and only those cells of the force field are updated. Passive tracers
+    // carry no momentum.
+    pub fn couple_particles(&mut self) -> Result<(), Box<dyn Error>> {
+        self.read_tracers_from_gpu()?;
+        let stencils: Vec<[(usize, f32); 8]> = (0..self.tracers.len())
+            .filter(|&k| self.tracers.diameters[k] > 0.0)
+            .map(|k| self.trilinear_stencil(self.tracers.positions[k]))
+            .collect();
+        let mut cells = CellIndex::default();
+        for &(n, _) in stencils.iter().flatten() {
+            cells.insert(n);
+        }
+        let fields = self.read_cell_fields(&cells.cells)?;
+
+        let mut reaction = CellForces::default();
+        let nu = self.viscosity;
+        let t = &self.tracers;
+        let particles = (0..t.len()).filter(|&k| t.diameters[k] > 0.0);
+        for (k, stencil) in particles.zip(&stencils) {
+            let mut uf = [0.0f32; 3];
+            for &(n, w) in stencil {
+                let cell = fields[cells.insert(n)]; // Slot of a cell listed above
+                for (d, value) in uf.iter_mut().enumerate() {
+                    *value += w * cell[1 + d];
+                }
+            }
+            let slip = [0, 1, 2].map(|d| uf[d] - t.velocities[k][d]);
+            let mut f = 1.0;
+            if t.drag == DragModel::SchillerNaumann {
+                let re = (slip[0] * slip[0] + slip[1] * slip[1] + slip[2] * slip[2]).sqrt()
+                    * t.diameters[k]
+                    / nu;
+                f += 0.15 * re.powf(0.687);
+            }
+            // Stokes drag 3 pi nu d f (u - v) on the particle, opposite on the fluid
+            let drag = 3.0 * PI * nu * t.diameters[k] * f;
+            for &(n, w) in stencil {
+                reaction.add(n, slip.map(|s| -w * drag * s));
+            }
+        }
+
+        let previous = std::mem::take(&mut self.tracers.reaction);
+        self.replace_cell_forces(&previous, &reaction)?;
+        self.tracers.reaction = reaction;
+        Ok(())
+    }
+}
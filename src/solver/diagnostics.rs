@@ -0,0 +1,188 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Derived quantities of a velocity field: vorticity, strain rate, Q and
+// lambda-2 criteria. They work on a VelocityField view of any u array, so the
+// same code serves live simulations (LBM::velocity_field() after readback),
+// checkpoints (LBM::load_checkpoint()) and stored snapshots. Gradients are
+// central differences, one-sided at the domain faces.
+
+use super::lbm::LBM;
+use crate::solver::transforms::n_from_xyz;
+
+use std::error::Error;
+
+// Borrowed velocity field on a regular grid: three interleaved components per
+// cell, x varying fastest, then y, then z (the layout of LBM::u)
+#[derive(Debug, Clone, Copy)]
+pub struct VelocityField<'a> {
+    u: &'a [f32],
+    dims: (usize, usize, usize),
+    spacing: f32, // Grid spacing used for the derivatives
+}
+
+impl<'a> VelocityField<'a> {
+    pub fn new(u: &'a [f32], dims: (usize, usize, usize), spacing: f32) -> Result<Self, Box<dyn Error>> {
+        let (nx, ny, nz) = dims;
+        if nx == 0 || ny == 0 || nz == 0 {
+            return Err("Velocity field dimensions must be greater than 0.".into());
+        }
+        if u.len() != nx * ny * nz * 3 {
+            return Err(format!(
+                "Velocity field has {} values, expected {} for a {}x{}x{} grid.",
+                u.len(),
+                nx * ny * nz * 3,
+                nx,
+                ny,
+                nz
+            )
+            .into());
+        }
+        Ok(VelocityField { u, dims, spacing })
+    }
+
+    pub fn dims(&self) -> (usize, usize, usize) {
+        self.dims
+    }
+
+    pub fn velocity(&self, x: usize, y: usize, z: usize) -> [f32; 3] {
+        let (nx, ny, nz) = self.dims;
+        let (x, y, z) = (x.min(nx - 1), y.min(ny - 1), z.min(nz - 1));
+        let n = n_from_xyz(&x, &y, &z, &nx, &ny);
+        [self.u[n * 3], self.u[n * 3 + 1], self.u[n * 3 + 2]]
+    }
+
+    // Velocity gradient, grad[d][k] = d u_d / d x_k
+    pub fn gradient(&self, x: usize, y: usize, z: usize) -> [[f32; 3]; 3] {
+        let (nx, ny, nz) = self.dims;
+        let steps = [
+            (x.saturating_sub(1), (x + 1).min(nx - 1)),
+            (y.saturating_sub(1), (y + 1).min(ny - 1)),
+            (z.saturating_sub(1), (z + 1).min(nz - 1)),
+        ];
+        let mut grad = [[0.0f32; 3]; 3];
+        for (k, &(lo, hi)) in steps.iter().enumerate() {
+            // Single cell along this axis, e.g. z in 2D
+            if hi == lo {
+                continue;
+            }
+            let (mut a, mut b) = ([x, y, z], [x, y, z]);
+            a[k] = lo;
+            b[k] = hi;
+            let ua = self.velocity(a[0], a[1], a[2]);
+            let ub = self.velocity(b[0], b[1], b[2]);
+            let h = (hi - lo) as f32 * self.spacing;
+            for d in 0..3 {
+                grad[d][k] = (ub[d] - ua[d]) / h;
+            }
+        }
+        grad
+    }
+
+    pub fn vorticity_vector(&self, x: usize, y: usize, z: usize) -> [f32; 3] {
+        let g = self.gradient(x, y, z);
+        [g[2][1] - g[1][2], g[0][2] - g[2][0], g[1][0] - g[0][1]]
+    }
+
+    pub fn vorticity(&self, x: usize, y: usize, z: usize) -> f32 {
+        let w = self.vorticity_vector(x, y, z);
+        (w[0] * w[0] + w[1] * w[1] + w[2] * w[2]).sqrt()
+    }
+
+    // Strain-rate tensor S = (grad u + grad u^T) / 2 as [Sxx, Syy, Szz, Sxy, Sxz, Syz]
+    pub fn strain_rate_tensor(&self, x: usize, y: usize, z: usize) -> [f32; 6] {
+        let g = self.gradient(x, y, z);
+        [
+            g[0][0],
+            g[1][1],
+            g[2][2],
+            0.5 * (g[0][1] + g[1][0]),
+            0.5 * (g[0][2] + g[2][0]),
+            0.5 * (g[1][2] + g[2][1]),
+        ]
+    }
+
+    // Strain-rate magnitude sqrt(2 S:S)
+    pub fn strain_rate(&self, x: usize, y: usize, z: usize) -> f32 {
+        let s = self.strain_rate_tensor(x, y, z);
+        let ss = s[0] * s[0] + s[1] * s[1] + s[2] * s[2] + 2.0 * (s[3] * s[3] + s[4] * s[4] + s[5] * s[5]);
+        (2.0 * ss).sqrt()
+    }
+
+    // Viscous dissipation rate per unit mass 2 nu S:S
+    pub fn dissipation(&self, x: usize, y: usize, z: usize, viscosity: f32) -> f32 {
+        let rate = self.strain_rate(x, y, z);
+        viscosity * rate * rate
+    }
+
+    // Q = (|W|^2 - |S|^2) / 2; positive where rotation dominates strain
+    pub fn q_criterion(&self, x: usize, y: usize, z: usize) -> f32 {
+        let (s, w) = split_gradient(&self.gradient(x, y, z));
+        let mut s_norm = 0.0;
+        let mut w_norm = 0.0;
+        for i in 0..3 {
+            for j in 0..3 {
+                s_norm += s[i][j] * s[i][j];
+                w_norm += w[i][j] * w[i][j];
+            }
+        }
+        0.5 * (w_norm - s_norm)
+    }
+
+    // Second eigenvalue of S^2 + W^2 (Jeong & Hussain); negative inside vortex cores
+    pub fn lambda2(&self, x: usize, y: usize, z: usize) -> f32 {
+        let (s, w) = split_gradient(&self.gradient(x, y, z));
+        let mut m = [[0.0f64; 3]; 3];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                for k in 0..3 {
+                    *value += (s[i][k] * s[k][j] + w[i][k] * w[k][j]) as f64;
+                }
+            }
+        }
+        symmetric_eigenvalues(&m)[1] as f32
+    }
+}
+
+// Symmetric and antisymmetric parts of a velocity gradient
+fn split_gradient(g: &[[f32; 3]; 3]) -> ([[f32; 3]; 3], [[f32; 3]; 3]) {
+    let mut s = [[0.0f32; 3]; 3];
+    let mut w = [[0.0f32; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            s[i][j] = 0.5 * (g[i][j] + g[j][i]);
+            w[i][j] = 0.5 * (g[i][j] - g[j][i]);
+        }
+    }
+    (s, w)
+}
+
+// Eigenvalues of a symmetric 3x3 matrix in ascending order (trigonometric solution)
+fn symmetric_eigenvalues(m: &[[f64; 3]; 3]) -> [f64; 3] {
+    let off = m[0][1] * m[0][1] + m[0][2] * m[0][2] + m[1][2] * m[1][2];
+    if off == 0.0 {
+        let mut diag = [m[0][0], m[1][1], m[2][2]];
+        diag.sort_by(|a, b| a.total_cmp(b));
+        return diag;
+    }
+    let q = (m[0][0] + m[1][1] + m[2][2]) / 3.0;
+    let p2 = (m[0][0] - q).powi(2) + (m[1][1] - q).powi(2) + (m[2][2] - q).powi(2) + 2.0 * off;
+    let p = (p2 / 6.0).sqrt();
+    // B = (M - qI) / p, r = det(B) / 2
+    let b = |i: usize, j: usize| (m[i][j] - if i == j { q } else { 0.0 }) / p;
+    let det = b(0, 0) * (b(1, 1) * b(2, 2) - b(1, 2) * b(2, 1)) - b(0, 1) * (b(1, 0) * b(2, 2) - b(1, 2) * b(2, 0))
+        + b(0, 2) * (b(1, 0) * b(2, 1) - b(1, 1) * b(2, 0));
+    let phi = (0.5 * det).clamp(-1.0, 1.0).acos() / 3.0;
+    let largest = q + 2.0 * p * phi.cos();
+    let smallest = q + 2.0 * p * (phi + 2.0 * std::f64::consts::PI / 3.0).cos();
+    [smallest, 3.0 * q - largest - smallest, largest]
+}
+
+impl LBM {
+    // View of the host velocity field, valid after read_from_gpu() or on a
+    // lattice returned by load_checkpoint()
+    pub fn velocity_field(&self) -> VelocityField<'_> {
+        VelocityField::new(&self.u, (self.Nx, self.Ny, self.Nz), self.output_spacing as f32)
+            .expect("Velocity vector has incorrect length")
+    }
+}
@@ -0,0 +1,143 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Statistics over the tracer ensemble: mean square displacement from the
+// release point, residence time in named regions and exit-time histograms.
+
+use super::lbm::LBM;
+use crate::solver::region::Region;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+#[derive(Debug, Clone, Copy)]
+pub struct DispersionSample {
+    pub step: usize,
+    pub particles: usize,
+    pub msd: [f64; 3], // Per axis, lattice units squared
+}
+
+#[derive(Default)]
+pub struct Dispersion {
+    pub interval: usize, // Sampling interval in steps, 0 disables the statistics
+    pub regions: Vec<(String, Region)>,
+    pub residence: Vec<HashMap<u32, usize>>, // Steps spent per particle ID, per region
+    pub samples: Vec<DispersionSample>,
+}
+
+impl LBM {
+    // Sample the tracer statistics every `interval` steps during run()
+    pub fn set_dispersion_statistics(&mut self, interval: usize) {
+        self.dispersion.interval = interval;
+    }
+
+    // Track the time particles spend inside `region`. Returns the region index.
+    pub fn add_residence_region(&mut self, name: &str, region: Region) -> usize {
+        self.dispersion.regions.push((name.to_string(), region));
+        self.dispersion.residence.push(HashMap::new());
+        self.dispersion.regions.len() - 1
+    }
+
+    pub fn update_dispersion_statistics(&mut self) -> Result<(), Box<dyn Error>> {
+        self.read_tracers_from_gpu()?;
+        let t = &self.tracers;
+        let mut msd = [0.0f64; 3];
+        for r in &t.travel {
+            for d in 0..3 {
+                msd[d] += (r[d] as f64).powi(2);
+            }
+        }
+        if !t.is_empty() {
+            msd.iter_mut().for_each(|m| *m /= t.len() as f64);
+        }
+        self.dispersion.samples.push(DispersionSample {
+            step: self.time_step,
+            particles: t.len(),
+            msd,
+        });
+
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let interval = self.dispersion.interval.max(1);
+        let dispersion = &mut self.dispersion;
+        for ((_, region), residence) in dispersion
+            .regions
+            .iter()
+            .zip(dispersion.residence.iter_mut())
+        {
+            for (p, id) in t.positions.iter().zip(t.ids.iter()) {
+                let c = [0, 1, 2].map(|d| (p[d].round() as usize).min(dims[d] - 1));
+                if region.contains(c[0], c[1], c[2]) {
+                    *residence.entry(*id).or_insert(0) += interval;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Summary in long format (quantity, label, value): MSD time series,
+    // residence time statistics per region and the exit-time histogram with
+    // bins of `bin_width` steps
+    pub fn export_dispersion_summary(
+        &self,
+        filename: &str,
+        bin_width: usize,
+    ) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writeln!(writer, "quantity,label,value")?;
+        for s in &self.dispersion.samples {
+            let total = s.msd[0] + s.msd[1] + s.msd[2];
+            writeln!(writer, "particles,{},{}", s.step, s.particles)?;
+            writeln!(writer, "msd_x,{},{:.6e}", s.step, s.msd[0])?;
+            writeln!(writer, "msd_y,{},{:.6e}", s.step, s.msd[1])?;
+            writeln!(writer, "msd_z,{},{:.6e}", s.step, s.msd[2])?;
+            writeln!(writer, "msd,{},{:.6e}", s.step, total)?;
+        }
+
+        for ((name, _), residence) in self
+            .dispersion
+            .regions
+            .iter()
+            .zip(&self.dispersion.residence)
+        {
+            let visitors = residence.len();
+            let total: usize = residence.values().sum();
+            let max = residence.values().copied().max().unwrap_or(0);
+            let mean = if visitors > 0 {
+                total as f64 / visitors as f64
+            } else {
+                0.0
+            };
+            writeln!(writer, "residence_visitors,{},{}", name, visitors)?;
+            writeln!(writer, "residence_mean,{},{:.3}", name, mean)?;
+            writeln!(writer, "residence_max,{},{}", name, max)?;
+        }
+
+        let exits = &self.tracers.exit_times;
+        writeln!(writer, "exited,all,{}", exits.len())?;
+        if !exits.is_empty() {
+            let mean = exits.iter().sum::<usize>() as f64 / exits.len() as f64;
+            writeln!(writer, "exit_time_mean,all,{:.3}", mean)?;
+            let bin_width = bin_width.max(1);
+            let bins = exits.iter().max().unwrap() / bin_width + 1;
+            let mut histogram = vec![0usize; bins];
+            for &age in exits {
+                histogram[age / bin_width] += 1;
+            }
+            for (b, count) in histogram.iter().enumerate() {
+                writeln!(
+                    writer,
+                    "exit_time_histogram,{}-{},{}",
+                    b * bin_width,
+                    (b + 1) * bin_width,
+                    count
+                )?;
+            }
+        }
+        for (name, count) in self.deposition_counts() {
+            writeln!(writer, "deposited,{},{}", name, count)?;
+        }
+        writer.flush()
+    }
+}
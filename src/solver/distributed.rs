@@ -0,0 +1,266 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Domain decomposition across the nodes of a cluster, built with the "mpi"
+// feature and launched with mpirun. Every rank owns one slab of the domain
+// (see MultiLBM for the slab layout) on a GPU of its node, picked by its rank
+// among the ranks sharing the node. Halo packets travel between neighbouring
+// ranks with MPI while the interior runs. Each rank writes its slab as
+// data_<t>_r<rank>.vti and rank 0 indexes the pieces in data_<t>.pvti.
+
+use super::lbm::LBM;
+use crate::solver::halo::HaloExchange;
+use crate::solver::multi::gpu_devices;
+use crate::solver::vti::{write_pvti, write_vti_piece, ArrayData, OutputArray};
+use crate::utils::terminal_utils;
+
+use mpi::environment::Universe;
+use mpi::point_to_point::send_receive_into;
+use mpi::topology::SimpleCommunicator;
+use mpi::traits::*;
+use std::error::Error;
+use std::time::Instant;
+
+pub struct DistributedLBM {
+    pub lbm: LBM, // Global setup, identical on every rank
+    pub universe: Universe,
+    pub world: SimpleCommunicator,
+    pub rank: usize,
+    pub ranks: usize,
+    pub slabs: Vec<(usize, usize)>, // (first layer, depth) of every rank
+    pub lattice: Option<LBM>,
+    pub halo: Option<HaloExchange>,
+}
+
+// Layers 1..=depth of an array computed on a slab lattice
+fn interior(array: OutputArray, len: usize, depth: usize) -> OutputArray {
+    let range = array.components * len..array.components * len * (depth + 1);
+    let data = match array.data {
+        ArrayData::Float32(v) => ArrayData::Float32(v[range].to_vec()),
+        ArrayData::UInt8(v) => ArrayData::UInt8(v[range].to_vec()),
+        ArrayData::UInt16(v) => ArrayData::UInt16(v[range].to_vec()),
+    };
+    OutputArray {
+        name: array.name,
+        components: array.components,
+        data,
+    }
+}
+
+impl DistributedLBM {
+    // Initialize MPI and assign this rank its slab of `lbm`
+    pub fn new(lbm: LBM) -> Result<DistributedLBM, Box<dyn Error>> {
+        let universe = mpi::initialize().ok_or("MPI is already initialized")?;
+        let world = universe.world();
+        let rank = world.rank() as usize;
+        let ranks = world.size() as usize;
+        lbm.check_decomposition(ranks)?;
+        let slabs = lbm.decomposition(ranks);
+        Ok(DistributedLBM {
+            lbm,
+            universe,
+            world,
+            rank,
+            ranks,
+            slabs,
+            lattice: None,
+            halo: None,
+        })
+    }
+
+    fn lower_rank(&self) -> i32 {
+        ((self.rank + self.ranks - 1) % self.ranks) as i32
+    }
+
+    fn upper_rank(&self) -> i32 {
+        ((self.rank + 1) % self.ranks) as i32
+    }
+
+    // Send `to_upper` up and `to_lower` down; returns what arrives from
+    // below and from above
+    fn exchange(&self, to_lower: &[f32], to_upper: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        let lower = self.world.process_at_rank(self.lower_rank());
+        let upper = self.world.process_at_rank(self.upper_rank());
+        let mut below = vec![0.0f32; to_upper.len()];
+        let mut above = vec![0.0f32; to_lower.len()];
+        send_receive_into(to_upper, &upper, &mut below[..], &lower);
+        send_receive_into(to_lower, &lower, &mut above[..], &upper);
+        (below, above)
+    }
+
+    // Build this rank's slab on a GPU of its node
+    fn decompose(&mut self) -> Result<(), Box<dyn Error>> {
+        let devices = gpu_devices()?;
+        if devices.is_empty() {
+            return Err("No OpenCL devices on this node.".into());
+        }
+        let node = self.world.split_shared(self.rank as i32);
+        let device = &devices[node.rank() as usize % devices.len()];
+        let (start, depth) = self.slabs[self.rank];
+        terminal_utils::print_log(&format!(
+            "Rank {}: layers {}..{} on {}",
+            self.rank,
+            start,
+            start + depth,
+            device.device_name.trim()
+        ));
+        let mut lattice = self.lbm.build_slab(
+            start,
+            depth,
+            Some((device.platform_index, device.device_index)),
+        )?;
+        lattice.output_arrays.clone_from(&self.lbm.output_arrays);
+        lattice.output_spacing = self.lbm.output_spacing;
+        self.halo = Some(lattice.create_halo_exchange()?);
+        lattice
+            .queue
+            .as_ref()
+            .ok_or("OpenCL queue is None")?
+            .finish()?;
+        self.lattice = Some(lattice);
+        Ok(())
+    }
+
+    // One step of this rank's slab, halos exchanged with the neighbour ranks
+    fn step(&mut self, t: usize) -> Result<(), Box<dyn Error>> {
+        let (_, depth) = self.slabs[self.rank];
+        let lattice = self.lattice.as_ref().ok_or("Slab lattice is None")?;
+        let halo = self.halo.as_ref().ok_or("Halo exchange is None")?;
+        let written = if t % 2 == 0 {
+            &lattice.f_new_buffer
+        } else {
+            &lattice.f_buffer
+        };
+        let written = written.as_ref().ok_or("f buffer is None")?.clone();
+
+        let wait = halo.unpacked.as_ref();
+        let mut ready = lattice.enqueue_layers(t, 1, 2, wait)?;
+        if depth > 1 {
+            ready = lattice.enqueue_layers(t, depth, depth + 1, wait)?;
+        }
+        if depth > 2 {
+            // The in-order compute queue orders it before the next step
+            let _interior = lattice.enqueue_layers(t, 2, depth, wait)?;
+        }
+        let (lower, upper) = halo.send(&written, depth, &ready)?;
+        let (below, above) = self.exchange(&lower, &upper);
+        self.halo
+            .as_mut()
+            .ok_or("Halo exchange is None")?
+            .receive(&written, depth, &below, &above)?;
+        Ok(())
+    }
+
+    // Write this rank's piece of step t, and the .pvti index on rank 0
+    fn write_output(&mut self, t: usize, magnitude: usize) -> Result<(), Box<dyn Error>> {
+        let (start, depth) = self.slabs[self.rank];
+        let (_, len) = self.lbm.slab_layers();
+        let lattice = self.lattice.as_mut().ok_or("Slab lattice is None")?;
+        self.halo
+            .as_ref()
+            .ok_or("Halo exchange is None")?
+            .queue
+            .finish()?;
+        lattice.read_from_gpu()?;
+
+        // Derived arrays at the slab faces need the neighbours' density and
+        // velocity in the halo layers
+        let layer = |l: usize| -> Vec<f32> {
+            let mut fields = lattice.density[l * len..(l + 1) * len].to_vec();
+            fields.extend_from_slice(&lattice.u[3 * l * len..3 * (l + 1) * len]);
+            fields
+        };
+        let (first, last) = (layer(1), layer(depth));
+        let (below, above) = self.exchange(&first, &last);
+        let lattice = self.lattice.as_mut().ok_or("Slab lattice is None")?;
+        for (l, fields) in [(0, &below), (depth + 1, &above)] {
+            lattice.density[l * len..(l + 1) * len].copy_from_slice(&fields[..len]);
+            lattice.u[3 * l * len..3 * (l + 1) * len].copy_from_slice(&fields[len..]);
+        }
+
+        let arrays: Vec<OutputArray> = lattice
+            .output_arrays()?
+            .into_iter()
+            .map(|a| interior(a, len, depth))
+            .collect();
+        let piece = |(start, depth): (usize, usize)| -> ([usize; 3], [usize; 3]) {
+            if self.lbm.Nz > 1 {
+                ([0, 0, start], [self.lbm.Nx, self.lbm.Ny, depth])
+            } else {
+                ([0, start, 0], [self.lbm.Nx, depth, 1])
+            }
+        };
+        let width = (self.ranks - 1).to_string().len();
+        let piece_name = |rank: usize| format!("data_{:0magnitude$}_r{:0width$}.vti", t, rank);
+
+        let (piece_start, piece_size) = piece((start, depth));
+        write_vti_piece(
+            &format!("output/{}", piece_name(self.rank)),
+            [0, 0, 0],
+            self.lbm.output_spacing,
+            piece_start,
+            piece_size,
+            &arrays,
+        )?;
+        if self.rank == 0 {
+            let pieces: Vec<(String, [usize; 3], [usize; 3])> = self
+                .slabs
+                .iter()
+                .enumerate()
+                .map(|(rank, &slab)| {
+                    let (start, size) = piece(slab);
+                    (piece_name(rank), start, size)
+                })
+                .collect();
+            write_pvti(
+                &format!("output/data_{:0magnitude$}.pvti", t),
+                self.lbm.output_spacing,
+                [self.lbm.Nx, self.lbm.Ny, self.lbm.Nz],
+                &pieces,
+                &arrays,
+            )?;
+        }
+        Ok(())
+    }
+
+    // Run the distributed domain; outputs are .vti pieces with a .pvti index
+    pub fn run(&mut self, time_steps: usize) -> Result<(), Box<dyn Error>> {
+        self.lbm.check_errors_in_input()?;
+        self.decompose()?;
+        if self.rank == 0 {
+            std::fs::create_dir_all("output")?;
+            if self.lbm.output_csv || self.lbm.output_vtk {
+                terminal_utils::print_warning("Distributed runs write .vti pieces only.");
+            }
+        }
+        self.world.barrier();
+
+        let magnitude = time_steps.to_string().len();
+        let start_time = Instant::now();
+        for t in 0..time_steps {
+            self.step(t)?;
+            self.lbm.time_step = t + 1;
+            if self.lbm.output_interval != 0 && t % self.lbm.output_interval == 0 {
+                self.write_output(t, magnitude)?;
+            }
+        }
+        if let Some(lattice) = &self.lattice {
+            lattice
+                .queue
+                .as_ref()
+                .ok_or("OpenCL queue is None")?
+                .finish()?;
+        }
+        self.world.barrier();
+
+        if self.rank == 0 {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let mlups = (self.lbm.N as f64 * time_steps as f64) / elapsed / 1e6;
+            terminal_utils::print_success(&format!(
+                "{} steps on {} ranks in {:.1}s ({:.2} MLUps)",
+                time_steps, self.ranks, elapsed, mlups
+            ));
+        }
+        Ok(())
+    }
+}
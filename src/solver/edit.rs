@@ -0,0 +1,97 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::{FLAG_EQ, FLAG_SOLID};
+use crate::solver::region::Region;
+use crate::solver::transforms::xyz_from_n;
+
+use std::error::Error;
+
+impl LBM {
+    // Set the flag of every cell inside `region`.
+    // Works before and after initialization; after initialize() the flags
+    // buffer is re-uploaded so the change takes effect on the next step.
+    pub fn fill_flags(&mut self, region: &Region, flag: u8) -> Result<usize, Box<dyn Error>> {
+        let mut count = 0;
+        for n in 0..self.N {
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            if region.contains(x, y, z) {
+                self.flags[n] = flag;
+                count += 1;
+            }
+        }
+        if self.flags_buffer.is_some() {
+            self.write_flags_to_gpu()?;
+            self.compute_wall_layer()?;
+        }
+        Ok(count)
+    }
+
+    // Set the velocity of every cell inside `region` (prescribed value for FLAG_EQ cells).
+    pub fn set_region_velocity(
+        &mut self,
+        region: &Region,
+        velocity: [f32; 3],
+    ) -> Result<usize, Box<dyn Error>> {
+        // Before set_conditions the per-cell Velocity array is still the source of truth
+        let before_conditions = self.velocity.len() == self.N;
+        let mut count = 0;
+        for n in 0..self.N {
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            if region.contains(x, y, z) {
+                self.u[n * 3] = velocity[0];
+                self.u[n * 3 + 1] = velocity[1];
+                self.u[n * 3 + 2] = velocity[2];
+                if before_conditions {
+                    self.velocity[n].x = velocity[0];
+                    self.velocity[n].y = velocity[1];
+                    self.velocity[n].z = velocity[2];
+                }
+                count += 1;
+            }
+        }
+        if self.u_buffer.is_some() {
+            self.write_u_to_gpu()?;
+        }
+        Ok(count)
+    }
+
+    // Change the prescribed velocity of the boundary cells inside `region`
+    // mid-run: FLAG_EQ cells, plus solid cells when moving walls are enabled.
+    // Only the contiguous runs of patched cells are written to the GPU.
+    pub fn update_boundary_velocity(
+        &mut self,
+        region: &Region,
+        velocity: [f32; 3],
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut runs: Vec<(usize, usize)> = vec![]; // (first cell, cell count)
+        for n in 0..self.N {
+            let boundary = self.flags[n] == FLAG_EQ
+                || (self.use_moving_walls && self.flags[n] == FLAG_SOLID);
+            if !boundary {
+                continue;
+            }
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            if !region.contains(x, y, z) {
+                continue;
+            }
+            self.u[n * 3..n * 3 + 3].copy_from_slice(&velocity);
+            match runs.last_mut() {
+                Some((start, len)) if *start + *len == n => *len += 1,
+                _ => runs.push((n, 1)),
+            }
+        }
+
+        if let Some(buffer) = self.u_buffer.as_ref() {
+            for &(start, len) in &runs {
+                buffer
+                    .write(&self.u[start * 3..(start + len) * 3])
+                    .offset(start * 3)
+                    .enq()
+                    .map_err(|e| format!("Failed to write 'velocity' buffer: {}", e))?;
+            }
+        }
+        Ok(runs.iter().map(|&(_, len)| len).sum())
+    }
+}
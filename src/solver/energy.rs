@@ -0,0 +1,106 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Global kinetic energy and enstrophy, reduced on the device so only a few
+// partial sums are read back, and appended to output/energy.csv during run().
+
+use super::lbm::LBM;
+use crate::solver::reductions::REDUCE_GROUP;
+
+use ocl::{flags::MEM_WRITE_ONLY, Buffer, Event, Kernel};
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+pub const ENERGY_FILE: &str = "output/energy.csv";
+
+#[derive(Debug, Clone, Copy)]
+pub struct EnergySample {
+    pub step: usize,
+    pub kinetic_energy: f64, // Sum of rho |u|^2 / 2 over non-solid cells
+    pub enstrophy: f64,      // Sum of |omega|^2 / 2 over non-solid cells
+}
+
+impl LBM {
+    // Sample kinetic energy and enstrophy every `interval` steps (0 disables)
+    pub fn set_energy_monitor(&mut self, interval: usize) {
+        self.energy_interval = interval;
+    }
+
+    // Total kinetic energy and enstrophy of the current device fields
+    pub fn kinetic_energy_enstrophy(&self) -> Result<(f64, f64), Box<dyn Error>> {
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let groups = self.N.div_ceil(REDUCE_GROUP);
+        let partial_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_WRITE_ONLY)
+            .len(groups * 2)
+            .build()
+            .map_err(|e| format!("Failed to build 'partial sums' buffer: {}", e))?;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("energy_enstrophy_kernel")
+            .queue(queue.clone())
+            .global_work_size(groups * REDUCE_GROUP)
+            .local_work_size(REDUCE_GROUP)
+            .arg(
+                self.density_buffer
+                    .as_ref()
+                    .ok_or("Density buffer is None")?,
+            )
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(&partial_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'energy_enstrophy_kernel': {}", e))?;
+        let mut launched = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut launched)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'energy_enstrophy_kernel': {}", e))?;
+        }
+
+        let mut partial = vec![0.0f32; groups * 2];
+        partial_buffer
+            .read(&mut partial)
+            .ewait(&launched)
+            .enq()
+            .map_err(|e| format!("Failed to read 'partial sums' buffer: {}", e))?;
+        let (mut energy, mut enstrophy) = (0.0f64, 0.0f64);
+        for pair in partial.chunks_exact(2) {
+            energy += pair[0] as f64;
+            enstrophy += pair[1] as f64;
+        }
+        Ok((energy, enstrophy))
+    }
+
+    // Start a fresh CSV file with the header row
+    pub fn start_energy_monitor(&mut self) -> Result<(), Box<dyn Error>> {
+        self.energy_history.clear();
+        let mut writer = BufWriter::new(File::create(ENERGY_FILE)?);
+        writeln!(writer, "step,kinetic_energy,enstrophy")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    // Sample the current step and append it to the CSV file
+    pub fn update_energy_monitor(&mut self) -> Result<(), Box<dyn Error>> {
+        let (kinetic_energy, enstrophy) = self.kinetic_energy_enstrophy()?;
+        let sample = EnergySample {
+            step: self.time_step,
+            kinetic_energy,
+            enstrophy,
+        };
+        let mut file = OpenOptions::new().append(true).open(ENERGY_FILE)?;
+        writeln!(
+            file,
+            "{},{},{}",
+            sample.step, sample.kinetic_energy, sample.enstrophy
+        )?;
+        self.energy_history.push(sample);
+        Ok(())
+    }
+}
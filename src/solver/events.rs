@@ -0,0 +1,127 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Event-ordered time stepping. Each stream-collide launch waits on the event
+// of the previous one (or on a marker covering everything enqueued since the
+// last sync), so the host no longer blocks on finish() every step and the
+// queue may run out of order. The host only synchronizes when a step has
+// host-side work or every MAX_QUEUED_STEPS steps to bound the queue depth.
+// Helper kernels order their own consumers: a read or kernel in the same call
+// waits on the helper's event, and helpers whose output is used by a later
+// call end with enqueue_barrier().
+
+use super::lbm::LBM;
+use crate::solver::parameters::TIMESTEP_ARG;
+
+use ocl::enums::{DeviceInfo, DeviceInfoResult};
+use ocl::{CommandQueueProperties, Event};
+use std::error::Error;
+
+pub const MAX_QUEUED_STEPS: usize = 16;
+
+impl LBM {
+    // Request an out-of-order command queue; ignored if the device lacks one
+    pub fn set_out_of_order_queue(&mut self, enabled: bool) {
+        self.out_of_order_queue = enabled;
+    }
+
+    // Whether the selected device supports out-of-order execution
+    pub fn supports_out_of_order_queue(&self) -> bool {
+        match self.device.map(|d| d.info(DeviceInfo::QueueProperties)) {
+            Some(Ok(DeviceInfoResult::QueueProperties(properties))) => {
+                properties.contains(CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE)
+            }
+            _ => false,
+        }
+    }
+
+    // On an out-of-order queue, make everything enqueued from now on wait for
+    // everything enqueued so far; a no-op on in-order queues
+    pub fn enqueue_barrier(&self) -> Result<(), Box<dyn Error>> {
+        if !self.out_of_order_queue {
+            return Ok(());
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        ocl::core::enqueue_barrier_with_wait_list(
+            queue.as_core(),
+            None::<&Event>,
+            None::<&mut Event>,
+            None,
+        )
+        .map_err(|e| format!("Failed to enqueue barrier: {}", e))?;
+        Ok(())
+    }
+
+    // Enqueue stream-collide for step `t` after the previous step's work
+    pub fn enqueue_stream_collide(&mut self, t: usize) -> Result<Event, Box<dyn Error>> {
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let dependency = match self.step_event.take() {
+            Some(event) => Some(event),
+            // After a sync the host may have enqueued other commands; a marker
+            // without a wait list completes once all of them have
+            None if self.out_of_order_queue => Some(
+                queue
+                    .enqueue_marker::<&Event>(None)
+                    .map_err(|e| format!("Failed to enqueue marker: {}", e))?,
+            ),
+            None => None,
+        };
+        let kernel = self
+            .stream_collide_kernel
+            .as_ref()
+            .ok_or("stream_collide_kernel not initialized")?;
+        kernel
+            .set_arg(TIMESTEP_ARG, t as i32)
+            .map_err(|e| format!("Failed to set kernel argument: {}", e))?;
+        let mut event = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .ewait(dependency.as_ref())
+                .enew(&mut event)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'stream_collide_kernel': {}", e))?;
+        }
+        self.step_event = Some(event.clone());
+        Ok(event)
+    }
+
+    // Block until all enqueued work is done; the next step then orders itself
+    // after anything the host enqueues in between
+    pub fn sync_step(&mut self) -> Result<(), Box<dyn Error>> {
+        self.queue
+            .as_ref()
+            .ok_or("OpenCL queue is None")?
+            .finish()
+            .map_err(|e| format!("Queue finish failed: {}", e))?;
+        self.step_event = None;
+        Ok(())
+    }
+
+    // Whether the host touches device data after step `t`, i.e. must sync
+    pub fn host_work_due(&self, t: usize) -> bool {
+        let step = t + 1;
+        let due = |interval: usize| interval > 0 && step % interval == 0;
+        self.profiling
+            || step % MAX_QUEUED_STEPS == 0
+            || !self.rigid_bodies.is_empty()
+            || !self.suspensions.is_empty()
+            || !self.device_bodies.is_empty()
+            || !self.immersed_boundaries.is_empty()
+            || !self.tracers.is_empty()
+            || !self.tracers.sources.is_empty()
+            || !self.output_regions.is_empty()
+            || due(self.dispersion.interval)
+            || due(self.conservation_interval)
+            || due(self.energy_interval)
+            || due(self.mach_interval)
+            || (due(self.flow_rate_interval) && !self.flow_rate_monitors.is_empty())
+            || (due(self.force_monitor_interval) && !self.force_monitors.is_empty())
+            || due(self.checkpoint_interval)
+            || (self.steady_tolerance > 0.0 && due(self.steady_check_every))
+            || self
+                .averages_start
+                .is_some_and(|warmup| step >= warmup && (step - warmup) % self.averages_every == 0)
+            || (self.output_interval != 0 && t % self.output_interval == 0)
+    }
+}
@@ -1,5 +1,39 @@
-// src/lbm/check.rs
+// src/solver/flags.rs
 // LBM FLAGS
+
+use serde::{Deserialize, Serialize};
+
 pub const FLAG_FLUID: u8 = 0;
 pub const FLAG_SOLID: u8 = 1;
 pub const FLAG_EQ: u8 = 2;
+pub const FLAG_FRESH: u8 = 3; // Transient: fluid cell uncovered by a moving solid, awaiting refill
+
+// Cell types a user may assign; stored on the device as the u8 flags above
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CellType {
+    Fluid = FLAG_FLUID,
+    Solid = FLAG_SOLID,
+    #[serde(alias = "eq")]
+    Equilibrium = FLAG_EQ,
+}
+
+impl From<CellType> for u8 {
+    fn from(cell: CellType) -> u8 {
+        cell as u8
+    }
+}
+
+impl TryFrom<u8> for CellType {
+    type Error = String;
+
+    fn try_from(flag: u8) -> Result<Self, Self::Error> {
+        match flag {
+            FLAG_FLUID => Ok(CellType::Fluid),
+            FLAG_SOLID => Ok(CellType::Solid),
+            FLAG_EQ => Ok(CellType::Equilibrium),
+            _ => Err(format!("Invalid cell flag: {}", flag)),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+
+use ocl::{flags::MEM_READ_ONLY, flags::MEM_WRITE_ONLY, Buffer, Event, Kernel};
+use std::error::Error;
+
+impl LBM {
+    // Hydrodynamic force and torque (about `center`) on the solid `cells` by
+    // momentum exchange, evaluated on the device from the latest populations
+    pub fn momentum_exchange(
+        &self,
+        cells: &[u32],
+        center: [f32; 3],
+    ) -> Result<([f32; 3], [f32; 3]), Box<dyn Error>> {
+        let out = self.momentum_exchange_cells(cells, center)?;
+        let mut force = [0.0f32; 3];
+        let mut torque = [0.0f32; 3];
+        for cell in out.chunks_exact(6) {
+            for d in 0..3 {
+                force[d] += cell[d];
+                torque[d] += cell[3 + d];
+            }
+        }
+        Ok((force, torque))
+    }
+
+    // Total hydrodynamic force and torque (about `center`) on all solid cells,
+    // evaluated from the fluid side over the wall layer
+    pub fn wall_loads(&self, center: [f32; 3]) -> Result<([f32; 3], [f32; 3]), Box<dyn Error>> {
+        let mut force = [0.0f32; 3];
+        let mut torque = [0.0f32; 3];
+        let count = self.wall_layer.len();
+        if count == 0 {
+            return Ok((force, torque));
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let out_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_WRITE_ONLY)
+            .len(count * 6)
+            .build()
+            .map_err(|e| format!("Failed to build 'momentum exchange' buffer: {}", e))?;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("wall_momentum_exchange_kernel")
+            .queue(queue.clone())
+            .global_work_size(count)
+            .arg(self.f_buffer.as_ref().ok_or("f buffer is None")?)
+            .arg(self.f_new_buffer.as_ref().ok_or("f_new buffer is None")?)
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(self.wall_layer_buffer.as_ref().ok_or("Wall layer buffer is None")?)
+            .arg(count as i32)
+            .arg(self.time_step as i32)
+            .arg(center[0])
+            .arg(center[1])
+            .arg(center[2])
+            .arg(&out_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'wall_momentum_exchange_kernel': {}", e))?;
+        let mut launched = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut launched)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'wall_momentum_exchange_kernel': {}", e))?;
+        }
+
+        let mut out = vec![0.0f32; count * 6];
+        out_buffer
+            .read(&mut out)
+            .ewait(&launched)
+            .enq()
+            .map_err(|e| format!("Failed to read 'momentum exchange' buffer: {}", e))?;
+        for cell in out.chunks_exact(6) {
+            for d in 0..3 {
+                force[d] += cell[d];
+                torque[d] += cell[3 + d];
+            }
+        }
+        Ok((force, torque))
+    }
+
+    // Per-cell force and torque (6 floats per entry of `cells`), e.g. to split
+    // the loads of many bodies evaluated in a single launch
+    pub fn momentum_exchange_cells(
+        &self,
+        cells: &[u32],
+        center: [f32; 3],
+    ) -> Result<Vec<f32>, Box<dyn Error>> {
+        if cells.is_empty() {
+            return Ok(vec![]);
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let cells_buffer = Buffer::<u32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_ONLY)
+            .len(cells.len())
+            .copy_host_slice(cells)
+            .build()
+            .map_err(|e| format!("Failed to build 'cells' buffer: {}", e))?;
+        let out_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_WRITE_ONLY)
+            .len(cells.len() * 6)
+            .build()
+            .map_err(|e| format!("Failed to build 'momentum exchange' buffer: {}", e))?;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("momentum_exchange_kernel")
+            .queue(queue.clone())
+            .global_work_size(cells.len())
+            .arg(self.f_buffer.as_ref().ok_or("f buffer is None")?)
+            .arg(self.f_new_buffer.as_ref().ok_or("f_new buffer is None")?)
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(&cells_buffer)
+            .arg(cells.len() as i32)
+            .arg(self.time_step as i32)
+            .arg(center[0])
+            .arg(center[1])
+            .arg(center[2])
+            .arg(&out_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'momentum_exchange_kernel': {}", e))?;
+        let mut launched = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut launched)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'momentum_exchange_kernel': {}", e))?;
+        }
+
+        let mut out = vec![0.0f32; cells.len() * 6];
+        out_buffer
+            .read(&mut out)
+            .ewait(&launched)
+            .enq()
+            .map_err(|e| format!("Failed to read 'momentum exchange' buffer: {}", e))?;
+        Ok(out)
+    }
+}
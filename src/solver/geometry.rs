@@ -0,0 +1,59 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::utils::npy;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+
+impl LBM {
+    // Dump the flags array as raw bytes (one u8 per cell, x fastest)
+    pub fn export_flags_raw(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(&self.flags)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    // Dump the flags array as a .npy file with shape (Nz, Ny, Nx)
+    pub fn export_flags_npy(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        npy::write_npy(path, &[self.Nz, self.Ny, self.Nx], &self.flags)
+    }
+
+    // Load flags previously written by export_flags_raw/export_flags_npy.
+    // The file format is detected from its header and must match the grid size.
+    pub fn import_flags(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let flags = if npy::is_npy_file(path) {
+            let (shape, data) = npy::read_npy::<u8>(path)?;
+            let expected = [self.Nz, self.Ny, self.Nx];
+            if shape.as_slice() != expected.as_slice() {
+                return Err(format!(
+                    "Geometry shape {:?} does not match grid (Nz, Ny, Nx) = {:?}.",
+                    shape, expected
+                )
+                .into());
+            }
+            data
+        } else {
+            let mut data = Vec::with_capacity(self.N);
+            File::open(path)?.read_to_end(&mut data)?;
+            if data.len() != self.N {
+                return Err(format!(
+                    "Raw geometry has {} cells, expected {}.",
+                    data.len(),
+                    self.N
+                )
+                .into());
+            }
+            data
+        };
+
+        self.flags = flags;
+        terminal_utils::print_log(&format!("Loaded geometry from {}", path));
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Halo exchange of one slab lattice of a MultiLBM, see kernel_halo.cl. Each
+// slab computes its two boundary layers first; while the interior layers run
+// on the compute queue, a second transfer queue packs the populations that
+// leave through each face and copies them to the host. The neighbours'
+// packets are then uploaded and unpacked into the halo layers, and the next
+// step waits on that before it starts. When all devices share one OpenCL
+// context the packets are instead copied device to device, see receive_from.
+
+use super::lbm::LBM;
+use crate::solver::parameters::TIMESTEP_ARG;
+
+use ocl::{flags::MEM_READ_WRITE, Buffer, Event, EventList, Kernel, Queue};
+use std::error::Error;
+
+pub struct HaloExchange {
+    pub queue: Queue, // Transfer queue of the slab's device
+    pub pack_kernel: Kernel,
+    pub unpack_kernel: Kernel,
+    pub lower: Buffer<f32>,      // Populations crossing the lower face
+    pub upper: Buffer<f32>,      // Populations crossing the upper face
+    pub from_below: Buffer<f32>, // Packet of the neighbour below, unpacked into layer 0
+    pub from_above: Buffer<f32>, // Packet of the neighbour above, unpacked into layer depth + 1
+    pub cells: usize,            // Cells per layer
+    pub packed_len: usize,       // Floats per packet
+    pub unpacked: Option<Event>,
+}
+
+impl HaloExchange {
+    // Pack the populations of `layer` moving along `sign` into `packed`
+    // once `wait` has completed
+    fn pack(
+        &self,
+        f: &Buffer<f32>,
+        layer: usize,
+        sign: i32,
+        packed: &Buffer<f32>,
+        wait: &EventList,
+    ) -> Result<Event, Box<dyn Error>> {
+        self.pack_kernel.set_arg(0, f)?;
+        self.pack_kernel.set_arg(1, packed)?;
+        self.pack_kernel.set_arg(2, layer as i32)?;
+        self.pack_kernel.set_arg(3, sign)?;
+        let mut event = Event::empty();
+        unsafe {
+            self.pack_kernel
+                .cmd()
+                .ewait(wait)
+                .enew(&mut event)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'pack_halo_kernel': {}", e))?;
+        }
+        Ok(event)
+    }
+
+    // Pack both faces of the slab into lower and upper after `wait`; the
+    // transfer queue is in order, so the returned event covers both
+    pub fn pack_faces(
+        &self,
+        f: &Buffer<f32>,
+        depth: usize,
+        wait: &EventList,
+    ) -> Result<Event, Box<dyn Error>> {
+        let _lower = self.pack(f, 1, -1, &self.lower, wait)?;
+        self.pack(f, depth, 1, &self.upper, wait)
+    }
+
+    // Packets leaving through the lower and upper faces of the slab after
+    // `ready` (its boundary layers) has run; blocks until they are on the host
+    pub fn send(
+        &self,
+        f: &Buffer<f32>,
+        depth: usize,
+        ready: &Event,
+    ) -> Result<(Vec<f32>, Vec<f32>), Box<dyn Error>> {
+        let _packed = self.pack_faces(f, depth, &EventList::from(vec![ready.clone()]))?;
+        let mut lower = vec![0.0f32; self.packed_len];
+        let mut upper = vec![0.0f32; self.packed_len];
+        self.lower
+            .cmd()
+            .queue(&self.queue)
+            .read(&mut lower)
+            .enq()
+            .map_err(|e| format!("Failed to read lower halo: {}", e))?;
+        self.upper
+            .cmd()
+            .queue(&self.queue)
+            .read(&mut upper)
+            .enq()
+            .map_err(|e| format!("Failed to read upper halo: {}", e))?;
+        Ok((lower, upper))
+    }
+
+    // Upload the neighbours' packets and unpack them into the halo layers:
+    // `below` moves up into layer 0, `above` down into layer depth + 1
+    pub fn receive(
+        &mut self,
+        f: &Buffer<f32>,
+        depth: usize,
+        below: &[f32],
+        above: &[f32],
+    ) -> Result<(), Box<dyn Error>> {
+        self.from_below
+            .cmd()
+            .queue(&self.queue)
+            .write(below)
+            .enq()
+            .map_err(|e| format!("Failed to write lower halo: {}", e))?;
+        self.from_above
+            .cmd()
+            .queue(&self.queue)
+            .write(above)
+            .enq()
+            .map_err(|e| format!("Failed to write upper halo: {}", e))?;
+        self.unpack(f, depth)
+    }
+
+    // Copy the neighbours' packets device to device once they are packed
+    // (`below_packed`, `above_packed`) and unpack them into the halo layers.
+    // Needs all slabs in one OpenCL context.
+    pub fn receive_from(
+        &mut self,
+        f: &Buffer<f32>,
+        depth: usize,
+        below: (&Buffer<f32>, &Event),
+        above: (&Buffer<f32>, &Event),
+    ) -> Result<(), Box<dyn Error>> {
+        for ((packet, packed), target) in [(below, &self.from_below), (above, &self.from_above)] {
+            packet
+                .cmd()
+                .queue(&self.queue)
+                .copy(target, None, None)
+                .ewait(packed)
+                .enq()
+                .map_err(|e| format!("Failed to copy halo between devices: {}", e))?;
+        }
+        self.unpack(f, depth)
+    }
+
+    // Unpack from_below into layer 0 and from_above into layer depth + 1
+    fn unpack(&mut self, f: &Buffer<f32>, depth: usize) -> Result<(), Box<dyn Error>> {
+        self.unpack_kernel.set_arg(1, f)?;
+        let mut event = Event::empty();
+        for (packed, layer, sign) in [(&self.from_below, 0, 1), (&self.from_above, depth + 1, -1)] {
+            self.unpack_kernel.set_arg(0, packed)?;
+            self.unpack_kernel.set_arg(2, layer as i32)?;
+            self.unpack_kernel.set_arg(3, sign)?;
+            unsafe {
+                self.unpack_kernel
+                    .cmd()
+                    .enew(&mut event)
+                    .enq()
+                    .map_err(|e| format!("Failed to enqueue 'unpack_halo_kernel': {}", e))?;
+            }
+        }
+        // The transfer queue is in order, so the last unpack covers both
+        self.unpacked = Some(event);
+        Ok(())
+    }
+}
+
+impl LBM {
+    // Transfer queue, packet buffers and kernels for a slab lattice
+    pub fn create_halo_exchange(&self) -> Result<HaloExchange, Box<dyn Error>> {
+        let context = self.context.as_ref().ok_or("OpenCL context is None")?;
+        let device = self.device.ok_or("OpenCL device is None")?;
+        let program = self.program.as_ref().ok_or("OpenCL program is None")?;
+        let queue = Queue::new(context, device, None)?;
+
+        let (cells, axis) = if self.Nz > 1 {
+            (self.Nx * self.Ny, 2)
+        } else {
+            (self.Nx, 1)
+        };
+        let c = self.model.vectors();
+        let directions = c.iter().filter(|c| c[axis] == 1).count();
+        let packed_len = directions * cells;
+
+        let packet = |name: &str| -> Result<Buffer<f32>, Box<dyn Error>> {
+            Ok(Buffer::<f32>::builder()
+                .queue(queue.clone())
+                .flags(MEM_READ_WRITE)
+                .len(packed_len)
+                .build()
+                .map_err(|e| format!("Failed to build '{}' halo buffer: {}", name, e))?)
+        };
+        let lower = packet("lower")?;
+        let upper = packet("upper")?;
+        let from_below = packet("from_below")?;
+        let from_above = packet("from_above")?;
+        let f = self.f_buffer.as_ref().ok_or("f buffer is None")?;
+
+        let kernel = |name: &str,
+                      first: &Buffer<f32>,
+                      second: &Buffer<f32>|
+         -> Result<Kernel, Box<dyn Error>> {
+            Ok(Kernel::builder()
+                .program(program)
+                .name(name)
+                .queue(queue.clone())
+                .global_work_size(cells)
+                .arg(first)
+                .arg(second)
+                .arg(0i32)
+                .arg(0i32)
+                .build()
+                .map_err(|e| format!("Failed to build '{}': {}", name, e))?)
+        };
+        let pack_kernel = kernel("pack_halo_kernel", f, &lower)?;
+        let unpack_kernel = kernel("unpack_halo_kernel", &from_below, f)?;
+
+        Ok(HaloExchange {
+            queue,
+            pack_kernel,
+            unpack_kernel,
+            lower,
+            upper,
+            from_below,
+            from_above,
+            cells,
+            packed_len,
+            unpacked: None,
+        })
+    }
+
+    // Run stream_collide_kernel on layers first..last of this slab lattice,
+    // after `wait` when given
+    pub fn enqueue_layers(
+        &self,
+        t: usize,
+        first: usize,
+        last: usize,
+        wait: Option<&Event>,
+    ) -> Result<Event, Box<dyn Error>> {
+        let kernel = self
+            .stream_collide_kernel
+            .as_ref()
+            .ok_or("Stream-collide kernel is None")?;
+        let cells = if self.Nz > 1 {
+            self.Nx * self.Ny
+        } else {
+            self.Nx
+        };
+        kernel.set_arg(TIMESTEP_ARG, t as i32)?;
+        let mut event = Event::empty();
+        unsafe {
+            let mut cmd = kernel
+                .cmd()
+                .global_work_offset(first * cells)
+                .global_work_size((last - first) * cells)
+                .enew(&mut event);
+            if let Some(wait) = wait {
+                cmd = cmd.ewait(wait);
+            }
+            cmd.enq()
+                .map_err(|e| format!("Failed to enqueue 'stream_collide_kernel': {}", e))?;
+        }
+        Ok(event)
+    }
+}
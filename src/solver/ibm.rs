@@ -0,0 +1,223 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::sparse::{CellForces, CellIndex};
+use crate::solver::transforms::n_from_xyz;
+
+use std::error::Error;
+
+// A set of Lagrangian markers enforcing a velocity on the fluid by direct forcing.
+// Positions are in lattice units (cell centers at integer coordinates).
+#[derive(Debug, Clone)]
+pub struct ImmersedBoundary {
+    pub name: String,
+    pub positions: Vec<[f32; 3]>,
+    pub velocities: Vec<[f32; 3]>,
+    pub forces: Vec<[f32; 3]>, // Force density exerted on the fluid at each marker
+    pub weights: Vec<f32>,     // Marker arc length (2D) or area (3D)
+    pub force_history: Vec<[f32; 3]>, // Hydrodynamic force on the body per step
+    pub power_history: Vec<f32>,      // Power transferred from the body to the fluid per step
+}
+
+impl ImmersedBoundary {
+    pub fn new(name: &str, positions: Vec<[f32; 3]>, weight: f32) -> Self {
+        let count = positions.len();
+        ImmersedBoundary {
+            name: name.to_string(),
+            positions,
+            velocities: vec![[0.0; 3]; count],
+            forces: vec![[0.0; 3]; count],
+            weights: vec![weight; count],
+            force_history: vec![],
+            power_history: vec![],
+        }
+    }
+
+    // Power delivered by the boundary to the fluid at the current step
+    pub fn power(&self) -> f32 {
+        self.forces
+            .iter()
+            .zip(self.velocities.iter())
+            .zip(self.weights.iter())
+            .map(|((f, v), w)| (f[0] * v[0] + f[1] * v[1] + f[2] * v[2]) * w)
+            .sum()
+    }
+
+    // Total hydrodynamic force acting on the boundary (reaction of the forcing)
+    pub fn hydrodynamic_force(&self) -> [f32; 3] {
+        let mut total = [0.0f32; 3];
+        for (force, weight) in self.forces.iter().zip(self.weights.iter()) {
+            for d in 0..3 {
+                total[d] -= force[d] * weight;
+            }
+        }
+        total
+    }
+}
+
+// Mean distance between consecutive markers of a closed curve, a suitable marker weight
+pub fn closed_curve_spacing(markers: &[[f32; 3]]) -> f32 {
+    if markers.len() < 2 {
+        return 1.0;
+    }
+    let total: f32 = (0..markers.len())
+        .map(|i| {
+            let (a, b) = (markers[i], markers[(i + 1) % markers.len()]);
+            ((b[0] - a[0]).powi(2) + (b[1] - a[1]).powi(2) + (b[2] - a[2]).powi(2)).sqrt()
+        })
+        .sum();
+    total / markers.len() as f32
+}
+
+// Peskin's 4-point cosine regularized delta function
+fn delta(r: f32) -> f32 {
+    let r = r.abs();
+    if r >= 2.0 {
+        0.0
+    } else {
+        0.25 * (1.0 + (std::f32::consts::PI * r / 2.0).cos())
+    }
+}
+
+impl LBM {
+    // Register an immersed boundary. Must be called before run()/initialize(),
+    // since it enables the per-cell force field in the generated kernel.
+    pub fn add_immersed_boundary(&mut self, boundary: ImmersedBoundary) -> usize {
+        self.use_force_field = true;
+        self.immersed_boundaries.push(boundary);
+        self.immersed_boundaries.len() - 1
+    }
+
+    // Lattice cells and delta weights in the support of a marker (periodic wrap)
+    pub fn ibm_stencil(&self, p: [f32; 3]) -> Vec<(usize, f32)> {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let mut axes: Vec<Vec<(usize, f32)>> = Vec::with_capacity(3);
+        for d in 0..3 {
+            if dims[d] == 1 {
+                axes.push(vec![(0, 1.0)]);
+                continue;
+            }
+            let base = p[d].floor() as i64;
+            let axis = (base - 1..=base + 2)
+                .map(|i| {
+                    let weight = delta(p[d] - i as f32);
+                    (i.rem_euclid(dims[d] as i64) as usize, weight)
+                })
+                .filter(|(_, weight)| *weight > 0.0)
+                .collect();
+            axes.push(axis);
+        }
+
+        let mut stencil = Vec::new();
+        for &(z, wz) in &axes[2] {
+            for &(y, wy) in &axes[1] {
+                for &(x, wx) in &axes[0] {
+                    stencil.push((n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny), wx * wy * wz));
+                }
+            }
+        }
+        stencil
+    }
+
+    // Interpolate density and velocity at an arbitrary point from the host fields
+    pub fn interpolate_fields(&self, p: [f32; 3]) -> (f32, [f32; 3]) {
+        let mut rho = 0.0f32;
+        let mut vel = [0.0f32; 3];
+        for (n, weight) in self.ibm_stencil(p) {
+            rho += self.density[n] * weight;
+            for (d, v) in vel.iter_mut().enumerate() {
+                *v += self.u[n * 3 + d] * weight;
+            }
+        }
+        (rho, vel)
+    }
+
+    // One explicit direct-forcing update: sync structures to markers, compute the
+    // marker forces from the current flow, spread them to the lattice and
+    // advance the structures with the reaction forces. Only the cells in the
+    // marker stencils are read from and written to the device.
+    pub fn update_immersed_boundaries(&mut self) -> Result<(), Box<dyn Error>> {
+        self.update_kinematic_bodies();
+        self.update_spring_markers();
+        for membrane in &self.membranes {
+            let boundary = &mut self.immersed_boundaries[membrane.boundary];
+            boundary.positions.clone_from(&membrane.positions);
+            boundary.velocities.clone_from(&membrane.velocities);
+        }
+
+        // Stencil of every marker, as (slot in `cells`, cell, weight)
+        let mut cells = CellIndex::default();
+        let stencils: Vec<Vec<Vec<(usize, usize, f32)>>> = self
+            .immersed_boundaries
+            .iter()
+            .map(|boundary| {
+                boundary
+                    .positions
+                    .iter()
+                    .map(|&p| {
+                        self.ibm_stencil(p)
+                            .into_iter()
+                            .map(|(n, w)| (cells.insert(n), n, w))
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+        let fields = self.read_cell_fields(&cells.cells)?;
+
+        // The Guo term injects (1 - omega / 2) F of momentum per step
+        let gain = 1.0 / (1.0 - 0.5 * self.omega);
+        let mut forcing = CellForces::default();
+        let mut boundaries = std::mem::take(&mut self.immersed_boundaries);
+        for (boundary, stencils) in boundaries.iter_mut().zip(&stencils) {
+            for (k, stencil) in stencils.iter().enumerate() {
+                let mut rho = 0.0f32;
+                let mut vel = [0.0f32; 3];
+                for &(slot, _, w) in stencil {
+                    rho += fields[slot][0] * w;
+                    for (d, v) in vel.iter_mut().enumerate() {
+                        *v += fields[slot][1 + d] * w;
+                    }
+                }
+                let target = boundary.velocities[k];
+                let mut force = [0.0f32; 3];
+                for d in 0..3 {
+                    force[d] = gain * rho * (target[d] - vel[d]);
+                }
+                boundary.forces[k] = force;
+
+                let weight = boundary.weights[k];
+                for &(_, n, w) in stencil {
+                    forcing.add(n, force.map(|f| f * w * weight));
+                }
+            }
+        }
+        for boundary in &mut boundaries {
+            let force = boundary.hydrodynamic_force();
+            let power = boundary.power();
+            boundary.force_history.push(force);
+            boundary.power_history.push(power);
+        }
+        self.immersed_boundaries = boundaries;
+
+        for membrane in &mut self.membranes {
+            let boundary = &self.immersed_boundaries[membrane.boundary];
+            let loads: Vec<[f32; 3]> = boundary
+                .forces
+                .iter()
+                .zip(boundary.weights.iter())
+                .map(|(f, w)| [-f[0] * w, -f[1] * w, -f[2] * w])
+                .collect();
+            membrane.step(&loads);
+        }
+        for body in &mut self.spring_bodies {
+            body.step(self.immersed_boundaries[body.boundary].hydrodynamic_force());
+        }
+
+        let previous = std::mem::take(&mut self.ibm_forcing);
+        self.replace_cell_forces(&previous, &forcing)?;
+        self.ibm_forcing = forcing;
+        Ok(())
+    }
+}
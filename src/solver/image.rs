@@ -0,0 +1,229 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// PNG frames of a scalar field on a plane (the whole domain in 2D), colormapped
+// with viridis and written each output interval for quick animations.
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::transforms::{n_from_xyz, Axis};
+use crate::solver::vti::ArrayData;
+
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Viridis sampled at 8 evenly spaced stops
+const VIRIDIS: [[f32; 3]; 8] = [
+    [68.0, 1.0, 84.0],
+    [70.0, 50.0, 127.0],
+    [54.0, 92.0, 141.0],
+    [39.0, 127.0, 142.0],
+    [31.0, 161.0, 135.0],
+    [74.0, 194.0, 109.0],
+    [159.0, 218.0, 58.0],
+    [253.0, 231.0, 37.0],
+];
+
+const SOLID_COLOR: [u8; 3] = [128, 128, 128];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageField {
+    VelocityMagnitude,
+    Vorticity, // Component normal to the plane
+    Density,
+}
+
+impl ImageField {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImageField::VelocityMagnitude => "velocity",
+            ImageField::Vorticity => "vorticity",
+            ImageField::Density => "density",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageOutput {
+    pub field: ImageField,
+    pub axis: Axis,
+    pub index: usize,
+    pub range: Option<(f32, f32)>, // Fixed color range, per frame when None
+}
+
+// Map t in [0, 1] to a viridis color
+fn colormap(t: f32) -> [u8; 3] {
+    let s = t.clamp(0.0, 1.0) * (VIRIDIS.len() - 1) as f32;
+    let i = (s.floor() as usize).min(VIRIDIS.len() - 2);
+    let f = s - i as f32;
+    [0, 1, 2].map(|c| (VIRIDIS[i][c] + f * (VIRIDIS[i + 1][c] - VIRIDIS[i][c])).round() as u8)
+}
+
+fn write_chunk(writer: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    let mut crc = Crc::new();
+    crc.update(kind);
+    crc.update(data);
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    writer.write_all(&crc.sum().to_be_bytes())
+}
+
+// 8-bit RGB PNG, rows top to bottom
+pub fn write_png(filename: &str, width: usize, height: usize, rgb: &[u8]) -> std::io::Result<()> {
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 2, 0, 0, 0]); // Depth 8, RGB, deflate, no filter, no interlace
+
+    // Every scanline starts with filter type 0
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rgb.chunks(width * 3) {
+        encoder.write_all(&[0])?;
+        encoder.write_all(row)?;
+    }
+    let data = encoder.finish()?;
+
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(&[137, 80, 78, 71, 13, 10, 26, 10])?;
+    write_chunk(&mut writer, b"IHDR", &header)?;
+    write_chunk(&mut writer, b"IDAT", &data)?;
+    write_chunk(&mut writer, b"IEND", &[])?;
+    writer.flush()
+}
+
+// Colormapped PNG of a width x height scalar field stored bottom row first.
+// Values are scaled from range.0 to range.1; cells where solid(i, j) is gray.
+pub fn write_field_png<S>(
+    filename: &str,
+    width: usize,
+    height: usize,
+    values: &[f32],
+    range: (f32, f32),
+    solid: S,
+) -> std::io::Result<()>
+where
+    S: Fn(usize, usize) -> bool,
+{
+    let (lo, hi) = range;
+    let span = if hi > lo { hi - lo } else { 1.0 };
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for j in (0..height).rev() {
+        for i in 0..width {
+            let color = if solid(i, j) {
+                SOLID_COLOR
+            } else {
+                colormap((values[i + j * width] - lo) / span)
+            };
+            rgb.extend_from_slice(&color);
+        }
+    }
+    write_png(filename, width, height, &rgb)
+}
+
+impl LBM {
+    // Render `field` on the plane `index` normal to `axis` every output
+    // interval (Axis::Z, 0 for 2D domains). Returns the image index.
+    pub fn add_image_output(&mut self, field: ImageField, axis: Axis, index: usize) -> usize {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let index = index.min(dims[axis.index()] - 1);
+        self.image_outputs.push(ImageOutput {
+            field,
+            axis,
+            index,
+            range: None,
+        });
+        self.image_outputs.len() - 1
+    }
+
+    // Fix the color range of an image output so frames are comparable
+    pub fn set_image_range(&mut self, image: usize, min: f32, max: f32) {
+        if let Some(output) = self.image_outputs.get_mut(image) {
+            output.range = Some((min, max));
+        }
+    }
+
+    // Write every image output of step `step` as
+    // output/<field>_<axis><index>_<step>.png
+    pub fn export_images(&self, step: usize, width: usize) -> Result<(), Box<dyn Error>> {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        for image in &self.image_outputs {
+            let normal = image.axis.index();
+            // Image axes: a to the right, b upwards
+            let (a, b) = match normal {
+                0 => (1, 2),
+                1 => (0, 2),
+                _ => (0, 1),
+            };
+            let mut min = [0; 3];
+            let mut max = [dims[0] - 1, dims[1] - 1, dims[2] - 1];
+            min[normal] = image.index;
+            max[normal] = image.index;
+            let (arrays, _) = self.read_box_arrays(min, max, 1)?;
+            let (density, velocity) = match (&arrays[0].data, &arrays[1].data) {
+                (ArrayData::Float32(rho), ArrayData::Float32(u)) => (rho, u),
+                _ => return Err("Unexpected plane array types".into()),
+            };
+
+            let (w, h) = (dims[a], dims[b]);
+            let at = |i: usize, j: usize| i + j * w;
+            let u = |i: usize, j: usize, d: usize| velocity[at(i, j) * 3 + d];
+            let mut values = vec![0.0f32; w * h];
+            for j in 0..h {
+                for i in 0..w {
+                    values[at(i, j)] = match image.field {
+                        ImageField::Density => density[at(i, j)],
+                        ImageField::VelocityMagnitude => {
+                            (0..3).map(|d| u(i, j, d).powi(2)).sum::<f32>().sqrt()
+                        }
+                        ImageField::Vorticity => {
+                            // One-sided differences at the edges
+                            let (i0, i1) = (i.saturating_sub(1), (i + 1).min(w - 1));
+                            let (j0, j1) = (j.saturating_sub(1), (j + 1).min(h - 1));
+                            let db_da = (u(i1, j, b) - u(i0, j, b)) / (i1 - i0).max(1) as f32;
+                            let da_db = (u(i, j1, a) - u(i, j0, a)) / (j1 - j0).max(1) as f32;
+                            // (a, b, normal) is left-handed for the y-normal plane
+                            let sign = if normal == 1 { -1.0 } else { 1.0 };
+                            sign * (db_da - da_db)
+                        }
+                    };
+                }
+            }
+
+            let solid = |i: usize, j: usize| {
+                let mut c = [0; 3];
+                c[normal] = image.index;
+                c[a] = i;
+                c[b] = j;
+                let n = n_from_xyz(&c[0], &c[1], &c[2], &self.Nx, &self.Ny);
+                self.flags.get(n) == Some(&FLAG_SOLID)
+            };
+            let (lo, hi) = image.range.unwrap_or_else(|| {
+                let fluid = (0..w * h).filter(|&k| !solid(k % w, k / w));
+                let (lo, hi) = fluid.fold((f32::MAX, f32::MIN), |(lo, hi), k| {
+                    (lo.min(values[k]), hi.max(values[k]))
+                });
+                if image.field == ImageField::Vorticity {
+                    let m = lo.abs().max(hi.abs());
+                    (-m, m)
+                } else {
+                    (lo, hi)
+                }
+            });
+            let axis = ["x", "y", "z"][normal];
+            let filename = format!(
+                "output/{}_{}{}_{:0width$}.png",
+                image.field.name(),
+                axis,
+                image.index,
+                step,
+                width = width
+            );
+            write_field_png(&filename, w, h, &values, (lo, hi), solid)?;
+        }
+        Ok(())
+    }
+}
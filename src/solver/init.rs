@@ -5,42 +5,47 @@ use std::vec;
 
 use super::lbm::LBM;
 
-use crate::solver::transforms::xyz_from_n;
+use crate::solver::transforms::n_from_xyz;
 use crate::utils::velocity::Velocity;
+use crate::solver::collision::CollisionOperator;
+use crate::solver::dispersion::Dispersion;
+use crate::solver::flags::CellType;
+use crate::solver::neighbors::NeighborIndexing;
 use crate::solver::precision::PrecisionMode;
-use crate::utils::terminal_utils::print_warning;
+use crate::solver::sparse::CellForces;
+use crate::solver::tracers::Tracers;
+use crate::solver::velocity_sets::VelocitySet;
+use crate::utils::terminal_utils::{print_info, print_warning};
+use std::collections::VecDeque;
 
 impl LBM {
     pub fn new(
         Nx: usize,
         Ny: usize,
         Nz: usize,
-        model: String,
+        model: VelocitySet,
         viscosity: f32,
         precision: PrecisionMode,
     ) -> Self {
-        let size = Nx * Ny * Nz;
-        let Q = match model.clone().as_str() {
-            "D2Q9" => 9,
-            "D3Q7" => 7,
-            "D3Q15" => 15,
-            "D3Q19" => 19,
-            "D3Q27" => 27,
-            _ => panic!("Unsupported model: {}", model),
-        };
-
-        println!(
+        print_info(&format!(
             "Initializing LBM with precision mode: {} - {}",
             format!("{:?}", precision).to_uppercase(),
             precision.description()
-        );
+        ));
+        Self::new_silent(Nx, Ny, Nz, model, viscosity, precision)
+    }
 
-        let (f_storage, f_compute_buffer) = match precision {
-            PrecisionMode::FP16S => {
-                (Some(vec![0u16; size * Q]), Some(vec![0.0f32; size * Q]))
-            }
-            _ => (None, None),
-        };
+    // Same as new() without the console banner, for internal helper lattices
+    pub fn new_silent(
+        Nx: usize,
+        Ny: usize,
+        Nz: usize,
+        model: VelocitySet,
+        viscosity: f32,
+        precision: PrecisionMode,
+    ) -> Self {
+        let size = Nx * Ny * Nz;
+        let Q = model.q();
 
         LBM {
             // --- Grid and Model Parameters ---
@@ -48,24 +53,35 @@ impl LBM {
             Ny,
             Nz,
             N: size,
-            model: model.clone(),
+            model,
             Q,
             viscosity,
             omega: 1.0 / (3.0 * viscosity + 0.5),
+            collision: CollisionOperator::BGK,
             precision_mode: precision,
             
-            f_storage,
-            f_compute_buffer,
+            checkpoint_f: None,
+            checkpoint_interval: 0,
+            checkpoint_keep: 1,
+            checkpoint_files: VecDeque::new(),
+            checkpoint_writer: None,
+            checkpoint_compression: None,
 
             // --- Simulation State ---
             time_steps: 0,
+            time_step: 0,
             found_errors: false,
+            self_test: false,
+            precision_audit: 0,
 
             // --- Lattice Data Arrays ---
             density: vec![1.0; size], // Initialize density to 1.0
             u: vec![0.0; size * 3],   // Initialize velocity to zero (size * 3 for 3 components per grid point)
             velocity: vec![Velocity::zero(); size], // Initialize input velocity to zero
             flags: vec![0u8; size],   // Initialize flags to 0 (fluid)
+            body_ids: vec![],
+            body_names: vec![],
+            wall_layer: vec![],
 
             // --- OpenCL Buffers and Handles ---
             f_buffer: None,
@@ -73,10 +89,24 @@ impl LBM {
             density_buffer: None,
             u_buffer: None,
             flags_buffer: None,
+            force_buffer: None,
+            neighbor_buffer: None,
+            wall_layer_buffer: None,
             platform: None,
             device: None,
+            device_choice: None,
+            assigned_device: None,
+            capabilities: None,
+            kernel_cache: true,
+            profiling: false,
+            kernel_times: vec![],
             context: None,
+            shared_context: None,
             queue: None,
+            out_of_order_queue: false,
+            step_event: None,
+            pinned_readback: true,
+            pinned_staging: None,
             program: None,
             stream_collide_kernel: None,
             equilibrium_kernel: None,
@@ -85,24 +115,111 @@ impl LBM {
             output_interval: 0,
             output_csv: false,
             output_vtk: false,
+            output_vti: false,
+            output_hdf5: false,
+            output_flags: false,
+            output_strides: [1; 6],
+            output_spacing: 1,
+            output_slices: vec![],
+            output_regions: vec![],
+            image_outputs: vec![],
+            output_arrays: vec![],
+            output_stress: false,
+            output_wall_shear: false,
+            remapped_stress: vec![],
+            remapped_wall_shear: vec![],
+            output_dissipation: false,
+            mirror_output: false,
+            output_geometry: false,
+            conservation_interval: 0,
+            conservation_correction: false,
+            conservation_history: vec![],
+            force_monitors: vec![],
+            force_monitor_interval: 0,
+            force_history: vec![],
+            energy_interval: 0,
+            energy_history: vec![],
+            flow_rate_monitors: vec![],
+            flow_rate_interval: 0,
+            flow_rate_history: vec![],
+            averages_start: None,
+            averages_every: 1,
+            averages_samples: 0,
+            averages_buffer: None,
+            averages: vec![],
+            mach_interval: 0,
+            mach_limit: 0.3,
+            mach_abort: false,
+            mach_exceeded: false,
+            max_speed_seen: 0.0,
+            steady_tolerance: 0.0,
+            steady_check_every: 1,
+            steady_previous: None,
+            steady_residual: None,
+            probes: vec![],
+            strouhal_reference: None,
+            tracers: Tracers::default(),
+            dispersion: Dispersion::default(),
+            pvd_entries: vec![],
+            hdf5_steps: vec![],
 
             // --- Forces ---
             use_constant_force: false,
             constant_force: None,
+            constant_force_compiled: false,
+            use_force_field: false,
+            force_field: vec![],
+
+            // --- Immersed Boundaries ---
+            immersed_boundaries: vec![],
+            ibm_forcing: CellForces::default(),
+            membranes: vec![],
+            kinematic_bodies: vec![],
+            spring_bodies: vec![],
+
+            // --- Moving Solids ---
+            use_moving_walls: false,
+            rigid_bodies: vec![],
+            device_bodies: vec![],
+            suspensions: vec![],
+            reflag_owner_buffer: None,
+            reflag_sdf: vec![],
+            reflag_sdf_buffer: None,
+
+            // --- Boundaries ---
+            symmetry_planes: 0,
+            in_place_streaming: false,
+            neighbor_indexing: NeighborIndexing::Modulo,
+            out_of_core_layers: 0,
         }
     }
 
     pub fn initialize(&mut self) {
+        let _span = tracing::info_span!(
+            target: "cappusim::init",
+            "initialize",
+            nx = self.Nx,
+            ny = self.Ny,
+            nz = self.Nz
+        )
+        .entered();
         self.platform = Some(
             self.get_ocl_platform()
                 .expect("Failed to get OpenCL platform"),
         );
         self.device = Some(self.get_ocl_device().expect("Failed to get OpenCL device"));
+        self.apply_device_capabilities()
+            .expect("Failed to query OpenCL device capabilities");
         self.context = Some(
             self.get_ocl_context()
                 .expect("Failed to get OpenCL context"),
         );
         self.queue = Some(self.get_ocl_queue().expect("Failed to get OpenCL queue"));
+        // Buffers of the previous context; the flags are uploaded without device bodies
+        self.pinned_staging = None;
+        self.reflag_owner_buffer = None;
+        self.reflag_sdf_buffer = None;
+        self.check_streaming_mode();
         self.program = Some(
             self.get_ocl_program()
                 .expect("Failed to generate OpenCL program."),
@@ -111,10 +228,15 @@ impl LBM {
             self.reserve_f_buffer()
                 .expect("Failed to reserve f_buffer."),
         );
-        self.f_new_buffer = Some(
-            self.reserve_f_new_buffer()
-                .expect("Failed to reserve f_new_buffer."),
-        );
+        // In-place streaming binds the same buffer for both lattices
+        self.f_new_buffer = if self.in_place_streaming {
+            self.f_buffer.clone()
+        } else {
+            Some(
+                self.reserve_f_new_buffer()
+                    .expect("Failed to reserve f_new_buffer."),
+            )
+        };
         self.density_buffer = Some(
             self.reserve_density_buffer()
                 .expect("Failed to reserve density_buffer."),
@@ -127,6 +249,17 @@ impl LBM {
             self.reserve_flags_buffer()
                 .expect("Failed to reserve flags_buffer."),
         );
+        self.force_buffer = Some(
+            self.reserve_force_buffer()
+                .expect("Failed to reserve force_buffer."),
+        );
+        self.neighbor_buffer = Some(
+            self.reserve_neighbor_buffer()
+                .expect("Failed to reserve neighbor_buffer."),
+        );
+        // Rebuilt on the new queue; the previous buffer belongs to the old context
+        self.compute_wall_layer()
+            .expect("Failed to compute the wall layer.");
 
         self.create_equilibrium_kernel()
             .expect("Failed to create 'equilibrium kernel'.");
@@ -135,20 +268,24 @@ impl LBM {
             .expect("Failed to create 'stream_collide' kernel.");
 
         self.calculate_vram_usage();
+        tracing::info!(
+            target: "cappusim::init",
+            "{}x{}x{} {} lattice initialized ({:?}, viscosity {})",
+            self.Nx, self.Ny, self.Nz, self.model, self.precision_mode, self.viscosity
+        );
     }
 
-    pub fn set_conditions<F>(&mut self, f: F)
-    where
-        F: Fn(&mut LBM, usize, usize, usize, usize), // x, y, z, n
-    {
-        for n in 0..self.N {
-            // Get the x, y, z coordinates from the linear index n
-            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
-            // Call the user-defined lambda function
-            f(self, x, y, z, n);
-        }
-        self.u = self.velocity_to_u(); // Transform 3D array to Flattened array
-        self.velocity = vec![]; 
+    // Set the type of one cell on the host; after initialize(), apply the
+    // change on the device with write_flags_to_gpu()
+    pub fn set_cell(&mut self, x: usize, y: usize, z: usize, cell: CellType) {
+        let n = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
+        self.flags[n] = cell.into();
+    }
+
+    // Type of one cell, or None for transient flags set by the solver
+    pub fn cell(&self, x: usize, y: usize, z: usize) -> Option<CellType> {
+        let n = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
+        CellType::try_from(self.flags[n]).ok()
     }
 
     pub fn set_constant_force(&mut self, F: Vec<f32>) {
@@ -159,5 +296,9 @@ impl LBM {
             }
         }
         self.use_constant_force = true;
+        // Takes effect at the next step when the kernel is already built
+        if let Err(err) = self.update_body_force_arg() {
+            print_warning(&format!("Warning: {}", err));
+        }
     }
 }
@@ -0,0 +1,142 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::utils::random::SplitMix64;
+
+use std::error::Error;
+
+// Releases particles uniformly inside a box at a fixed mean rate
+#[derive(Debug, Clone)]
+pub struct ParticleSource {
+    pub name: String,
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+    pub rate: f32, // Particles per step, fractional rates accumulate
+    pub velocity: [f32; 3],
+    pub diameter: f32, // 0 for passive tracers
+    pub density: f32,
+    pub released: usize,
+    accumulator: f32,
+    rng: SplitMix64,
+}
+
+impl LBM {
+    // Inject `rate` particles per step inside the box [min, max] with the
+    // given initial velocity, diameter and relative density. Returns the
+    // source index.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_particle_source(
+        &mut self,
+        name: &str,
+        min: [f32; 3],
+        max: [f32; 3],
+        rate: f32,
+        velocity: [f32; 3],
+        diameter: f32,
+        density: f32,
+    ) -> usize {
+        let seed = 0x5eed + self.tracers.sources.len() as u64;
+        self.tracers.sources.push(ParticleSource {
+            name: name.to_string(),
+            min,
+            max,
+            rate,
+            velocity,
+            diameter,
+            density,
+            released: 0,
+            accumulator: 0.0,
+            rng: SplitMix64::new(seed),
+        });
+        self.tracers.sources.len() - 1
+    }
+
+    // Remove particles crossing the domain faces normal to the selected axes
+    // instead of wrapping them around (non-periodic inflow/outflow domains)
+    pub fn set_particle_outlets(&mut self, axes: [bool; 3]) {
+        self.tracers.open_faces = (0..3).filter(|&d| axes[d]).map(|d| 1u8 << d).sum();
+    }
+
+    // Drop particles that left the domain or hit a solid, tallying deposition
+    // per body, then release new particles from the sources. The device
+    // buffers are only rebuilt when the particle set actually changes.
+    pub fn manage_particles(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut removed = [0i32];
+        if let Some(buffer) = self.tracers.removed_buffer.as_ref() {
+            buffer
+                .read(&mut removed[..])
+                .enq()
+                .map_err(|e| format!("Failed to read 'removed tracers' buffer: {}", e))?;
+        }
+        if removed[0] > 0 {
+            let mut status = vec![0i32; self.tracers.len()];
+            self.tracers
+                .status_buffer
+                .as_ref()
+                .ok_or("Tracer status buffer is None")?
+                .read(&mut status)
+                .enq()
+                .map_err(|e| format!("Failed to read 'tracer status' buffer: {}", e))?;
+            self.read_tracers_from_gpu()?;
+            self.tracers.invalidate();
+            self.tracers.deposition.resize(self.body_names.len() + 1, 0);
+
+            let mut keep = Vec::with_capacity(status.len());
+            for (k, &s) in status.iter().enumerate() {
+                keep.push(s == 0);
+                if s < 0 {
+                    self.tracers.exited += 1;
+                    let age = self.time_step - self.tracers.released_at[k];
+                    self.tracers.exit_times.push(age);
+                } else if s > 0 {
+                    let n = (s - 1) as usize;
+                    let body = self.body_ids.get(n).copied().unwrap_or(0) as usize;
+                    self.tracers.deposition[body] += 1;
+                }
+            }
+            self.tracers.retain(&keep);
+        }
+
+        let mut sources = std::mem::take(&mut self.tracers.sources);
+        for source in sources.iter_mut() {
+            source.accumulator += source.rate;
+            let count = source.accumulator.floor() as usize;
+            if count == 0 {
+                continue;
+            }
+            source.accumulator -= count as f32;
+            let positions: Vec<[f32; 3]> = (0..count)
+                .map(|_| [0, 1, 2].map(|d| source.rng.range(source.min[d], source.max[d])))
+                .collect();
+            if self.tracers.buffer.is_some() {
+                self.read_tracers_from_gpu()?;
+                self.tracers.invalidate();
+            }
+            let step = self.time_step;
+            self.tracers.push(
+                &positions,
+                source.velocity,
+                source.diameter,
+                source.density,
+                step,
+            );
+            source.released += count;
+        }
+        self.tracers.sources = sources;
+        Ok(())
+    }
+
+    // Deposited particle count per surface: unnamed walls first, then named bodies
+    pub fn deposition_counts(&self) -> Vec<(String, usize)> {
+        let mut counts = vec![(
+            "walls".to_string(),
+            self.tracers.deposition.first().copied().unwrap_or(0),
+        )];
+        for (k, name) in self.body_names.iter().enumerate() {
+            let count = self.tracers.deposition.get(k + 1).copied().unwrap_or(0);
+            counts.push((name.clone(), count));
+        }
+        counts
+    }
+}
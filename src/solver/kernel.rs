@@ -1,73 +1,145 @@
-#![allow(non_snake_case)] // Allow non-snake_case naming convention
-#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
-use super::lbm::LBM;
-use crate::solver::precision::PrecisionMode;
-
-use std::error::Error;
-
-pub const KERNEL_EQUILIBRIUM_SRC: &str = include_str!("../kernels/kernel_equilibrium.cl");
-pub const KERNEL_VELOCITY_SETS_SRC: &str = include_str!("../kernels/kernel_velocity_sets.cl");
-pub const KERNEL_STREAM_COLLIDE_SRC: &str = include_str!("../kernels/kernel_stream_collide.cl");
-
-impl LBM {
-    pub fn generate_custom_kernel(&mut self) -> Result<String, Box<dyn Error>> {
-        let precision_defines = match self.precision_mode {
-            PrecisionMode::FP32 => {
-                "#define USE_FP32\n#define FLOAT_TYPE float\n#define FLOAT4_TYPE float4\n"
-            },
-            PrecisionMode::FP16S => {
-                "#define USE_FP16S\n#define FLOAT_TYPE float\n#define STORAGE_TYPE half\n#define FLOAT4_TYPE float4\n"
-            },
-            PrecisionMode::FP16C => {
-                "#define USE_FP16C\n#define FLOAT_TYPE half\n#define FLOAT4_TYPE half4\n"
-            },
-        };
-
-        // Add force definition if use_constant_force is enabled
-        let constant_force_define = if self.use_constant_force {
-            format!(
-            r#"#define USE_CONSTANT_FORCE
-            #define FX {}
-            #define FY {}
-            #define FZ {}
-            "#,
-            self.constant_force.as_ref().unwrap()[0],
-            self.constant_force.as_ref().unwrap()[1],
-            self.constant_force.as_ref().unwrap()[2]
-            )
-        } else {
-            "".to_string()
-        };
-
-        let kernel_source = format!(
-            r#"
-        {}
-        #define NX {}
-        #define NY {}
-        #define NZ {}
-        #define N {}
-        #define Q {}
-        #define {}
-        #define FLAG_FLUID 0
-        #define FLAG_SOLID 1
-        #define FLAG_EQ 2
-        {}
-        {}
-        {}
-        {}
-        "#,
-            precision_defines,
-            self.Nx,
-            self.Ny,
-            self.Nz,
-            self.N,
-            self.Q,
-            self.model.as_str(),
-            constant_force_define,
-            KERNEL_VELOCITY_SETS_SRC,
-            KERNEL_STREAM_COLLIDE_SRC,
-            KERNEL_EQUILIBRIUM_SRC,
-        );
-        Ok(kernel_source)
-    }
-}
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+use super::lbm::LBM;
+use crate::solver::precision::PrecisionMode;
+
+use std::error::Error;
+
+pub const KERNEL_EQUILIBRIUM_SRC: &str = include_str!("../kernels/kernel_equilibrium.cl");
+pub const KERNEL_VELOCITY_SETS_SRC: &str = include_str!("../kernels/kernel_velocity_sets.cl");
+pub const KERNEL_STREAM_COLLIDE_SRC: &str = include_str!("../kernels/kernel_stream_collide.cl");
+pub const KERNEL_REFILL_SRC: &str = include_str!("../kernels/kernel_refill.cl");
+pub const KERNEL_CONSERVATION_SRC: &str = include_str!("../kernels/kernel_conservation.cl");
+pub const KERNEL_MOMENTUM_EXCHANGE_SRC: &str = include_str!("../kernels/kernel_momentum_exchange.cl");
+pub const KERNEL_REFLAG_SRC: &str = include_str!("../kernels/kernel_reflag.cl");
+pub const KERNEL_TRACERS_SRC: &str = include_str!("../kernels/kernel_tracers.cl");
+pub const KERNEL_REDUCTIONS_SRC: &str = include_str!("../kernels/kernel_reductions.cl");
+pub const KERNEL_AVERAGES_SRC: &str = include_str!("../kernels/kernel_averages.cl");
+pub const KERNEL_HALO_SRC: &str = include_str!("../kernels/kernel_halo.cl");
+pub const KERNEL_CELLS_SRC: &str = include_str!("../kernels/kernel_cells.cl");
+
+impl LBM {
+    pub fn generate_custom_kernel(&mut self) -> Result<String, Box<dyn Error>> {
+        let precision_defines = match self.precision_mode {
+            PrecisionMode::FP32 => {
+                "#define USE_FP32\n#define FLOAT_TYPE float\n#define FLOAT4_TYPE float4\n"
+            },
+            PrecisionMode::FP16S => {
+                "#define USE_FP16S\n#define FLOAT_TYPE float\n#define STORAGE_TYPE half\n#define FLOAT4_TYPE float4\n"
+            },
+            PrecisionMode::FP16C => {
+                "#define USE_FP16C\n#define FLOAT_TYPE half\n#define FLOAT4_TYPE half4\n"
+            },
+            // Reuses the FP16S kernels with bfloat16 conversions
+            PrecisionMode::BF16S => {
+                "#define USE_FP16S\n#define USE_BF16S\n#define FLOAT_TYPE float\n#define STORAGE_TYPE ushort\n#define FLOAT4_TYPE float4\n"
+            },
+        };
+
+        // Enable the constant force term; its value is a kernel argument
+        self.constant_force_compiled = self.use_constant_force;
+        let constant_force_define = if self.use_constant_force {
+            "#define USE_CONSTANT_FORCE\n"
+        } else {
+            ""
+        };
+
+        let force_field_define = if self.use_force_field {
+            "#define USE_FORCE_FIELD\n"
+        } else {
+            ""
+        };
+
+        let moving_walls_define = if self.use_moving_walls {
+            "#define USE_MOVING_WALLS\n"
+        } else {
+            ""
+        };
+
+        let symmetry_define = if self.symmetry_planes != 0 {
+            format!("#define SYMMETRY_PLANES {}\n", self.symmetry_planes)
+        } else {
+            "".to_string()
+        };
+
+        let streaming_define = if self.in_place_streaming {
+            "#define IN_PLACE_STREAMING\n"
+        } else {
+            ""
+        };
+
+        let neighbor_define = self.neighbor_indexing.kernel_define();
+
+        let collision_define = self.collision.kernel_define();
+
+        // Extensions and vector widths of the device
+        let capability_defines = self
+            .capabilities
+            .map(|c| c.kernel_defines())
+            .unwrap_or_default();
+
+        let kernel_source = format!(
+            r#"
+        {}
+        {}
+        #define NX {}
+        #define NY {}
+        #define NZ {}
+        #define N {}
+        #define Q {}
+        #define {}
+        #define FLAG_FLUID 0
+        #define FLAG_SOLID 1
+        #define FLAG_EQ 2
+        #define FLAG_FRESH 3
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        {}
+        "#,
+            precision_defines,
+            capability_defines,
+            self.Nx,
+            self.Ny,
+            self.Nz,
+            self.N,
+            self.Q,
+            self.model.as_str(),
+            constant_force_define,
+            force_field_define,
+            moving_walls_define,
+            symmetry_define,
+            streaming_define,
+            neighbor_define,
+            collision_define,
+            KERNEL_VELOCITY_SETS_SRC,
+            KERNEL_STREAM_COLLIDE_SRC,
+            KERNEL_EQUILIBRIUM_SRC,
+            KERNEL_REFILL_SRC,
+            KERNEL_CONSERVATION_SRC,
+            KERNEL_MOMENTUM_EXCHANGE_SRC,
+            KERNEL_REFLAG_SRC,
+            KERNEL_TRACERS_SRC,
+            KERNEL_REDUCTIONS_SRC,
+            KERNEL_AVERAGES_SRC,
+            KERNEL_HALO_SRC,
+            KERNEL_CELLS_SRC,
+        );
+        Ok(kernel_source)
+    }
+}
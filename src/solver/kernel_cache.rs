@@ -0,0 +1,96 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// On-disk cache of compiled OpenCL programs. The generated source bakes the
+// lattice size and options in as defines, so binaries are keyed by a hash of
+// the full source together with the device, driver and CappuSim version.
+// CAPPUSIM_KERNEL_CACHE sets the directory; otherwise $XDG_CACHE_HOME or
+// ~/.cache is used, falling back to the system temp directory.
+
+use super::lbm::LBM;
+use crate::utils::terminal_utils;
+
+use ocl::enums::{DeviceInfo, DeviceInfoResult, ProgramInfo, ProgramInfoResult};
+use ocl::Program;
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+pub fn kernel_cache_dir() -> PathBuf {
+    if let Some(dir) = std::env::var_os("CAPPUSIM_KERNEL_CACHE") {
+        return PathBuf::from(dir);
+    }
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir);
+    base.join("cappusim").join("kernels")
+}
+
+impl LBM {
+    // Enable or disable the compiled kernel cache (on by default)
+    pub fn set_kernel_cache(&mut self, enabled: bool) {
+        self.kernel_cache = enabled;
+    }
+
+    // Cache file of `source` on the selected device
+    pub fn kernel_cache_path(&self, source: &str) -> Option<PathBuf> {
+        let device = self.device.as_ref()?;
+        let driver = match device.info(DeviceInfo::DriverVersion) {
+            Ok(DeviceInfoResult::DriverVersion(version)) => version,
+            _ => String::new(),
+        };
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        device.name().unwrap_or_default().hash(&mut hasher);
+        driver.hash(&mut hasher);
+        source.hash(&mut hasher);
+        Some(kernel_cache_dir().join(format!("{:016x}.bin", hasher.finish())))
+    }
+
+    // Program built from a cached binary, or None when there is no usable one
+    pub fn load_cached_program(&self, path: &Path) -> Option<Program> {
+        let binary = fs::read(path).ok()?;
+        let program = Program::builder()
+            .binaries(&[&binary])
+            .devices(self.device.as_ref()?)
+            .build(self.context.as_ref()?);
+        match program {
+            Ok(program) => Some(program),
+            Err(_) => {
+                // Stale or rejected by the driver: rebuild from source
+                let _ = fs::remove_file(path);
+                None
+            }
+        }
+    }
+
+    // Store the device binary of a freshly built program; failures only warn
+    pub fn store_program_binary(&self, program: &Program, path: &Path) {
+        let binary = match program.info(ProgramInfo::Binaries) {
+            Ok(ProgramInfoResult::Binaries(mut binaries)) if !binaries.is_empty() => {
+                binaries.swap_remove(0)
+            }
+            _ => return,
+        };
+        if binary.is_empty() {
+            return;
+        }
+        // Write beside the target and rename so concurrent runs never read a
+        // partial file
+        let partial = path.with_extension(format!("{}.partial", std::process::id()));
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&partial, &binary))
+            .and_then(|_| fs::rename(&partial, path));
+        if let Err(err) = result {
+            terminal_utils::print_warning(&format!(
+                "Could not cache the compiled kernel in {}: {}",
+                path.display(),
+                err
+            ));
+        }
+    }
+}
@@ -0,0 +1,292 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::ibm::ImmersedBoundary;
+use crate::solver::moving::{motion_rates, Motion, Pose};
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::sync::Arc;
+
+// Shared pose function of a rigid marker motion
+#[derive(Clone)]
+pub struct RigidMotion(pub Arc<dyn Fn(f32) -> Pose + Send + Sync>);
+
+impl fmt::Debug for RigidMotion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RigidMotion(..)")
+    }
+}
+
+// Prescribed rigid or wave-like motions for immersed boundary markers.
+// Rotations are about the z axis, so 3D bodies behave as extruded 2D sections.
+// All parameters are in lattice units; frequencies in cycles per time step.
+#[derive(Debug, Clone)]
+pub enum Kinematics {
+    // Combined heave (translation along y) and pitch (rotation about `pivot`)
+    HeavePitch {
+        pivot: [f32; 3],
+        heave_amplitude: f32,
+        pitch_amplitude: f32, // Radians
+        frequency: f32,
+        phase: f32, // Pitch lead over heave, radians
+    },
+    // Constant angular velocity about `center` (paddles, rotors)
+    Rotation {
+        center: [f32; 3],
+        angular_velocity: f32, // Radians per time step
+    },
+    // Travelling transverse wave along x displacing markers along `normal`
+    Peristaltic {
+        amplitude: f32,
+        wavelength: f32,
+        wave_speed: f32,
+        normal: [f32; 3],
+    },
+    // Arbitrary rigid motion (see moving::Pose) about `center`
+    Rigid {
+        center: [f32; 3],
+        motion: RigidMotion,
+    },
+}
+
+pub fn rotate_z(v: [f32; 3], angle: f32) -> [f32; 3] {
+    let (s, c) = angle.sin_cos();
+    [c * v[0] - s * v[1], s * v[0] + c * v[1], v[2]]
+}
+
+impl Kinematics {
+    pub fn heave_pitch(
+        pivot: [f32; 3],
+        heave_amplitude: f32,
+        pitch_amplitude_deg: f32,
+        frequency: f32,
+        phase_deg: f32,
+    ) -> Self {
+        Kinematics::HeavePitch {
+            pivot,
+            heave_amplitude,
+            pitch_amplitude: pitch_amplitude_deg.to_radians(),
+            frequency,
+            phase: phase_deg.to_radians(),
+        }
+    }
+
+    pub fn rotation(center: [f32; 3], angular_velocity: f32) -> Self {
+        Kinematics::Rotation {
+            center,
+            angular_velocity,
+        }
+    }
+
+    pub fn peristaltic(amplitude: f32, wavelength: f32, wave_speed: f32, normal: [f32; 3]) -> Self {
+        Kinematics::Peristaltic {
+            amplitude,
+            wavelength,
+            wave_speed,
+            normal,
+        }
+    }
+
+    pub fn rigid(center: [f32; 3], motion: Motion) -> Self {
+        Kinematics::Rigid {
+            center,
+            motion: RigidMotion(Arc::from(motion)),
+        }
+    }
+
+    // Position and velocity at time `t` of a marker whose rest position is `reference`
+    pub fn evaluate(&self, reference: [f32; 3], t: f32) -> ([f32; 3], [f32; 3]) {
+        match *self {
+            Kinematics::HeavePitch {
+                pivot,
+                heave_amplitude,
+                pitch_amplitude,
+                frequency,
+                phase,
+            } => {
+                let omega = 2.0 * PI * frequency;
+                let heave = heave_amplitude * (omega * t).sin();
+                let heave_rate = heave_amplitude * omega * (omega * t).cos();
+                let theta = pitch_amplitude * (omega * t + phase).sin();
+                let theta_rate = pitch_amplitude * omega * (omega * t + phase).cos();
+
+                let arm = rotate_z(
+                    [
+                        reference[0] - pivot[0],
+                        reference[1] - pivot[1],
+                        reference[2] - pivot[2],
+                    ],
+                    theta,
+                );
+                let position = [
+                    pivot[0] + arm[0],
+                    pivot[1] + heave + arm[1],
+                    pivot[2] + arm[2],
+                ];
+                let velocity = [-theta_rate * arm[1], heave_rate + theta_rate * arm[0], 0.0];
+                (position, velocity)
+            }
+            Kinematics::Rotation {
+                center,
+                angular_velocity,
+            } => {
+                let arm = rotate_z(
+                    [
+                        reference[0] - center[0],
+                        reference[1] - center[1],
+                        reference[2] - center[2],
+                    ],
+                    angular_velocity * t,
+                );
+                let position = [center[0] + arm[0], center[1] + arm[1], center[2] + arm[2]];
+                let velocity = [-angular_velocity * arm[1], angular_velocity * arm[0], 0.0];
+                (position, velocity)
+            }
+            Kinematics::Peristaltic {
+                amplitude,
+                wavelength,
+                wave_speed,
+                normal,
+            } => {
+                let k = 2.0 * PI / wavelength;
+                let phase = k * (reference[0] - wave_speed * t);
+                let displacement = amplitude * phase.sin();
+                let rate = -amplitude * k * wave_speed * phase.cos();
+                let position = [
+                    reference[0] + displacement * normal[0],
+                    reference[1] + displacement * normal[1],
+                    reference[2] + displacement * normal[2],
+                ];
+                (
+                    position,
+                    [rate * normal[0], rate * normal[1], rate * normal[2]],
+                )
+            }
+            Kinematics::Rigid {
+                center,
+                ref motion,
+            } => {
+                let pose = (motion.0)(t);
+                let (v, omega) = motion_rates(&*motion.0, t);
+                let arm = rotate_z(
+                    [
+                        reference[0] - center[0],
+                        reference[1] - center[1],
+                        reference[2] - center[2],
+                    ],
+                    pose.angle,
+                );
+                let position = [0, 1, 2].map(|d| center[d] + pose.translation[d] + arm[d]);
+                let velocity = [v[0] - omega * arm[1], v[1] + omega * arm[0], v[2]];
+                (position, velocity)
+            }
+        }
+    }
+}
+
+// An immersed boundary whose markers follow prescribed kinematics
+#[derive(Debug, Clone)]
+pub struct KinematicBody {
+    pub boundary: usize, // Index into lbm.immersed_boundaries
+    pub reference: Vec<[f32; 3]>,
+    pub kinematics: Kinematics,
+}
+
+// Evenly spaced markers on a circle in the x-y plane
+pub fn circle_markers(center: [f32; 3], radius: f32, count: usize) -> Vec<[f32; 3]> {
+    (0..count)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f32 / count as f32;
+            [
+                center[0] + radius * angle.cos(),
+                center[1] + radius * angle.sin(),
+                center[2],
+            ]
+        })
+        .collect()
+}
+
+// Evenly spaced markers on the segment from `a` to `b` (paddles, walls)
+pub fn line_markers(a: [f32; 3], b: [f32; 3], count: usize) -> Vec<[f32; 3]> {
+    let count = count.max(2);
+    (0..count)
+        .map(|i| {
+            let s = i as f32 / (count - 1) as f32;
+            [
+                a[0] + s * (b[0] - a[0]),
+                a[1] + s * (b[1] - a[1]),
+                a[2] + s * (b[2] - a[2]),
+            ]
+        })
+        .collect()
+}
+
+// Markers on the surface of a symmetric NACA 00xx section with its leading
+// edge at `leading_edge`, chord along +x; `count` markers per side, ordered
+// around the closed contour (upper surface forward, lower surface back)
+pub fn naca_markers(
+    leading_edge: [f32; 3],
+    chord: f32,
+    thickness: f32,
+    count: usize,
+) -> Vec<[f32; 3]> {
+    let count = count.max(3);
+    let point = |i: usize, side: f32| {
+        // Cosine spacing clusters markers at the leading and trailing edges
+        let beta = PI * i as f32 / (count - 1) as f32;
+        let xc = 0.5 * (1.0 - beta.cos());
+        let yt = 5.0
+            * thickness
+            * (0.2969 * xc.sqrt() - 0.1260 * xc - 0.3516 * xc.powi(2) + 0.2843 * xc.powi(3)
+                - 0.1015 * xc.powi(4));
+        [
+            leading_edge[0] + xc * chord,
+            leading_edge[1] + side * yt * chord,
+            leading_edge[2],
+        ]
+    };
+    let mut markers: Vec<[f32; 3]> = (0..count).map(|i| point(i, 1.0)).collect();
+    // Leading and trailing edge points are shared by both surfaces
+    markers.extend((1..count - 1).rev().map(|i| point(i, -1.0)));
+    markers
+}
+
+impl LBM {
+    // Register an immersed boundary driven by prescribed kinematics; its
+    // markers start at their state for the current step
+    pub fn add_kinematic_body(
+        &mut self,
+        name: &str,
+        reference: Vec<[f32; 3]>,
+        marker_weight: f32,
+        kinematics: Kinematics,
+    ) -> usize {
+        let boundary = self.add_immersed_boundary(ImmersedBoundary::new(
+            name,
+            reference.clone(),
+            marker_weight,
+        ));
+        self.kinematic_bodies.push(KinematicBody {
+            boundary,
+            reference,
+            kinematics,
+        });
+        self.update_kinematic_bodies();
+        self.kinematic_bodies.len() - 1
+    }
+
+    // Move kinematic body markers to their prescribed state at the current step
+    pub fn update_kinematic_bodies(&mut self) {
+        let t = self.time_step as f32;
+        for body in &self.kinematic_bodies {
+            let boundary = &mut self.immersed_boundaries[body.boundary];
+            for (k, reference) in body.reference.iter().enumerate() {
+                let (position, velocity) = body.kinematics.evaluate(*reference, t);
+                boundary.positions[k] = position;
+                boundary.velocities[k] = velocity;
+            }
+        }
+    }
+}
@@ -2,9 +2,37 @@
 #![allow(non_snake_case)]
 #![allow(clippy::upper_case_acronyms)]
 
+use crate::solver::body_loads::{BodyLoadSample, ForceMonitor};
+use crate::solver::capabilities::DeviceCapabilities;
+use crate::solver::collision::CollisionOperator;
+use crate::solver::conservation::ConservationSample;
+use crate::solver::dispersion::Dispersion;
+use crate::solver::energy::EnergySample;
+use crate::solver::flow_rate::{FlowRateMonitor, FlowRateSample};
+use crate::solver::ibm::ImmersedBoundary;
+use crate::solver::image::ImageOutput;
+use crate::solver::kinematics::KinematicBody;
+use crate::solver::membrane::ElasticMembrane;
+use crate::solver::neighbors::NeighborIndexing;
+use crate::solver::pinned::PinnedStaging;
 use crate::solver::precision::PrecisionMode;
+use crate::solver::probes::Probe;
+use crate::solver::profiling::KernelTiming;
+use crate::solver::reflag::DeviceBody;
+use crate::solver::rigid::RigidBody;
+use crate::solver::roi::OutputRegion;
+use crate::solver::slices::OutputSlice;
+use crate::solver::sparse::CellForces;
+use crate::solver::spectrum::StrouhalReference;
+use crate::solver::spring::SpringMountedBody;
+use crate::solver::suspension::Suspension;
+use crate::solver::tracers::Tracers;
+use crate::solver::velocity_sets::VelocitySet;
+use crate::solver::xdmf::Hdf5Step;
 use crate::utils::velocity::Velocity;
-use ocl::{Buffer, Context, Device, Kernel, Platform, Program, Queue};
+use ocl::{Buffer, Context, Device, Event, Kernel, Platform, Program, Queue};
+use std::collections::VecDeque;
+use std::thread::JoinHandle;
 
 pub struct LBM {
     // Grid dimensions
@@ -14,15 +42,21 @@ pub struct LBM {
     pub N: usize,
 
     // LBM model parameters
-    pub model: String,
+    pub model: VelocitySet,
     pub Q: usize,
     pub viscosity: f32,
     pub omega: f32,
+    pub collision: CollisionOperator,
     pub time_steps: usize,
+    pub time_step: usize,
 
     // F types
-    pub f_storage: Option<Vec<u16>>,
-    pub f_compute_buffer: Option<Vec<f32>>,
+    pub checkpoint_f: Option<Vec<f32>>, // Raw populations uploaded by run() when resuming
+    pub checkpoint_interval: usize,     // Autosave interval in steps (0 = off)
+    pub checkpoint_keep: usize,
+    pub checkpoint_files: VecDeque<String>, // Autosaves on disk, oldest first
+    pub checkpoint_writer: Option<JoinHandle<std::io::Result<()>>>,
+    pub checkpoint_compression: Option<i32>, // zstd level, uncompressed when None
 
     // Macroscopic variables
     pub density: Vec<f32>,
@@ -31,6 +65,9 @@ pub struct LBM {
 
     // Flags and markers
     pub flags: Vec<u8>,
+    pub body_ids: Vec<u16>, // Per-cell body tag (0 = none), empty until add_body
+    pub body_names: Vec<String>,
+    pub wall_layer: Vec<u32>, // Fluid cells with a solid lattice neighbour, see compute_wall_layer
 
     // OpenCL buffers
     pub f_buffer: Option<Buffer<f32>>,
@@ -38,24 +75,112 @@ pub struct LBM {
     pub density_buffer: Option<Buffer<f32>>,
     pub u_buffer: Option<Buffer<f32>>,
     pub flags_buffer: Option<Buffer<u8>>,
+    pub force_buffer: Option<Buffer<f32>>,
+    pub neighbor_buffer: Option<Buffer<i32>>,
+    pub wall_layer_buffer: Option<Buffer<u32>>,
 
     // OpenCL context
     pub platform: Option<Platform>,
     pub device: Option<Device>,
+    pub device_choice: Option<(usize, usize)>, // (platform, device) indices picked at initialize()
+    pub assigned_device: Option<(usize, usize)>, // (platform, device) bypassing the selection, for MultiLBM
+    pub capabilities: Option<DeviceCapabilities>,
     pub context: Option<Context>,
+    pub shared_context: Option<Context>, // Multi-device context to initialize in instead of a new one
     pub queue: Option<Queue>,
+    pub out_of_order_queue: bool, // Requested; used only when the device supports it
+    pub step_event: Option<Event>, // Last stream-collide launch not yet synchronized
+    pub pinned_readback: bool, // Read density and velocity through page-locked buffers
+    pub pinned_staging: Option<PinnedStaging>,
     pub program: Option<Program>,
+    pub kernel_cache: bool, // Reuse compiled program binaries across runs
+    pub profiling: bool,    // Queue created with profiling enabled, kernels timed in run()
+    pub kernel_times: Vec<KernelTiming>,
     pub equilibrium_kernel: Option<Kernel>,
     pub stream_collide_kernel: Option<Kernel>,
 
     // Simulation control
     pub found_errors: bool,
+    pub self_test: bool, // Verify the device with a Taylor-Green run before run()
+    pub precision_audit: usize, // Steps of the FP32 comparison run before run() (0 = off)
     pub output_interval: usize,
     pub output_csv: bool,
     pub output_vtk: bool,
+    pub output_vti: bool,
+    pub output_hdf5: bool,
+    pub output_flags: bool,
+    pub output_strides: [usize; 6], // Per OutputTarget
+    pub output_spacing: usize,      // Cell spacing of decimated output views
+    pub output_slices: Vec<OutputSlice>, // Planes read back and written each output
+    pub output_regions: Vec<OutputRegion>, // Subvolumes with their own interval
+    pub image_outputs: Vec<ImageOutput>, // PNG frames of plane fields
+    pub output_arrays: Vec<String>, // Arrays written to .vti/HDF5 files, empty for all
+    pub output_stress: bool,
+    pub output_wall_shear: bool,
+    pub remapped_stress: Vec<[f32; 6]>, // Stress carried onto output views, which have no f
+    pub remapped_wall_shear: Vec<f32>,
+    pub output_dissipation: bool,
+    pub mirror_output: bool, // Write fields reflected across the symmetry planes
+    pub output_geometry: bool, // Probe and immersed body polydata series
+    pub conservation_interval: usize, // Mass/momentum monitor interval (0 = off)
+    pub conservation_correction: bool,
+    pub conservation_history: Vec<ConservationSample>,
+    pub force_monitors: Vec<ForceMonitor>,
+    pub force_monitor_interval: usize, // Body load sampling interval (0 = off)
+    pub force_history: Vec<BodyLoadSample>,
+    pub energy_interval: usize, // Kinetic energy/enstrophy monitor interval (0 = off)
+    pub energy_history: Vec<EnergySample>,
+    pub flow_rate_monitors: Vec<FlowRateMonitor>,
+    pub flow_rate_interval: usize, // Flow rate sampling interval (0 = off)
+    pub flow_rate_history: Vec<FlowRateSample>,
+    pub averages_start: Option<usize>, // Warm-up step of the time averages (None = off)
+    pub averages_every: usize,
+    pub averages_samples: usize,
+    pub averages_buffer: Option<Buffer<f32>>,
+    pub averages: Vec<f32>, // Host copy of the running means, AVERAGE_FIELDS per cell
+    pub mach_interval: usize, // Maximum velocity monitor interval (0 = off)
+    pub mach_limit: f32,      // Velocity in lattice units that triggers the warning
+    pub mach_abort: bool,
+    pub mach_exceeded: bool, // Limit exceeded at the last check, to warn once per excursion
+    pub max_speed_seen: f32,
+    pub steady_tolerance: f32, // Relative velocity change that ends run_until_steady (0 = off)
+    pub steady_check_every: usize,
+    pub steady_previous: Option<Buffer<f32>>, // Device velocity at the previous steady-state check
+    pub steady_residual: Option<f32>,
+    pub probes: Vec<Probe>,
+    pub strouhal_reference: Option<StrouhalReference>, // Probe signal analysed after run()
+    pub tracers: Tracers,
+    pub dispersion: Dispersion,
+    pub pvd_entries: Vec<(usize, usize, String)>, // (step, part, file) of the .pvd collection
+    pub hdf5_steps: Vec<Hdf5Step>, // Steps stored in the HDF5 file, for the XDMF index
     pub precision_mode: PrecisionMode,
 
     // Forces
     pub use_constant_force: bool,
     pub constant_force: Option<Vec<f32>>,
+    pub constant_force_compiled: bool, // Kernel built with USE_CONSTANT_FORCE
+    pub use_force_field: bool,
+    pub force_field: Vec<f32>,
+
+    // Immersed boundaries and structures coupled to them
+    pub immersed_boundaries: Vec<ImmersedBoundary>,
+    pub ibm_forcing: CellForces, // Force density last spread by the markers
+    pub membranes: Vec<ElasticMembrane>,
+    pub kinematic_bodies: Vec<KinematicBody>,
+    pub spring_bodies: Vec<SpringMountedBody>,
+
+    // --- Moving Solids ---
+    pub use_moving_walls: bool, // Bounce-back adds the wall velocity stored in u of solid cells
+    pub rigid_bodies: Vec<RigidBody>,
+    pub device_bodies: Vec<DeviceBody>,
+    pub suspensions: Vec<Suspension>,
+    pub reflag_owner_buffer: Option<Buffer<u16>>, // Device body ID per cell
+    pub reflag_sdf: Vec<f32>, // Samples of the SDF shapes, see sample_sdf
+    pub reflag_sdf_buffer: Option<Buffer<f32>>,
+
+    // --- Boundaries ---
+    pub symmetry_planes: u8, // Bit 2*axis: lower face, bit 2*axis+1: upper face
+    pub in_place_streaming: bool, // AA pattern with a single population buffer
+    pub neighbor_indexing: NeighborIndexing,
+    pub out_of_core_layers: usize, // Slab depth of out-of-core runs, 0 keeps the domain on the device
 }
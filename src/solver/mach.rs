@@ -0,0 +1,55 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Maximum velocity monitor: the largest |u| is reduced on the device and
+// compared with a limit above which compressibility errors dominate.
+
+use super::lbm::LBM;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+
+const LATTICE_SOUND_SPEED: f32 = 0.577_350_26; // 1 / sqrt(3)
+
+impl LBM {
+    // Check the maximum velocity every `interval` steps (0 disables) and warn
+    // when it exceeds `limit` lattice units (about 0.3); with `abort` the run
+    // stops instead
+    pub fn set_mach_monitor(&mut self, interval: usize, limit: f32, abort: bool) {
+        self.mach_interval = interval;
+        self.mach_limit = limit;
+        self.mach_abort = abort;
+    }
+
+    // Largest velocity magnitude over the non-solid cells on the device
+    pub fn max_speed(&self) -> Result<f32, Box<dyn Error>> {
+        let velocity = self.u_buffer.as_ref().ok_or("Velocity buffer is None")?;
+        Ok(self.field_max(velocity, 3)?.max(0.0) as f32)
+    }
+
+    // Sample the maximum velocity; an error is returned when the limit is
+    // exceeded and the monitor is set to abort. Warns once per excursion.
+    pub fn update_mach_monitor(&mut self) -> Result<(), Box<dyn Error>> {
+        let speed = self.max_speed()?;
+        self.max_speed_seen = self.max_speed_seen.max(speed);
+        if speed <= self.mach_limit {
+            self.mach_exceeded = false;
+            return Ok(());
+        }
+        let message = format!(
+            "Maximum velocity {:.4} (Mach {:.3}) exceeds {} at step {}",
+            speed,
+            speed / LATTICE_SOUND_SPEED,
+            self.mach_limit,
+            self.time_step
+        );
+        if self.mach_abort {
+            return Err(message.into());
+        }
+        if !self.mach_exceeded {
+            terminal_utils::print_warning(&format!("Warning: {}", message));
+            self.mach_exceeded = true;
+        }
+        Ok(())
+    }
+}
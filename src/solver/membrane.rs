@@ -0,0 +1,213 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::ibm::ImmersedBoundary;
+
+// Mass-spring model of a flexible filament (2D) or sheet (3D).
+// Nodes double as immersed boundary markers; all quantities in lattice units.
+#[derive(Debug, Clone)]
+pub struct ElasticMembrane {
+    pub boundary: usize, // Index into lbm.immersed_boundaries
+    pub positions: Vec<[f32; 3]>,
+    pub velocities: Vec<[f32; 3]>,
+    pub pinned: Vec<bool>,
+    pub node_mass: f32,
+    pub marker_weight: f32,
+    pub springs: Vec<(usize, usize, f32)>, // (i, j, rest length)
+    pub hinges: Vec<(usize, usize, usize)>, // Consecutive node triplets for bending
+    pub stretching_stiffness: f32,
+    pub bending_stiffness: f32,
+    pub gravity: [f32; 3],
+    pub substeps: usize,
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn norm(a: [f32; 3]) -> f32 {
+    (a[0] * a[0] + a[1] * a[1] + a[2] * a[2]).sqrt()
+}
+
+impl ElasticMembrane {
+    // Straight filament from `start` to `end` with its first node pinned
+    pub fn filament(
+        start: [f32; 3],
+        end: [f32; 3],
+        nodes: usize,
+        node_mass: f32,
+        stretching_stiffness: f32,
+        bending_stiffness: f32,
+    ) -> Self {
+        let nodes = nodes.max(2);
+        let segment = norm(sub(end, start)) / (nodes - 1) as f32;
+        let positions: Vec<[f32; 3]> = (0..nodes)
+            .map(|i| {
+                let s = i as f32 / (nodes - 1) as f32;
+                [
+                    start[0] + s * (end[0] - start[0]),
+                    start[1] + s * (end[1] - start[1]),
+                    start[2] + s * (end[2] - start[2]),
+                ]
+            })
+            .collect();
+        let springs = (0..nodes - 1).map(|i| (i, i + 1, segment)).collect();
+        let hinges = (0..nodes.saturating_sub(2)).map(|i| (i, i + 1, i + 2)).collect();
+        let mut pinned = vec![false; nodes];
+        pinned[0] = true;
+
+        ElasticMembrane {
+            boundary: 0,
+            velocities: vec![[0.0; 3]; nodes],
+            positions,
+            pinned,
+            node_mass,
+            marker_weight: segment,
+            springs,
+            hinges,
+            stretching_stiffness,
+            bending_stiffness,
+            gravity: [0.0; 3],
+            substeps: 20,
+        }
+    }
+
+    // Rectangular sheet spanned by `edge_u` x `edge_v` from `origin`, with the
+    // nodes along the leading edge (u = 0) pinned
+    pub fn sheet(
+        origin: [f32; 3],
+        edge_u: [f32; 3],
+        edge_v: [f32; 3],
+        (nu, nv): (usize, usize),
+        node_mass: f32,
+        stretching_stiffness: f32,
+        bending_stiffness: f32,
+    ) -> Self {
+        let (nu, nv) = (nu.max(2), nv.max(2));
+        let du = norm(edge_u) / (nu - 1) as f32;
+        let dv = norm(edge_v) / (nv - 1) as f32;
+        let index = |i: usize, j: usize| j * nu + i;
+
+        let mut positions = Vec::with_capacity(nu * nv);
+        for j in 0..nv {
+            for i in 0..nu {
+                let s = i as f32 / (nu - 1) as f32;
+                let t = j as f32 / (nv - 1) as f32;
+                positions.push([
+                    origin[0] + s * edge_u[0] + t * edge_v[0],
+                    origin[1] + s * edge_u[1] + t * edge_v[1],
+                    origin[2] + s * edge_u[2] + t * edge_v[2],
+                ]);
+            }
+        }
+
+        let mut springs = Vec::new();
+        let mut hinges = Vec::new();
+        for j in 0..nv {
+            for i in 0..nu {
+                if i + 1 < nu {
+                    springs.push((index(i, j), index(i + 1, j), du));
+                }
+                if j + 1 < nv {
+                    springs.push((index(i, j), index(i, j + 1), dv));
+                }
+                if i + 2 < nu {
+                    hinges.push((index(i, j), index(i + 1, j), index(i + 2, j)));
+                }
+                if j + 2 < nv {
+                    hinges.push((index(i, j), index(i, j + 1), index(i, j + 2)));
+                }
+            }
+        }
+        let pinned = (0..nu * nv).map(|k| k % nu == 0).collect();
+
+        ElasticMembrane {
+            boundary: 0,
+            velocities: vec![[0.0; 3]; nu * nv],
+            positions,
+            pinned,
+            node_mass,
+            marker_weight: du * dv,
+            springs,
+            hinges,
+            stretching_stiffness,
+            bending_stiffness,
+            gravity: [0.0; 3],
+            substeps: 20,
+        }
+    }
+
+    // Elastic forces on every node (springs + linear bending)
+    pub fn elastic_forces(&self) -> Vec<[f32; 3]> {
+        let mut forces = vec![[0.0f32; 3]; self.positions.len()];
+        for &(i, j, rest) in &self.springs {
+            let d = sub(self.positions[j], self.positions[i]);
+            let length = norm(d).max(1e-12);
+            let magnitude = self.stretching_stiffness * (length - rest) / length;
+            for k in 0..3 {
+                forces[i][k] += magnitude * d[k];
+                forces[j][k] -= magnitude * d[k];
+            }
+        }
+        for &(a, b, c) in &self.hinges {
+            let (pa, pb, pc) = (self.positions[a], self.positions[b], self.positions[c]);
+            for k in 0..3 {
+                let curvature = self.bending_stiffness * (pa[k] - 2.0 * pb[k] + pc[k]);
+                forces[a][k] -= curvature;
+                forces[b][k] += 2.0 * curvature;
+                forces[c][k] -= curvature;
+            }
+        }
+        forces
+    }
+
+    // Advance one lattice time step under the given external (fluid) loads
+    pub fn step(&mut self, loads: &[[f32; 3]]) {
+        let substeps = self.substeps.max(1);
+        let dt = 1.0 / substeps as f32;
+        for _ in 0..substeps {
+            let forces = self.elastic_forces();
+            for i in 0..self.positions.len() {
+                if self.pinned[i] {
+                    self.velocities[i] = [0.0; 3];
+                    continue;
+                }
+                for k in 0..3 {
+                    let acceleration = (forces[i][k] + loads[i][k]) / self.node_mass + self.gravity[k];
+                    // Semi-implicit (symplectic) Euler
+                    self.velocities[i][k] += dt * acceleration;
+                    self.positions[i][k] += dt * self.velocities[i][k];
+                }
+            }
+        }
+    }
+
+    // Total kinetic plus elastic stretching energy, for stability monitoring
+    pub fn energy(&self) -> f32 {
+        let kinetic: f32 = self
+            .velocities
+            .iter()
+            .map(|v| 0.5 * self.node_mass * (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]))
+            .sum();
+        let elastic: f32 = self
+            .springs
+            .iter()
+            .map(|&(i, j, rest)| {
+                let stretch = norm(sub(self.positions[j], self.positions[i])) - rest;
+                0.5 * self.stretching_stiffness * stretch * stretch
+            })
+            .sum();
+        kinetic + elastic
+    }
+}
+
+impl LBM {
+    // Couple a membrane to the flow through a new immersed boundary
+    pub fn add_membrane(&mut self, name: &str, mut membrane: ElasticMembrane) -> usize {
+        let boundary = ImmersedBoundary::new(name, membrane.positions.clone(), membrane.marker_weight);
+        membrane.boundary = self.add_immersed_boundary(boundary);
+        self.membranes.push(membrane);
+        self.membranes.len() - 1
+    }
+}
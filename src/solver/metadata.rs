@@ -0,0 +1,126 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// JSON sidecar describing a run (lattice, physics, device, code version and
+// timing), written next to the outputs so they stay interpretable later.
+
+use super::lbm::LBM;
+
+use std::error::Error;
+use std::fs;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// Finite floats as numbers, anything else as null
+pub fn json_number(v: f64) -> String {
+    if v.is_finite() {
+        format!("{}", v)
+    } else {
+        "null".to_string()
+    }
+}
+
+// Commit of the working directory, with a "-dirty" suffix for uncommitted
+// changes; "unknown" outside a git checkout
+fn git_hash() -> String {
+    let run = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string())
+    };
+    match run(&["rev-parse", "HEAD"]) {
+        Some(hash) if !hash.is_empty() => match run(&["status", "--porcelain"]) {
+            Some(status) if !status.is_empty() => format!("{}-dirty", hash),
+            _ => hash,
+        },
+        _ => "unknown".to_string(),
+    }
+}
+
+// Per-run values that are not part of the LBM state
+pub struct RunTiming {
+    pub start_step: usize,
+    pub end_step: usize,
+    pub wall_time: Option<f64>, // Seconds, None while the run is in progress
+    pub mlups: Option<f64>,
+}
+
+impl LBM {
+    // Write the run description to `path` as JSON. run() writes
+    // output/run.json when it starts and again with the timing when it ends.
+    pub fn write_run_metadata(&self, path: &str, timing: &RunTiming) -> Result<(), Box<dyn Error>> {
+        let device = self
+            .device
+            .as_ref()
+            .and_then(|d| d.name().ok())
+            .unwrap_or_else(|| "unknown".to_string());
+        let platform = self
+            .platform
+            .as_ref()
+            .and_then(|p| p.name().ok())
+            .unwrap_or_else(|| "unknown".to_string());
+        let force = match &self.constant_force {
+            Some(f) if self.use_constant_force => format!(
+                "[{}]",
+                f.iter()
+                    .map(|v| json_number(*v as f64))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            _ => "null".to_string(),
+        };
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let optional = |v: Option<f64>| v.map(json_number).unwrap_or_else(|| "null".to_string());
+
+        let fields = [
+            ("grid", format!("[{}, {}, {}]", self.Nx, self.Ny, self.Nz)),
+            ("model", json_string(self.model.as_str())),
+            ("viscosity", json_number(self.viscosity as f64)),
+            ("omega", json_number(self.omega as f64)),
+            (
+                "precision",
+                json_string(&format!("{:?}", self.precision_mode)),
+            ),
+            ("constant_force", force),
+            ("force_field", self.use_force_field.to_string()),
+            ("device", json_string(&device)),
+            ("platform", json_string(&platform)),
+            ("git_hash", json_string(&git_hash())),
+            ("cappusim_version", json_string(env!("CARGO_PKG_VERSION"))),
+            ("start_step", timing.start_step.to_string()),
+            ("end_step", timing.end_step.to_string()),
+            ("output_interval", self.output_interval.to_string()),
+            ("wall_time_s", optional(timing.wall_time)),
+            ("mlups", optional(timing.mlups)),
+            ("written_unix", created.to_string()),
+        ];
+        let body: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("  {}: {}", json_string(key), value))
+            .collect();
+        fs::write(path, format!("{{\n{}\n}}\n", body.join(",\n")))?;
+        Ok(())
+    }
+}
@@ -1,11 +1,86 @@
+#[cfg(feature = "ndarray")]
+pub mod arrays;
+pub mod audit;
+pub mod averages;
+pub mod balance;
+pub mod bodies;
+pub mod body_loads;
+pub mod builder;
+pub mod capabilities;
 pub mod check;
+pub mod checkpoint;
+pub mod collision;
+pub mod conditions;
+pub mod config;
+pub mod config_file;
+pub mod conservation;
+pub mod coupling;
+pub mod diagnostics;
+pub mod dispersion;
+#[cfg(feature = "mpi")]
+pub mod distributed;
+pub mod edit;
+pub mod energy;
+pub mod events;
 pub mod flags;
+pub mod flow_rate;
+pub mod forces;
+pub mod geometry;
+pub mod halo;
+pub mod ibm;
+pub mod image;
 pub mod init;
+pub mod injection;
 pub mod kernel;
+pub mod kernel_cache;
+pub mod kinematics;
 pub mod lbm;
+pub mod mach;
+pub mod membrane;
+pub mod metadata;
+pub mod moving;
+pub mod multi;
+pub mod neighbors;
+pub mod npy;
 pub mod opencl;
+pub mod out_of_core;
 pub mod output;
+pub mod parameters;
+pub mod pinned;
+pub mod polydata;
+pub mod porous;
+pub mod post;
 pub mod precision;
+pub mod presets;
+pub mod pressure;
+pub mod probes;
+pub mod profiling;
+pub mod pvd;
+pub mod reductions;
+pub mod reflag;
+pub mod region;
+pub mod restart;
+pub mod rigid;
+pub mod roi;
 pub mod run;
+pub mod selftest;
+pub mod slices;
+pub mod sparse;
+pub mod spectrum;
+pub mod spring;
+pub mod stats;
+pub mod steady;
+pub mod streaming;
+pub mod streamlines;
+pub mod stress;
+pub mod suspension;
+pub mod symmetry;
+pub mod terrain;
+pub mod tiling;
+pub mod tracers;
 pub mod transforms;
+pub mod velocity_sets;
+pub mod vti;
+pub mod wall_layer;
+pub mod xdmf;
 pub mod benchmark;
\ No newline at end of file
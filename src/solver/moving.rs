@@ -0,0 +1,298 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::{FLAG_FLUID, FLAG_FRESH, FLAG_SOLID};
+use crate::solver::kinematics::Kinematics;
+use crate::solver::reflag::Primitive;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+
+use ocl::{flags::MEM_READ_ONLY, Buffer, Event, Kernel};
+use std::error::Error;
+
+// Signed distance function in the body frame (negative inside)
+pub type Sdf = Box<dyn Fn([f32; 3]) -> f32 + Send + Sync>;
+
+// Rigid displacement from the reference configuration: a rotation about the
+// z-parallel axis through the body center followed by a translation
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pose {
+    pub translation: [f32; 3],
+    pub angle: f32, // Radians, counter-clockwise
+}
+
+// Pose as a function of the time step
+pub type Motion = Box<dyn Fn(f32) -> Pose + Send + Sync>;
+
+// Linear and angular velocity of a motion from central differences of the pose
+pub fn motion_rates(motion: &(dyn Fn(f32) -> Pose + Send + Sync), t: f32) -> ([f32; 3], f32) {
+    let (a, b) = (motion(t - 0.5), motion(t + 0.5));
+    (
+        [
+            b.translation[0] - a.translation[0],
+            b.translation[1] - a.translation[1],
+            b.translation[2] - a.translation[2],
+        ],
+        b.angle - a.angle,
+    )
+}
+
+impl LBM {
+    // Immersed boundary whose markers follow the rigid `motion`; `reference`
+    // holds the marker positions at the identity pose and `center` the
+    // rotation center. Returns the kinematic body index.
+    pub fn add_moving_markers(
+        &mut self,
+        name: &str,
+        center: [f32; 3],
+        reference: Vec<[f32; 3]>,
+        marker_weight: f32,
+        motion: Motion,
+    ) -> usize {
+        self.add_kinematic_body(name, reference, marker_weight, Kinematics::rigid(center, motion))
+    }
+
+    // Voxelized solid following `motion`, described by an SDF in its body
+    // frame (origin at `center`). The SDF is sampled once and the body is
+    // re-flagged on the device every `interval` steps (see reflag.rs). Call
+    // after set_conditions. Returns the body ID.
+    pub fn add_moving_solid(
+        &mut self,
+        name: &str,
+        center: [f32; 3],
+        interval: usize,
+        motion: Motion,
+        sdf: Sdf,
+    ) -> u16 {
+        // The body extent is pose invariant, so one scan at the reference pose bounds it
+        let local = |p: [f32; 3]| [p[0] - center[0], p[1] - center[1], p[2] - center[2]];
+        let reach = self.solid_reach(center, &|p| sdf(local(p)) <= 0.0);
+        let primitive = self.sample_sdf(&*sdf, reach);
+        self.add_device_body_every(name, center, primitive, motion, interval)
+    }
+
+    // Analytic solid spinning at a constant angular velocity (radians per
+    // step) about the z-parallel axis through `center`, e.g. turbine rotors.
+    // It is re-flagged on the device every `interval` steps (see reflag.rs);
+    // bodies without an analytic primitive use add_moving_solid with an SDF.
+    pub fn add_rotating_body(
+        &mut self,
+        name: &str,
+        center: [f32; 3],
+        angular_velocity: f32,
+        interval: usize,
+        primitive: Primitive,
+    ) -> u16 {
+        let motion: Motion = Box::new(move |t| Pose {
+            translation: [0.0; 3],
+            angle: angular_velocity * t,
+        });
+        self.add_device_body_every(name, center, primitive, motion, interval)
+    }
+
+    // Largest distance from `origin` of a cell inside a solid (full domain scan)
+    pub fn solid_reach(&self, origin: [f32; 3], inside: &dyn Fn([f32; 3]) -> bool) -> f32 {
+        let mut reach = 0.0f32;
+        for n in 0..self.N {
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            let p = [x as f32, y as f32, z as f32];
+            if inside(p) {
+                let r = (0..3)
+                    .map(|d| (p[d] - origin[d]).powi(2))
+                    .sum::<f32>()
+                    .sqrt();
+                reach = reach.max(r);
+            }
+        }
+        reach
+    }
+
+    // Switch the kernel to moving-wall bounce-back. Static solids must not
+    // inherit inflow velocities as wall motion, so their u is cleared first.
+    pub fn enable_moving_walls(&mut self) {
+        if self.use_moving_walls {
+            return;
+        }
+        for n in 0..self.N {
+            if self.flags[n] == FLAG_SOLID {
+                self.u[n * 3..n * 3 + 3].fill(0.0);
+            }
+        }
+        self.use_moving_walls = true;
+    }
+
+    // Re-rasterize a solid body: cells of `old_cells` it owns that are no longer
+    // `inside` become fluid, and fluid or owned cells inside the sphere `bounds`
+    // (origin, reach) become solid with the given wall velocity. Static walls,
+    // equilibrium cells and other bodies are left alone. Returns (covered
+    // cells, uncovered cells).
+    pub fn rasterize_solid(
+        &mut self,
+        body_id: u16,
+        old_cells: &[u32],
+        bounds: ([f32; 3], f32),
+        inside: &dyn Fn([f32; 3]) -> bool,
+        wall_velocity: &dyn Fn([f32; 3]) -> [f32; 3],
+    ) -> (Vec<u32>, Vec<u32>) {
+        let (origin, reach) = bounds;
+        let mut fresh = Vec::new();
+        for &n in old_cells {
+            let n = n as usize;
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            if self.body_ids[n] == body_id && !inside([x as f32, y as f32, z as f32]) {
+                self.flags[n] = FLAG_FLUID;
+                self.body_ids[n] = 0;
+                fresh.push(n as u32);
+            }
+        }
+
+        let range = |center: f32, size: usize| {
+            let lo = (center - reach - 1.0).floor().max(0.0) as usize;
+            let hi = ((center + reach + 1.0).ceil().max(0.0) as usize).min(size - 1);
+            lo..=hi
+        };
+        let mut covered = Vec::new();
+        for z in range(origin[2], self.Nz) {
+            for y in range(origin[1], self.Ny) {
+                for x in range(origin[0], self.Nx) {
+                    let p = [x as f32, y as f32, z as f32];
+                    if !inside(p) {
+                        continue;
+                    }
+                    let n = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
+                    if self.flags[n] != FLAG_FLUID && self.body_ids[n] != body_id {
+                        continue;
+                    }
+                    self.flags[n] = FLAG_SOLID;
+                    self.body_ids[n] = body_id;
+                    self.u[n * 3..n * 3 + 3].copy_from_slice(&wall_velocity(p));
+                    covered.push(n as u32);
+                }
+            }
+        }
+        (covered, fresh)
+    }
+
+    // Upload the flags and rebuild the populations of the uncovered `cells`
+    // from equilibrium at their current u plus the non-equilibrium part of a
+    // neighbour. The cells are uploaded as FLAG_FRESH so the refill never
+    // extrapolates from another stale cell, then returned to the fluid.
+    pub fn refill_cells(&mut self, cells: &[u32]) -> Result<(), Box<dyn Error>> {
+        for &n in cells {
+            self.flags[n as usize] = FLAG_FRESH;
+        }
+        let uploaded = self.write_flags_to_gpu();
+        for &n in cells {
+            self.flags[n as usize] = FLAG_FLUID;
+        }
+        uploaded?;
+        if cells.is_empty() {
+            return Ok(());
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let cells_buffer = Buffer::<u32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_ONLY)
+            .len(cells.len())
+            .copy_host_slice(cells)
+            .build()
+            .map_err(|e| format!("Failed to build 'cells' buffer: {}", e))?;
+        self.enqueue_refill(&cells_buffer, cells.len())
+    }
+
+    // Refill the first `count` FLAG_FRESH cells listed in a device buffer
+    pub fn enqueue_refill(
+        &self,
+        cells_buffer: &Buffer<u32>,
+        count: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        if count == 0 {
+            return Ok(());
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("refill_kernel")
+            .queue(queue.clone())
+            .global_work_size(count)
+            .arg(self.f_buffer.as_ref().ok_or("f buffer is None")?)
+            .arg(self.f_new_buffer.as_ref().ok_or("f_new buffer is None")?)
+            .arg(
+                self.density_buffer
+                    .as_ref()
+                    .ok_or("Density buffer is None")?,
+            )
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(cells_buffer)
+            .arg(count as i32)
+            .arg(self.time_step as i32)
+            .build()
+            .map_err(|e| format!("Failed to build 'refill_kernel': {}", e))?;
+
+        let finish = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("refill_finish_kernel")
+            .queue(queue.clone())
+            .global_work_size(count)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(cells_buffer)
+            .arg(count as i32)
+            .build()
+            .map_err(|e| format!("Failed to build 'refill_finish_kernel': {}", e))?;
+
+        let mut refilled = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut refilled)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'refill_kernel': {}", e))?;
+            finish
+                .cmd()
+                .ewait(&refilled)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'refill_finish_kernel': {}", e))?;
+        }
+        queue.finish()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solver::flags::{CellType, FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+    use crate::solver::lbm::LBM;
+
+    #[test]
+    fn rasterize_solid_only_takes_fluid_cells() {
+        let (nx, ny) = (16, 16);
+        let mut lbm = LBM::builder().size(nx, ny, 1).viscosity(0.1).build().unwrap();
+        lbm.set_conditions(|lbm, x, y, z, _n| {
+            if x == 0 {
+                lbm.set_cell(x, y, z, CellType::Equilibrium);
+            } else if y == 0 {
+                lbm.set_cell(x, y, z, CellType::Solid);
+            }
+        });
+        let other = lbm.register_body("other");
+        let body = lbm.register_body("body");
+        let disk = |p: [f32; 3]| (p[0] - 8.0).powi(2) + (p[1] - 8.0).powi(2) <= 4.0;
+        let (taken, _) = lbm.rasterize_solid(other, &[], ([8.0, 8.0, 0.0], 2.0), &disk, &|_| [0.0; 3]);
+
+        // A body covering the whole domain claims the free fluid only
+        let everywhere = ([8.0, 8.0, 0.0], 16.0);
+        let (covered, _) = lbm.rasterize_solid(body, &[], everywhere, &|_| true, &|_| [0.0; 3]);
+        assert_eq!(covered.len(), (nx - 1) * (ny - 1) - taken.len());
+        assert_eq!(lbm.flags[4 * nx], FLAG_EQ);
+        assert!(taken.iter().all(|&n| lbm.body_ids[n as usize] == other));
+
+        // Leaving the domain releases exactly those cells
+        let (_, released) = lbm.rasterize_solid(body, &covered, everywhere, &|_| false, &|_| [0.0; 3]);
+        assert_eq!(released, covered);
+        assert!(released.iter().all(|&n| lbm.flags[n as usize] == FLAG_FLUID));
+        assert_eq!(lbm.flags[4 * nx], FLAG_EQ);
+        assert_eq!(lbm.flags[4], FLAG_SOLID);
+        assert!(taken.iter().all(|&n| lbm.flags[n as usize] == FLAG_SOLID));
+    }
+}
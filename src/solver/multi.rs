@@ -0,0 +1,442 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Domain decomposition across the OpenCL devices of one node. The domain is
+// split along the slowest axis (z, or y in 2D) into one slab per device; each
+// slab lattice carries a halo layer on both sides. After every step the
+// boundary layers of each slab are copied into the halos of its neighbours
+// (periodically), so the interior cells of every slab stream exactly as on a
+// single device; the transfers overlap the interior work, see halo.rs. Only
+// FP32 two-lattice streaming is supported.
+
+use super::lbm::LBM;
+use crate::solver::balance::BALANCE_INTERVAL;
+use crate::solver::halo::HaloExchange;
+use crate::solver::opencl::{list_devices, DeviceListing};
+use crate::solver::precision::PrecisionMode;
+use crate::utils::terminal_utils;
+
+use indicatif::ProgressStyle;
+use ocl::{Buffer, Context, Device, Event, EventList, Platform};
+use std::error::Error;
+use std::time::Instant;
+
+pub struct Subdomain {
+    pub lattice: LBM, // depth + 2 layers, on its own device
+    pub start: usize, // First layer of the global domain
+    pub depth: usize, // Interior layers
+    pub halo: HaloExchange,
+    pub kernel_events: Vec<Event>, // Launches of the last step, for load balancing
+}
+
+pub struct MultiLBM {
+    pub lbm: LBM, // Global domain: setup, host fields and outputs
+    pub devices: Vec<DeviceListing>,
+    pub subdomains: Vec<Subdomain>,
+    pub balance: bool,  // Size slabs by device speed and rebalance when one lags
+    pub lagging: usize, // Consecutive load samples with a lagging device
+    pub peer: bool,     // Copy halos device to device when the devices share a context
+}
+
+impl LBM {
+    // Whether this setup can be split into `parts` slabs
+    pub fn check_decomposition(&self, parts: usize) -> Result<(), Box<dyn Error>> {
+        let (layers, _) = self.slab_layers();
+        if layers < parts {
+            return Err(format!("Cannot split {} layers into {} slabs.", layers, parts).into());
+        }
+        if self.precision_mode != PrecisionMode::FP32 {
+            return Err("Decomposed runs support FP32 only.".into());
+        }
+        if self.in_place_streaming || self.symmetry_planes != 0 {
+            return Err(
+                "Decomposed runs support neither in-place streaming nor symmetry planes.".into(),
+            );
+        }
+        if self.use_force_field {
+            return Err("Decomposed runs do not support per-cell force fields.".into());
+        }
+        Ok(())
+    }
+
+    // Slab lattice of layers start..start + depth and its halos on `device`,
+    // filled with the host fields and initialized in equilibrium
+    pub fn build_slab(
+        &self,
+        start: usize,
+        depth: usize,
+        device: Option<(usize, usize)>,
+    ) -> Result<LBM, Box<dyn Error>> {
+        let (layers, len) = self.slab_layers();
+        let mut lattice = self.slab_lattice(depth, device);
+        for l in 0..depth + 2 {
+            let z = (start + layers + l - 1) % layers;
+            let cells = z * len..(z + 1) * len;
+            lattice.density[l * len..(l + 1) * len].copy_from_slice(&self.density[cells.clone()]);
+            lattice.u[3 * l * len..3 * (l + 1) * len]
+                .copy_from_slice(&self.u[3 * cells.start..3 * cells.end]);
+            lattice.flags[l * len..(l + 1) * len].copy_from_slice(&self.flags[cells]);
+        }
+        lattice
+            .density_buffer
+            .as_ref()
+            .ok_or("Density buffer is None")?
+            .write(&lattice.density)
+            .enq()?;
+        lattice
+            .u_buffer
+            .as_ref()
+            .ok_or("Velocity buffer is None")?
+            .write(&lattice.u)
+            .enq()?;
+        lattice
+            .flags_buffer
+            .as_ref()
+            .ok_or("Flags buffer is None")?
+            .write(&lattice.flags)
+            .enq()?;
+        unsafe {
+            lattice
+                .equilibrium_kernel
+                .as_ref()
+                .ok_or("Equilibrium kernel is None")?
+                .enq()?;
+        }
+        Ok(lattice)
+    }
+
+    // (first layer, depth) of each of `parts` slabs: equal, the first ones
+    // one layer deeper when uneven
+    pub fn decomposition(&self, parts: usize) -> Vec<(usize, usize)> {
+        let (layers, _) = self.slab_layers();
+        let mut start = 0;
+        (0..parts)
+            .map(|i| {
+                let depth = layers / parts + usize::from(i < layers % parts);
+                start += depth;
+                (start - depth, depth)
+            })
+            .collect()
+    }
+}
+
+// The population buffer step t writes
+fn written_buffer(lattice: &LBM, t: usize) -> Result<Buffer<f32>, Box<dyn Error>> {
+    let f = if t % 2 == 0 {
+        &lattice.f_new_buffer
+    } else {
+        &lattice.f_buffer
+    };
+    Ok(f.as_ref().ok_or("f buffer is None")?.clone())
+}
+
+// GPUs of all platforms, or every device when there is no GPU
+pub fn gpu_devices() -> Result<Vec<DeviceListing>, Box<dyn Error>> {
+    let listings = list_devices()?;
+    let gpus: Vec<DeviceListing> = listings
+        .iter()
+        .filter(|d| d.device_type == "GPU")
+        .cloned()
+        .collect();
+    Ok(if gpus.is_empty() { listings } else { gpus })
+}
+
+impl MultiLBM {
+    // Decompose over every GPU of the node
+    pub fn new(lbm: LBM) -> Result<MultiLBM, Box<dyn Error>> {
+        Ok(MultiLBM::with_devices(lbm, gpu_devices()?))
+    }
+
+    // Decompose over the given devices, one slab each
+    pub fn with_devices(lbm: LBM, devices: Vec<DeviceListing>) -> MultiLBM {
+        MultiLBM {
+            lbm,
+            devices,
+            subdomains: Vec::new(),
+            balance: true,
+            lagging: 0,
+            peer: true,
+        }
+    }
+
+    // Copy halos device to device instead of through host memory when all
+    // devices are on one platform (on by default, falls back automatically)
+    pub fn set_peer_transfers(&mut self, enabled: bool) {
+        self.peer = enabled;
+    }
+
+    // One OpenCL context over all devices, when they share a platform and
+    // the driver accepts it
+    fn shared_context(&self) -> Option<Context> {
+        let platform_index = self.devices.first()?.platform_index;
+        if self.devices.len() < 2
+            || self
+                .devices
+                .iter()
+                .any(|d| d.platform_index != platform_index)
+        {
+            return None;
+        }
+        let platform = Platform::list().into_iter().nth(platform_index)?;
+        let all = Device::list_all(platform).ok()?;
+        let devices: Vec<Device> = self
+            .devices
+            .iter()
+            .map(|d| all.get(d.device_index).copied())
+            .collect::<Option<_>>()?;
+        Context::builder()
+            .platform(platform)
+            .devices(&devices[..])
+            .build()
+            .ok()
+    }
+
+    // Build the slab lattice of each device, filled with the host fields and
+    // initialized in equilibrium
+    pub fn decompose(&mut self, slabs: &[(usize, usize)]) -> Result<(), Box<dyn Error>> {
+        self.subdomains.clear();
+        for (&(start, depth), device) in slabs.iter().zip(&self.devices) {
+            terminal_utils::print_log(&format!(
+                "Layers {}..{} on {}",
+                start,
+                start + depth,
+                device.device_name.trim()
+            ));
+            let lattice = self.lbm.build_slab(
+                start,
+                depth,
+                Some((device.platform_index, device.device_index)),
+            )?;
+            let halo = lattice.create_halo_exchange()?;
+            self.subdomains.push(Subdomain {
+                lattice,
+                halo,
+                start,
+                depth,
+                kernel_events: Vec::new(),
+            });
+        }
+        for subdomain in &self.subdomains {
+            subdomain
+                .lattice
+                .queue
+                .as_ref()
+                .ok_or("OpenCL queue is None")?
+                .finish()?;
+        }
+        Ok(())
+    }
+
+    // One step of every slab. Each device computes its boundary layers, then
+    // its interior while the boundary populations travel to the neighbours'
+    // halos in the buffer the next step reads.
+    fn step(&mut self, t: usize) -> Result<(), Box<dyn Error>> {
+        let written = |lattice: &LBM| written_buffer(lattice, t);
+
+        // Packet buffers may still be read by the previous step's copies
+        let previous: Vec<Option<Event>> = self
+            .subdomains
+            .iter()
+            .map(|s| s.halo.unpacked.clone())
+            .collect();
+        let mut boundaries = Vec::with_capacity(self.subdomains.len());
+        for s in &mut self.subdomains {
+            let wait = s.halo.unpacked.as_ref();
+            let mut events = vec![s.lattice.enqueue_layers(t, 1, 2, wait)?];
+            if s.depth > 1 {
+                events.push(s.lattice.enqueue_layers(t, s.depth, s.depth + 1, wait)?);
+            }
+            boundaries.push(events.last().cloned().ok_or("No boundary kernel")?);
+            if s.depth > 2 {
+                // The in-order compute queue orders it before the next step
+                events.push(s.lattice.enqueue_layers(t, 2, s.depth, wait)?);
+            }
+            s.kernel_events = events;
+        }
+
+        if self.peer {
+            match self.exchange_peer(&boundaries, &previous, t) {
+                Ok(()) => return Ok(()),
+                Err(e) => {
+                    terminal_utils::print_warning(&format!(
+                        "Device-to-device halo copies failed ({}), exchanging through host memory.",
+                        e
+                    ));
+                    self.peer = false;
+                }
+            }
+        }
+
+        // Interior kernels keep running while the packets are read
+        let mut packets = Vec::with_capacity(self.subdomains.len());
+        for (s, ready) in self.subdomains.iter().zip(&boundaries) {
+            packets.push(s.halo.send(&written(&s.lattice)?, s.depth, ready)?);
+        }
+        let count = self.subdomains.len();
+        for i in 0..count {
+            let f = written(&self.subdomains[i].lattice)?;
+            let below = &packets[(i + count - 1) % count].1;
+            let above = &packets[(i + 1) % count].0;
+            let s = &mut self.subdomains[i];
+            s.halo.receive(&f, s.depth, below, above)?;
+        }
+        Ok(())
+    }
+
+    // Pack every slab after its boundary layers and copy the packets straight
+    // into the neighbours' halo buffers
+    fn exchange_peer(
+        &mut self,
+        boundaries: &[Event],
+        previous: &[Option<Event>],
+        t: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let count = self.subdomains.len();
+        let mut packed = Vec::with_capacity(count);
+        for (i, s) in self.subdomains.iter().enumerate() {
+            let mut wait = EventList::from(vec![boundaries[i].clone()]);
+            for neighbour in [(i + count - 1) % count, (i + 1) % count] {
+                if let Some(event) = &previous[neighbour] {
+                    wait.push(event.clone());
+                }
+            }
+            packed.push(
+                s.halo
+                    .pack_faces(&written_buffer(&s.lattice, t)?, s.depth, &wait)?,
+            );
+        }
+        for i in 0..count {
+            let (below, above) = ((i + count - 1) % count, (i + 1) % count);
+            let below_packet = self.subdomains[below].halo.upper.clone();
+            let above_packet = self.subdomains[above].halo.lower.clone();
+            let f = written_buffer(&self.subdomains[i].lattice, t)?;
+            let s = &mut self.subdomains[i];
+            s.halo.receive_from(
+                &f,
+                s.depth,
+                (&below_packet, &packed[below]),
+                (&above_packet, &packed[above]),
+            )?;
+        }
+        Ok(())
+    }
+
+    // Wait for all queues of every device
+    pub fn finish(&self) -> Result<(), Box<dyn Error>> {
+        for s in &self.subdomains {
+            s.lattice
+                .queue
+                .as_ref()
+                .ok_or("OpenCL queue is None")?
+                .finish()?;
+            s.halo.queue.finish()?;
+        }
+        Ok(())
+    }
+
+    // Gather density and velocity of every slab into the global host fields
+    pub fn read_from_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        self.finish()?;
+        let (_, len) = self.lbm.slab_layers();
+        for s in &mut self.subdomains {
+            s.lattice.read_from_gpu()?;
+            let cells = s.start * len..(s.start + s.depth) * len;
+            let interior = len..(s.depth + 1) * len;
+            self.lbm.density[cells.clone()].copy_from_slice(&s.lattice.density[interior.clone()]);
+            self.lbm.u[3 * cells.start..3 * cells.end]
+                .copy_from_slice(&s.lattice.u[3 * interior.start..3 * interior.end]);
+        }
+        Ok(())
+    }
+
+    // Run the decomposed domain. Field outputs are written from the global
+    // lattice; monitors and the other per-step features of LBM::run() are not
+    // available.
+    pub fn run(&mut self, time_steps: usize) -> Result<(), Box<dyn Error>> {
+        self.lbm.check_errors_in_input()?;
+        if self.devices.is_empty() {
+            return Err("No OpenCL devices to decompose the domain over.".into());
+        }
+        self.lbm.check_decomposition(self.devices.len())?;
+        self.lbm.shared_context = if self.peer {
+            self.shared_context()
+        } else {
+            None
+        };
+        if self.peer && self.lbm.shared_context.is_none() {
+            terminal_utils::print_log(
+                "Devices do not share an OpenCL context, exchanging halos through host memory",
+            );
+            self.peer = false;
+        }
+        let slabs = if self.balance && self.devices.len() > 1 {
+            // Kernel timestamps measure the share of each device
+            self.lbm.profiling = true;
+            let speeds = self.probe_devices()?;
+            self.lbm.weighted_decomposition(&speeds)
+        } else {
+            self.lbm.decomposition(self.devices.len())
+        };
+        self.decompose(&slabs)?;
+        self.lagging = 0;
+
+        let lbm = &self.lbm;
+        let magnitude = time_steps.to_string().len();
+        let pb = terminal_utils::progress_bar(time_steps as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:55.cyan/blue}] {pos}/{len} ({eta}) {msg}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        let (output_interval, output_csv, output_vtk, output_vti) = (
+            lbm.output_interval,
+            lbm.output_csv,
+            lbm.output_vtk,
+            lbm.output_vti,
+        );
+        std::fs::create_dir_all("output")?;
+        let start_time = Instant::now();
+
+        for t in 0..time_steps {
+            // Every device steps concurrently on its own queues
+            self.step(t)?;
+            self.lbm.time_step = t + 1;
+            if self.balance && self.subdomains.len() > 1 && (t + 1) % BALANCE_INTERVAL == 0 {
+                self.check_balance(t)?;
+            }
+
+            if output_interval != 0 && t % output_interval == 0 {
+                self.read_from_gpu()?;
+                if output_csv {
+                    self.lbm.output_to_csv(&format!(
+                        "output/data_{:0width$}.csv",
+                        t,
+                        width = magnitude
+                    ))?;
+                }
+                if output_vtk {
+                    self.lbm.export_to_vtk(&format!(
+                        "output/data_{:0width$}.vtk",
+                        t,
+                        width = magnitude
+                    ))?;
+                }
+                if output_vti {
+                    self.lbm.export_to_vti(&format!(
+                        "output/data_{:0width$}.vti",
+                        t,
+                        width = magnitude
+                    ))?;
+                }
+            }
+
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let mlups = (self.lbm.N as f64 * (t + 1) as f64) / elapsed / 1e6;
+            pb.set_message(format!("{:.2} MLUps", mlups));
+            pb.inc(1);
+        }
+        pb.finish();
+        self.read_from_gpu()
+    }
+}
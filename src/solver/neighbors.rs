@@ -0,0 +1,75 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// How stream_collide_kernel finds the source cell of each link, see
+// source_cell() in kernel_stream_collide.cl. Which mode is fastest depends on
+// the device: integer modulo is slow on most GPUs, the table costs Q ints of
+// memory and bandwidth per cell. Compare them with the benchmark.
+
+use super::lbm::LBM;
+
+use ocl::{flags::MEM_READ_WRITE, Buffer, Kernel};
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NeighborIndexing {
+    #[default]
+    Modulo, // Periodic wrap with integer modulo
+    Wrap,  // Periodic wrap with compare-and-select, no modulo
+    Table, // Precomputed source cell of every link
+}
+
+impl NeighborIndexing {
+    // Define selecting the mode in the kernel source
+    pub fn kernel_define(&self) -> &'static str {
+        match self {
+            NeighborIndexing::Modulo => "",
+            NeighborIndexing::Wrap => "#define NEIGHBOR_WRAP\n",
+            NeighborIndexing::Table => "#define NEIGHBOR_TABLE\n",
+        }
+    }
+}
+
+impl LBM {
+    // Select the neighbour indexing of the next initialize()
+    pub fn set_neighbor_indexing(&mut self, mode: NeighborIndexing) {
+        self.neighbor_indexing = mode;
+    }
+
+    // Bytes of device memory taken by the neighbour table
+    pub fn neighbor_table_bytes(&self) -> usize {
+        if self.neighbor_indexing == NeighborIndexing::Table {
+            self.N * self.Q * std::mem::size_of::<i32>()
+        } else {
+            0
+        }
+    }
+
+    // Neighbour table filled on the device, or a placeholder when unused
+    pub fn reserve_neighbor_buffer(&mut self) -> Result<Buffer<i32>, Box<dyn Error>> {
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let table = self.neighbor_indexing == NeighborIndexing::Table;
+        let neighbor_buffer = Buffer::<i32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_WRITE)
+            .len(if table { self.N * self.Q } else { 1 })
+            .build()
+            .map_err(|e| format!("Failed to build 'neighbors' buffer: {}", e))?;
+        if table {
+            let kernel = Kernel::builder()
+                .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+                .name("neighbor_table_kernel")
+                .queue(queue.clone())
+                .global_work_size(self.N)
+                .arg(&neighbor_buffer)
+                .build()
+                .map_err(|e| format!("Failed to build 'neighbor_table_kernel': {}", e))?;
+            unsafe {
+                kernel
+                    .enq()
+                    .map_err(|e| format!("Failed to enqueue 'neighbor_table_kernel': {}", e))?;
+            }
+        }
+        Ok(neighbor_buffer)
+    }
+}
@@ -0,0 +1,39 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// NumPy .npy dumps of single fields, loadable with numpy.load() without any
+// parsing. Arrays are C-ordered with shape (Nz, Ny, Nx[, components]).
+
+use super::lbm::LBM;
+use crate::solver::vti::{ArrayData, OUTPUT_ARRAYS};
+use crate::utils::npy;
+
+use std::error::Error;
+
+impl LBM {
+    // Write the host copy of `field` (one of OUTPUT_ARRAYS) to `path` as .npy;
+    // call after read_from_gpu() or run()
+    pub fn dump_numpy(&self, path: &str, field: &str) -> Result<(), Box<dyn Error>> {
+        if !OUTPUT_ARRAYS.contains(&field) {
+            return Err(format!(
+                "Unknown field '{}', expected one of {}",
+                field,
+                OUTPUT_ARRAYS.join(", ")
+            )
+            .into());
+        }
+        let array = self
+            .arrays_matching(|name| name == field)?
+            .pop()
+            .ok_or(format!("Field '{}' is not available", field))?;
+        let mut shape = vec![self.Nz, self.Ny, self.Nx];
+        if array.components > 1 {
+            shape.push(array.components);
+        }
+        match &array.data {
+            ArrayData::Float32(values) => npy::write_npy(path, &shape, values),
+            ArrayData::UInt8(values) => npy::write_npy(path, &shape, values),
+            ArrayData::UInt16(values) => npy::write_npy(path, &shape, values),
+        }
+    }
+}
@@ -2,32 +2,218 @@
 #![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
 use super::lbm::LBM;
 
-use crate::solver::precision::PrecisionMode;
+use crate::solver::precision::{bf16_to_f32, half_to_f32, PrecisionMode};
 use crate::utils::terminal_utils;
-use ocl::{flags::MEM_READ_WRITE, Buffer, Context, Device, Kernel, Platform, Program, Queue};
+use ocl::enums::{DeviceInfo, DeviceInfoResult};
+use ocl::{flags::MEM_READ_WRITE, Buffer, CommandQueueProperties, Context, Device, DeviceType, Kernel, Platform, Program, Queue};
 use std::error::Error;
-use std::mem::size_of;
+
+// Generated source of a kernel build that failed, for reading alongside the log
+const KERNEL_DUMP_FILE: &str = "cappusim_kernel_failed.cl";
+
+// One OpenCL device as seen by list_devices()
+#[derive(Debug, Clone)]
+pub struct DeviceListing {
+    pub platform_index: usize,
+    pub device_index: usize, // Index within its platform
+    pub platform_name: String,
+    pub device_name: String,
+    pub device_type: String, // GPU, CPU, Accelerator or Other
+    pub compute_units: u32,
+    pub global_memory_bytes: u64,
+    pub fp16: bool, // cl_khr_fp16 (native half arithmetic, used by FP16C)
+    pub fp64: bool, // cl_khr_fp64
+}
+
+// Every device of every OpenCL platform, in the order the indices refer to
+pub fn list_devices() -> Result<Vec<DeviceListing>, Box<dyn Error>> {
+    let mut listings = Vec::new();
+    for (platform_index, platform) in Platform::list().into_iter().enumerate() {
+        let platform_name = platform.name().unwrap_or_else(|_| "Unknown Platform".to_string());
+        for (device_index, device) in Device::list_all(platform)?.into_iter().enumerate() {
+            let device_type = match device.info(DeviceInfo::Type)? {
+                DeviceInfoResult::Type(t) if t.contains(DeviceType::GPU) => "GPU",
+                DeviceInfoResult::Type(t) if t.contains(DeviceType::CPU) => "CPU",
+                DeviceInfoResult::Type(t) if t.contains(DeviceType::ACCELERATOR) => "Accelerator",
+                _ => "Other",
+            };
+            let compute_units = match device.info(DeviceInfo::MaxComputeUnits)? {
+                DeviceInfoResult::MaxComputeUnits(units) => units,
+                _ => 0,
+            };
+            let global_memory_bytes = match device.info(DeviceInfo::GlobalMemSize)? {
+                DeviceInfoResult::GlobalMemSize(size) => size,
+                _ => 0,
+            };
+            let extensions = match device.info(DeviceInfo::Extensions)? {
+                DeviceInfoResult::Extensions(extensions) => extensions,
+                _ => String::new(),
+            };
+            listings.push(DeviceListing {
+                platform_index,
+                device_index,
+                platform_name: platform_name.clone(),
+                device_name: device.name().unwrap_or_else(|_| "Unknown Device".to_string()),
+                device_type: device_type.to_string(),
+                compute_units,
+                global_memory_bytes,
+                fp16: extensions.contains("cl_khr_fp16"),
+                fp64: extensions.contains("cl_khr_fp64"),
+            });
+        }
+    }
+    Ok(listings)
+}
+
+// Print list_devices() as a table
+pub fn print_devices() {
+    let listings = match list_devices() {
+        Ok(listings) => listings,
+        Err(err) => {
+            terminal_utils::print_error(&format!("Failed to list OpenCL devices: {}", err));
+            return;
+        }
+    };
+    if listings.is_empty() {
+        terminal_utils::print_warning("No OpenCL devices found.");
+        return;
+    }
+    terminal_utils::print_info(&"-".repeat(72));
+    terminal_utils::print_info(&format!(
+        "{:<4} {:<4} {:<34} {:<6} {:>4} {:>8} {:>4} {:>4}",
+        "Plat", "Dev", "Device", "Type", "CUs", "Memory", "FP16", "FP64"
+    ));
+    terminal_utils::print_info(&"-".repeat(72));
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let mut platform = usize::MAX;
+    for d in &listings {
+        if d.platform_index != platform {
+            platform = d.platform_index;
+            terminal_utils::print_info(&d.platform_name);
+        }
+        let name: String = d.device_name.trim().chars().take(34).collect();
+        terminal_utils::print_info(&format!(
+            "{:<4} {:<4} {:<34} {:<6} {:>4} {:>6.1}GB {:>4} {:>4}",
+            d.platform_index,
+            d.device_index,
+            name,
+            d.device_type,
+            d.compute_units,
+            d.global_memory_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+            yes_no(d.fp16),
+            yes_no(d.fp64)
+        ));
+    }
+    terminal_utils::print_info(&"-".repeat(72));
+}
+
+// Default choice across all platforms: GPUs first, then the most global
+// memory; the first listed device wins ties
+pub fn preferred_device(listings: &[DeviceListing]) -> Option<&DeviceListing> {
+    listings
+        .iter()
+        .rev()
+        .max_by_key(|d| (d.device_type == "GPU", d.global_memory_bytes))
+}
+
+// Whether an override value selects an index/name: numbers match the index,
+// anything else is a case-insensitive fragment of the name
+fn override_matches(value: &str, index: usize, name: &str) -> bool {
+    let value = value.trim();
+    match value.parse::<usize>() {
+        Ok(i) => i == index,
+        Err(_) => name.to_lowercase().contains(&value.to_lowercase()),
+    }
+}
+
+// Device chosen for a run. CAPPUSIM_PLATFORM and CAPPUSIM_DEVICE (platform
+// index or name, device index within its platform or name) restrict the
+// candidates; the default preference then picks among those left.
+pub fn select_device(listings: &[DeviceListing]) -> Result<DeviceListing, Box<dyn Error>> {
+    let platform = std::env::var("CAPPUSIM_PLATFORM").ok();
+    let device = std::env::var("CAPPUSIM_DEVICE").ok();
+    let candidates: Vec<DeviceListing> = listings
+        .iter()
+        .filter(|d| {
+            platform
+                .as_deref()
+                .map_or(true, |p| override_matches(p, d.platform_index, &d.platform_name))
+                && device
+                    .as_deref()
+                    .map_or(true, |v| override_matches(v, d.device_index, &d.device_name))
+        })
+        .cloned()
+        .collect();
+    preferred_device(&candidates).cloned().ok_or_else(|| {
+        format!(
+            "No OpenCL device matches CAPPUSIM_PLATFORM={} CAPPUSIM_DEVICE={}",
+            platform.as_deref().unwrap_or("(unset)"),
+            device.as_deref().unwrap_or("(unset)")
+        )
+        .into()
+    })
+}
 
 impl LBM {
+    // Pick the device for this run and return its platform; get_ocl_device
+    // then returns the device itself
     pub fn get_ocl_platform(&mut self) -> Result<Platform, Box<dyn Error>> {
+        let listings = list_devices()?;
+        let choice = match self.assigned_device {
+            Some((platform_index, device_index)) => listings
+                .iter()
+                .find(|d| d.platform_index == platform_index && d.device_index == device_index)
+                .cloned()
+                .ok_or(format!("OpenCL device {}/{} not found", platform_index, device_index))?,
+            None => select_device(&listings)?,
+        };
+        let overridden =
+            std::env::var_os("CAPPUSIM_PLATFORM").is_some() || std::env::var_os("CAPPUSIM_DEVICE").is_some();
+        if self.assigned_device.is_some() {
+            terminal_utils::print_log(&format!(
+                "Using assigned device {} on {}",
+                choice.device_name.trim(),
+                choice.platform_name.trim()
+            ));
+        } else if overridden {
+            terminal_utils::print_log(&format!(
+                "Selected {} on {} from the CAPPUSIM_PLATFORM/CAPPUSIM_DEVICE environment variables",
+                choice.device_name.trim(),
+                choice.platform_name.trim()
+            ));
+        } else if listings.len() > 1 {
+            terminal_utils::print_log(&format!(
+                "Selected {} ({}, {:.1} GB) out of {} OpenCL devices: GPUs are preferred, then the largest memory",
+                choice.device_name.trim(),
+                choice.device_type,
+                choice.global_memory_bytes as f64 / (1024.0 * 1024.0 * 1024.0),
+                listings.len()
+            ));
+        }
+        self.device_choice = Some((choice.platform_index, choice.device_index));
+
         let platform = Platform::list()
             .into_iter()
-            .next()
+            .nth(choice.platform_index)
             .ok_or("Platform not found")?;
-        println!("Platform: {}", &platform.name()?);
+        terminal_utils::print_info(&format!("Platform: {}", &platform.name()?));
         Ok(platform)
     }
 
     pub fn get_ocl_device(&mut self) -> Result<Device, Box<dyn Error>> {
+        let (_, device_index) = self.device_choice.unwrap_or((0, 0));
         let device = Device::list_all(self.platform.as_ref().unwrap())?
             .into_iter()
-            .next()
+            .nth(device_index)
             .ok_or("Device not found")?;
-        println!("Device: {}", device.name()?);
+        terminal_utils::print_info(&format!("Device: {}", device.name()?));
         Ok(device)
     }
 
     pub fn get_ocl_context(&mut self) -> Result<Context, Box<dyn Error>> {
+        if let Some(context) = &self.shared_context {
+            return Ok(context.clone());
+        }
         // Create a context for the selected device
         let context = Context::builder()
             .platform(self.platform.unwrap())
@@ -38,27 +224,78 @@ impl LBM {
     }
 
     pub fn get_ocl_queue(&mut self) -> Result<Queue, Box<dyn Error>> {
-        // Create a command queue for the device
-        let queue = Queue::new(self.context.as_ref().unwrap(), self.device.unwrap(), None)
+        // Create a command queue for the device, with event timestamps when
+        // profiling and out-of-order execution when requested and supported
+        let mut properties = CommandQueueProperties::empty();
+        if self.profiling {
+            properties |= CommandQueueProperties::PROFILING_ENABLE;
+        }
+        if self.out_of_order_queue {
+            if self.supports_out_of_order_queue() {
+                properties |= CommandQueueProperties::OUT_OF_ORDER_EXEC_MODE_ENABLE;
+            } else {
+                terminal_utils::print_warning("Out-of-order queues are not supported by this device, using an in-order queue.");
+                self.out_of_order_queue = false;
+            }
+        }
+        let properties = if properties.is_empty() { None } else { Some(properties) };
+        let queue = Queue::new(self.context.as_ref().unwrap(), self.device.unwrap(), properties)
             .expect("Failed to create command queue.");
         Ok(queue)
     }
 
     pub fn get_ocl_program(&mut self) -> Result<Program, Box<dyn Error>> {
-        // Define OpenCL program
+        // Define OpenCL program, reusing a cached binary of the same source
+        let source = self.generate_custom_kernel()?;
+        let cache_path = if self.kernel_cache {
+            self.kernel_cache_path(&source)
+        } else {
+            None
+        };
+        if let Some(path) = &cache_path {
+            if let Some(program) = self.load_cached_program(path) {
+                terminal_utils::print_log("Loaded compiled kernels from cache");
+                return Ok(program);
+            }
+        }
         let program = Program::builder()
-            .src(self.generate_custom_kernel().unwrap())
+            .src(source.as_str())
             .devices(self.device.as_ref().unwrap())
-            .build(self.context.as_ref().unwrap())
-            .expect("Failed to build program.");
-        Ok(program)
+            .build(self.context.as_ref().unwrap());
+        match program {
+            Ok(program) => {
+                if let Some(path) = &cache_path {
+                    self.store_program_binary(&program, path);
+                }
+                Ok(program)
+            }
+            Err(err) => {
+                // The error carries the driver's build log; keep the exact
+                // source it refers to, since the defines are generated per run
+                terminal_utils::print_error(&format!("OpenCL kernel compilation failed:\n{}", err));
+                match std::fs::write(KERNEL_DUMP_FILE, &source) {
+                    Ok(()) => terminal_utils::print_log(&format!(
+                        "Generated kernel source written to {}",
+                        KERNEL_DUMP_FILE
+                    )),
+                    Err(e) => terminal_utils::print_error(&format!(
+                        "Failed to write {}: {}",
+                        KERNEL_DUMP_FILE, e
+                    )),
+                }
+                Err(format!("Failed to build program: {}", err).into())
+            }
+        }
     }
 
+    // Populations are stored direction-major (structure of arrays): direction q
+    // of cell n lives at q * N + n, so consecutive work items access
+    // consecutive addresses. Kernels and host readers all assume this layout.
     pub fn reserve_f_buffer(&mut self) -> Result<Buffer<f32>, Box<dyn Error>> {
         let f_buffer = Buffer::<f32>::builder()
             .queue(self.queue.as_ref().unwrap().clone())
             .flags(MEM_READ_WRITE)
-            .len(self.N * self.Q)
+            .len(self.precision_mode.f_buffer_len(self.N * self.Q))
             .build()
             .expect("Failed to build 'f' buffer.");
         Ok(f_buffer)
@@ -68,7 +305,7 @@ impl LBM {
         let f_new_buffer = Buffer::<f32>::builder()
             .queue(self.queue.as_ref().unwrap().clone())
             .flags(MEM_READ_WRITE)
-            .len(self.N * self.Q)
+            .len(self.precision_mode.f_buffer_len(self.N * self.Q))
             .build()
             .expect("Failed to build 'f_new' buffer.");
         Ok(f_new_buffer)
@@ -107,6 +344,22 @@ impl LBM {
         Ok(flags_buffer)
     }
 
+    pub fn reserve_force_buffer(&mut self) -> Result<Buffer<f32>, Box<dyn Error>> {
+        // Only allocate the full field when the kernel actually reads it
+        if self.use_force_field && self.force_field.len() != self.N * 3 {
+            self.force_field = vec![0.0; self.N * 3];
+        }
+        let len = if self.use_force_field { self.N * 3 } else { 3 };
+        let force_buffer = Buffer::<f32>::builder()
+            .queue(self.queue.as_ref().unwrap().clone())
+            .flags(MEM_READ_WRITE)
+            .len(len)
+            .fill_val(0.0f32)
+            .build()
+            .expect("Failed to build 'force' buffer.");
+        Ok(force_buffer)
+    }
+
     pub fn get_optimal_work_group_size(&self) -> Result<usize, Box<dyn Error>> {
         Ok(64)  // Always return 64
     }
@@ -127,7 +380,10 @@ impl LBM {
                 .arg(self.u_buffer.as_ref().unwrap())
                 .arg(self.flags_buffer.as_ref().unwrap())
                 .arg(self.omega)
-                .arg(0i32) // timestep or other args as needed
+                .arg(0i32) // Time step, set per launch at TIMESTEP_ARG
+                .arg(self.force_buffer.as_ref().unwrap())
+                .arg(self.body_force_arg())
+                .arg(self.neighbor_buffer.as_ref().unwrap())
                 .build()
                 .expect("Failed to build OpenCL 'stream_collide_kernel'."),
         );
@@ -152,6 +408,10 @@ impl LBM {
 
     // Read data from GPU to CPU
     pub fn read_from_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.pinned_readback {
+            return self.read_from_gpu_pinned();
+        }
+
         // Velocity
         self.u_buffer
             .as_ref()
@@ -171,9 +431,115 @@ impl LBM {
         Ok(())
     }
 
+    // Upload host flags to the GPU
+    pub fn write_flags_to_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        self.flags_buffer
+            .as_ref()
+            .ok_or("Flags buffer is None")?
+            .write(&self.flags)
+            .enq()
+            .map_err(|e| format!("Failed to write 'flags' buffer: {}", e))?;
+        Ok(())
+    }
+
+    // Upload host velocity to the GPU
+    pub fn write_u_to_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        self.u_buffer
+            .as_ref()
+            .ok_or("Velocity buffer is None")?
+            .write(&self.u)
+            .enq()
+            .map_err(|e| format!("Failed to write 'velocity' buffer: {}", e))?;
+        Ok(())
+    }
+
+    // Upload the host force field to the GPU
+    pub fn write_force_to_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        self.force_buffer
+            .as_ref()
+            .ok_or("Force buffer is None")?
+            .write(&self.force_field)
+            .enq()
+            .map_err(|e| format!("Failed to write 'force' buffer: {}", e))?;
+        Ok(())
+    }
+
+    // Read the most recently written distribution functions from GPU to CPU.
+    // Returns f in direction-major layout (q * N + n), decoded to f32 for FP16 modes.
+    // Buffer holding the latest populations: step t writes into f_new when t
+    // is even, so after an odd number of completed steps they live in f_new.
+    fn current_f_buffer(&self) -> Result<&Buffer<f32>, Box<dyn Error>> {
+        if self.time_step % 2 == 1 {
+            Ok(self.f_new_buffer.as_ref().ok_or("f_new buffer is None")?)
+        } else {
+            Ok(self.f_buffer.as_ref().ok_or("f buffer is None")?)
+        }
+    }
+
+    // Latest populations exactly as stored on the device (packed halves in
+    // the FP16 modes)
+    pub fn read_f_raw_from_gpu(&self) -> Result<Vec<f32>, Box<dyn Error>> {
+        let mut raw = vec![0.0f32; self.precision_mode.f_buffer_len(self.N * self.Q)];
+        self.current_f_buffer()?
+            .read(&mut raw)
+            .enq()
+            .map_err(|e| format!("Failed to read 'f' buffer: {}", e))?;
+        Ok(raw)
+    }
+
+    // Upload populations from read_f_raw_from_gpu into the buffer the next
+    // step reads
+    pub fn write_f_raw_to_gpu(&self, raw: &[f32]) -> Result<(), Box<dyn Error>> {
+        self.current_f_buffer()?
+            .write(raw)
+            .enq()
+            .map_err(|e| format!("Failed to write 'f' buffer: {}", e))?;
+        Ok(())
+    }
+
+    pub fn read_f_from_gpu(&self) -> Result<Vec<f32>, Box<dyn Error>> {
+        let raw = self.read_f_raw_from_gpu()?;
+
+        let f = match self.precision_mode {
+            PrecisionMode::FP32 => raw,
+            PrecisionMode::FP16S | PrecisionMode::FP16C => {
+                // Buffer holds packed halves: two per 32-bit word, little-endian
+                let halves = raw
+                    .iter()
+                    .flat_map(|word| {
+                        let bits = word.to_bits();
+                        [(bits & 0xffff) as u16, (bits >> 16) as u16]
+                    })
+                    .take(self.N * self.Q)
+                    .map(half_to_f32);
+                if self.precision_mode == PrecisionMode::FP16C {
+                    // Undo the shift of ddf_encode, f = w[q] (1 + h); slots
+                    // only ever swap opposite directions, which share a weight
+                    let weights = self.model.weights();
+                    halves
+                        .enumerate()
+                        .map(|(i, h)| weights[i / self.N] * (1.0 + h))
+                        .collect()
+                } else {
+                    halves.collect()
+                }
+            }
+            PrecisionMode::BF16S => raw
+                .iter()
+                .flat_map(|word| {
+                    let bits = word.to_bits();
+                    [(bits & 0xffff) as u16, (bits >> 16) as u16]
+                })
+                .take(self.N * self.Q)
+                .map(bf16_to_f32)
+                .collect(),
+        };
+        Ok(self.pull_layout(f))
+    }
+
     pub fn calculate_vram_usage(&self) {
         // Manual calculation based on precision mode
-        // f, f_new: N*Q, density: N, u: N*3, flags: N
+        // f, f_new: N*Q, density: N, u: N*3, flags: N, force: N*3 (optional)
         let n = self.N;
         let q = self.Q;
         let f_bytes;
@@ -181,6 +547,7 @@ impl LBM {
         let density_bytes = n * std::mem::size_of::<f32>();
         let u_bytes = n * 3 * std::mem::size_of::<f32>();
         let flags_bytes = n * std::mem::size_of::<u8>();
+        let force_bytes = if self.use_force_field { n * 3 * std::mem::size_of::<f32>() } else { 0 };
 
         // Assume self.precision_mode: String or enum ("FP32", "FP16S", "FP16C")
         let precision = &self.precision_mode;
@@ -189,18 +556,24 @@ impl LBM {
                 f_bytes = n * q * std::mem::size_of::<f32>();
                 f_new_bytes = n * q * std::mem::size_of::<f32>();
             },
-            PrecisionMode::FP16S | PrecisionMode::FP16C => {
+            PrecisionMode::FP16S | PrecisionMode::FP16C | PrecisionMode::BF16S => {
                 f_bytes = n * q * 2; // half = 2 bytes
                 f_new_bytes = n * q * 2;
             }
         }
+        // A single shared buffer when streaming in place
+        let f_new_bytes = if self.in_place_streaming { 0 } else { f_new_bytes };
 
-        let total_vram = f_bytes + f_new_bytes + density_bytes + u_bytes + flags_bytes;
+        let neighbor_bytes = self.neighbor_table_bytes();
 
-        println!(
-            "VRAM usage: {:.2} MB",
-            total_vram as f64 / (1024.0 * 1024.0)
-        );
+        let total_vram = f_bytes + f_new_bytes + density_bytes + u_bytes + flags_bytes + force_bytes + neighbor_bytes;
+
+        let mb = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+        terminal_utils::print_info(&format!("VRAM usage: {:.2} MB", mb(total_vram)));
+        terminal_utils::print_detail(&format!(
+            "  f: {:.2} MB, f_new: {:.2} MB, density: {:.2} MB, u: {:.2} MB, flags: {:.2} MB, force: {:.2} MB, neighbors: {:.2} MB",
+            mb(f_bytes), mb(f_new_bytes), mb(density_bytes), mb(u_bytes), mb(flags_bytes), mb(force_bytes), mb(neighbor_bytes)
+        ));
         terminal_utils::print_success("OpenCL device and context initialized successfully!");
     }
 }
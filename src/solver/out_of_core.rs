@@ -0,0 +1,371 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Out-of-core execution for domains larger than device memory. The lattice
+// lives in host RAM and every step is computed slab by slab: a slab of layers
+// along the slowest axis (z, or y in 2D) plus one halo layer on each side is
+// uploaded to a small device lattice, advanced one step and its interior
+// copied back. Interior cells only pull from the layers next to them, so the
+// halo results are discarded and the wrap-around of the slab lattice is
+// never seen. Host memory holds a single population lattice; the two layers
+// a later slab still needs before the update are kept aside.
+
+use super::lbm::LBM;
+use crate::solver::parameters::TIMESTEP_ARG;
+use crate::solver::precision::PrecisionMode;
+use crate::utils::terminal_utils;
+
+use indicatif::ProgressStyle;
+use std::error::Error;
+use std::time::Instant;
+
+impl LBM {
+    // Run in slabs of `layers` layers kept in host memory, 0 disables
+    pub fn set_out_of_core(&mut self, layers: usize) {
+        self.out_of_core_layers = layers;
+    }
+
+    // Number of layers along the slab axis and cells per layer
+    pub fn slab_layers(&self) -> (usize, usize) {
+        if self.Nz > 1 {
+            (self.Nz, self.Nx * self.Ny)
+        } else {
+            (self.Ny, self.Nx)
+        }
+    }
+
+    fn check_out_of_core(&self) -> Result<(), Box<dyn Error>> {
+        if self.precision_mode != PrecisionMode::FP32 {
+            return Err("Out-of-core runs support FP32 only.".into());
+        }
+        if self.in_place_streaming || self.symmetry_planes != 0 {
+            return Err(
+                "Out-of-core runs support neither in-place streaming nor symmetry planes.".into(),
+            );
+        }
+        if self.use_force_field {
+            return Err("Out-of-core runs do not support per-cell force fields.".into());
+        }
+        if self.output_stress || self.output_wall_shear {
+            return Err(
+                "Out-of-core runs cannot write stress or wall shear, which need the device populations."
+                    .into(),
+            );
+        }
+
+        // Features the slab loop does not drive; the run goes on without them
+        let ignored = [
+            (
+                !self.immersed_boundaries.is_empty() || !self.membranes.is_empty(),
+                "immersed boundaries",
+            ),
+            (
+                !self.rigid_bodies.is_empty()
+                    || !self.device_bodies.is_empty()
+                    || !self.kinematic_bodies.is_empty()
+                    || !self.spring_bodies.is_empty()
+                    || !self.suspensions.is_empty(),
+                "moving and rigid bodies",
+            ),
+            (!self.probes.is_empty() || !self.tracers.is_empty(), "probes and tracers"),
+            (
+                self.conservation_interval > 0
+                    || self.force_monitor_interval > 0
+                    || self.energy_interval > 0
+                    || self.flow_rate_interval > 0
+                    || self.mach_interval > 0
+                    || self.averages_start.is_some(),
+                "monitors and time averages",
+            ),
+            (
+                self.checkpoint_interval > 0 || self.checkpoint_f.is_some(),
+                "checkpoints",
+            ),
+            (
+                self.output_hdf5
+                    || self.mirror_output
+                    || !self.output_slices.is_empty()
+                    || !self.output_regions.is_empty()
+                    || !self.image_outputs.is_empty(),
+                "HDF5, mirrored, slice, region and image outputs",
+            ),
+        ];
+        for (_, feature) in ignored.iter().filter(|(used, _)| *used) {
+            terminal_utils::print_warning(&format!("Out-of-core runs ignore {}.", feature));
+        }
+        Ok(())
+    }
+
+    // Device lattice of `depth` interior layers plus the two halos, on the
+    // given (platform, device) or the default one
+    pub fn slab_lattice(&self, depth: usize, device: Option<(usize, usize)>) -> LBM {
+        let (nx, ny, nz) = if self.Nz > 1 {
+            (self.Nx, self.Ny, depth + 2)
+        } else {
+            (self.Nx, depth + 2, 1)
+        };
+        let mut slab = LBM::new_silent(
+            nx,
+            ny,
+            nz,
+            self.model,
+            self.viscosity,
+            self.precision_mode,
+        );
+        if let Some(force) = &self.constant_force {
+            slab.set_constant_force(force.clone());
+        }
+        slab.neighbor_indexing = self.neighbor_indexing;
+        slab.collision = self.collision;
+        slab.assigned_device = device;
+        slab.profiling = self.profiling;
+        slab.shared_context = self.shared_context.clone();
+        slab.initialize();
+        slab
+    }
+
+    // Copy layers z0 - 1 ..= z0 + depth (periodic) of the host fields into the
+    // slab lattice. Populations come from `f` except for the halos, which are
+    // given explicitly because the host copy may already hold the next step.
+    fn upload_slab(
+        &self,
+        slab: &mut LBM,
+        f: &[f32],
+        z0: usize,
+        halos: (&[f32], &[f32]),
+        read_buffer: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let (layers, len) = self.slab_layers();
+        let depth = slab.N / len - 2;
+        let mut slab_f = vec![0.0f32; slab.N * self.Q];
+        for l in 0..depth + 2 {
+            let z = (z0 + layers + l - 1) % layers;
+            for q in 0..self.Q {
+                let target = q * slab.N + l * len;
+                let source = if l == 0 {
+                    &halos.0[q * len..(q + 1) * len]
+                } else if l == depth + 1 {
+                    &halos.1[q * len..(q + 1) * len]
+                } else {
+                    &f[q * self.N + z * len..q * self.N + (z + 1) * len]
+                };
+                slab_f[target..target + len].copy_from_slice(source);
+            }
+            let cells = z * len..(z + 1) * len;
+            slab.density[l * len..(l + 1) * len].copy_from_slice(&self.density[cells.clone()]);
+            slab.u[3 * l * len..3 * (l + 1) * len]
+                .copy_from_slice(&self.u[3 * cells.start..3 * cells.end]);
+            slab.flags[l * len..(l + 1) * len].copy_from_slice(&self.flags[cells]);
+        }
+
+        let f_buffer = if read_buffer {
+            &slab.f_buffer
+        } else {
+            &slab.f_new_buffer
+        };
+        f_buffer
+            .as_ref()
+            .ok_or("f buffer is None")?
+            .write(&slab_f)
+            .enq()?;
+        slab.density_buffer
+            .as_ref()
+            .ok_or("Density buffer is None")?
+            .write(&slab.density)
+            .enq()?;
+        slab.u_buffer
+            .as_ref()
+            .ok_or("Velocity buffer is None")?
+            .write(&slab.u)
+            .enq()?;
+        slab.flags_buffer
+            .as_ref()
+            .ok_or("Flags buffer is None")?
+            .write(&slab.flags)
+            .enq()?;
+        Ok(())
+    }
+
+    // Copy the interior layers of the slab back to layers z0.. of the host
+    fn download_slab(
+        &mut self,
+        slab: &mut LBM,
+        f: &mut [f32],
+        z0: usize,
+        from_f: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let (_, len) = self.slab_layers();
+        let depth = slab.N / len - 2;
+        let mut slab_f = vec![0.0f32; slab.N * self.Q];
+        let f_buffer = if from_f {
+            &slab.f_buffer
+        } else {
+            &slab.f_new_buffer
+        };
+        f_buffer
+            .as_ref()
+            .ok_or("f buffer is None")?
+            .read(&mut slab_f)
+            .enq()?;
+        slab.read_from_gpu()?;
+
+        for q in 0..self.Q {
+            let source = q * slab.N + len;
+            let target = q * self.N + z0 * len;
+            f[target..target + depth * len].copy_from_slice(&slab_f[source..source + depth * len]);
+        }
+        let cells = z0 * len..(z0 + depth) * len;
+        self.density[cells.clone()].copy_from_slice(&slab.density[len..(depth + 1) * len]);
+        self.u[3 * cells.start..3 * cells.end]
+            .copy_from_slice(&slab.u[3 * len..3 * (depth + 1) * len]);
+        Ok(())
+    }
+
+    // Populations of one layer, for all directions
+    fn layer_populations(&self, f: &[f32], z: usize) -> Vec<f32> {
+        let (_, len) = self.slab_layers();
+        (0..self.Q)
+            .flat_map(|q| {
+                f[q * self.N + z * len..q * self.N + (z + 1) * len]
+                    .iter()
+                    .copied()
+            })
+            .collect()
+    }
+
+    // Slab starts and depths covering the slab axis
+    fn slabs(&self) -> Vec<(usize, usize)> {
+        let (layers, _) = self.slab_layers();
+        let depth = self.out_of_core_layers.clamp(1, layers);
+        (0..layers)
+            .step_by(depth)
+            .map(|z0| (z0, depth.min(layers - z0)))
+            .collect()
+    }
+
+    // run() for domains that do not fit on the device, see set_out_of_core().
+    // Only the lattice itself and the field outputs are supported.
+    pub fn run_out_of_core(&mut self, time_steps: usize) -> Result<(), Box<dyn Error>> {
+        self.check_out_of_core()?;
+        let slabs = self.slabs();
+        terminal_utils::print_log(&format!(
+            "Out-of-core run in {} slabs of up to {} layers",
+            slabs.len(),
+            slabs[0].1
+        ));
+
+        // One device lattice per slab depth (the last slab may be shorter)
+        let mut lattices: Vec<(usize, LBM)> = Vec::new();
+        for &(_, depth) in &slabs {
+            if !lattices.iter().any(|(d, _)| *d == depth) {
+                lattices.push((depth, self.slab_lattice(depth, None)));
+            }
+        }
+        let (layers, _) = self.slab_layers();
+        let mut f = vec![0.0f32; self.N * self.Q];
+
+        // Equilibrium populations from rho and u, slab by slab
+        for &(z0, depth) in &slabs {
+            let slab = &mut lattices
+                .iter_mut()
+                .find(|(d, _)| *d == depth)
+                .ok_or("Missing slab lattice")?
+                .1;
+            let below = self.layer_populations(&f, (z0 + layers - 1) % layers);
+            let above = self.layer_populations(&f, (z0 + depth) % layers);
+            self.upload_slab(slab, &f, z0, (&below, &above), true)?;
+            unsafe {
+                slab.equilibrium_kernel
+                    .as_ref()
+                    .ok_or("Equilibrium kernel is None")?
+                    .enq()?;
+            }
+            slab.queue
+                .as_ref()
+                .ok_or("OpenCL queue is None")?
+                .finish()?;
+            self.download_slab(slab, &mut f, z0, true)?;
+        }
+
+        let magnitude = time_steps.to_string().len();
+        let pb = terminal_utils::progress_bar(time_steps as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{bar:55.cyan/blue}] {pos}/{len} ({eta}) {msg}")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        std::fs::create_dir_all("output")?;
+        let start_time = Instant::now();
+
+        for t in 0..time_steps {
+            // Pre-step layers overwritten before the slabs that need them run
+            let first = self.layer_populations(&f, 0);
+            let mut below = self.layer_populations(&f, layers - 1);
+            for &(z0, depth) in &slabs {
+                let slab = &mut lattices
+                    .iter_mut()
+                    .find(|(d, _)| *d == depth)
+                    .ok_or("Missing slab lattice")?
+                    .1;
+                let z1 = z0 + depth;
+                let above = if z1 == layers {
+                    first.clone()
+                } else {
+                    self.layer_populations(&f, z1)
+                };
+                let last = self.layer_populations(&f, z1 - 1);
+
+                // The kernel reads f on even steps and f_new on odd ones
+                let even = t % 2 == 0;
+                self.upload_slab(slab, &f, z0, (&below, &above), even)?;
+                let kernel = slab
+                    .stream_collide_kernel
+                    .as_ref()
+                    .ok_or("Stream-collide kernel is None")?;
+                kernel.set_arg(TIMESTEP_ARG, t as i32)?;
+                unsafe {
+                    kernel.enq()?;
+                }
+                slab.queue
+                    .as_ref()
+                    .ok_or("OpenCL queue is None")?
+                    .finish()?;
+                self.download_slab(slab, &mut f, z0, !even)?;
+                below = last;
+            }
+            self.time_step = t + 1;
+
+            if self.output_interval != 0 && t % self.output_interval == 0 {
+                if self.output_csv {
+                    self.output_to_csv(&format!(
+                        "output/data_{:0width$}.csv",
+                        t,
+                        width = magnitude
+                    ))?;
+                }
+                if self.output_vtk {
+                    self.export_to_vtk(&format!(
+                        "output/data_{:0width$}.vtk",
+                        t,
+                        width = magnitude
+                    ))?;
+                }
+                if self.output_vti {
+                    self.export_to_vti(&format!(
+                        "output/data_{:0width$}.vti",
+                        t,
+                        width = magnitude
+                    ))?;
+                }
+            }
+
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let mlups = (self.N as f64 * (t + 1) as f64) / elapsed / 1e6;
+            pb.set_message(format!("{:.2} MLUps", mlups));
+            pb.inc(1);
+        }
+        pb.finish();
+        Ok(())
+    }
+}
@@ -7,6 +7,17 @@ use std::error::Error;
 use std::fs::File;
 use std::io::{BufWriter, Write};
 
+// Output targets with their own spatial stride
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputTarget {
+    Csv,
+    Vtk,
+    Vti,
+    Hdf5,
+    Slices,
+    Regions,
+}
+
 impl LBM {
     pub fn set_output_csv(&mut self, state: bool) {
         self.output_csv = state;
@@ -16,85 +27,27 @@ impl LBM {
         self.output_vtk = state;
     }
 
-    pub fn calculate_vorticity(&self, x: usize, y: usize, z: usize) -> f32 {
-        let (vort_x, vort_y, vort_z) = self.calculate_vorticity_vector(x, y, z);
-
-        (vort_x * vort_x + vort_y * vort_y + vort_z * vort_z).sqrt()
+    pub fn set_output_vti(&mut self, state: bool) {
+        self.output_vti = state;
     }
 
-    pub fn calculate_vorticity_vector(&self, x: usize, y: usize, z: usize) -> (f32, f32, f32) {
-        let dx = 1.0;
-        let dy = 1.0;
-        let dz = 1.0;
-
-        let get = |x, y, z, d| {
-            if x >= self.Nx || y >= self.Ny || z >= self.Nz {
-                0.0
-            } else {
-                let i = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
-                self.u[i * 3 + d]
-            }
-        };
-
-        let du_dy = (get(x, y + 1, z, 0) - get(x, y.saturating_sub(1), z, 0)) / (2.0 * dy);
-        let du_dz = (get(x, y, z + 1, 0) - get(x, y, z.saturating_sub(1), 0)) / (2.0 * dz);
-        let dv_dx = (get(x + 1, y, z, 1) - get(x.saturating_sub(1), y, z, 1)) / (2.0 * dx);
-        let dv_dz = (get(x, y, z + 1, 1) - get(x, y, z.saturating_sub(1), 1)) / (2.0 * dz);
-        let dw_dx = (get(x + 1, y, z, 2) - get(x.saturating_sub(1), y, z, 2)) / (2.0 * dx);
-        let dw_dy = (get(x, y + 1, z, 2) - get(x, y.saturating_sub(1), z, 2)) / (2.0 * dy);
+    // Add strain-rate magnitude and viscous dissipation to the field outputs
+    pub fn set_output_dissipation(&mut self, state: bool) {
+        self.output_dissipation = state;
+    }
 
-        let vort_x = dw_dy - dv_dz;
-        let vort_y = du_dz - dw_dx;
-        let vort_z = dv_dx - du_dy;
+    // Write the flags (and body tags) once at startup as output/flags.vti
+    pub fn set_output_flags(&mut self, state: bool) {
+        self.output_flags = state;
+    }
 
-        (vort_x, vort_y, vort_z)
+    // Write only every `stride`-th cell along each axis to `target`
+    pub fn set_output_stride(&mut self, target: OutputTarget, stride: usize) {
+        self.output_strides[target as usize] = stride.max(1);
     }
 
-    pub fn calculate_q_criterion(&self, x: usize, y: usize, z: usize) -> f32 {
-        let dx = 1.0_f32;
-        let dy = 1.0_f32;
-        let dz = 1.0_f32;
-
-        let get = |x: usize, y: usize, z: usize, d: usize| -> f32 {
-            let xi = x.clamp(0, self.Nx - 1);
-            let yi = y.clamp(0, self.Ny - 1);
-            let zi = z.clamp(0, self.Nz - 1);
-            let i = n_from_xyz(&xi, &yi, &zi, &self.Nx, &self.Ny);
-            self.u[i * 3 + d]
-        };
-
-        let du_dx = (get(x + 1, y, z, 0) - get(x.saturating_sub(1), y, z, 0)) / (2.0 * dx);
-        let du_dy = (get(x, y + 1, z, 0) - get(x, y.saturating_sub(1), z, 0)) / (2.0 * dy);
-        let du_dz = (get(x, y, z + 1, 0) - get(x, y, z.saturating_sub(1), 0)) / (2.0 * dz);
-
-        let dv_dx = (get(x + 1, y, z, 1) - get(x.saturating_sub(1), y, z, 1)) / (2.0 * dx);
-        let dv_dy = (get(x, y + 1, z, 1) - get(x, y.saturating_sub(1), z, 1)) / (2.0 * dy);
-        let dv_dz = (get(x, y, z + 1, 1) - get(x, y, z.saturating_sub(1), 1)) / (2.0 * dz);
-
-        let dw_dx = (get(x + 1, y, z, 2) - get(x.saturating_sub(1), y, z, 2)) / (2.0 * dx);
-        let dw_dy = (get(x, y + 1, z, 2) - get(x, y.saturating_sub(1), z, 2)) / (2.0 * dy);
-        let dw_dz = (get(x, y, z + 1, 2) - get(x, y, z.saturating_sub(1), 2)) / (2.0 * dz);
-
-        // Strain tensor S (Symmetric)
-        let s_xx: f32 = du_dx;
-        let s_yy: f32 = dv_dy;
-        let s_zz: f32 = dw_dz;
-        let s_xy: f32 = 0.5 * (du_dy + dv_dx);
-        let s_xz: f32 = 0.5 * (du_dz + dw_dx);
-        let s_yz: f32 = 0.5 * (dv_dz + dw_dy);
-
-        // Vorticity tensor W (Antisymmetric)
-        let w_xy: f32 = 0.5 * (du_dy - dv_dx);
-        let w_xz: f32 = 0.5 * (du_dz - dw_dx);
-        let w_yz: f32 = 0.5 * (dv_dz - dw_dy);
-
-        let s_norm = s_xx.powi(2)
-            + s_yy.powi(2)
-            + s_zz.powi(2)
-            + 2.0 * (s_xy.powi(2) + s_xz.powi(2) + s_yz.powi(2));
-        let w_norm = 2.0 * (w_xy.powi(2) + w_xz.powi(2) + w_yz.powi(2));
-
-        0.5 * (w_norm - s_norm)
+    pub fn output_stride(&self, target: OutputTarget) -> usize {
+        self.output_strides[target as usize]
     }
 
     pub fn output_to_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
@@ -112,9 +65,11 @@ impl LBM {
         )?;
 
         // Iterate over the grid and write the data
+        let field = self.velocity_field();
         for n in 0..self.N {
             // Get the x, y, z coordinates from the linear index n
             let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            let s = self.output_spacing;
             // Get density and velocity
             let rho = &self.density[n];
             let ux = self.u[n * 3];
@@ -122,13 +77,13 @@ impl LBM {
             let uz = self.u[n * 3 + 2];
 
             // Calculate vorticity
-            let vorticity = self.calculate_vorticity(x, y, z);
-            let q_criteria = self.calculate_q_criterion(x, y, z);
+            let vorticity = field.vorticity(x, y, z);
+            let q_criteria = field.q_criterion(x, y, z);
             // Write the data to the file
             writeln!(
                 writer,
                 "{}, {}, {}, {:.6}, {:.6}, {:.6}, {:.6}, {:.6}, {:.6}", // Format floating-point numbers to 6 decimal places
-                x, y, z, rho, ux, uy, uz, vorticity, q_criteria
+                x * s, y * s, z * s, rho, ux, uy, uz, vorticity, q_criteria
             )?;
         }
 
@@ -155,18 +110,20 @@ impl LBM {
         writeln!(writer, "DATASET STRUCTURED_POINTS")?;
         writeln!(writer, "DIMENSIONS {} {} {}", self.Nx, self.Ny, self.Nz)?;
         writeln!(writer, "ORIGIN 0 0 0")?;
-        writeln!(writer, "SPACING 1 1 1")?;
+        let s = self.output_spacing;
+        writeln!(writer, "SPACING {} {} {}", s, s, s)?;
         writeln!(writer, "POINT_DATA {}", total_points)?;
 
         // Cache Q-criterion and vorticity
         let mut q_crit = vec![0.0; self.N];
-        let mut vorticity = vec![(0.0, 0.0, 0.0); self.N];
+        let mut vorticity = vec![[0.0; 3]; self.N];
+        let field = self.velocity_field();
         for z in 0..self.Nz {
             for y in 0..self.Ny {
                 for x in 0..self.Nx {
                     let i = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
-                    q_crit[i] = self.calculate_q_criterion(x, y, z);
-                    vorticity[i] = self.calculate_vorticity_vector(x, y, z);
+                    q_crit[i] = field.q_criterion(x, y, z);
+                    vorticity[i] = field.vorticity_vector(x, y, z);
                 }
             }
         }
@@ -199,10 +156,73 @@ impl LBM {
 
         // Vorticity
         writeln!(writer, "VECTORS vorticity float")?;
-        for (vx, vy, vz) in &vorticity {
+        for [vx, vy, vz] in &vorticity {
             writeln!(writer, "{:.6} {:.6} {:.6}", vx, vy, vz)?;
         }
 
+        // Strain-rate magnitude and viscous dissipation
+        if self.output_dissipation {
+            let mut strain_rate = vec![0.0; self.N];
+            for (n, rate) in strain_rate.iter_mut().enumerate() {
+                let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+                *rate = field.strain_rate(x, y, z);
+            }
+            writeln!(writer, "SCALARS strain_rate float")?;
+            writeln!(writer, "LOOKUP_TABLE default")?;
+            for val in &strain_rate {
+                writeln!(writer, "{:.6e}", val)?;
+            }
+            writeln!(writer, "SCALARS dissipation float")?;
+            writeln!(writer, "LOOKUP_TABLE default")?;
+            for val in &strain_rate {
+                writeln!(writer, "{:.6e}", self.viscosity * val * val)?;
+            }
+        }
+
+        // Deviatoric stress tensor (symmetric, written as full 3x3)
+        if self.output_stress {
+            let stress = self
+                .calculate_stress_tensor()
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+            writeln!(writer, "TENSORS stress float")?;
+            for s in &stress {
+                writeln!(writer, "{:.6e} {:.6e} {:.6e}", s[0], s[3], s[4])?;
+                writeln!(writer, "{:.6e} {:.6e} {:.6e}", s[3], s[1], s[5])?;
+                writeln!(writer, "{:.6e} {:.6e} {:.6e}", s[4], s[5], s[2])?;
+            }
+        }
+
+        // Wall shear stress on the fluid cells next to solids
+        if self.output_wall_shear {
+            let shear = self
+                .calculate_wall_shear_stress()
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+            writeln!(writer, "SCALARS wall_shear float")?;
+            writeln!(writer, "LOOKUP_TABLE default")?;
+            for val in &shear {
+                writeln!(writer, "{:.6e}", val)?;
+            }
+        }
+
+        // Time averages and Reynolds stresses (symmetric, written as full 3x3)
+        if self.has_averages() {
+            writeln!(writer, "SCALARS mean_density float")?;
+            writeln!(writer, "LOOKUP_TABLE default")?;
+            for val in self.mean_density() {
+                writeln!(writer, "{:.6}", val)?;
+            }
+            writeln!(writer, "VECTORS mean_velocity float")?;
+            for u in self.mean_velocity().chunks_exact(3) {
+                writeln!(writer, "{:.6} {:.6} {:.6}", u[0], u[1], u[2])?;
+            }
+            writeln!(writer, "TENSORS reynolds_stress float")?;
+            for r in &self.reynolds_stress() {
+                writeln!(writer, "{:.6e} {:.6e} {:.6e}", r[0], r[3], r[4])?;
+                writeln!(writer, "{:.6e} {:.6e} {:.6e}", r[3], r[1], r[5])?;
+                writeln!(writer, "{:.6e} {:.6e} {:.6e}", r[4], r[5], r[2])?;
+            }
+        }
+
         // Solid (flags) field for ParaView visualization
         // writeln!(writer, "SCALARS solid int 1")?;
         // writeln!(writer, "LOOKUP_TABLE default")?;
@@ -0,0 +1,59 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Physical parameters passed to stream_collide_kernel as arguments rather than
+// baked into the source, so they can change between steps without a rebuild.
+// Only whether a constant force is applied at all is a compile-time switch.
+
+use super::lbm::LBM;
+
+use ocl::prm::Float4;
+use std::error::Error;
+
+// Argument indices of stream_collide_kernel
+pub const OMEGA_ARG: usize = 5;
+pub const TIMESTEP_ARG: usize = 6;
+pub const BODY_FORCE_ARG: usize = 8;
+
+impl LBM {
+    // Constant force density as the kernel argument, zero when unset
+    pub fn body_force_arg(&self) -> Float4 {
+        match (&self.constant_force, self.use_constant_force) {
+            (Some(f), true) => Float4::new(f[0], f[1], f[2], 0.0),
+            _ => Float4::new(0.0, 0.0, 0.0, 0.0),
+        }
+    }
+
+    // Change the kinematic viscosity; takes effect at the next step
+    pub fn set_viscosity(&mut self, viscosity: f32) -> Result<(), Box<dyn Error>> {
+        if viscosity <= 0.0 {
+            return Err(format!("Viscosity must be positive, got {}", viscosity).into());
+        }
+        self.viscosity = viscosity;
+        self.omega = 1.0 / (3.0 * viscosity + 0.5);
+        if let Some(kernel) = self.stream_collide_kernel.as_ref() {
+            kernel
+                .set_arg(OMEGA_ARG, self.omega)
+                .map_err(|e| format!("Failed to set omega: {}", e))?;
+        }
+        Ok(())
+    }
+
+    // Push the current constant force to a built kernel. A kernel compiled
+    // without USE_CONSTANT_FORCE ignores it until the next initialize().
+    pub fn update_body_force_arg(&self) -> Result<(), Box<dyn Error>> {
+        let Some(kernel) = self.stream_collide_kernel.as_ref() else {
+            return Ok(());
+        };
+        if !self.constant_force_compiled && self.use_constant_force {
+            return Err(
+                "The kernel was built without a constant force; it applies from the next run"
+                    .into(),
+            );
+        }
+        kernel
+            .set_arg(BODY_FORCE_ARG, self.body_force_arg())
+            .map_err(|e| format!("Failed to set the body force: {}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Legacy VTK POLYDATA files for sensor and moving-body geometry. One file per
+// output step and kind (probes_<t>.vtk, bodies_<t>.vtk) forms a time series
+// ParaView groups automatically; point IDs stay stable across the series.
+
+use super::lbm::LBM;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+fn write_header<W: Write>(writer: &mut W, title: &str, points: &[[f32; 3]]) -> std::io::Result<()> {
+    writeln!(writer, "# vtk DataFile Version 3.0")?;
+    writeln!(writer, "{}", title)?;
+    writeln!(writer, "ASCII")?;
+    writeln!(writer, "DATASET POLYDATA")?;
+    writeln!(writer, "POINTS {} float", points.len())?;
+    for p in points {
+        writeln!(writer, "{:.6} {:.6} {:.6}", p[0], p[1], p[2])?;
+    }
+    // One vertex cell per point so the points render without glyph filters
+    writeln!(writer, "VERTICES {} {}", points.len(), points.len() * 2)?;
+    for i in 0..points.len() {
+        writeln!(writer, "1 {}", i)?;
+    }
+    writeln!(writer, "POINT_DATA {}", points.len())
+}
+
+fn write_ids<W: Write>(writer: &mut W, name: &str, ids: &[usize]) -> std::io::Result<()> {
+    writeln!(writer, "SCALARS {} int 1", name)?;
+    writeln!(writer, "LOOKUP_TABLE default")?;
+    for id in ids {
+        writeln!(writer, "{}", id)?;
+    }
+    Ok(())
+}
+
+fn write_vectors<W: Write>(writer: &mut W, name: &str, values: &[[f32; 3]]) -> std::io::Result<()> {
+    writeln!(writer, "VECTORS {} float", name)?;
+    for v in values {
+        writeln!(writer, "{:.6e} {:.6e} {:.6e}", v[0], v[1], v[2])?;
+    }
+    Ok(())
+}
+
+impl LBM {
+    pub fn set_output_geometry(&mut self, state: bool) {
+        self.output_geometry = state;
+    }
+
+    // Probe locations with their latest samples
+    pub fn export_probes_vtk(&self, filename: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        let points: Vec<[f32; 3]> = self
+            .probes
+            .iter()
+            .map(|p| p.position.map(|c| c as f32))
+            .collect();
+        write_header(&mut writer, "CappuSim probes", &points)?;
+        write_ids(
+            &mut writer,
+            "probe_id",
+            &(0..points.len()).collect::<Vec<_>>(),
+        )?;
+
+        let latest: Vec<_> = self.probes.iter().map(|p| p.samples.last()).collect();
+        writeln!(writer, "SCALARS density float")?;
+        writeln!(writer, "LOOKUP_TABLE default")?;
+        for s in &latest {
+            writeln!(writer, "{:.6}", s.map_or(0.0, |s| s.density))?;
+        }
+        let velocity: Vec<[f32; 3]> = latest
+            .iter()
+            .map(|s| s.map_or([0.0; 3], |s| s.velocity))
+            .collect();
+        write_vectors(&mut writer, "velocity", &velocity)?;
+        writer.flush()
+    }
+
+    // Immersed boundary markers of all bodies with their velocities and the
+    // hydrodynamic force on each marker
+    pub fn export_bodies_vtk(&self, filename: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        let mut points = Vec::new();
+        let mut body_ids = Vec::new();
+        let mut marker_ids = Vec::new();
+        let mut velocities = Vec::new();
+        let mut forces = Vec::new();
+        for (id, body) in self.immersed_boundaries.iter().enumerate() {
+            for k in 0..body.positions.len() {
+                points.push(body.positions[k]);
+                body_ids.push(id);
+                marker_ids.push(k);
+                velocities.push(body.velocities[k]);
+                forces.push(body.forces[k].map(|f| -f * body.weights[k]));
+            }
+        }
+        write_header(&mut writer, "CappuSim immersed bodies", &points)?;
+        write_ids(&mut writer, "body_id", &body_ids)?;
+        write_ids(&mut writer, "marker_id", &marker_ids)?;
+        write_vectors(&mut writer, "velocity", &velocities)?;
+        write_vectors(&mut writer, "force", &forces)?;
+        writer.flush()
+    }
+
+    // Tracer positions from the last read_tracers_from_gpu
+    pub fn export_tracers_vtk(&self, filename: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        write_header(&mut writer, "CappuSim tracers", &self.tracers.positions)?;
+        let ids: Vec<usize> = self.tracers.ids.iter().map(|&id| id as usize).collect();
+        write_ids(&mut writer, "tracer_id", &ids)?;
+        write_vectors(&mut writer, "velocity", &self.tracers.velocities)?;
+        writeln!(writer, "SCALARS diameter float")?;
+        writeln!(writer, "LOOKUP_TABLE default")?;
+        for d in &self.tracers.diameters {
+            writeln!(writer, "{:.6}", d)?;
+        }
+        writer.flush()
+    }
+}
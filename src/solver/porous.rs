@@ -0,0 +1,136 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::transforms::n_from_xyz;
+use crate::utils::random::SplitMix64;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct SpherePacking {
+    pub centers: Vec<[f32; 3]>,
+    pub radii: Vec<f32>,
+    pub porosity: f32,
+}
+
+impl LBM {
+    // Fill the inclusive box [min, max] with randomly placed, non-overlapping
+    // spheres (disks when Nz == 1) by random sequential addition until the
+    // porosity of the box drops to `target_porosity` or no more spheres fit.
+    // Radii are drawn uniformly from [radius_min, radius_max]; the same seed
+    // always produces the same packing.
+    pub fn generate_sphere_packing(
+        &mut self,
+        min: (usize, usize, usize),
+        max: (usize, usize, usize),
+        (radius_min, radius_max): (f32, f32),
+        target_porosity: f32,
+        seed: u64,
+    ) -> Result<SpherePacking, Box<dyn Error>> {
+        if max.0 >= self.Nx || max.1 >= self.Ny || max.2 >= self.Nz {
+            return Err("Packing region exceeds the domain.".into());
+        }
+        if min.0 > max.0 || min.1 > max.1 || min.2 > max.2 {
+            return Err("Packing region is empty.".into());
+        }
+        if radius_min <= 0.0 || radius_max < radius_min {
+            return Err("Invalid sphere radius range.".into());
+        }
+        if !(0.0..1.0).contains(&target_porosity) {
+            return Err("Target porosity must be in [0, 1).".into());
+        }
+
+        let is_2d = self.Nz == 1;
+        let mut rng = SplitMix64::new(seed);
+
+        let total_cells = (max.0 - min.0 + 1) * (max.1 - min.1 + 1) * (max.2 - min.2 + 1);
+        let mut solid_cells = 0usize;
+        for z in min.2..=max.2 {
+            for y in min.1..=max.1 {
+                for x in min.0..=max.0 {
+                    if self.flags[n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny)] == FLAG_SOLID {
+                        solid_cells += 1;
+                    }
+                }
+            }
+        }
+        let porosity = |solid: usize| 1.0 - solid as f32 / total_cells as f32;
+
+        let mut centers: Vec<[f32; 3]> = Vec::new();
+        let mut radii: Vec<f32> = Vec::new();
+        let max_failures = 10_000;
+        let mut failures = 0;
+
+        while porosity(solid_cells) > target_porosity && failures < max_failures {
+            let r = rng.range(radius_min, radius_max);
+            // Keep spheres entirely within the region
+            let lo = [min.0 as f32 + r, min.1 as f32 + r, min.2 as f32 + r];
+            let hi = [max.0 as f32 - r, max.1 as f32 - r, max.2 as f32 - r];
+            if lo[0] > hi[0] || lo[1] > hi[1] || (!is_2d && lo[2] > hi[2]) {
+                failures += 1;
+                continue;
+            }
+            let center = [
+                rng.range(lo[0], hi[0]),
+                rng.range(lo[1], hi[1]),
+                if is_2d { 0.0 } else { rng.range(lo[2], hi[2]) },
+            ];
+
+            let overlaps = centers.iter().zip(radii.iter()).any(|(c, rc)| {
+                let d2 = (c[0] - center[0]).powi(2)
+                    + (c[1] - center[1]).powi(2)
+                    + (c[2] - center[2]).powi(2);
+                d2 < (r + rc).powi(2)
+            });
+            if overlaps {
+                failures += 1;
+                continue;
+            }
+            failures = 0;
+
+            // Voxelize the new sphere
+            let bounds = |c: f32, lo: usize, hi: usize| {
+                (c - r).floor().max(lo as f32) as usize..=(c + r).ceil().min(hi as f32) as usize
+            };
+            let z_range = if is_2d { 0..=0 } else { bounds(center[2], min.2, max.2) };
+            for z in z_range {
+                for y in bounds(center[1], min.1, max.1) {
+                    for x in bounds(center[0], min.0, max.0) {
+                        let d2 = (x as f32 - center[0]).powi(2)
+                            + (y as f32 - center[1]).powi(2)
+                            + if is_2d { 0.0 } else { (z as f32 - center[2]).powi(2) };
+                        let n = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
+                        if d2 <= r * r && self.flags[n] != FLAG_SOLID {
+                            self.flags[n] = FLAG_SOLID;
+                            solid_cells += 1;
+                        }
+                    }
+                }
+            }
+            centers.push(center);
+            radii.push(r);
+        }
+
+        let achieved = porosity(solid_cells);
+        if achieved > target_porosity {
+            terminal_utils::print_warning(&format!(
+                "Sphere packing jammed at porosity {:.3} (target {:.3}).",
+                achieved, target_porosity
+            ));
+        }
+        terminal_utils::print_log(&format!(
+            "Generated {} spheres, porosity {:.3}",
+            centers.len(),
+            achieved
+        ));
+
+        Ok(SpherePacking {
+            centers,
+            radii,
+            porosity: achieved,
+        })
+    }
+}
@@ -0,0 +1,257 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Post-processing of stored output directories (`cappusim post <run_dir>`).
+// Snapshots written by run() (data_*.vtk or data_*.csv) are loaded back into a
+// host-only LBM so the derived-field code in output.rs is reused unchanged.
+
+use super::lbm::LBM;
+use crate::solver::image::write_field_png;
+use crate::solver::precision::PrecisionMode;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+use crate::solver::velocity_sets::VelocitySet;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// One stored time step
+pub struct Snapshot {
+    pub step: usize,
+    pub dims: (usize, usize, usize),
+    pub density: Vec<f32>,
+    pub u: Vec<f32>,
+}
+
+// Time step encoded in a data_<step>.<ext> file name
+fn snapshot_step(path: &Path) -> Option<usize> {
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix("data_")?
+        .parse()
+        .ok()
+}
+
+// Snapshot files in a run directory sorted by step; VTK is preferred over CSV
+pub fn list_snapshots(run_dir: &str) -> Result<Vec<(usize, PathBuf)>, Box<dyn Error>> {
+    let mut vtk = Vec::new();
+    let mut csv = Vec::new();
+    for entry in fs::read_dir(run_dir)? {
+        let path = entry?.path();
+        let Some(step) = snapshot_step(&path) else {
+            continue;
+        };
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("vtk") => vtk.push((step, path)),
+            Some("csv") => csv.push((step, path)),
+            _ => {}
+        }
+    }
+    let mut files = if vtk.is_empty() { csv } else { vtk };
+    files.sort();
+    Ok(files)
+}
+
+impl Snapshot {
+    pub fn load(step: usize, path: &Path) -> Result<Self, Box<dyn Error>> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("vtk") => Self::load_vtk(step, path),
+            Some("csv") => Self::load_csv(step, path),
+            _ => Err(format!("Unsupported snapshot file {}.", path.display()).into()),
+        }
+    }
+
+    // Legacy ASCII STRUCTURED_POINTS files as written by export_to_vtk
+    fn load_vtk(step: usize, path: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut lines = reader.lines();
+        let mut dims = (0, 0, 0);
+        let mut density = Vec::new();
+        let mut u = Vec::new();
+
+        while let Some(line) = lines.next() {
+            let line = line?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                ["DIMENSIONS", nx, ny, nz] => dims = (nx.parse()?, ny.parse()?, nz.parse()?),
+                ["SCALARS", "density", ..] => {
+                    lines.next(); // LOOKUP_TABLE
+                    let n = dims.0 * dims.1 * dims.2;
+                    for _ in 0..n {
+                        density.push(
+                            lines
+                                .next()
+                                .ok_or("Truncated density field.")??
+                                .trim()
+                                .parse()?,
+                        );
+                    }
+                }
+                ["VECTORS", "velocity", ..] => {
+                    let n = dims.0 * dims.1 * dims.2;
+                    for _ in 0..n {
+                        let row = lines.next().ok_or("Truncated velocity field.")??;
+                        for v in row.split_whitespace().take(3) {
+                            u.push(v.parse()?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let n = dims.0 * dims.1 * dims.2;
+        if n == 0 || density.len() != n || u.len() != n * 3 {
+            return Err(format!("{} is missing density or velocity data.", path.display()).into());
+        }
+        Ok(Snapshot {
+            step,
+            dims,
+            density,
+            u,
+        })
+    }
+
+    // CSV files as written by output_to_csv (x, y, z, rho, ux, uy, uz, ...)
+    fn load_csv(step: usize, path: &Path) -> Result<Self, Box<dyn Error>> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut rows = Vec::new();
+        let mut dims = (0, 0, 0);
+        for line in reader.lines().skip(1) {
+            let line = line?;
+            let cols: Vec<&str> = line.split(',').map(|c| c.trim()).collect();
+            if cols.len() < 7 {
+                continue;
+            }
+            let (x, y, z): (usize, usize, usize) =
+                (cols[0].parse()?, cols[1].parse()?, cols[2].parse()?);
+            dims = (dims.0.max(x + 1), dims.1.max(y + 1), dims.2.max(z + 1));
+            let values: [f32; 4] = [
+                cols[3].parse()?,
+                cols[4].parse()?,
+                cols[5].parse()?,
+                cols[6].parse()?,
+            ];
+            rows.push((x, y, z, values));
+        }
+
+        let n = dims.0 * dims.1 * dims.2;
+        if n == 0 || rows.len() != n {
+            return Err(format!("{} does not cover a full grid.", path.display()).into());
+        }
+        let mut density = vec![0.0; n];
+        let mut u = vec![0.0; n * 3];
+        for (x, y, z, v) in rows {
+            let i = n_from_xyz(&x, &y, &z, &dims.0, &dims.1);
+            density[i] = v[0];
+            u[i * 3..i * 3 + 3].copy_from_slice(&v[1..]);
+        }
+        Ok(Snapshot {
+            step,
+            dims,
+            density,
+            u,
+        })
+    }
+}
+
+impl LBM {
+    // Write a PNG image of a scalar field on the mid-depth x-y slice, scaled
+    // to [min, max] of the slice (same renderer as add_image_output)
+    pub fn render_slice_png(&self, field: &[f32], path: &str) -> Result<(), Box<dyn Error>> {
+        let z = self.Nz / 2;
+        let slice: Vec<f32> = (0..self.Ny)
+            .flat_map(|y| (0..self.Nx).map(move |x| (x, y)))
+            .map(|(x, y)| field[n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny)])
+            .collect();
+        let min = slice.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = slice.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        write_field_png(path, self.Nx, self.Ny, &slice, (min, max), |_, _| false)?;
+        Ok(())
+    }
+
+    // Regenerate derived fields, images, monitors and time averages for every
+    // snapshot in `run_dir`; results go to `<run_dir>/post`
+    pub fn post_process(run_dir: &str) -> Result<(), Box<dyn Error>> {
+        let files = list_snapshots(run_dir)?;
+        if files.is_empty() {
+            return Err(format!("No data_* snapshots found in {}.", run_dir).into());
+        }
+        let post_dir = Path::new(run_dir).join("post");
+        fs::create_dir_all(&post_dir)?;
+
+        let first = Snapshot::load(files[0].0, &files[0].1)?;
+        let (nx, ny, nz) = first.dims;
+        let model = if nz == 1 { VelocitySet::D2Q9 } else { VelocitySet::D3Q19 };
+        // Viscosity is not stored and derived fields do not depend on it
+        let mut lbm = LBM::new(nx, ny, nz, model, 0.1, PrecisionMode::FP32);
+        lbm.velocity = vec![];
+
+        let mut mean_density = vec![0.0f64; lbm.N];
+        let mut mean_u = vec![0.0f64; lbm.N * 3];
+        let mut pending = Some(first);
+        let mut monitors = BufWriter::new(File::create(post_dir.join("monitors.csv"))?);
+        writeln!(
+            monitors,
+            "step,mass,kinetic_energy,max_velocity,max_vorticity"
+        )?;
+
+        for (step, path) in &files {
+            let snapshot = match pending.take() {
+                Some(snapshot) => snapshot,
+                None => Snapshot::load(*step, path)?,
+            };
+            if snapshot.dims != (nx, ny, nz) {
+                return Err(format!("{} has a different grid size.", path.display()).into());
+            }
+            lbm.density = snapshot.density;
+            lbm.u = snapshot.u;
+
+            let mut speed = vec![0.0f32; lbm.N];
+            let mut vorticity = vec![0.0f32; lbm.N];
+            let (mut mass, mut energy, mut max_u, mut max_w) = (0.0f64, 0.0f64, 0.0f32, 0.0f32);
+            let field = lbm.velocity_field();
+            for n in 0..lbm.N {
+                let (x, y, z) = xyz_from_n(&n, &nx, &ny);
+                let u = &lbm.u[n * 3..n * 3 + 3];
+                let u2 = u[0] * u[0] + u[1] * u[1] + u[2] * u[2];
+                speed[n] = u2.sqrt();
+                vorticity[n] = field.vorticity(x, y, z);
+                mass += lbm.density[n] as f64;
+                energy += 0.5 * lbm.density[n] as f64 * u2 as f64;
+                max_u = max_u.max(speed[n]);
+                max_w = max_w.max(vorticity[n]);
+                mean_density[n] += lbm.density[n] as f64;
+                for d in 0..3 {
+                    mean_u[n * 3 + d] += u[d] as f64;
+                }
+            }
+            writeln!(
+                monitors,
+                "{},{:.6},{:.6e},{:.6e},{:.6e}",
+                step, mass, energy, max_u, max_w
+            )?;
+
+            let stem = post_dir.join(format!("data_{:06}", step));
+            lbm.export_to_vtk(&format!("{}.vtk", stem.display()))?;
+            lbm.render_slice_png(&speed, &format!("{}_velocity.png", stem.display()))?;
+            lbm.render_slice_png(&vorticity, &format!("{}_vorticity.png", stem.display()))?;
+        }
+        monitors.flush()?;
+
+        // Time-averaged fields
+        let count = files.len() as f64;
+        lbm.density = mean_density.iter().map(|v| (v / count) as f32).collect();
+        lbm.u = mean_u.iter().map(|v| (v / count) as f32).collect();
+        lbm.export_to_vtk(&post_dir.join("average.vtk").display().to_string())?;
+
+        terminal_utils::print_success(&format!(
+            "Post-processed {} snapshots into {}",
+            files.len(),
+            post_dir.display()
+        ));
+        Ok(())
+    }
+}
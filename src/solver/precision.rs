@@ -1,25 +1,44 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
 pub enum PrecisionMode {
+    #[default]
     FP32,     // Full precision
     FP16S,    // FP16 Storage, FP32 Compute
     FP16C,    // FP16 Compute
+    BF16S,    // bfloat16 Storage, FP32 Compute (experimental)
 }
 
-impl PrecisionMode {
-    pub fn from_str(s: &str) -> Result<Self, String> {
+impl std::str::FromStr for PrecisionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
         match s.to_uppercase().as_str() {
             "FP32" => Ok(PrecisionMode::FP32),
             "FP16S" => Ok(PrecisionMode::FP16S),
             "FP16C" => Ok(PrecisionMode::FP16C),
-            _ => Err(format!("Invalid precision mode: {}. Use FP32, FP16S, or FP16C", s)),
+            "BF16S" => Ok(PrecisionMode::BF16S),
+            _ => Err(format!("Invalid precision mode: {}. Use FP32, FP16S, FP16C, or BF16S", s)),
         }
     }
+}
 
+impl PrecisionMode {
     pub fn memory_factor(&self) -> f32 {
         match self {
             PrecisionMode::FP32 => 1.0,
             PrecisionMode::FP16S => 0.6,  // ~60%
             PrecisionMode::FP16C => 0.5,  // ~50%
+            PrecisionMode::BF16S => 0.6,  // ~60%, as FP16S
+        }
+    }
+
+    // f32 words of a population buffer holding `populations` values: FP16
+    // modes pack two halves into each word, halving the DDF memory
+    pub fn f_buffer_len(&self, populations: usize) -> usize {
+        match self {
+            PrecisionMode::FP32 => populations,
+            PrecisionMode::FP16S | PrecisionMode::FP16C | PrecisionMode::BF16S => populations.div_ceil(2),
         }
     }
 
@@ -28,6 +47,81 @@ impl PrecisionMode {
             PrecisionMode::FP32 => "Full FP32 precision (maximum accuracy)",
             PrecisionMode::FP16S => "FP16 storage, FP32 compute (balanced)",
             PrecisionMode::FP16C => "FP16 compute (maximum performance)",
+            PrecisionMode::BF16S => "bfloat16 storage, FP32 compute (experimental, float exponent range)",
         }
     }
-}
\ No newline at end of file
+}
+
+// Convert an IEEE 754 half-precision bit pattern to f32
+pub fn half_to_f32(h: u16) -> f32 {
+    let sign = ((h >> 15) & 0x1) as u32;
+    let exponent = ((h >> 10) & 0x1f) as u32;
+    let mantissa = (h & 0x3ff) as u32;
+
+    let bits = if exponent == 0 {
+        if mantissa == 0 {
+            sign << 31
+        } else {
+            // Subnormal half: normalize into an f32
+            let mut e: i32 = -1;
+            let mut m = mantissa;
+            while m & 0x400 == 0 {
+                m <<= 1;
+                e += 1;
+            }
+            (sign << 31) | (((127 - 15 - e) as u32) << 23) | ((m & 0x3ff) << 13)
+        }
+    } else if exponent == 0x1f {
+        (sign << 31) | (0xff << 23) | (mantissa << 13)
+    } else {
+        (sign << 31) | ((exponent + 127 - 15) << 23) | (mantissa << 13)
+    };
+    f32::from_bits(bits)
+}
+
+// Convert a bfloat16 bit pattern (the upper half of an f32) to f32
+pub fn bf16_to_f32(h: u16) -> f32 {
+    f32::from_bits((h as u32) << 16)
+}
+
+// Convert an f32 to an IEEE 754 half-precision bit pattern (round to nearest even)
+pub fn f32_to_half(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Inf or NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+    let e = exponent - 127 + 15;
+    if e >= 0x1f {
+        return sign | 0x7c00; // Overflow to infinity
+    }
+    if e <= 0 {
+        if e < -10 {
+            return sign; // Underflow to zero
+        }
+        // Subnormal half
+        let m = mantissa | 0x80_0000;
+        let shift = (14 - e) as u32;
+        let half_m = m >> shift;
+        let round_bit = 1 << (shift - 1);
+        let rounded = if (m & round_bit) != 0 && ((m & (round_bit - 1)) != 0 || (half_m & 1) != 0) {
+            half_m + 1
+        } else {
+            half_m
+        };
+        return sign | rounded as u16;
+    }
+    let half = ((e as u32) << 10) | (mantissa >> 13);
+    let round = mantissa & 0x1fff;
+    let half = if round > 0x1000 || (round == 0x1000 && (half & 1) != 0) {
+        half + 1
+    } else {
+        half
+    };
+    sign | half as u16
+}
@@ -0,0 +1,277 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Built-in scenarios runnable by name from the command line:
+//
+//     cappusim run --preset von_karman --nx 512 --re 200
+//
+// Each preset is the 2D setup of the matching example, extruded periodically
+// along z when nz > 1. The viscosity follows from the Reynolds number, the
+// characteristic velocity u0 and the preset's characteristic length.
+
+use super::lbm::LBM;
+use crate::solver::flags::CellType;
+use crate::solver::precision::PrecisionMode;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+use std::f32::consts::PI;
+
+// Below this viscosity the BGK relaxation time is too close to 0.5 to be stable
+const MIN_STABLE_VISCOSITY: f32 = 5e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Poiseuille,
+    VonKarman,
+    TaylorGreen,
+    Cavity,
+    Airfoil,
+}
+
+// Parameters of a preset run; every field can be overridden from the CLI
+#[derive(Debug, Clone)]
+pub struct PresetParams {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize, // 1 for 2D
+    pub re: f32,   // Reynolds number
+    pub u0: f32,   // Characteristic velocity in lattice units
+    pub time_steps: usize,
+    pub output_interval: usize, // 0 disables field output
+    pub precision: PrecisionMode,
+}
+
+impl std::str::FromStr for Preset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Preset::ALL
+            .into_iter()
+            .find(|p| p.name() == s.to_lowercase().replace('-', "_"))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Preset::ALL.iter().map(|p| p.name()).collect();
+                format!("Unknown preset '{}'. Available: {}", s, names.join(", "))
+            })
+    }
+}
+
+impl Preset {
+    pub const ALL: [Preset; 5] = [
+        Preset::Poiseuille,
+        Preset::VonKarman,
+        Preset::TaylorGreen,
+        Preset::Cavity,
+        Preset::Airfoil,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Poiseuille => "poiseuille",
+            Preset::VonKarman => "von_karman",
+            Preset::TaylorGreen => "taylor_green",
+            Preset::Cavity => "cavity",
+            Preset::Airfoil => "airfoil",
+        }
+    }
+
+    // Setup of the matching example
+    pub fn default_params(&self) -> PresetParams {
+        let (nx, ny, re, u0, time_steps) = match self {
+            Preset::Poiseuille => (512, 128, 50.0, 0.05, 50000),
+            Preset::VonKarman => (256, 128, 400.0, 0.1, 10000),
+            Preset::TaylorGreen => (128, 128, 1280.0, 0.1, 10000),
+            Preset::Cavity => (100, 100, 400.0, 0.05, 50000),
+            Preset::Airfoil => (1024, 512, 3000.0, 0.1, 10000),
+        };
+        PresetParams {
+            nx,
+            ny,
+            nz: 1,
+            re,
+            u0,
+            time_steps,
+            output_interval: time_steps / 50,
+            precision: PrecisionMode::FP32,
+        }
+    }
+
+    // Length the Reynolds number is based on
+    pub fn characteristic_length(&self, params: &PresetParams) -> f32 {
+        match self {
+            Preset::Poiseuille => params.ny as f32 - 2.0, // Channel height between the walls
+            Preset::VonKarman => 0.16 * params.nx as f32, // Cylinder diameter
+            Preset::TaylorGreen => params.nx as f32,      // Vortex period
+            Preset::Cavity => params.nx as f32,           // Cavity width
+            Preset::Airfoil => 0.3 * params.nx as f32,    // Chord
+        }
+    }
+
+    pub fn viscosity(&self, params: &PresetParams) -> f32 {
+        params.u0 * self.characteristic_length(params) / params.re
+    }
+
+    // Lattice with the preset's geometry, initial state and outputs
+    pub fn build(&self, params: &PresetParams) -> Result<LBM, Box<dyn Error>> {
+        let (nx, ny, u0) = (params.nx, params.ny, params.u0);
+        if nx < 8 || ny < 8 {
+            return Err("Presets need at least 8 cells along x and y.".into());
+        }
+        let viscosity = self.viscosity(params);
+        if !viscosity.is_finite() || viscosity <= 0.0 {
+            return Err("The Reynolds number and u0 must be positive.".into());
+        }
+        if viscosity < MIN_STABLE_VISCOSITY {
+            terminal_utils::print_warning(&format!(
+                "Viscosity {:.2e} is likely unstable; increase the resolution or lower the Reynolds number.",
+                viscosity
+            ));
+        }
+        terminal_utils::print_info(&format!(
+            "Preset {}: {}x{}x{}, Re = {}, u0 = {}, viscosity = {:.5}",
+            self.name(),
+            nx,
+            ny,
+            params.nz,
+            params.re,
+            u0,
+            viscosity
+        ));
+
+        let mut builder = LBM::builder()
+            .size(nx, ny, params.nz)
+            .viscosity(viscosity)
+            .precision(params.precision)
+            .output_interval(params.output_interval)
+            .output_vti(params.output_interval > 0);
+        if *self == Preset::Poiseuille {
+            // Body force giving a centreline velocity u0, u_max = F H^2 / (8 nu)
+            let h = self.characteristic_length(params);
+            builder = builder.constant_force([8.0 * viscosity * u0 / (h * h), 0.0, 0.0]);
+        }
+        let mut lbm = builder.build()?;
+
+        match self {
+            Preset::Poiseuille => lbm.par_set_conditions(|lbm, x, y, z, _n| {
+                if y == 0 || y == ny - 1 {
+                    lbm.set_cell(x, y, z, CellType::Solid);
+                }
+            }),
+            Preset::VonKarman => {
+                let radius = 0.08 * nx as f32;
+                let (cx, cy) = (0.25 * nx as f32, 0.5 * ny as f32);
+                lbm.par_set_conditions(|lbm, x, y, z, n| {
+                    let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+                    let cell = if dx * dx + dy * dy <= radius * radius || y == 0 || y == ny - 1 {
+                        CellType::Solid
+                    } else if x == 0 || x == nx - 1 {
+                        CellType::Equilibrium
+                    } else {
+                        CellType::Fluid
+                    };
+                    lbm.set_cell(x, y, z, cell);
+                    if cell != CellType::Solid {
+                        lbm.velocity[n].x = u0;
+                    }
+                });
+            }
+            Preset::TaylorGreen => lbm.par_set_conditions(|lbm, x, y, _z, n| {
+                let fx = 2.0 * PI * x as f32 / nx as f32;
+                let fy = 2.0 * PI * y as f32 / ny as f32;
+                lbm.velocity[n].x = -u0 * fx.cos() * fy.sin();
+                lbm.velocity[n].y = u0 * fx.sin() * fy.cos();
+            }),
+            Preset::Cavity => lbm.par_set_conditions(|lbm, x, y, z, n| {
+                if y == 0 || x == 0 || x == nx - 1 {
+                    lbm.set_cell(x, y, z, CellType::Solid);
+                } else if y == ny - 1 {
+                    lbm.set_cell(x, y, z, CellType::Equilibrium);
+                    lbm.velocity[n].x = u0; // Moving lid
+                }
+            }),
+            Preset::Airfoil => {
+                // NACA 0012 at 10 degrees angle of attack
+                let chord = self.characteristic_length(params);
+                let (cx, cy) = (0.3 * nx as f32, 0.5 * ny as f32);
+                let (sin, cos) = (10.0f32.to_radians().sin(), 10.0f32.to_radians().cos());
+                lbm.par_set_conditions(|lbm, x, y, z, n| {
+                    let (dx, dy) = (x as f32 - cx, y as f32 - cy);
+                    let xc = (dx * cos + dy * sin) / chord;
+                    let yc = (-dx * sin + dy * cos) / chord;
+                    let inside =
+                        (0.0..=1.0).contains(&xc) && yc.abs() <= naca0012_half_thickness(xc);
+                    let cell = if inside || y == 0 || y == ny - 1 {
+                        CellType::Solid
+                    } else if x == 0 || x == nx - 1 {
+                        CellType::Equilibrium
+                    } else {
+                        CellType::Fluid
+                    };
+                    lbm.set_cell(x, y, z, cell);
+                    if cell != CellType::Solid {
+                        lbm.velocity[n].x = u0;
+                    }
+                });
+            }
+        }
+        Ok(lbm)
+    }
+}
+
+// Half thickness of a NACA 0012 profile at chord position xc in [0, 1], in chords
+fn naca0012_half_thickness(xc: f32) -> f32 {
+    5.0 * 0.12
+        * (0.2969 * xc.sqrt() - 0.1260 * xc - 0.3516 * xc.powi(2) + 0.2843 * xc.powi(3)
+            - 0.1015 * xc.powi(4))
+}
+
+impl PresetParams {
+    // Apply `--nx`, `--ny`, `--nz`, `--re`, `--u0`, `--steps`,
+    // `--output-interval` and `--precision` overrides. A new nx without ny
+    // keeps the preset's aspect ratio.
+    pub fn apply_args(&mut self, args: &[String]) -> Result<(), Box<dyn Error>> {
+        let aspect = self.ny as f32 / self.nx as f32;
+        let mut ny_given = false;
+        let mut i = 0;
+        while i < args.len() {
+            let flag = args[i].as_str();
+            let value = args
+                .get(i + 1)
+                .ok_or_else(|| format!("Missing value for {}.", flag))?;
+            let invalid = || format!("Invalid value '{}' for {}.", value, flag);
+            match flag {
+                "--nx" => self.nx = value.parse().map_err(|_| invalid())?,
+                "--ny" => {
+                    self.ny = value.parse().map_err(|_| invalid())?;
+                    ny_given = true;
+                }
+                "--nz" => self.nz = value.parse().map_err(|_| invalid())?,
+                "--re" => self.re = value.parse().map_err(|_| invalid())?,
+                "--u0" => self.u0 = value.parse().map_err(|_| invalid())?,
+                "--steps" => self.time_steps = value.parse().map_err(|_| invalid())?,
+                "--output-interval" => {
+                    self.output_interval = value.parse().map_err(|_| invalid())?
+                }
+                "--precision" => self.precision = value.parse()?,
+                _ => return Err(format!("Unknown option {}.", flag).into()),
+            }
+            i += 2;
+        }
+        if !ny_given {
+            self.ny = ((self.nx as f32 * aspect).round() as usize).max(1);
+        }
+        Ok(())
+    }
+}
+
+impl LBM {
+    // Build and run a named preset with command line overrides
+    pub fn run_preset(name: &str, args: &[String]) -> Result<(), Box<dyn Error>> {
+        let preset: Preset = name.parse()?;
+        let mut params = preset.default_params();
+        params.apply_args(args)?;
+        let mut lbm = preset.build(&params)?;
+        lbm.run(params.time_steps);
+        Ok(())
+    }
+}
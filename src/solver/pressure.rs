@@ -0,0 +1,122 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Surface pressure coefficient of a tagged body, Cp = (p - p_inf) / (rho_inf U^2 / 2)
+// with p = rho / 3, sampled on the fluid cells that touch the body.
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SurfaceCoordinate {
+    Angle, // Degrees about the body centroid in the x-y plane, 0 upstream (-x), 90 at +y
+    Chord, // (x - x_leading_edge) / chord along x
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SurfacePressure {
+    pub coordinate: f32,
+    pub position: [usize; 3],
+    pub density: f32,
+    pub cp: f32,
+}
+
+impl LBM {
+    // Cp on the fluid cells next to body `name`, sorted by `coordinate`, for a
+    // free stream of density `rho_inf` and speed `u_inf`. Uses the host fields.
+    pub fn surface_pressure(
+        &self,
+        name: &str,
+        coordinate: SurfaceCoordinate,
+        rho_inf: f32,
+        u_inf: f32,
+    ) -> Result<Vec<SurfacePressure>, Box<dyn Error>> {
+        let id = self
+            .body_id(name)
+            .ok_or(format!("Unknown body '{}'", name))?;
+        let cells = self.body_cells(id);
+        if cells.is_empty() {
+            return Err(format!("Body '{}' has no solid cells", name).into());
+        }
+
+        // Centroid and streamwise extent of the body
+        let mut centroid = [0.0f32; 2];
+        let (mut x_min, mut x_max) = (usize::MAX, 0);
+        for &n in &cells {
+            let (x, y, _) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            centroid[0] += x as f32;
+            centroid[1] += y as f32;
+            x_min = x_min.min(x);
+            x_max = x_max.max(x);
+        }
+        let centroid = centroid.map(|c| c / cells.len() as f32);
+        let chord = (x_max - x_min + 1) as f32;
+
+        let c = self.model.vectors();
+        let dynamic_pressure = 0.5 * rho_inf * u_inf * u_inf;
+        let mut surface = vec![];
+        for n in 0..self.N {
+            if self.flags[n] == FLAG_SOLID {
+                continue;
+            }
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            let touches = c.iter().take(self.Q).any(|cq| {
+                let xn = (x as i32 + cq[0]).rem_euclid(self.Nx as i32) as usize;
+                let yn = (y as i32 + cq[1]).rem_euclid(self.Ny as i32) as usize;
+                let zn = (z as i32 + cq[2]).rem_euclid(self.Nz as i32) as usize;
+                let m = n_from_xyz(&xn, &yn, &zn, &self.Nx, &self.Ny);
+                self.flags[m] == FLAG_SOLID && self.body_ids[m] == id
+            });
+            if !touches {
+                continue;
+            }
+            let s = match coordinate {
+                SurfaceCoordinate::Angle => {
+                    let (dx, dy) = (x as f32 - centroid[0], y as f32 - centroid[1]);
+                    dy.atan2(-dx).to_degrees().rem_euclid(360.0)
+                }
+                SurfaceCoordinate::Chord => (x as f32 - x_min as f32) / chord,
+            };
+            surface.push(SurfacePressure {
+                coordinate: s,
+                position: [x, y, z],
+                density: self.density[n],
+                cp: (self.density[n] - rho_inf) / 3.0 / dynamic_pressure,
+            });
+        }
+        surface.sort_by(|a, b| a.coordinate.total_cmp(&b.coordinate));
+        Ok(surface)
+    }
+
+    // Write surface_pressure() as CSV (coordinate, cell, density, Cp)
+    pub fn export_surface_pressure_csv(
+        &self,
+        path: &str,
+        name: &str,
+        coordinate: SurfaceCoordinate,
+        rho_inf: f32,
+        u_inf: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        let surface = self.surface_pressure(name, coordinate, rho_inf, u_inf)?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        let column = match coordinate {
+            SurfaceCoordinate::Angle => "angle",
+            SurfaceCoordinate::Chord => "x_c",
+        };
+        writeln!(writer, "{},x,y,z,rho,cp", column)?;
+        for p in &surface {
+            writeln!(
+                writer,
+                "{:.6},{},{},{},{:.6},{:.6}",
+                p.coordinate, p.position[0], p.position[1], p.position[2], p.density, p.cp
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
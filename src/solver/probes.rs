@@ -0,0 +1,72 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::transforms::n_from_xyz;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProbeSample {
+    pub step: usize,
+    pub density: f32,
+    pub velocity: [f32; 3],
+}
+
+// A point sensor sampled at every output interval
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub name: String,
+    pub position: [usize; 3],
+    pub samples: Vec<ProbeSample>,
+}
+
+impl LBM {
+    // Register a probe at lattice cell `position`; returns its ID (index)
+    pub fn add_probe(&mut self, name: &str, position: [usize; 3]) -> usize {
+        let position = [
+            position[0].min(self.Nx - 1),
+            position[1].min(self.Ny - 1),
+            position[2].min(self.Nz - 1),
+        ];
+        self.probes.push(Probe {
+            name: name.to_string(),
+            position,
+            samples: vec![],
+        });
+        self.probes.len() - 1
+    }
+
+    // Record the host-side fields at every probe (call after read_from_gpu)
+    pub fn sample_probes(&mut self) {
+        let step = self.time_step;
+        for probe in &mut self.probes {
+            let [x, y, z] = probe.position;
+            let n = n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny);
+            probe.samples.push(ProbeSample {
+                step,
+                density: self.density[n],
+                velocity: [self.u[n * 3], self.u[n * 3 + 1], self.u[n * 3 + 2]],
+            });
+        }
+    }
+
+    // All probe histories in one long-format CSV table
+    pub fn export_probes_csv(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "probe_id,name,step,rho,ux,uy,uz")?;
+        for (id, probe) in self.probes.iter().enumerate() {
+            for s in &probe.samples {
+                writeln!(
+                    writer,
+                    "{},{},{},{:.6},{:.6},{:.6},{:.6}",
+                    id, probe.name, s.step, s.density, s.velocity[0], s.velocity[1], s.velocity[2]
+                )?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Opt-in per-kernel GPU timing. With profiling on the command queue is created
+// with CL_QUEUE_PROFILING_ENABLE and run() records the start/end timestamps of
+// the equilibrium and stream-collide launches, reported after the metrics.
+
+use super::lbm::LBM;
+use crate::utils::terminal_utils;
+
+use ocl::enums::{ProfilingInfo, ProfilingInfoResult};
+use ocl::Event;
+
+#[derive(Debug, Clone)]
+pub struct KernelTiming {
+    pub name: String,
+    pub calls: usize,
+    pub seconds: f64, // Accumulated device execution time
+}
+
+// Device execution time of a completed event in seconds
+pub fn event_seconds(event: &Event) -> Option<f64> {
+    let start = match event.profiling_info(ProfilingInfo::Start).ok()? {
+        ProfilingInfoResult::Start(ns) => ns,
+        _ => return None,
+    };
+    let end = match event.profiling_info(ProfilingInfo::End).ok()? {
+        ProfilingInfoResult::End(ns) => ns,
+        _ => return None,
+    };
+    Some(end.saturating_sub(start) as f64 * 1e-9)
+}
+
+impl LBM {
+    // Enable per-kernel timing; must be set before run() creates the queue
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.profiling = enabled;
+    }
+
+    // Add the device time of a finished launch of kernel `name`
+    pub fn record_kernel_time(&mut self, name: &str, event: &Event) {
+        let Some(seconds) = event_seconds(event) else {
+            return;
+        };
+        tracing::trace!(target: "cappusim::profiling", "{}: {:.3} us", name, seconds * 1e6);
+        match self.kernel_times.iter_mut().find(|k| k.name == name) {
+            Some(timing) => {
+                timing.calls += 1;
+                timing.seconds += seconds;
+            }
+            None => self.kernel_times.push(KernelTiming {
+                name: name.to_string(),
+                calls: 1,
+                seconds,
+            }),
+        }
+    }
+
+    // Per-kernel breakdown as a share of the wall time of the run
+    pub fn print_kernel_profile(&self, wall_seconds: f64) {
+        if self.kernel_times.is_empty() {
+            return;
+        }
+        terminal_utils::print_info(&format!(
+            "{:<28}{:>10}{:>14}{:>14}{:>9}",
+            "Kernel", "Calls", "Total [ms]", "Mean [us]", "Wall %"
+        ));
+        for k in &self.kernel_times {
+            terminal_utils::print_info(&format!(
+                "{:<28}{:>10}{:>14.3}{:>14.3}{:>8.1}%",
+                k.name,
+                k.calls,
+                k.seconds * 1e3,
+                k.seconds * 1e6 / k.calls as f64,
+                100.0 * k.seconds / wall_seconds.max(f64::EPSILON)
+            ));
+        }
+        let gpu: f64 = self.kernel_times.iter().map(|k| k.seconds).sum();
+        terminal_utils::print_info(&format!(
+            "GPU kernel time {:.3} s of {:.3} s wall time ({:.1}% host/transfer overhead)\n",
+            gpu,
+            wall_seconds,
+            100.0 * (1.0 - gpu / wall_seconds.max(f64::EPSILON)).max(0.0)
+        ));
+    }
+}
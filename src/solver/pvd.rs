@@ -0,0 +1,131 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// ParaView collection (.pvd) tying the per-step field and particle files of a
+// run together, so one file opens both and animates them in sync.
+
+use super::lbm::LBM;
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// Collection parts
+pub const PVD_PART_FIELDS: usize = 0;
+pub const PVD_PART_PARTICLES: usize = 1;
+
+impl LBM {
+    // Register an output file of `part` written at `step`; the path is
+    // stored relative to the collection file
+    pub fn record_output(&mut self, step: usize, part: usize, filename: &str) {
+        let name = Path::new(filename)
+            .file_name()
+            .map_or(filename.to_string(), |f| f.to_string_lossy().into_owned());
+        self.pvd_entries.push((step, part, name));
+    }
+
+    // Write the collection of all recorded outputs
+    pub fn write_pvd(&self, filename: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writeln!(writer, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            writer,
+            "<VTKFile type=\"Collection\" version=\"0.1\" byte_order=\"LittleEndian\">"
+        )?;
+        writeln!(writer, "  <Collection>")?;
+        for (step, part, file) in &self.pvd_entries {
+            writeln!(
+                writer,
+                "    <DataSet timestep=\"{}\" group=\"\" part=\"{}\" file=\"{}\"/>",
+                step, part, file
+            )?;
+        }
+        writeln!(writer, "  </Collection>")?;
+        writeln!(writer, "</VTKFile>")?;
+        writer.flush()
+    }
+
+    // Tracer positions, IDs, velocities and diameters as XML PolyData (.vtp)
+    pub fn export_tracers_vtp(&self, filename: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        let t = &self.tracers;
+        let count = t.len();
+        writeln!(writer, "<?xml version=\"1.0\"?>")?;
+        writeln!(
+            writer,
+            "<VTKFile type=\"PolyData\" version=\"0.1\" byte_order=\"LittleEndian\">"
+        )?;
+        writeln!(writer, "  <PolyData>")?;
+        writeln!(
+            writer,
+            "    <Piece NumberOfPoints=\"{}\" NumberOfVerts=\"{}\" NumberOfLines=\"0\" NumberOfStrips=\"0\" NumberOfPolys=\"0\">",
+            count, count
+        )?;
+
+        writeln!(
+            writer,
+            "      <PointData Scalars=\"id\" Vectors=\"velocity\">"
+        )?;
+        writeln!(
+            writer,
+            "        <DataArray type=\"Int32\" Name=\"id\" format=\"ascii\">"
+        )?;
+        for id in &t.ids {
+            writeln!(writer, "          {}", id)?;
+        }
+        writeln!(writer, "        </DataArray>")?;
+        writeln!(
+            writer,
+            "        <DataArray type=\"Float32\" Name=\"velocity\" NumberOfComponents=\"3\" format=\"ascii\">"
+        )?;
+        for v in &t.velocities {
+            writeln!(writer, "          {:.6e} {:.6e} {:.6e}", v[0], v[1], v[2])?;
+        }
+        writeln!(writer, "        </DataArray>")?;
+        writeln!(
+            writer,
+            "        <DataArray type=\"Float32\" Name=\"diameter\" format=\"ascii\">"
+        )?;
+        for d in &t.diameters {
+            writeln!(writer, "          {:.6}", d)?;
+        }
+        writeln!(writer, "        </DataArray>")?;
+        writeln!(writer, "      </PointData>")?;
+
+        writeln!(writer, "      <Points>")?;
+        writeln!(
+            writer,
+            "        <DataArray type=\"Float32\" NumberOfComponents=\"3\" format=\"ascii\">"
+        )?;
+        for p in &t.positions {
+            writeln!(writer, "          {:.6} {:.6} {:.6}", p[0], p[1], p[2])?;
+        }
+        writeln!(writer, "        </DataArray>")?;
+        writeln!(writer, "      </Points>")?;
+
+        // One vertex per particle
+        writeln!(writer, "      <Verts>")?;
+        writeln!(
+            writer,
+            "        <DataArray type=\"Int32\" Name=\"connectivity\" format=\"ascii\">"
+        )?;
+        for i in 0..count {
+            writeln!(writer, "          {}", i)?;
+        }
+        writeln!(writer, "        </DataArray>")?;
+        writeln!(
+            writer,
+            "        <DataArray type=\"Int32\" Name=\"offsets\" format=\"ascii\">"
+        )?;
+        for i in 0..count {
+            writeln!(writer, "          {}", i + 1)?;
+        }
+        writeln!(writer, "        </DataArray>")?;
+        writeln!(writer, "      </Verts>")?;
+
+        writeln!(writer, "    </Piece>")?;
+        writeln!(writer, "  </PolyData>")?;
+        writeln!(writer, "</VTKFile>")?;
+        writer.flush()
+    }
+}
@@ -0,0 +1,144 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Parallel reductions of device fields over the non-solid cells. Each
+// work-group reduces its cells in local memory and only the per-group
+// partials are read back, so monitors never copy whole fields to the host.
+
+use super::lbm::LBM;
+
+use ocl::{flags::MEM_WRITE_ONLY, Buffer, Event, Kernel};
+use std::error::Error;
+
+pub const REDUCE_GROUP: usize = 256; // Work-group size of the reduction kernels
+pub const REDUCE_MAX_COMPONENTS: usize = 4;
+
+// Modifier bits of reduce_field_kernel
+const REDUCE_DIFFERENCE: i32 = 4;
+const REDUCE_WEIGHTED: i32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reduction {
+    Sum,        // Per-component sums
+    Max,        // Largest value (scalars) or magnitude (vectors)
+    SumSquares, // Sum of squared values or magnitudes
+}
+
+impl Reduction {
+    fn mode(&self) -> i32 {
+        match self {
+            Reduction::Sum => 0,
+            Reduction::Max => 1,
+            Reduction::SumSquares => 2,
+        }
+    }
+}
+
+impl LBM {
+    // Reduce `field` with `components` floats per cell. The per-cell value is
+    // field - subtract when given, times weight when given. Sum returns one
+    // value per component, Max and SumSquares a single value.
+    pub fn reduce_field(
+        &self,
+        field: &Buffer<f32>,
+        components: usize,
+        reduction: Reduction,
+        weight: Option<&Buffer<f32>>,
+        subtract: Option<&Buffer<f32>>,
+    ) -> Result<Vec<f64>, Box<dyn Error>> {
+        if components == 0 || components > REDUCE_MAX_COMPONENTS {
+            return Err(format!("Cannot reduce a field of {} components", components).into());
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let groups = self.N.div_ceil(REDUCE_GROUP);
+        let outputs = if reduction == Reduction::Sum {
+            components
+        } else {
+            1
+        };
+        let partial_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_WRITE_ONLY)
+            .len(groups * outputs)
+            .build()
+            .map_err(|e| format!("Failed to build 'partial reductions' buffer: {}", e))?;
+
+        let mut mode = reduction.mode();
+        if subtract.is_some() {
+            mode |= REDUCE_DIFFERENCE;
+        }
+        if weight.is_some() {
+            mode |= REDUCE_WEIGHTED;
+        }
+        // Unused inputs are bound to the field itself
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("reduce_field_kernel")
+            .queue(queue.clone())
+            .global_work_size(groups * REDUCE_GROUP)
+            .local_work_size(REDUCE_GROUP)
+            .arg(field)
+            .arg(subtract.unwrap_or(field))
+            .arg(weight.unwrap_or(field))
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(components as i32)
+            .arg(mode)
+            .arg(&partial_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'reduce_field_kernel': {}", e))?;
+        let mut launched = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut launched)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'reduce_field_kernel': {}", e))?;
+        }
+
+        let mut partial = vec![0.0f32; groups * outputs];
+        partial_buffer
+            .read(&mut partial)
+            .ewait(&launched)
+            .enq()
+            .map_err(|e| format!("Failed to read 'partial reductions' buffer: {}", e))?;
+        let mut result = vec![0.0f64; outputs];
+        if reduction == Reduction::Max {
+            result[0] = partial
+                .iter()
+                .fold(f64::NEG_INFINITY, |m, &p| m.max(p as f64));
+        } else {
+            for group in partial.chunks_exact(outputs) {
+                for (r, &p) in result.iter_mut().zip(group) {
+                    *r += p as f64;
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    // Per-component sum of a field
+    pub fn field_sum(
+        &self,
+        field: &Buffer<f32>,
+        components: usize,
+    ) -> Result<Vec<f64>, Box<dyn Error>> {
+        self.reduce_field(field, components, Reduction::Sum, None, None)
+    }
+
+    // Largest value (scalars) or magnitude (vectors) of a field
+    pub fn field_max(&self, field: &Buffer<f32>, components: usize) -> Result<f64, Box<dyn Error>> {
+        Ok(self.reduce_field(field, components, Reduction::Max, None, None)?[0])
+    }
+
+    // L2 norm of a field, or of its difference to `subtract`
+    pub fn field_l2_norm(
+        &self,
+        field: &Buffer<f32>,
+        components: usize,
+        subtract: Option<&Buffer<f32>>,
+    ) -> Result<f64, Box<dyn Error>> {
+        let squares =
+            self.reduce_field(field, components, Reduction::SumSquares, None, subtract)?;
+        Ok(squares[0].sqrt())
+    }
+}
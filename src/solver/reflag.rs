@@ -0,0 +1,379 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::moving::{Motion, Pose};
+
+use ocl::{flags::MEM_READ_ONLY, flags::MEM_READ_WRITE, flags::MEM_WRITE_ONLY, Buffer, Event, Kernel};
+use std::error::Error;
+
+// Floats per body descriptor, must match REFLAG_STRIDE in kernel_reflag.cl
+const REFLAG_STRIDE: usize = 32;
+
+// Shapes the device can rasterize, in the body frame centered on the body
+// center. Sampled shapes come from LBM::sample_sdf.
+#[derive(Debug, Clone, Copy)]
+pub enum Primitive {
+    Sphere { radius: f32 },
+    Box { half_extents: [f32; 3] },
+    Cylinder { radius: f32, half_length: f32 }, // Axis along the body z axis
+    Sampled { offset: usize, half_width: usize }, // SDF grid in reflag_sdf
+}
+
+impl Primitive {
+    fn code_and_params(&self) -> (f32, [f32; 3]) {
+        match *self {
+            Primitive::Sphere { radius } => (0.0, [radius, 0.0, 0.0]),
+            Primitive::Box { half_extents } => (1.0, half_extents),
+            Primitive::Cylinder {
+                radius,
+                half_length,
+            } => (2.0, [radius, half_length, 0.0]),
+            // Passed as int bits, since large offsets are not exact in f32
+            Primitive::Sampled { offset, half_width } => (
+                3.0,
+                [
+                    f32::from_bits(offset as u32),
+                    f32::from_bits(half_width as u32),
+                    0.0,
+                ],
+            ),
+        }
+    }
+
+    // Radius of the bounding sphere
+    pub fn reach(&self) -> f32 {
+        match *self {
+            Primitive::Sphere { radius } => radius,
+            Primitive::Box { half_extents: h } => (h[0] * h[0] + h[1] * h[1] + h[2] * h[2]).sqrt(),
+            Primitive::Cylinder {
+                radius,
+                half_length,
+            } => (radius * radius + half_length * half_length).sqrt(),
+            // Samples beyond the reach of the SDF are positive
+            Primitive::Sampled { half_width, .. } => half_width as f32,
+        }
+    }
+}
+
+// Descriptor of a body with center `center`, body-to-world `rotation`, linear
+// `velocity` and world-frame `angular_velocity`, see kernel_reflag.cl
+pub fn reflag_descriptor(
+    primitive: &Primitive,
+    body_id: u16,
+    center: [f32; 3],
+    rotation: &[[f32; 3]; 3],
+    velocity: [f32; 3],
+    angular_velocity: [f32; 3],
+) -> [f32; REFLAG_STRIDE] {
+    let (code, params) = primitive.code_and_params();
+    let mut d = [0.0f32; REFLAG_STRIDE];
+    d[0] = code;
+    d[1..4].copy_from_slice(&params);
+    d[4..7].copy_from_slice(&center);
+    d[7] = body_id as f32;
+    d[8..11].copy_from_slice(&velocity);
+    d[11..14].copy_from_slice(&angular_velocity);
+    for (i, row) in rotation.iter().enumerate() {
+        d[16 + 3 * i..19 + 3 * i].copy_from_slice(row);
+    }
+    d
+}
+
+// Moving solid re-flagged on the device every step; only its descriptor
+// crosses PCIe instead of the full flags and velocity arrays
+pub struct DeviceBody {
+    pub name: String,
+    pub body_id: u16,
+    pub center: [f32; 3], // Center in the reference configuration
+    pub primitive: Primitive,
+    pub motion: Motion,
+    pub interval: usize, // Steps between re-flags
+}
+
+impl DeviceBody {
+    fn descriptor(&self, t: f32) -> [f32; REFLAG_STRIDE] {
+        let pose: Pose = (self.motion)(t);
+        let (a, b) = ((self.motion)(t - 0.5), (self.motion)(t + 0.5));
+        let (sin, cos) = pose.angle.sin_cos();
+        let rotation = [[cos, -sin, 0.0], [sin, cos, 0.0], [0.0, 0.0, 1.0]];
+        reflag_descriptor(
+            &self.primitive,
+            self.body_id,
+            [0, 1, 2].map(|k| self.center[k] + pose.translation[k]),
+            &rotation,
+            [0, 1, 2].map(|k| b.translation[k] - a.translation[k]),
+            [0.0, 0.0, b.angle - a.angle],
+        )
+    }
+}
+
+impl LBM {
+    // Add a body following `motion` (see moving::Pose) that is rasterized on
+    // the device. Call after set_conditions. Returns the body ID.
+    // Host flags only reflect these bodies after read_flags_from_gpu.
+    pub fn add_device_body(
+        &mut self,
+        name: &str,
+        center: [f32; 3],
+        primitive: Primitive,
+        motion: Motion,
+    ) -> u16 {
+        self.add_device_body_every(name, center, primitive, motion, 1)
+    }
+
+    // add_device_body re-flagged only every `interval` steps
+    pub fn add_device_body_every(
+        &mut self,
+        name: &str,
+        center: [f32; 3],
+        primitive: Primitive,
+        motion: Motion,
+        interval: usize,
+    ) -> u16 {
+        let body_id = self.register_body(name);
+        self.enable_moving_walls();
+        self.device_bodies.push(DeviceBody {
+            name: name.to_string(),
+            body_id,
+            center,
+            primitive,
+            motion,
+            interval: interval.max(1),
+        });
+        body_id
+    }
+
+    // Sample `sdf` (body frame, negative inside) on the unit grid around the
+    // body origin that covers `reach`, for rasterization on the device.
+    // The samples are uploaded with the next re-flag.
+    pub fn sample_sdf(&mut self, sdf: &dyn Fn([f32; 3]) -> f32, reach: f32) -> Primitive {
+        // One sample beyond the reach so the interpolation sees the surface
+        let half_width = reach.ceil() as usize + 1;
+        let offset = self.reflag_sdf.len();
+        let h = half_width as f32;
+        let side = 2 * half_width + 1;
+        for z in 0..side {
+            for y in 0..side {
+                for x in 0..side {
+                    self.reflag_sdf.push(sdf([x as f32 - h, y as f32 - h, z as f32 - h]));
+                }
+            }
+        }
+        self.reflag_sdf_buffer = None;
+        Primitive::Sampled { offset, half_width }
+    }
+
+    // Re-flag the device bodies due at the current step and refill the cells
+    // they uncovered. Runs after host-side geometry uploads so it has the last word.
+    pub fn update_device_bodies(&mut self) -> Result<(), Box<dyn Error>> {
+        let step = self.time_step;
+        let t = step as f32;
+        let mut descriptors = vec![];
+        let mut reaches = vec![];
+        for body in self.device_bodies.iter().filter(|body| step % body.interval == 0) {
+            let d = body.descriptor(t);
+            // Padded for cells left behind since the last re-flag
+            let speed = (d[8] * d[8] + d[9] * d[9] + d[10] * d[10]).sqrt();
+            reaches.push(body.primitive.reach() + 2.0 + speed * body.interval as f32);
+            descriptors.extend_from_slice(&d);
+        }
+        if reaches.is_empty() {
+            return Ok(());
+        }
+        self.reflag_bodies(&descriptors, &reaches, false)?;
+        Ok(())
+    }
+
+    // Rasterize the bodies of `descriptors` on the device within the union of
+    // the spheres of radius `reaches` around their centers, then refill the
+    // cells they uncovered. Returns the covered cells if `read_covered`.
+    pub fn reflag_bodies(
+        &mut self,
+        descriptors: &[f32],
+        reaches: &[f32],
+        read_covered: bool,
+    ) -> Result<Vec<u32>, Box<dyn Error>> {
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?.clone();
+
+        if self.reflag_owner_buffer.is_none() {
+            self.reflag_owner_buffer = Some(
+                Buffer::<u16>::builder()
+                    .queue(queue.clone())
+                    .flags(MEM_READ_WRITE)
+                    .len(self.N)
+                    .fill_val(0u16)
+                    .build()
+                    .map_err(|e| format!("Failed to build 'owner' buffer: {}", e))?,
+            );
+        }
+        if self.reflag_sdf_buffer.is_none() {
+            // Zero-length buffers are invalid in OpenCL
+            let buffer = Buffer::<f32>::builder()
+                .queue(queue.clone())
+                .flags(MEM_READ_ONLY)
+                .len(self.reflag_sdf.len().max(1))
+                .build()
+                .map_err(|e| format!("Failed to build 'sdf' buffer: {}", e))?;
+            if !self.reflag_sdf.is_empty() {
+                buffer
+                    .write(&self.reflag_sdf)
+                    .enq()
+                    .map_err(|e| format!("Failed to write 'sdf' buffer: {}", e))?;
+            }
+            self.reflag_sdf_buffer = Some(buffer);
+        }
+
+        // Union of the bounding boxes
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let mut lo = dims;
+        let mut hi = [0usize; 3];
+        for (d, reach) in descriptors.chunks_exact(REFLAG_STRIDE).zip(reaches) {
+            for k in 0..3 {
+                let a = (d[4 + k] - reach).floor().max(0.0) as usize;
+                let b = ((d[4 + k] + reach).ceil().max(0.0) as usize).min(dims[k] - 1);
+                lo[k] = lo[k].min(a);
+                hi[k] = hi[k].max(b);
+            }
+        }
+        if (0..3).any(|k| lo[k] > hi[k]) {
+            return Ok(vec![]);
+        }
+        let size = [0, 1, 2].map(|k| hi[k] - lo[k] + 1);
+        let region = size[0] * size[1] * size[2];
+
+        let bodies_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_ONLY)
+            .len(descriptors.len())
+            .copy_host_slice(descriptors)
+            .build()
+            .map_err(|e| format!("Failed to build 'bodies' buffer: {}", e))?;
+        let fresh_buffer = Buffer::<u32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_WRITE)
+            .len(region)
+            .build()
+            .map_err(|e| format!("Failed to build 'fresh' buffer: {}", e))?;
+        let covered_buffer = Buffer::<u32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_WRITE_ONLY)
+            .len(region)
+            .build()
+            .map_err(|e| format!("Failed to build 'covered' buffer: {}", e))?;
+        let count_buffer = Buffer::<i32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_WRITE)
+            .len(2)
+            .fill_val(0i32)
+            .build()
+            .map_err(|e| format!("Failed to build 'reflag count' buffer: {}", e))?;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("reflag_kernel")
+            .queue(queue.clone())
+            .global_work_size(region)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(self.reflag_owner_buffer.as_ref().unwrap())
+            .arg(&bodies_buffer)
+            .arg(self.reflag_sdf_buffer.as_ref().unwrap())
+            .arg((descriptors.len() / REFLAG_STRIDE) as i32)
+            .arg(lo[0] as i32)
+            .arg(lo[1] as i32)
+            .arg(lo[2] as i32)
+            .arg(size[0] as i32)
+            .arg(size[1] as i32)
+            .arg(size[2] as i32)
+            .arg(&fresh_buffer)
+            .arg(&covered_buffer)
+            .arg(&count_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'reflag_kernel': {}", e))?;
+        let mut launched = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut launched)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'reflag_kernel': {}", e))?;
+        }
+
+        // [fresh, covered]
+        let mut count = [0i32; 2];
+        count_buffer
+            .read(&mut count[..])
+            .ewait(&launched)
+            .enq()
+            .map_err(|e| format!("Failed to read 'reflag count' buffer: {}", e))?;
+        let mut covered = vec![0u32; if read_covered { count[1].max(0) as usize } else { 0 }];
+        if !covered.is_empty() {
+            covered_buffer
+                .read(&mut covered)
+                .enq()
+                .map_err(|e| format!("Failed to read 'covered' buffer: {}", e))?;
+        }
+        self.enqueue_refill(&fresh_buffer, count[0].max(0) as usize)?;
+        Ok(covered)
+    }
+
+    // Download the device flags, e.g. before exporting geometry
+    pub fn read_flags_from_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        self.flags_buffer
+            .as_ref()
+            .ok_or("Flags buffer is None")?
+            .read(&mut self.flags)
+            .enq()
+            .map_err(|e| format!("Failed to read 'flags' buffer: {}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Primitive;
+    use crate::solver::flags::{CellType, FLAG_EQ, FLAG_SOLID};
+    use crate::solver::lbm::LBM;
+    use crate::solver::moving::Pose;
+
+    // Runs only where an OpenCL platform is installed
+    fn device_available() -> bool {
+        ocl::core::get_platform_ids().is_ok_and(|platforms| !platforms.is_empty())
+    }
+
+    #[test]
+    fn bodies_leave_equilibrium_cells_alone() {
+        if !device_available() {
+            return;
+        }
+        let (nx, ny) = (32, 16);
+        let mut lbm = LBM::builder().size(nx, ny, 1).viscosity(0.1).build().unwrap();
+        lbm.set_conditions(|lbm, x, y, z, _n| {
+            if x == 0 {
+                lbm.set_cell(x, y, z, CellType::Equilibrium);
+            }
+        });
+        // Sphere over the inlet column at t = 0, clear of it from t = 1
+        lbm.add_device_body(
+            "ball",
+            [0.0, 8.0, 0.0],
+            Primitive::Sphere { radius: 4.0 },
+            Box::new(|t| Pose {
+                translation: [10.0 * t, 0.0, 0.0],
+                angle: 0.0,
+            }),
+        );
+        lbm.initialize();
+        for step in 0..2 {
+            lbm.time_step = step;
+            lbm.update_device_bodies().unwrap();
+            lbm.read_flags_from_gpu().unwrap();
+            for y in 0..ny {
+                assert_eq!(lbm.flags[y * nx], FLAG_EQ, "inlet cell (0, {}) at step {}", y, step);
+            }
+        }
+        // The body itself was rasterized next to the inlet
+        assert_eq!(lbm.flags[8 * nx + 10], FLAG_SOLID);
+    }
+}
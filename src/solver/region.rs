@@ -0,0 +1,34 @@
+// src/solver/region.rs
+// Selection of lattice cells by bounding box or by an arbitrary predicate.
+
+pub enum Region {
+    // Inclusive axis-aligned bounding box: (x0, y0, z0) to (x1, y1, z1)
+    BoundingBox {
+        min: (usize, usize, usize),
+        max: (usize, usize, usize),
+    },
+    // Any cell for which the closure (x, y, z) returns true
+    Predicate(Box<dyn Fn(usize, usize, usize) -> bool + Send + Sync>),
+}
+
+impl Region {
+    pub fn bbox(min: (usize, usize, usize), max: (usize, usize, usize)) -> Self {
+        Region::BoundingBox { min, max }
+    }
+
+    pub fn predicate<F>(f: F) -> Self
+    where
+        F: Fn(usize, usize, usize) -> bool + Send + Sync + 'static,
+    {
+        Region::Predicate(Box::new(f))
+    }
+
+    pub fn contains(&self, x: usize, y: usize, z: usize) -> bool {
+        match self {
+            Region::BoundingBox { min, max } => {
+                x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1 && z >= min.2 && z <= max.2
+            }
+            Region::Predicate(f) => f(x, y, z),
+        }
+    }
+}
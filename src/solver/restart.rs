@@ -0,0 +1,122 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Approximate restart from macroscopic fields: density and velocity are read
+// from a previous legacy VTK or .npy output and run() rebuilds the populations
+// in equilibrium. Non-equilibrium parts and the step counter are not restored;
+// use save_checkpoint/load_checkpoint for an exact restart.
+
+use super::lbm::LBM;
+use crate::utils::npy::read_npy;
+
+use std::error::Error;
+use std::fs;
+
+// Next whitespace-separated value of a legacy VTK file
+fn next_token<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    what: &str,
+) -> Result<&'a str, Box<dyn Error>> {
+    tokens
+        .next()
+        .ok_or_else(|| format!("Unexpected end of VTK file while reading {}", what).into())
+}
+
+fn read_floats<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    count: usize,
+    what: &str,
+) -> Result<Vec<f32>, Box<dyn Error>> {
+    let mut values = Vec::with_capacity(count);
+    for _ in 0..count {
+        values.push(next_token(tokens, what)?.parse::<f32>()?);
+    }
+    Ok(values)
+}
+
+impl LBM {
+    // Replace density and velocity with the fields of an ASCII .vtk file
+    // written by export_to_vtk for a lattice of the same size. Call after
+    // set_conditions so the loaded fields are not overwritten.
+    pub fn load_fields_vtk(&mut self, path: &str) -> Result<(), Box<dyn Error>> {
+        let text = fs::read_to_string(path)?;
+        let mut tokens = text.split_whitespace();
+        let mut density = None;
+        let mut u = None;
+        while let Some(token) = tokens.next() {
+            match token {
+                "BINARY" => return Err("Only ASCII VTK files can be loaded".into()),
+                "DIMENSIONS" => {
+                    let mut dims = [0usize; 3];
+                    for d in dims.iter_mut() {
+                        *d = next_token(&mut tokens, "DIMENSIONS")?.parse()?;
+                    }
+                    if dims != [self.Nx, self.Ny, self.Nz] {
+                        return Err(format!(
+                            "VTK dimensions {:?} do not match the lattice {}x{}x{}",
+                            dims, self.Nx, self.Ny, self.Nz
+                        )
+                        .into());
+                    }
+                }
+                "SCALARS" if next_token(&mut tokens, "SCALARS")? == "density" => {
+                    next_token(&mut tokens, "SCALARS")?; // Type
+                    while next_token(&mut tokens, "density")? != "LOOKUP_TABLE" {}
+                    next_token(&mut tokens, "LOOKUP_TABLE")?; // Table name
+                    density = Some(read_floats(&mut tokens, self.N, "density")?);
+                }
+                "VECTORS" if next_token(&mut tokens, "VECTORS")? == "velocity" => {
+                    next_token(&mut tokens, "VECTORS")?; // Type
+                    u = Some(read_floats(&mut tokens, self.N * 3, "velocity")?);
+                }
+                _ => {}
+            }
+            if density.is_some() && u.is_some() {
+                break;
+            }
+        }
+        let density = density.ok_or("VTK file has no density field")?;
+        let u = u.ok_or("VTK file has no velocity field")?;
+        self.set_restart_fields(density, u);
+        Ok(())
+    }
+
+    // Replace density and velocity with float32 .npy arrays of shape
+    // (Nz, Ny, Nx) and (Nz, Ny, Nx, 3), as written by dump_numpy. Call after
+    // set_conditions so the loaded fields are not overwritten.
+    pub fn load_fields_numpy(
+        &mut self,
+        density_path: &str,
+        velocity_path: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let dims = [self.Nz, self.Ny, self.Nx];
+        let (shape, density) = read_npy::<f32>(density_path)?;
+        if shape != dims {
+            return Err(format!("Density shape {:?} does not match {:?}", shape, dims).into());
+        }
+        let (shape, u) = read_npy::<f32>(velocity_path)?;
+        if shape != [self.Nz, self.Ny, self.Nx, 3] {
+            return Err(format!(
+                "Velocity shape {:?} does not match {:?}",
+                shape,
+                [self.Nz, self.Ny, self.Nx, 3]
+            )
+            .into());
+        }
+        self.set_restart_fields(density, u);
+        Ok(())
+    }
+
+    fn set_restart_fields(&mut self, density: Vec<f32>, u: Vec<f32>) {
+        self.density = density;
+        self.u = u;
+        // The per-cell Velocity array is only kept until set_conditions
+        if self.velocity.len() == self.N {
+            for (n, v) in self.velocity.iter_mut().enumerate() {
+                v.x = self.u[n * 3];
+                v.y = self.u[n * 3 + 1];
+                v.z = self.u[n * 3 + 2];
+            }
+        }
+    }
+}
@@ -0,0 +1,257 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::moving::Sdf;
+use crate::solver::reflag::{reflag_descriptor, Primitive};
+use crate::solver::transforms::xyz_from_n;
+
+use std::error::Error;
+
+// Quaternion (w, x, y, z) helpers for body orientation
+fn quat_mul(a: [f32; 4], b: [f32; 4]) -> [f32; 4] {
+    [
+        a[0] * b[0] - a[1] * b[1] - a[2] * b[2] - a[3] * b[3],
+        a[0] * b[1] + a[1] * b[0] + a[2] * b[3] - a[3] * b[2],
+        a[0] * b[2] - a[1] * b[3] + a[2] * b[0] + a[3] * b[1],
+        a[0] * b[3] + a[1] * b[2] - a[2] * b[1] + a[3] * b[0],
+    ]
+}
+
+fn quat_to_matrix(q: [f32; 4]) -> [[f32; 3]; 3] {
+    let [w, x, y, z] = q;
+    [
+        [
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - w * z),
+            2.0 * (x * z + w * y),
+        ],
+        [
+            2.0 * (x * y + w * z),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - w * x),
+        ],
+        [
+            2.0 * (x * z - w * y),
+            2.0 * (y * z + w * x),
+            1.0 - 2.0 * (x * x + y * y),
+        ],
+    ]
+}
+
+fn mat_vec(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|i| m[i][0] * v[0] + m[i][1] * v[1] + m[i][2] * v[2])
+}
+
+fn mat_t_vec(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    [0, 1, 2].map(|i| m[0][i] * v[0] + m[1][i] * v[1] + m[2][i] * v[2])
+}
+
+pub fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RigidBodySample {
+    pub step: usize,
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+    pub force: [f32; 3],  // Hydrodynamic force
+    pub torque: [f32; 3], // Hydrodynamic torque about the center of mass
+}
+
+// A voxelized solid moving freely under hydrodynamic loads and gravity.
+// The SDF is given in the body frame with its origin at the center of mass
+// and its axes along the principal axes of inertia. It is sampled once and
+// re-flagged on the device every step (see reflag.rs).
+pub struct RigidBody {
+    pub name: String,
+    pub body_id: u16,
+    pub sdf: Sdf,
+    pub shape: Primitive, // Samples of the SDF
+    pub reach: f32,
+    pub cells: Vec<u32>, // Cells covered at the last re-flag
+
+    pub density: f32, // Relative to the fluid (rho_fluid = 1)
+    pub volume: f32,  // Cell count at creation
+    pub mass: f32,
+    pub inertia: [f32; 3], // Principal moments in the body frame
+
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub orientation: [f32; 4],      // Unit quaternion (w, x, y, z)
+    pub angular_velocity: [f32; 3], // World frame
+
+    pub gravity: [f32; 3],
+    pub locked: [bool; 6], // Fixed translations x, y, z and rotations x, y, z
+    pub history: Vec<RigidBodySample>,
+}
+
+impl RigidBody {
+    // Restrict motion, e.g. [false, true, true, true, true, true] for a
+    // cylinder that may only move along x
+    pub fn lock(&mut self, locked: [bool; 6]) {
+        self.locked = locked;
+        self.apply_locks();
+    }
+
+    fn apply_locks(&mut self) {
+        for d in 0..3 {
+            if self.locked[d] {
+                self.velocity[d] = 0.0;
+            }
+            if self.locked[3 + d] {
+                self.angular_velocity[d] = 0.0;
+            }
+        }
+    }
+
+    // One explicit step (dt = 1) of the Newton-Euler equations. Buoyancy is
+    // added explicitly since the fluid carries no hydrostatic pressure.
+    // Bodies lighter than about 1.5x the fluid density may become unstable.
+    fn integrate(&mut self, force: [f32; 3], torque: [f32; 3]) {
+        let net_mass = self.mass - self.volume;
+        for (d, v) in self.velocity.iter_mut().enumerate() {
+            *v += (force[d] + net_mass * self.gravity[d]) / self.mass;
+        }
+
+        // Euler's equations in the body frame
+        let rotation = quat_to_matrix(self.orientation);
+        let w = mat_t_vec(&rotation, self.angular_velocity);
+        let t = mat_t_vec(&rotation, torque);
+        let iw = [0, 1, 2].map(|d| self.inertia[d] * w[d]);
+        let gyro = cross(w, iw);
+        let w_new: [f32; 3] =
+            [0, 1, 2].map(|d| w[d] + (t[d] - gyro[d]) / self.inertia[d].max(f32::EPSILON));
+        self.angular_velocity = mat_vec(&rotation, w_new);
+        self.apply_locks();
+
+        for d in 0..3 {
+            self.position[d] += self.velocity[d];
+        }
+        let [wx, wy, wz] = self.angular_velocity;
+        let dq = quat_mul([0.0, wx, wy, wz], self.orientation);
+        let mut q = [0, 1, 2, 3].map(|i| self.orientation[i] + 0.5 * dq[i]);
+        let norm = q.iter().map(|v| v * v).sum::<f32>().sqrt();
+        q.iter_mut().for_each(|v| *v /= norm);
+        self.orientation = q;
+    }
+
+    pub fn rotation_angle_z(&self) -> f32 {
+        let [w, x, y, z] = self.orientation;
+        (2.0 * (w * z + x * y)).atan2(1.0 - 2.0 * (y * y + z * z))
+    }
+}
+
+impl LBM {
+    // Add a freely moving solid of relative density `density` with its center
+    // of mass at `position`. Call after set_conditions. Returns its index.
+    // Host flags only reflect the body after read_flags_from_gpu.
+    pub fn add_rigid_body(
+        &mut self,
+        name: &str,
+        position: [f32; 3],
+        density: f32,
+        sdf: Sdf,
+    ) -> usize {
+        let body_id = self.register_body(name);
+        let local = |p: [f32; 3]| [p[0] - position[0], p[1] - position[1], p[2] - position[2]];
+        let inside = |p: [f32; 3]| sdf(local(p)) <= 0.0;
+        let reach = self.solid_reach(position, &inside);
+
+        // Mass properties from the voxels, principal axes assumed along the body frame
+        let mut volume = 0.0f32;
+        let mut inertia = [0.0f32; 3];
+        for n in 0..self.N {
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            let p = [x as f32, y as f32, z as f32];
+            if !inside(p) {
+                continue;
+            }
+            let r = local(p);
+            volume += 1.0;
+            inertia[0] += density * (r[1] * r[1] + r[2] * r[2]);
+            inertia[1] += density * (r[0] * r[0] + r[2] * r[2]);
+            inertia[2] += density * (r[0] * r[0] + r[1] * r[1]);
+        }
+
+        let shape = self.sample_sdf(&*sdf, reach);
+        self.enable_moving_walls();
+        self.rigid_bodies.push(RigidBody {
+            name: name.to_string(),
+            body_id,
+            sdf,
+            shape,
+            reach,
+            cells: vec![],
+            density,
+            volume,
+            mass: density * volume,
+            inertia,
+            position,
+            velocity: [0.0; 3],
+            orientation: [1.0, 0.0, 0.0, 0.0],
+            angular_velocity: [0.0; 3],
+            gravity: [0.0; 3],
+            locked: [false; 6],
+            history: vec![],
+        });
+        self.rigid_bodies.len() - 1
+    }
+
+    // Couple the rigid bodies to the flow for one step: momentum-exchange
+    // loads, Newton-Euler integration and re-flagging with refill on the device
+    pub fn update_rigid_bodies(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut bodies = std::mem::take(&mut self.rigid_bodies);
+        let mut result = Ok(());
+        for body in bodies.iter_mut() {
+            if let Err(err) = self.update_rigid_body(body) {
+                result = Err(err);
+                break;
+            }
+        }
+        self.rigid_bodies = bodies;
+        result
+    }
+
+    fn update_rigid_body(&mut self, body: &mut RigidBody) -> Result<(), Box<dyn Error>> {
+        // The body enters the flow at its first update
+        if body.cells.is_empty() {
+            body.cells = self.reflag_rigid_body(body)?;
+        }
+        let (force, torque) = self.momentum_exchange(&body.cells, body.position)?;
+        body.integrate(force, torque);
+        body.history.push(RigidBodySample {
+            step: self.time_step,
+            position: body.position,
+            velocity: body.velocity,
+            angular_velocity: body.angular_velocity,
+            force,
+            torque,
+        });
+        body.cells = self.reflag_rigid_body(body)?;
+        Ok(())
+    }
+
+    // Rasterize a rigid body at its current state on the device and refill the
+    // cells it left. Returns the cells it covers.
+    fn reflag_rigid_body(&mut self, body: &RigidBody) -> Result<Vec<u32>, Box<dyn Error>> {
+        let descriptor = reflag_descriptor(
+            &body.shape,
+            body.body_id,
+            body.position,
+            &quat_to_matrix(body.orientation),
+            body.velocity,
+            body.angular_velocity,
+        );
+        // Padded for the cells left behind by the last step
+        let speed = body.velocity.iter().map(|v| v * v).sum::<f32>().sqrt();
+        let reach = body.shape.reach() + 2.0 + speed;
+        self.reflag_bodies(&descriptor, &[reach], true)
+    }
+}
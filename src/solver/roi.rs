@@ -0,0 +1,64 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Region-of-interest output: a bounding box written at its own interval,
+// read back with rectangular device reads instead of the whole domain.
+
+use super::lbm::LBM;
+use crate::solver::output::OutputTarget;
+use crate::solver::vti::write_vti;
+
+use std::error::Error;
+
+#[derive(Debug, Clone)]
+pub struct OutputRegion {
+    pub name: String,
+    pub min: [usize; 3],
+    pub max: [usize; 3], // Inclusive
+    pub interval: usize, // Steps between outputs
+}
+
+impl LBM {
+    // Write density and velocity inside the inclusive box [min, max] every
+    // `interval` steps as output/roi_<name>_<step>.vti. The box is clamped to
+    // the domain. Returns the region index.
+    pub fn add_output_region(
+        &mut self,
+        name: &str,
+        min: (usize, usize, usize),
+        max: (usize, usize, usize),
+        interval: usize,
+    ) -> usize {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let max = [max.0, max.1, max.2];
+        let max = [0, 1, 2].map(|d| max[d].min(dims[d] - 1));
+        let min = [min.0, min.1, min.2];
+        let min = [0, 1, 2].map(|d| min[d].min(max[d]));
+        self.output_regions.push(OutputRegion {
+            name: name.to_string(),
+            min,
+            max,
+            interval: interval.max(1),
+        });
+        self.output_regions.len() - 1
+    }
+
+    // Write the regions due at step `step`
+    pub fn export_output_regions(&self, step: usize, width: usize) -> Result<(), Box<dyn Error>> {
+        let stride = self.output_stride(OutputTarget::Regions);
+        for region in &self.output_regions {
+            if step % region.interval != 0 {
+                continue;
+            }
+            let (arrays, size) = self.read_box_arrays(region.min, region.max, stride)?;
+            let filename = format!(
+                "output/roi_{}_{:0width$}.vti",
+                region.name,
+                step,
+                width = width
+            );
+            write_vti(&filename, region.min, stride, size, &arrays)?;
+        }
+        Ok(())
+    }
+}
@@ -2,51 +2,122 @@
 #![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
 
 use super::lbm::LBM;
+use crate::solver::metadata::RunTiming;
+use crate::solver::output::OutputTarget;
+use crate::solver::precision::PrecisionMode;
+use crate::solver::pvd::{PVD_PART_FIELDS, PVD_PART_PARTICLES};
 use crate::utils::terminal_utils;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::ProgressStyle;
+use ocl::Event;
 use std::path::Path;
 use std::time::Instant;
 
 impl LBM {
     pub fn run(&mut self, time_steps: usize) {
+        let _span = tracing::info_span!(target: "cappusim::run", "run", time_steps).entered();
         // Print welcome message
         terminal_utils::print_welcome_message();
         self.time_steps = time_steps;
-        println!("{}", "-".repeat(72));
+        terminal_utils::print_info(&"-".repeat(72));
 
         // Check for errors in input parameters
         if let Err(err) = self.check_errors_in_input() {
             terminal_utils::print_error(&format!("Error: {}", err));
             return;
         }
+        self.check_geometry();
+
+        // Optional device self-test before committing to a long run
+        if self.self_test {
+            if let Err(err) = self.check_device() {
+                terminal_utils::print_error(&format!("Error: {}", err));
+                return;
+            }
+        }
+
+        // Domains larger than device memory run slab by slab from host RAM
+        if self.out_of_core_layers > 0 {
+            if let Err(err) = self.run_out_of_core(time_steps) {
+                terminal_utils::print_error(&format!("Error: {}", err));
+            }
+            return;
+        }
+
+        // Optional accuracy cost of the reduced precision, reported only
+        if self.precision_audit > 0 && self.precision_mode != PrecisionMode::FP32 {
+            match self.audit_precision(self.precision_mode, self.precision_audit) {
+                Ok(report) => LBM::print_precision_audit(&report),
+                Err(err) => terminal_utils::print_warning(&format!("Precision audit failed: {}", err)),
+            }
+        }
 
         // Initialize OpenCL
         self.initialize();
+        self.kernel_times.clear();
 
         terminal_utils::print_name();
 
-        // Initialize f in equilibrium from rho and u
-        unsafe {
-            self.equilibrium_kernel
-                .as_ref()
-                .unwrap()
-                .enq()
-                .expect("Failed to enqueue 'equilibrium_kernel'.");
-            self.queue
-                .as_ref()
-                .unwrap()
-                .finish()
-                .expect("Queue finish failed.");
+        // Continue from checkpointed populations, or initialize f in
+        // equilibrium from rho and u
+        let start = if let Some(f) = self.checkpoint_f.take() {
+            if let Err(err) = self.write_f_raw_to_gpu(&f) {
+                terminal_utils::print_error(&format!("Error restoring checkpoint: {}", err));
+                return;
+            }
+            terminal_utils::print_log(&format!("Resuming from step {}", self.time_step));
+            self.time_step
+        } else {
+            let mut event = Event::empty();
+            unsafe {
+                self.equilibrium_kernel
+                    .as_ref()
+                    .unwrap()
+                    .cmd()
+                    .enew(&mut event)
+                    .enq()
+                    .expect("Failed to enqueue 'equilibrium_kernel'.");
+                self.queue
+                    .as_ref()
+                    .unwrap()
+                    .finish()
+                    .expect("Queue finish failed.");
+            }
+            if self.profiling {
+                self.record_kernel_time("equilibrium_kernel", &event);
+            }
+            self.time_step = 0;
+            0
+        };
+        self.step_event = None;
+        // run_until_steady runs open-ended and pads file names to a fixed width
+        let open_ended = self.time_steps == usize::MAX;
+        let end = start.saturating_add(self.time_steps);
+        let magnitude = if open_ended { 9 } else { end.to_string().len() };
+
+        if self.conservation_interval > 0 {
+            self.start_conservation_monitor();
         }
+        self.mach_exceeded = false;
+        self.averages_buffer = None;
+        self.averages.clear();
+        self.max_speed_seen = 0.0;
 
         // Create a progress bar with MLUPs display
-        let pb = ProgressBar::new(self.time_steps as u64);
-        pb.set_style(
-            ProgressStyle::default_bar()
-                .template("{spinner:.green} [{bar:55.cyan/blue}] {pos}/{len} ({eta}) {msg}")
-                .unwrap()
-                .progress_chars("=> "),
-        );
+        let pb = terminal_utils::progress_bar(self.time_steps as u64);
+        if open_ended {
+            pb.set_style(
+                ProgressStyle::default_spinner()
+                    .template("{spinner:.green} {pos} steps ({elapsed}) {msg}")
+                    .unwrap(),
+            );
+        } else {
+            pb.set_style(
+                ProgressStyle::default_bar()
+                    .template("{spinner:.green} [{bar:55.cyan/blue}] {pos}/{len} ({eta}) {msg}")
+                    .unwrap()
+                    .progress_chars("=> "),
+            );
+        }
         
         // Recreate output folder
         let output_path = Path::new("output");
@@ -56,46 +127,336 @@ impl LBM {
         }
         std::fs::create_dir(output_path).expect("Failed to create output directory.");
 
+        // Run description, completed with the timing at the end
+        let mut timing = RunTiming {
+            start_step: start,
+            end_step: end,
+            wall_time: None,
+            mlups: None,
+        };
+        if let Err(err) = self.write_run_metadata("output/run.json", &timing) {
+            terminal_utils::print_error(&format!("Error writing run metadata: {}", err));
+        }
+
+        // Monitors append to their CSV files during the loop
+        let monitor_forces = self.force_monitor_interval > 0 && !self.force_monitors.is_empty();
+        if monitor_forces {
+            if let Err(err) = self.start_force_monitors() {
+                terminal_utils::print_error(&format!("Error creating body loads file: {}", err));
+                return;
+            }
+        }
+        if self.energy_interval > 0 {
+            if let Err(err) = self.start_energy_monitor() {
+                terminal_utils::print_error(&format!("Error creating energy file: {}", err));
+                return;
+            }
+        }
+        let monitor_flow = self.flow_rate_interval > 0 && !self.flow_rate_monitors.is_empty();
+        if monitor_flow {
+            if let Err(err) = self.start_flow_rate_monitors() {
+                terminal_utils::print_error(&format!("Error creating flow rates file: {}", err));
+                return;
+            }
+        }
+
+        // Static geometry, written once
+        if self.output_flags {
+            let mirrored = if self.mirror_output { self.mirrored_output() } else { None };
+            let target = mirrored.as_ref().unwrap_or(self);
+            if let Err(err) = target.export_flags_vti("output/flags.vti") {
+                terminal_utils::print_error(&format!("Error exporting flags: {}", err));
+                return;
+            }
+        }
+
         // Start timing
         let start_time = Instant::now();
         let mut last_update_time = start_time;
-        let mut last_step = 0;
+        let mut last_step = start;
 
         // Main Loop using fused stream-collide kernel
-        for t in 0..self.time_steps {
-            unsafe {
-                let kernel = self.stream_collide_kernel.as_ref().expect("stream_collide_kernel not initialized");
-                kernel.set_arg(6, &(t as i32))
-                    .expect("Failed to set kernel argument.");
-                kernel.enq()
-                    .expect("Failed to enqueue 'stream_collide_kernel'.");
-                self.queue
-                    .as_ref()
-                    .unwrap()
-                    .finish()
-                    .expect("Queue finish failed.");
+        for t in start..end {
+            // Resolved particle suspensions, rasterized on the host. They
+            // upload the full flags, so the device-flagged bodies follow.
+            if !self.suspensions.is_empty() {
+                if let Err(err) = self.update_suspensions() {
+                    terminal_utils::print_error(&format!("Error updating suspensions: {}", err));
+                    return;
+                }
+            }
+
+            // Free rigid bodies driven by the flow
+            if !self.rigid_bodies.is_empty() {
+                if let Err(err) = self.update_rigid_bodies() {
+                    terminal_utils::print_error(&format!("Error updating rigid bodies: {}", err));
+                    return;
+                }
+            }
+
+            // Bodies with a prescribed motion re-flagged on the device
+            if !self.device_bodies.is_empty() {
+                if let Err(err) = self.update_device_bodies() {
+                    terminal_utils::print_error(&format!("Error re-flagging device bodies: {}", err));
+                    return;
+                }
+            }
+
+            // Immersed boundary forcing and structure update
+            if !self.immersed_boundaries.is_empty() {
+                if let Err(err) = self.update_immersed_boundaries() {
+                    terminal_utils::print_error(&format!("Error updating immersed boundaries: {}", err));
+                    return;
+                }
+            }
+
+            // Particle drag returned to the fluid
+            if self.tracers.two_way && !self.tracers.is_empty() {
+                if let Err(err) = self.couple_particles() {
+                    terminal_utils::print_error(&format!("Error coupling particles: {}", err));
+                    return;
+                }
+            }
+
+            // Steps are chained by events; the host only waits when it needs
+            // the fields of this step
+            let event = match self.enqueue_stream_collide(t) {
+                Ok(event) => event,
+                Err(err) => {
+                    terminal_utils::print_error(&format!("Error: {}", err));
+                    return;
+                }
+            };
+            if self.host_work_due(t) {
+                if let Err(err) = self.sync_step() {
+                    terminal_utils::print_error(&format!("Error: {}", err));
+                    return;
+                }
+            }
+            if self.profiling {
+                self.record_kernel_time("stream_collide_kernel", &event);
+            }
+            self.time_step = t + 1;
+
+            // Lagrangian tracers follow the new velocity field
+            if !self.tracers.is_empty() {
+                if let Err(err) = self.advect_tracers() {
+                    terminal_utils::print_error(&format!("Error advecting tracers: {}", err));
+                    return;
+                }
+            }
+            if !self.tracers.is_empty() || !self.tracers.sources.is_empty() {
+                if let Err(err) = self.manage_particles() {
+                    terminal_utils::print_error(&format!("Error managing particles: {}", err));
+                    return;
+                }
+            }
+            if self.dispersion.interval > 0 && self.time_step % self.dispersion.interval == 0 {
+                if let Err(err) = self.update_dispersion_statistics() {
+                    terminal_utils::print_error(&format!("Error sampling dispersion statistics: {}", err));
+                    return;
+                }
+            }
+
+            // Global mass/momentum drift monitor
+            if self.conservation_interval > 0 && self.time_step % self.conservation_interval == 0 {
+                if let Err(err) = self.update_conservation_monitor() {
+                    terminal_utils::print_error(&format!("Error monitoring conservation: {}", err));
+                    return;
+                }
+            }
+
+            // Kinetic energy and enstrophy, reduced on the device
+            if self.energy_interval > 0 && self.time_step % self.energy_interval == 0 {
+                if let Err(err) = self.update_energy_monitor() {
+                    terminal_utils::print_error(&format!("Error monitoring energy: {}", err));
+                    return;
+                }
+            }
+
+            // Running means for turbulence statistics
+            if self.averages_due() {
+                if let Err(err) = self.accumulate_averages() {
+                    terminal_utils::print_error(&format!("Error accumulating averages: {}", err));
+                    return;
+                }
+            }
+
+            // Compressibility check on the maximum velocity
+            if self.mach_interval > 0 && self.time_step % self.mach_interval == 0 {
+                if let Err(err) = self.update_mach_monitor() {
+                    terminal_utils::print_error(&format!("Stopping run: {}", err));
+                    return;
+                }
+            }
+
+            // Flow rate through planes and cross-sections
+            if monitor_flow && self.time_step % self.flow_rate_interval == 0 {
+                if let Err(err) = self.update_flow_rate_monitors() {
+                    terminal_utils::print_error(&format!("Error monitoring flow rates: {}", err));
+                    return;
+                }
+            }
+
+            // Body force and torque time series
+            if monitor_forces && self.time_step % self.force_monitor_interval == 0 {
+                if let Err(err) = self.update_force_monitors() {
+                    terminal_utils::print_error(&format!("Error monitoring body loads: {}", err));
+                    return;
+                }
+            }
+
+            // Rolling checkpoints, written in the background
+            if self.checkpoint_interval > 0 && self.time_step % self.checkpoint_interval == 0 {
+                if let Err(err) = self.autosave_checkpoint() {
+                    terminal_utils::print_error(&format!("Error writing checkpoint: {}", err));
+                    return;
+                }
+            }
+
+            // Region-of-interest subvolumes, each at its own interval
+            if !self.output_regions.is_empty() {
+                if let Err(err) = self.export_output_regions(t, magnitude) {
+                    terminal_utils::print_error(&format!("Error exporting output regions: {}", err));
+                    return;
+                }
             }
 
             // Output data
             if (self.output_interval != 0) && (t % self.output_interval == 0) {
-                if let Err(err) = self.read_from_gpu() {
-                    terminal_utils::print_error(&format!("Error reading data from GPU: {}", err));
-                    return;
+                let _span = tracing::info_span!(target: "cappusim::output", "output", step = t).entered();
+                if self.needs_full_readback() {
+                    if let Err(err) = self.read_from_gpu() {
+                        terminal_utils::print_error(&format!("Error reading data from GPU: {}", err));
+                        return;
+                    }
+                    if let Err(err) = self.read_averages_from_gpu() {
+                        terminal_utils::print_error(&format!("Error reading averages from GPU: {}", err));
+                        return;
+                    }
                 }
-                let magnitude = self.time_steps.to_string().len();
+                if !self.device_bodies.is_empty() || !self.rigid_bodies.is_empty() {
+                    if let Err(err) = self.read_flags_from_gpu() {
+                        terminal_utils::print_error(&format!("Error reading flags from GPU: {}", err));
+                        return;
+                    }
+                }
+                // Full-domain view when only a symmetric half is simulated
+                let mirrored = if self.mirror_output { self.mirrored_output() } else { None };
+                let target = mirrored.as_ref().unwrap_or(self);
                 if self.output_csv {
                     let filename = format!("output/data_{:0width$}.csv", t, width = magnitude);
-                    if let Err(err) = self.output_to_csv(&filename.to_string()) {
+                    let decimated = target.decimated(self.output_stride(OutputTarget::Csv));
+                    if let Err(err) = decimated.as_ref().unwrap_or(target).output_to_csv(&filename.to_string()) {
                         terminal_utils::print_error(&format!("Error exporting data: {}", err));
                         return;
                     }
+                    tracing::debug!(target: "cappusim::output", "Wrote {}", filename);
                 }
+                let mut field_files = Vec::new();
                 if self.output_vtk {
                     let filename = format!("output/data_{:0width$}.vtk", t, width = magnitude);
-                    if let Err(err) = self.export_to_vtk(&filename) {
+                    let decimated = target.decimated(self.output_stride(OutputTarget::Vtk));
+                    if let Err(err) = decimated.as_ref().unwrap_or(target).export_to_vtk(&filename) {
                         terminal_utils::print_error(&format!("Error exporting VTK data: {}", err));
                         return;
                     }
+                    tracing::debug!(target: "cappusim::output", "Wrote {}", filename);
+                    field_files.push(filename);
+                }
+                if self.output_vti {
+                    let filename = format!("output/data_{:0width$}.vti", t, width = magnitude);
+                    let decimated = target.decimated(self.output_stride(OutputTarget::Vti));
+                    if let Err(err) = decimated.as_ref().unwrap_or(target).export_to_vti(&filename) {
+                        terminal_utils::print_error(&format!("Error exporting VTI data: {}", err));
+                        return;
+                    }
+                    tracing::debug!(target: "cappusim::output", "Wrote {}", filename);
+                    field_files.push(filename);
+                }
+                let mut hdf5_step = None;
+                if self.output_hdf5 {
+                    let truncate = self.hdf5_steps.is_empty();
+                    let decimated = target.decimated(self.output_stride(OutputTarget::Hdf5));
+                    match decimated.as_ref().unwrap_or(target).export_to_hdf5("output/simulation.h5", t, truncate) {
+                        Ok(entry) => {
+                            tracing::debug!(target: "cappusim::output", "Wrote step {} to output/simulation.h5", t);
+                            hdf5_step = Some(entry)
+                        }
+                        Err(err) => {
+                            terminal_utils::print_error(&format!("Error exporting HDF5 data: {}", err));
+                            return;
+                        }
+                    }
+                }
+                for filename in field_files {
+                    self.record_output(t, PVD_PART_FIELDS, &filename);
+                }
+                if let Some(entry) = hdf5_step {
+                    self.hdf5_steps.push(entry);
+                    if let Err(err) = self.write_xdmf("output/simulation.xmf", "output/simulation.h5") {
+                        terminal_utils::print_error(&format!("Error writing XDMF index: {}", err));
+                        return;
+                    }
+                }
+                if !self.output_slices.is_empty() {
+                    if let Err(err) = self.export_output_slices(t, magnitude) {
+                        terminal_utils::print_error(&format!("Error exporting output slices: {}", err));
+                        return;
+                    }
+                }
+                if !self.image_outputs.is_empty() {
+                    if let Err(err) = self.export_images(t, magnitude) {
+                        terminal_utils::print_error(&format!("Error exporting images: {}", err));
+                        return;
+                    }
+                }
+                if !self.probes.is_empty() {
+                    self.sample_probes();
+                }
+                if !self.tracers.is_empty() {
+                    if let Err(err) = self.read_tracers_from_gpu() {
+                        terminal_utils::print_error(&format!("Error reading tracers from GPU: {}", err));
+                        return;
+                    }
+                    if self.output_csv {
+                        let filename = format!("output/tracers_{:0width$}.csv", t, width = magnitude);
+                        if let Err(err) = self.export_tracers_csv(&filename) {
+                            terminal_utils::print_error(&format!("Error exporting tracers: {}", err));
+                            return;
+                        }
+                    }
+                    if self.output_vtk || self.output_vti {
+                        let filename = format!("output/tracers_{:0width$}.vtp", t, width = magnitude);
+                        if let Err(err) = self.export_tracers_vtp(&filename) {
+                            terminal_utils::print_error(&format!("Error exporting tracers: {}", err));
+                            return;
+                        }
+                        self.record_output(t, PVD_PART_PARTICLES, &filename);
+                    }
+                }
+                // Rewritten every output so partial runs can be opened too
+                if self.output_vtk || self.output_vti {
+                    if let Err(err) = self.write_pvd("output/simulation.pvd") {
+                        terminal_utils::print_error(&format!("Error writing PVD collection: {}", err));
+                        return;
+                    }
+                }
+                if self.output_geometry {
+                    if !self.probes.is_empty() {
+                        let filename = format!("output/probes_{:0width$}.vtk", t, width = magnitude);
+                        if let Err(err) = self.export_probes_vtk(&filename) {
+                            terminal_utils::print_error(&format!("Error exporting probe geometry: {}", err));
+                            return;
+                        }
+                    }
+                    if !self.immersed_boundaries.is_empty() {
+                        let filename = format!("output/bodies_{:0width$}.vtk", t, width = magnitude);
+                        if let Err(err) = self.export_bodies_vtk(&filename) {
+                            terminal_utils::print_error(&format!("Error exporting body geometry: {}", err));
+                            return;
+                        }
+                    }
                 }
             }
 
@@ -113,21 +474,71 @@ impl LBM {
                     last_step = t;
                 }
             }
+
+            // Stop once the velocity field has settled
+            if self.steady_tolerance > 0.0 && self.time_step % self.steady_check_every == 0 {
+                match self.check_steady_state() {
+                    Ok(true) => break,
+                    Ok(false) => {}
+                    Err(err) => {
+                        terminal_utils::print_error(&format!("Error checking steady state: {}", err));
+                        return;
+                    }
+                }
+            }
+        }
+
+        // Wait for the steps still in flight
+        if let Err(err) = self.sync_step() {
+            terminal_utils::print_error(&format!("Error: {}", err));
+            return;
         }
 
         // Calculate total execution time
         let elapsed_time = start_time.elapsed();
         let elapsed_seconds = elapsed_time.as_secs_f64();
-        // Calculate average MLUps
-        let mlups = (self.N as f64 * self.time_steps as f64) / elapsed_seconds / 1_000_000.0;
+        // Calculate average MLUps over the steps actually run
+        let steps = self.time_step - start;
+        let mlups = (self.N as f64 * steps as f64) / elapsed_seconds / 1_000_000.0;
 
         // Read data from GPU to CPU
         if let Err(err) = self.read_from_gpu() {
             terminal_utils::print_error(&format!("Error reading data from GPU: {}", err));
             return;
         }
+        if let Err(err) = self.read_averages_from_gpu() {
+            terminal_utils::print_error(&format!("Error reading averages from GPU: {}", err));
+            return;
+        }
         pb.finish_with_message(format!("[{:.2} MLUPs final]", mlups));
+        if let Err(err) = self.finish_autosave() {
+            terminal_utils::print_error(&format!("Error writing checkpoint: {}", err));
+        }
 
-        terminal_utils::print_metrics(self.time_steps as u64, elapsed_seconds, mlups);
+        terminal_utils::print_metrics(steps as u64, elapsed_seconds, mlups);
+        if self.profiling {
+            self.print_kernel_profile(elapsed_seconds);
+        }
+        timing.end_step = self.time_step;
+        timing.wall_time = Some(elapsed_seconds);
+        timing.mlups = Some(mlups);
+        if let Err(err) = self.write_run_metadata("output/run.json", &timing) {
+            terminal_utils::print_error(&format!("Error writing run metadata: {}", err));
+        }
+        if self.strouhal_reference.is_some() {
+            self.print_strouhal_number();
+        }
+        if self.mach_interval > 0 {
+            terminal_utils::print_log(&format!("Maximum velocity: {:.4}", self.max_speed_seen));
+        }
+        if self.conservation_interval > 0 {
+            terminal_utils::print_log(&format!("Maximum relative mass drift: {:.3e}", self.max_mass_drift()));
+        }
+        if self.dispersion.interval > 0 {
+            let bin_width = self.dispersion.interval;
+            if let Err(err) = self.export_dispersion_summary("output/dispersion.csv", bin_width) {
+                terminal_utils::print_error(&format!("Error exporting dispersion statistics: {}", err));
+            }
+        }
     }
 }
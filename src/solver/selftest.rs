@@ -0,0 +1,144 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_FLUID;
+use crate::solver::parameters::TIMESTEP_ARG;
+use crate::solver::precision::PrecisionMode;
+use crate::solver::velocity_sets::VelocitySet;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+use std::f32::consts::PI;
+
+// Decaying 2D Taylor-Green vortex used as the reference problem
+const SELF_TEST_SIZE: usize = 32;
+const SELF_TEST_STEPS: usize = 200;
+const SELF_TEST_U0: f32 = 0.02;
+const SELF_TEST_VISCOSITY: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SelfTestReport {
+    pub precision: PrecisionMode,
+    pub energy_ratio: f32,          // E(t) / E(0) measured on the device
+    pub expected_energy_ratio: f32, // Analytic exp(-4 nu k^2 t)
+    pub velocity_error: f32,        // Relative L2 error against the analytic field
+    pub tolerance: f32,
+    pub passed: bool,
+}
+
+// Analytic Taylor-Green velocity at time t
+fn taylor_green_velocity(x: usize, y: usize, t: f32) -> (f32, f32) {
+    let k = 2.0 * PI / SELF_TEST_SIZE as f32;
+    let decay = (-2.0 * SELF_TEST_VISCOSITY * k * k * t).exp();
+    let (kx, ky) = (k * x as f32, k * y as f32);
+    (
+        -SELF_TEST_U0 * kx.cos() * ky.sin() * decay,
+        SELF_TEST_U0 * kx.sin() * ky.cos() * decay,
+    )
+}
+
+impl LBM {
+    pub fn set_self_test(&mut self, state: bool) {
+        self.self_test = state;
+    }
+
+    // Run a tiny Taylor-Green vortex on the selected device with the given
+    // precision and compare its velocity field and energy decay against the
+    // analytic solution, to catch broken drivers or devices before long runs
+    pub fn run_self_test(precision: PrecisionMode) -> Result<SelfTestReport, Box<dyn Error>> {
+        let size = SELF_TEST_SIZE;
+        let mut lbm = LBM::new_silent(
+            size,
+            size,
+            1,
+            VelocitySet::D2Q9,
+            SELF_TEST_VISCOSITY,
+            precision,
+        );
+        lbm.set_conditions(|lbm, x, y, _z, n| {
+            let (ux, uy) = taylor_green_velocity(x, y, 0.0);
+            let k = 2.0 * PI / size as f32;
+            lbm.flags[n] = FLAG_FLUID;
+            // Pressure field of the vortex, p = rho / 3
+            lbm.density[n] = 1.0
+                - 0.75
+                    * SELF_TEST_U0
+                    * SELF_TEST_U0
+                    * ((2.0 * k * x as f32).cos() + (2.0 * k * y as f32).cos());
+            lbm.velocity[n].x = ux;
+            lbm.velocity[n].y = uy;
+        });
+        let initial_energy: f32 = lbm.u.iter().map(|v| v * v).sum();
+
+        lbm.initialize();
+        let queue = lbm.queue.as_ref().ok_or("OpenCL queue is None")?;
+        unsafe {
+            lbm.equilibrium_kernel
+                .as_ref()
+                .ok_or("equilibrium_kernel not initialized")?
+                .enq()?;
+            let kernel = lbm
+                .stream_collide_kernel
+                .as_ref()
+                .ok_or("stream_collide_kernel not initialized")?;
+            for t in 0..SELF_TEST_STEPS {
+                kernel.set_arg(TIMESTEP_ARG, t as i32)?;
+                kernel.enq()?;
+            }
+        }
+        queue.finish()?;
+        lbm.time_step = SELF_TEST_STEPS;
+        lbm.read_from_gpu()?;
+
+        let t = SELF_TEST_STEPS as f32;
+        let mut error = 0.0f32;
+        let mut norm = 0.0f32;
+        for y in 0..size {
+            for x in 0..size {
+                let n = y * size + x;
+                let (ux, uy) = taylor_green_velocity(x, y, t);
+                error += (lbm.u[n * 3] - ux).powi(2) + (lbm.u[n * 3 + 1] - uy).powi(2);
+                norm += ux * ux + uy * uy;
+            }
+        }
+        let energy: f32 = lbm.u.iter().map(|v| v * v).sum();
+        let k = 2.0 * PI / size as f32;
+
+        let tolerance = match precision {
+            PrecisionMode::FP32 => 0.02,
+            PrecisionMode::FP16S => 0.05,
+            PrecisionMode::FP16C => 0.10,
+            PrecisionMode::BF16S => 0.10, // 8-bit mantissa
+        };
+        let velocity_error = (error / norm).sqrt();
+        Ok(SelfTestReport {
+            precision,
+            energy_ratio: energy / initial_energy,
+            expected_energy_ratio: (-4.0 * SELF_TEST_VISCOSITY * k * k * t).exp(),
+            velocity_error,
+            tolerance,
+            // NaN fails this comparison as well
+            passed: velocity_error < tolerance,
+        })
+    }
+
+    // Self-test for this simulation's precision mode with console report
+    pub fn check_device(&self) -> Result<(), Box<dyn Error>> {
+        let report = LBM::run_self_test(self.precision_mode)?;
+        let summary = format!(
+            "Self-test ({:?}): velocity error {:.3e} (tolerance {:.0e}), energy ratio {:.4} (expected {:.4})",
+            report.precision,
+            report.velocity_error,
+            report.tolerance,
+            report.energy_ratio,
+            report.expected_energy_ratio
+        );
+        if report.passed {
+            terminal_utils::print_success(&summary);
+            Ok(())
+        } else {
+            Err(format!("{} - device results are wrong.", summary).into())
+        }
+    }
+}
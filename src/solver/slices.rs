@@ -0,0 +1,158 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Plane output: only the selected 2D slices of density and velocity are read
+// back from the device (one rectangular read per array) and written as .vti
+// pieces in global coordinates, so they overlay full-domain files.
+
+use super::lbm::LBM;
+use crate::solver::output::OutputTarget;
+use crate::solver::transforms::Axis;
+use crate::solver::vti::{write_vti, ArrayData, OutputArray};
+
+use ocl::{Buffer, OclPrm};
+use std::error::Error;
+
+// Keep every `stride`-th cell of a box of `size` cells with `components`
+// values per cell. Returns the values and the size of the decimated box.
+fn decimate_box(
+    values: Vec<f32>,
+    components: usize,
+    size: [usize; 3],
+    stride: usize,
+) -> (Vec<f32>, [usize; 3]) {
+    if stride <= 1 {
+        return (values, size);
+    }
+    let kept = size.map(|n| n.div_ceil(stride));
+    let mut out = Vec::with_capacity(kept[0] * kept[1] * kept[2] * components);
+    for z in (0..size[2]).step_by(stride) {
+        for y in (0..size[1]).step_by(stride) {
+            for x in (0..size[0]).step_by(stride) {
+                let n = (z * size[1] + y) * size[0] + x;
+                out.extend_from_slice(&values[n * components..(n + 1) * components]);
+            }
+        }
+    }
+    (out, kept)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutputSlice {
+    pub axis: Axis,
+    pub index: usize, // Cell index along the axis
+}
+
+impl OutputSlice {
+    // Inclusive cell range covered by the plane
+    pub fn bounds(&self, dims: [usize; 3]) -> ([usize; 3], [usize; 3]) {
+        let a = self.axis.index();
+        let mut min = [0; 3];
+        let mut max = [dims[0] - 1, dims[1] - 1, dims[2] - 1];
+        min[a] = self.index;
+        max[a] = self.index;
+        (min, max)
+    }
+}
+
+impl LBM {
+    // Write the plane `index` normal to `axis` every output interval; the
+    // index is clamped to the domain. Returns the slice index.
+    pub fn add_output_slice(&mut self, axis: Axis, index: usize) -> usize {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let index = index.min(dims[axis.index()] - 1);
+        self.output_slices.push(OutputSlice { axis, index });
+        self.output_slices.len() - 1
+    }
+
+    // Read the cells [min, max] (inclusive) of a per-cell device buffer with
+    // `components` values per cell, x fastest
+    pub fn read_box<T: OclPrm>(
+        &self,
+        buffer: &Buffer<T>,
+        components: usize,
+        min: [usize; 3],
+        max: [usize; 3],
+    ) -> Result<Vec<T>, Box<dyn Error>> {
+        let size = [0, 1, 2].map(|d| max[d] - min[d] + 1);
+        let bytes = components * std::mem::size_of::<T>();
+        let mut data = vec![T::default(); size[0] * size[1] * size[2] * components];
+        buffer
+            .read(&mut data)
+            .rect(
+                [min[0] * bytes, min[1], min[2]],
+                [0, 0, 0],
+                [size[0] * bytes, size[1], size[2]],
+                self.Nx * bytes,
+                self.Nx * self.Ny * bytes,
+                size[0] * bytes,
+                size[0] * size[1] * bytes,
+            )
+            .enq()
+            .map_err(|e| format!("Failed to read buffer region: {}", e))?;
+        Ok(data)
+    }
+
+    // Density and velocity of the cells [min, max] straight from the device,
+    // keeping every `stride`-th cell. Returns the arrays and their grid size.
+    pub fn read_box_arrays(
+        &self,
+        min: [usize; 3],
+        max: [usize; 3],
+        stride: usize,
+    ) -> Result<(Vec<OutputArray>, [usize; 3]), Box<dyn Error>> {
+        let density = self
+            .density_buffer
+            .as_ref()
+            .ok_or("Density buffer is None")?;
+        let velocity = self.u_buffer.as_ref().ok_or("Velocity buffer is None")?;
+        let size = [0, 1, 2].map(|d| max[d] - min[d] + 1);
+        let (density, kept) = decimate_box(self.read_box(density, 1, min, max)?, 1, size, stride);
+        let (velocity, _) = decimate_box(self.read_box(velocity, 3, min, max)?, 3, size, stride);
+        let arrays = vec![
+            OutputArray {
+                name: "density",
+                components: 1,
+                data: ArrayData::Float32(density),
+            },
+            OutputArray {
+                name: "velocity",
+                components: 3,
+                data: ArrayData::Float32(velocity),
+            },
+        ];
+        Ok((arrays, kept))
+    }
+
+    // Write every output slice of step `step` as
+    // output/slice_<axis><index>_<step>.vti
+    pub fn export_output_slices(&self, step: usize, width: usize) -> Result<(), Box<dyn Error>> {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let stride = self.output_stride(OutputTarget::Slices);
+        for slice in &self.output_slices {
+            let (min, max) = slice.bounds(dims);
+            let (arrays, size) = self.read_box_arrays(min, max, stride)?;
+            let axis = ["x", "y", "z"][slice.axis.index()];
+            let filename = format!(
+                "output/slice_{}{}_{:0width$}.vti",
+                axis,
+                slice.index,
+                step,
+                width = width
+            );
+            write_vti(&filename, min, stride, size, &arrays)?;
+        }
+        Ok(())
+    }
+
+    // Whether the output step needs the whole domain on the host; slices and
+    // images read their planes separately
+    pub fn needs_full_readback(&self) -> bool {
+        self.output_csv
+            || self.output_vtk
+            || self.output_vti
+            || self.output_hdf5
+            || !self.probes.is_empty()
+            || (self.output_slices.is_empty() && self.image_outputs.is_empty())
+    }
+}
@@ -0,0 +1,222 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Transfers restricted to listed cells. Host-side models such as immersed
+// boundaries and two-way particle coupling only touch the fields and forces in
+// the stencils of a few points, so they gather those cells and update the
+// force field there instead of moving the full arrays across PCIe every step.
+
+use super::lbm::LBM;
+
+use ocl::{flags::MEM_READ_ONLY, flags::MEM_WRITE_ONLY, Buffer, Event, Kernel};
+use std::collections::HashMap;
+use std::error::Error;
+
+// Distinct cells in insertion order, each with a slot index
+#[derive(Debug, Clone, Default)]
+pub struct CellIndex {
+    pub cells: Vec<u32>,
+    slots: HashMap<u32, usize>,
+}
+
+impl CellIndex {
+    // Slot of cell `n`, added if new
+    pub fn insert(&mut self, n: usize) -> usize {
+        let cells = &mut self.cells;
+        *self.slots.entry(n as u32).or_insert_with(|| {
+            cells.push(n as u32);
+            cells.len() - 1
+        })
+    }
+
+    pub fn get(&self, n: u32) -> Option<usize> {
+        self.slots.get(&n).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+// Force density a model adds to the force field, per cell
+#[derive(Debug, Clone, Default)]
+pub struct CellForces {
+    pub index: CellIndex,
+    pub forces: Vec<[f32; 3]>,
+}
+
+impl CellForces {
+    pub fn add(&mut self, n: usize, force: [f32; 3]) {
+        let slot = self.index.insert(n);
+        if slot == self.forces.len() {
+            self.forces.push([0.0; 3]);
+        }
+        for (f, value) in self.forces[slot].iter_mut().zip(force) {
+            *f += value;
+        }
+    }
+
+    pub fn get(&self, n: u32) -> [f32; 3] {
+        self.index.get(n).map_or([0.0; 3], |slot| self.forces[slot])
+    }
+}
+
+impl LBM {
+    // Density and velocity of the listed cells, [rho, ux, uy, uz] per cell
+    pub fn read_cell_fields(&self, cells: &[u32]) -> Result<Vec<[f32; 4]>, Box<dyn Error>> {
+        if cells.is_empty() {
+            return Ok(vec![]);
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let cells_buffer = Buffer::<u32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_ONLY)
+            .len(cells.len())
+            .copy_host_slice(cells)
+            .build()
+            .map_err(|e| format!("Failed to build 'cells' buffer: {}", e))?;
+        let out_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_WRITE_ONLY)
+            .len(cells.len() * 4)
+            .build()
+            .map_err(|e| format!("Failed to build 'cell fields' buffer: {}", e))?;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("gather_cells_kernel")
+            .queue(queue.clone())
+            .global_work_size(cells.len())
+            .arg(
+                self.density_buffer
+                    .as_ref()
+                    .ok_or("Density buffer is None")?,
+            )
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(&cells_buffer)
+            .arg(cells.len() as i32)
+            .arg(&out_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'gather_cells_kernel': {}", e))?;
+        let mut launched = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut launched)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'gather_cells_kernel': {}", e))?;
+        }
+
+        let mut out = vec![0.0f32; cells.len() * 4];
+        out_buffer
+            .read(&mut out)
+            .ewait(&launched)
+            .enq()
+            .map_err(|e| format!("Failed to read 'cell fields' buffer: {}", e))?;
+        Ok(out
+            .chunks_exact(4)
+            .map(|v| [v[0], v[1], v[2], v[3]])
+            .collect())
+    }
+
+    // Swap a model's contribution to the force field from `old` to `new`, on
+    // the host copy and on the device, touching only the cells of either
+    pub fn replace_cell_forces(
+        &mut self,
+        old: &CellForces,
+        new: &CellForces,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut delta = new.clone();
+        for (&n, force) in old.index.cells.iter().zip(&old.forces) {
+            delta.add(n as usize, force.map(|f| -f));
+        }
+        if delta.index.is_empty() {
+            return Ok(());
+        }
+        if self.force_field.len() != self.N * 3 {
+            self.force_field = vec![0.0; self.N * 3];
+        }
+        for (&n, force) in delta.index.cells.iter().zip(&delta.forces) {
+            let n = n as usize;
+            for (f, value) in self.force_field[n * 3..n * 3 + 3].iter_mut().zip(force) {
+                *f += value;
+            }
+        }
+
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let count = delta.index.len();
+        let cells_buffer = Buffer::<u32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_ONLY)
+            .len(count)
+            .copy_host_slice(&delta.index.cells)
+            .build()
+            .map_err(|e| format!("Failed to build 'cells' buffer: {}", e))?;
+        let values: Vec<f32> = delta.forces.iter().flatten().copied().collect();
+        let values_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_READ_ONLY)
+            .len(values.len())
+            .copy_host_slice(&values)
+            .build()
+            .map_err(|e| format!("Failed to build 'cell forces' buffer: {}", e))?;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("add_cell_forces_kernel")
+            .queue(queue.clone())
+            .global_work_size(count)
+            .arg(self.force_buffer.as_ref().ok_or("Force buffer is None")?)
+            .arg(&cells_buffer)
+            .arg(count as i32)
+            .arg(&values_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'add_cell_forces_kernel': {}", e))?;
+        unsafe {
+            kernel
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'add_cell_forces_kernel': {}", e))?;
+        }
+        // The next step must see the forces
+        self.enqueue_barrier()
+    }
+
+    // Host force field without the contributions of the immersed boundaries
+    // and particles, which rebuild them at their first update after a restart
+    pub fn static_force_field(&self) -> Vec<f32> {
+        let mut field = self.force_field.clone();
+        if field.len() != self.N * 3 {
+            return field;
+        }
+        for model in [&self.ibm_forcing, &self.tracers.reaction] {
+            for (&n, force) in model.index.cells.iter().zip(&model.forces) {
+                let n = n as usize;
+                for (f, value) in field[n * 3..n * 3 + 3].iter_mut().zip(force) {
+                    *f -= value;
+                }
+            }
+        }
+        field
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CellForces;
+
+    #[test]
+    fn cell_forces_accumulate_per_cell() {
+        let mut forces = CellForces::default();
+        forces.add(7, [1.0, 0.0, 0.0]);
+        forces.add(3, [0.0, 2.0, 0.0]);
+        forces.add(7, [0.5, 0.0, -1.0]);
+        assert_eq!(forces.index.cells, vec![7, 3]);
+        assert_eq!(forces.get(7), [1.5, 0.0, -1.0]);
+        assert_eq!(forces.get(3), [0.0, 2.0, 0.0]);
+        assert_eq!(forces.get(5), [0.0; 3]);
+    }
+}
@@ -0,0 +1,165 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Spectral analysis of probe histories: the dominant frequency of a velocity
+// component (e.g. the cross-flow velocity in a wake) and the Strouhal number
+// St = f L / U derived from it.
+
+use super::lbm::LBM;
+use crate::utils::terminal_utils;
+
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy)]
+pub struct StrouhalReference {
+    pub probe: usize,
+    pub component: usize,  // Velocity component analysed (0 = x, 1 = y, 2 = z)
+    pub length: f32,       // Reference length in lattice units
+    pub velocity: f32,     // Reference velocity in lattice units
+    pub after_step: usize, // Samples before this step are treated as transient
+}
+
+// In-place radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f64], im: &mut [f64]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f64;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (s, c) = (angle * k as f64).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let tr = re[b] * c - im[b] * s;
+                let ti = re[b] * s + im[b] * c;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// Frequency (cycles per unit of `dt`) of the strongest non-zero peak of a
+// uniformly sampled signal. The mean is removed and a Hann window applied;
+// the peak is refined by parabolic interpolation. None for fewer than 4
+// samples or a constant signal.
+pub fn dominant_frequency(signal: &[f32], dt: f32) -> Option<f32> {
+    let n = signal.len();
+    if n < 4 || dt <= 0.0 {
+        return None;
+    }
+    let mean = signal.iter().map(|&v| v as f64).sum::<f64>() / n as f64;
+    let size = n.next_power_of_two() * 2; // Zero padding for a finer grid
+    let mut re = vec![0.0f64; size];
+    let mut im = vec![0.0f64; size];
+    for (i, &v) in signal.iter().enumerate() {
+        let window = 0.5 - 0.5 * (2.0 * PI * i as f64 / (n - 1) as f64).cos();
+        re[i] = (v as f64 - mean) * window;
+    }
+    fft(&mut re, &mut im);
+
+    let power: Vec<f64> = (0..size / 2)
+        .map(|k| re[k] * re[k] + im[k] * im[k])
+        .collect();
+    let (peak, &max) = power
+        .iter()
+        .enumerate()
+        .skip(1)
+        .max_by(|a, b| a.1.total_cmp(b.1))?;
+    if max <= 0.0 {
+        return None;
+    }
+    let mut bin = peak as f64;
+    if peak + 1 < power.len() {
+        let (a, b, c) = (power[peak - 1], power[peak], power[peak + 1]);
+        let denom = a - 2.0 * b + c;
+        if denom != 0.0 {
+            bin += 0.5 * (a - c) / denom;
+        }
+    }
+    Some((bin / (size as f64 * dt as f64)) as f32)
+}
+
+impl LBM {
+    // Dominant frequency (per time step) of velocity `component` at `probe`,
+    // using the samples from `after_step` on
+    pub fn probe_frequency(
+        &self,
+        probe: usize,
+        component: usize,
+        after_step: usize,
+    ) -> Option<f32> {
+        let samples: Vec<_> = self
+            .probes
+            .get(probe)?
+            .samples
+            .iter()
+            .filter(|s| s.step >= after_step)
+            .collect();
+        if samples.len() < 2 {
+            return None;
+        }
+        let dt = (samples[1].step - samples[0].step) as f32;
+        let signal: Vec<f32> = samples
+            .iter()
+            .map(|s| s.velocity[component.min(2)])
+            .collect();
+        dominant_frequency(&signal, dt)
+    }
+
+    // Report the shedding frequency and Strouhal number of a probe signal at
+    // the end of run(); `after_step` skips the start-up transient
+    pub fn set_strouhal_reference(
+        &mut self,
+        probe: usize,
+        component: usize,
+        length: f32,
+        velocity: f32,
+        after_step: usize,
+    ) {
+        self.strouhal_reference = Some(StrouhalReference {
+            probe,
+            component,
+            length,
+            velocity,
+            after_step,
+        });
+    }
+
+    // Strouhal number f L / U of the configured probe signal
+    pub fn strouhal_number(&self) -> Option<(f32, f32)> {
+        let reference = self.strouhal_reference?;
+        let frequency =
+            self.probe_frequency(reference.probe, reference.component, reference.after_step)?;
+        Some((frequency, frequency * reference.length / reference.velocity))
+    }
+
+    pub fn print_strouhal_number(&self) {
+        match self.strouhal_number() {
+            Some((frequency, strouhal)) => terminal_utils::print_log(&format!(
+                "Dominant frequency: {:.6e} per step ({:.1} steps), Strouhal number: {:.4}",
+                frequency,
+                1.0 / frequency,
+                strouhal
+            )),
+            None => terminal_utils::print_warning(
+                "Strouhal number unavailable: not enough probe samples after the transient.",
+            ),
+        }
+    }
+}
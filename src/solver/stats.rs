@@ -0,0 +1,86 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::{FLAG_EQ, FLAG_FLUID, FLAG_SOLID};
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+
+pub type BoundingBox = ([usize; 3], [usize; 3]); // Inclusive (min, max)
+
+#[derive(Debug, Clone, Default)]
+pub struct DomainStats {
+    pub total_cells: usize,
+    pub fluid_cells: usize,
+    pub solid_cells: usize,
+    pub eq_cells: usize,
+    pub porosity: f32, // Non-solid cells / total cells
+    // Number of solid faces exposed to non-solid cells (lattice units: length in
+    // 2D, area in 3D). A staircase estimate, so it overestimates curved surfaces.
+    pub wetted_area: f32,
+    pub solid_bounds: Option<BoundingBox>,
+    pub body_bounds: Vec<(String, Option<BoundingBox>)>,
+}
+
+fn grow(bounds: &mut Option<BoundingBox>, p: [usize; 3]) {
+    match bounds {
+        Some((min, max)) => {
+            for d in 0..3 {
+                min[d] = min[d].min(p[d]);
+                max[d] = max[d].max(p[d]);
+            }
+        }
+        None => *bounds = Some((p, p)),
+    }
+}
+
+impl LBM {
+    // Cell counts, porosity, wetted surface and solid extents of the current flags
+    pub fn domain_stats(&self) -> DomainStats {
+        let mut stats = DomainStats {
+            total_cells: self.N,
+            body_bounds: self.body_names.iter().map(|b| (b.clone(), None)).collect(),
+            ..Default::default()
+        };
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let has_bodies = self.body_ids.len() == self.N;
+
+        for n in 0..self.N {
+            match self.flags[n] {
+                FLAG_FLUID => stats.fluid_cells += 1,
+                FLAG_EQ => stats.eq_cells += 1,
+                _ => {}
+            }
+            if self.flags[n] != FLAG_SOLID {
+                continue;
+            }
+            stats.solid_cells += 1;
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            grow(&mut stats.solid_bounds, [x, y, z]);
+            if has_bodies && self.body_ids[n] > 0 {
+                grow(&mut stats.body_bounds[self.body_ids[n] as usize - 1].1, [x, y, z]);
+            }
+
+            // Axis-aligned faces, periodic wrap like the kernel; skip collapsed axes
+            for (d, &size) in dims.iter().enumerate() {
+                if size == 1 {
+                    continue;
+                }
+                for step in [1, size - 1] {
+                    let mut p = [x, y, z];
+                    p[d] = (p[d] + step) % size;
+                    let m = n_from_xyz(&p[0], &p[1], &p[2], &self.Nx, &self.Ny);
+                    if self.flags[m] != FLAG_SOLID {
+                        stats.wetted_area += 1.0;
+                    }
+                }
+            }
+        }
+
+        stats.porosity = if self.N > 0 {
+            (self.N - stats.solid_cells) as f32 / self.N as f32
+        } else {
+            0.0
+        };
+        stats
+    }
+}
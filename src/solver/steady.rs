@@ -0,0 +1,75 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Steady-state detection: run() compares the velocity field every few steps
+// with the previous check and stops once the relative change is small enough.
+
+use super::lbm::LBM;
+use crate::utils::terminal_utils;
+
+use ocl::{flags::MEM_READ_WRITE, Buffer};
+use std::error::Error;
+
+impl LBM {
+    // Run until the relative change of the velocity field between two checks
+    // `check_every` steps apart drops below `tolerance`. Returns whether the
+    // run converged (false when it was stopped by an error).
+    pub fn run_until_steady(&mut self, tolerance: f32, check_every: usize) -> bool {
+        self.steady_tolerance = tolerance;
+        self.steady_check_every = check_every.max(1);
+        self.steady_previous = None;
+        self.steady_residual = None;
+        self.run(usize::MAX);
+        self.steady_tolerance = 0.0;
+
+        let converged = self.steady_residual.is_some_and(|r| r < tolerance);
+        if converged {
+            terminal_utils::print_log(&format!(
+                "Converged at step {} (relative change {:.3e})",
+                self.time_step,
+                self.steady_residual.unwrap_or(0.0)
+            ));
+        }
+        converged
+    }
+
+    // Relative L2 change ||u - u_prev|| / ||u|| since the previous check;
+    // returns true once it is below the tolerance
+    // Both norms are reduced on the device, where the previous field is kept.
+    pub fn check_steady_state(&mut self) -> Result<bool, Box<dyn Error>> {
+        let velocity = self.u_buffer.as_ref().ok_or("Velocity buffer is None")?;
+        let converged = match self.steady_previous.as_ref() {
+            Some(previous) => {
+                let change = self.field_l2_norm(velocity, 3, Some(previous))?;
+                let norm = self.field_l2_norm(velocity, 3, None)?;
+                // A field at rest that stays at rest is steady too
+                let residual = if norm > 0.0 {
+                    (change / norm) as f32
+                } else {
+                    change as f32
+                };
+                self.steady_residual = Some(residual);
+                residual < self.steady_tolerance
+            }
+            None => false,
+        };
+
+        if self.steady_previous.is_none() {
+            let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+            let buffer = Buffer::<f32>::builder()
+                .queue(queue.clone())
+                .flags(MEM_READ_WRITE)
+                .len(self.N * 3)
+                .build()
+                .map_err(|e| format!("Failed to build 'previous velocity' buffer: {}", e))?;
+            self.steady_previous = Some(buffer);
+        }
+        let velocity = self.u_buffer.as_ref().ok_or("Velocity buffer is None")?;
+        velocity
+            .copy(self.steady_previous.as_ref().unwrap(), None, None)
+            .enq()
+            .map_err(|e| format!("Failed to copy velocity buffer: {}", e))?;
+        self.enqueue_barrier()?;
+        Ok(converged)
+    }
+}
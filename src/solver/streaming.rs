@@ -0,0 +1,78 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// In-place streaming (AA pattern). A single DDF buffer is bound as both f and
+// f_new, halving the population memory; the kernels alternate between a local
+// step (even) and a neighbour step (odd), see the slot helpers in
+// kernel_stream_collide.cl. The raw buffer layout depends on the step parity,
+// so checkpoints record the streaming mode and resume only in that mode.
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+use crate::solver::velocity_sets::opposite;
+use crate::utils::terminal_utils;
+
+impl LBM {
+    // Stream in place with one population buffer instead of two
+    pub fn set_in_place_streaming(&mut self, enabled: bool) {
+        if self.checkpoint_f.is_some() && enabled != self.in_place_streaming {
+            terminal_utils::print_warning(
+                "The loaded checkpoint fixes the streaming mode; keeping the mode it was written with.",
+            );
+            return;
+        }
+        self.in_place_streaming = enabled;
+    }
+
+    // Symmetry planes reflect populations while pulling, which the AA pattern
+    // does not do; such runs keep the two-lattice scheme
+    pub fn check_streaming_mode(&mut self) {
+        if self.in_place_streaming && self.symmetry_planes != 0 && self.checkpoint_f.is_some() {
+            // The checkpointed populations are in the in-place layout
+            terminal_utils::print_warning(
+                "The loaded checkpoint uses in-place streaming, which does not support symmetry planes; ignoring them.",
+            );
+            self.symmetry_planes = 0;
+        }
+        if self.in_place_streaming && self.symmetry_planes != 0 {
+            terminal_utils::print_warning(
+                "In-place streaming does not support symmetry planes; using two population buffers.",
+            );
+            self.in_place_streaming = false;
+        }
+    }
+
+    // Rearrange populations read from the in-place buffer into the two-lattice
+    // layout, i.e. the post-collision populations q * N + n of each cell
+    pub fn pull_layout(&self, f: Vec<f32>) -> Vec<f32> {
+        if !self.in_place_streaming {
+            return f;
+        }
+        let c = self.model.vectors();
+        let mut pulled = vec![0.0f32; f.len()];
+        for n in 0..self.N {
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+            for q in 0..self.Q {
+                let own = opposite(q) * self.N + n;
+                let slot = if self.time_step % 2 == 1 {
+                    // Last step was local: stored reversed in the cell
+                    own
+                } else {
+                    // Last step pushed to the neighbours, or bounced back
+                    let xn = (x as i32 + c[q][0]).rem_euclid(self.Nx as i32) as usize;
+                    let yn = (y as i32 + c[q][1]).rem_euclid(self.Ny as i32) as usize;
+                    let zn = (z as i32 + c[q][2]).rem_euclid(self.Nz as i32) as usize;
+                    let m = n_from_xyz(&xn, &yn, &zn, &self.Nx, &self.Ny);
+                    if self.flags[m] == FLAG_SOLID {
+                        own
+                    } else {
+                        q * self.N + m
+                    }
+                };
+                pulled[q * self.N + n] = f[slot];
+            }
+        }
+        pulled
+    }
+}
@@ -0,0 +1,200 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// Streamlines of the current host velocity field and pathlines through the
+// stored snapshots of a run, written as legacy VTK POLYDATA polylines.
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::post::{list_snapshots, Snapshot};
+use crate::solver::transforms::n_from_xyz;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+pub type Polyline = Vec<([f32; 3], f32)>; // (point, speed)
+
+// Trilinear velocity at `p`; None outside the domain or next to a solid cell
+fn sample_velocity(
+    u: &[f32],
+    flags: Option<&[u8]>,
+    dims: (usize, usize, usize),
+    p: [f32; 3],
+) -> Option<[f32; 3]> {
+    let (nx, ny, nz) = dims;
+    let size = [nx, ny, nz];
+    for d in 0..3 {
+        if p[d] < 0.0 || p[d] > (size[d] - 1) as f32 {
+            return None;
+        }
+    }
+    let base = p.map(|c| c.floor() as usize);
+    let s = [0, 1, 2].map(|d| p[d] - base[d] as f32);
+    let mut v = [0.0f32; 3];
+    for k in 0..8 {
+        let offset = [k & 1, (k >> 1) & 1, (k >> 2) & 1];
+        let c = [0, 1, 2].map(|d| (base[d] + offset[d]).min(size[d] - 1));
+        let n = n_from_xyz(&c[0], &c[1], &c[2], &nx, &ny);
+        if flags.is_some_and(|f| f[n] == FLAG_SOLID) {
+            return None;
+        }
+        let weight: f32 = (0..3)
+            .map(|d| if offset[d] == 1 { s[d] } else { 1.0 - s[d] })
+            .product();
+        for d in 0..3 {
+            v[d] += weight * u[n * 3 + d];
+        }
+    }
+    Some(v)
+}
+
+fn speed(v: [f32; 3]) -> f32 {
+    (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt()
+}
+
+fn advance(p: [f32; 3], v: [f32; 3], h: f32) -> [f32; 3] {
+    [0, 1, 2].map(|d| p[d] + h * v[d])
+}
+
+fn write_polylines(filename: &str, title: &str, lines: &[Polyline]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(filename)?);
+    let points: usize = lines.iter().map(|l| l.len()).sum();
+    writeln!(writer, "# vtk DataFile Version 3.0")?;
+    writeln!(writer, "{}", title)?;
+    writeln!(writer, "ASCII")?;
+    writeln!(writer, "DATASET POLYDATA")?;
+    writeln!(writer, "POINTS {} float", points)?;
+    for (p, _) in lines.iter().flatten() {
+        writeln!(writer, "{:.6} {:.6} {:.6}", p[0], p[1], p[2])?;
+    }
+    writeln!(writer, "LINES {} {}", lines.len(), points + lines.len())?;
+    let mut next = 0;
+    for line in lines {
+        write!(writer, "{}", line.len())?;
+        for i in next..next + line.len() {
+            write!(writer, " {}", i)?;
+        }
+        writeln!(writer)?;
+        next += line.len();
+    }
+    writeln!(writer, "POINT_DATA {}", points)?;
+    writeln!(writer, "SCALARS speed float 1")?;
+    writeln!(writer, "LOOKUP_TABLE default")?;
+    for (_, s) in lines.iter().flatten() {
+        writeln!(writer, "{:.6e}", s)?;
+    }
+    writeln!(writer, "CELL_DATA {}", lines.len())?;
+    writeln!(writer, "SCALARS line_id int 1")?;
+    writeln!(writer, "LOOKUP_TABLE default")?;
+    for i in 0..lines.len() {
+        writeln!(writer, "{}", i)?;
+    }
+    writer.flush()
+}
+
+impl LBM {
+    // RK4 streamline through the host velocity field from `seed`, traced both
+    // downstream and upstream with step `h` (cells) for up to `max_steps` each
+    pub fn trace_streamline(&self, seed: [f32; 3], h: f32, max_steps: usize) -> Polyline {
+        let dims = (self.Nx, self.Ny, self.Nz);
+        let flags = (self.flags.len() == self.N).then_some(&self.flags[..]);
+        let field = |p| sample_velocity(&self.u, flags, dims, p);
+        let integrate = |direction: f32| {
+            let mut line = Vec::new();
+            let mut p = seed;
+            for _ in 0..max_steps {
+                let Some(k1) = field(p) else { break };
+                if speed(k1) < 1e-8 {
+                    break;
+                }
+                line.push((p, speed(k1)));
+                let hs = direction * h / speed(k1);
+                let Some(k2) = field(advance(p, k1, 0.5 * hs)) else {
+                    break;
+                };
+                let Some(k3) = field(advance(p, k2, 0.5 * hs)) else {
+                    break;
+                };
+                let Some(k4) = field(advance(p, k3, hs)) else {
+                    break;
+                };
+                p = [0, 1, 2]
+                    .map(|d| p[d] + hs / 6.0 * (k1[d] + 2.0 * k2[d] + 2.0 * k3[d] + k4[d]));
+            }
+            line
+        };
+        let mut line: Polyline = integrate(-1.0).into_iter().skip(1).rev().collect();
+        line.extend(integrate(1.0));
+        line
+    }
+
+    // Streamlines from every seed point of the current host fields (call after
+    // read_from_gpu during a run)
+    pub fn export_streamlines_vtk(
+        &self,
+        seeds: &[[f32; 3]],
+        filename: &str,
+        h: f32,
+        max_steps: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let lines: Vec<Polyline> = seeds
+            .iter()
+            .map(|&s| self.trace_streamline(s, h, max_steps))
+            .filter(|l| l.len() > 1)
+            .collect();
+        write_polylines(filename, "CappuSim streamlines", &lines)?;
+        Ok(())
+    }
+
+    // Pathlines of particles released at `seeds` at the first snapshot of
+    // `run_dir`, advected with the velocity linearly interpolated in time
+    // between consecutive snapshots (one lattice step per integration step)
+    pub fn export_pathlines_vtk(
+        run_dir: &str,
+        seeds: &[[f32; 3]],
+        filename: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let files = list_snapshots(run_dir)?;
+        if files.len() < 2 {
+            return Err(format!("Pathlines need at least two snapshots in {}.", run_dir).into());
+        }
+        let mut lines: Vec<Polyline> = seeds.iter().map(|&s| vec![(s, 0.0)]).collect();
+        let mut alive = vec![true; seeds.len()];
+        let mut previous = Snapshot::load(files[0].0, &files[0].1)?;
+        for (step, path) in &files[1..] {
+            let next = Snapshot::load(*step, path)?;
+            if next.dims != previous.dims {
+                return Err(format!("{} has a different grid size.", path.display()).into());
+            }
+            let span = (next.step - previous.step).max(1);
+            let field = |p: [f32; 3], t: f32| {
+                let a = sample_velocity(&previous.u, None, previous.dims, p)?;
+                let b = sample_velocity(&next.u, None, next.dims, p)?;
+                let s = t / span as f32;
+                Some([0, 1, 2].map(|d| (1.0 - s) * a[d] + s * b[d]))
+            };
+            for (line, alive) in lines.iter_mut().zip(alive.iter_mut()) {
+                for k in 0..span {
+                    if !*alive {
+                        break;
+                    }
+                    let (p, _) = *line.last().unwrap();
+                    let t = k as f32;
+                    // Midpoint rule in space and time
+                    let next_point = field(p, t)
+                        .and_then(|k1| field(advance(p, k1, 0.5), t + 0.5))
+                        .map(|k2| (advance(p, k2, 1.0), speed(k2)));
+                    match next_point {
+                        Some(point) => line.push(point),
+                        None => *alive = false,
+                    }
+                }
+            }
+            previous = next;
+        }
+        let lines: Vec<Polyline> = lines.into_iter().filter(|l| l.len() > 1).collect();
+        write_polylines(filename, "CappuSim pathlines", &lines)?;
+        Ok(())
+    }
+}
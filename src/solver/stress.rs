@@ -0,0 +1,143 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+use crate::solver::velocity_sets::opposite;
+
+use ocl::{flags::MEM_WRITE_ONLY, Buffer, Event, Kernel};
+use std::error::Error;
+
+impl LBM {
+    pub fn set_output_stress(&mut self, state: bool) {
+        self.output_stress = state;
+    }
+
+    // Deviatoric stress tensor from the non-equilibrium moments of f:
+    //   sigma_ab = -(1 - omega / 2) * sum_q (f_q - feq_q) c_qa c_qb
+    // Components per cell are [xx, yy, zz, xy, xz, yz]; solid cells are zero.
+    pub fn calculate_stress_tensor(&self) -> Result<Vec<[f32; 6]>, Box<dyn Error>> {
+        if self.remapped_stress.len() == self.N {
+            return Ok(self.remapped_stress.clone());
+        }
+        let f = self.read_f_from_gpu()?;
+        let (c, w) = (self.model.vectors(), self.model.weights());
+        let prefactor = -(1.0 - 0.5 * self.omega);
+
+        let mut stress = vec![[0.0f32; 6]; self.N];
+        let mut f_pop = vec![0.0f32; self.Q];
+
+        for (n, sigma) in stress.iter_mut().enumerate() {
+            if self.flags[n] == FLAG_SOLID {
+                continue;
+            }
+            let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+
+            // Pull-stream the post-collision populations exactly like the kernel
+            let mut rho = 0.0f32;
+            let (mut ux, mut uy, mut uz) = (0.0f32, 0.0f32, 0.0f32);
+            for q in 0..self.Q {
+                let xp = (x as i32 - c[q][0]).rem_euclid(self.Nx as i32) as usize;
+                let yp = (y as i32 - c[q][1]).rem_euclid(self.Ny as i32) as usize;
+                let zp = (z as i32 - c[q][2]).rem_euclid(self.Nz as i32) as usize;
+                let np = n_from_xyz(&xp, &yp, &zp, &self.Nx, &self.Ny);
+                f_pop[q] = if self.flags[np] == FLAG_SOLID {
+                    f[opposite(q) * self.N + n]
+                } else {
+                    f[q * self.N + np]
+                };
+                rho += f_pop[q];
+                ux += c[q][0] as f32 * f_pop[q];
+                uy += c[q][1] as f32 * f_pop[q];
+                uz += c[q][2] as f32 * f_pop[q];
+            }
+            if rho <= 1e-10 {
+                continue;
+            }
+            ux /= rho;
+            uy /= rho;
+            uz /= rho;
+            let u2 = ux * ux + uy * uy + uz * uz;
+
+            let mut pi = [0.0f32; 6];
+            for q in 0..self.Q {
+                let (cx, cy, cz) = (c[q][0] as f32, c[q][1] as f32, c[q][2] as f32);
+                let cu = cx * ux + cy * uy + cz * uz;
+                let feq = rho * w[q] * (1.0 + 3.0 * cu + 4.5 * cu * cu - 1.5 * u2);
+                let fneq = f_pop[q] - feq;
+                pi[0] += fneq * cx * cx;
+                pi[1] += fneq * cy * cy;
+                pi[2] += fneq * cz * cz;
+                pi[3] += fneq * cx * cy;
+                pi[4] += fneq * cx * cz;
+                pi[5] += fneq * cy * cz;
+            }
+            for (s, p) in sigma.iter_mut().zip(pi.iter()) {
+                *s = prefactor * p;
+            }
+        }
+
+        Ok(stress)
+    }
+
+    pub fn set_output_wall_shear(&mut self, state: bool) {
+        self.output_wall_shear = state;
+    }
+
+    // Wall shear stress magnitude on fluid cells next to solids: the tangential
+    // part of the traction sigma . n, where the wall normal n points from the
+    // solid neighbours (along the lattice links) into the fluid. Evaluated on
+    // the device for the wall layer only; zero elsewhere.
+    pub fn calculate_wall_shear_stress(&self) -> Result<Vec<f32>, Box<dyn Error>> {
+        if self.remapped_wall_shear.len() == self.N {
+            return Ok(self.remapped_wall_shear.clone());
+        }
+        let mut shear = vec![0.0f32; self.N];
+        if self.wall_layer.is_empty() {
+            return Ok(shear);
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        let out_buffer = Buffer::<f32>::builder()
+            .queue(queue.clone())
+            .flags(MEM_WRITE_ONLY)
+            .len(self.wall_layer.len())
+            .build()
+            .map_err(|e| format!("Failed to build 'wall shear' buffer: {}", e))?;
+
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("wall_shear_kernel")
+            .queue(queue.clone())
+            .global_work_size(self.wall_layer.len())
+            .arg(self.f_buffer.as_ref().ok_or("f buffer is None")?)
+            .arg(self.f_new_buffer.as_ref().ok_or("f_new buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(self.wall_layer_buffer.as_ref().ok_or("Wall layer buffer is None")?)
+            .arg(self.wall_layer.len() as i32)
+            .arg(self.time_step as i32)
+            .arg(-(1.0 - 0.5 * self.omega))
+            .arg(&out_buffer)
+            .build()
+            .map_err(|e| format!("Failed to build 'wall_shear_kernel': {}", e))?;
+        let mut launched = Event::empty();
+        unsafe {
+            kernel
+                .cmd()
+                .enew(&mut launched)
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'wall_shear_kernel': {}", e))?;
+        }
+
+        let mut out = vec![0.0f32; self.wall_layer.len()];
+        out_buffer
+            .read(&mut out)
+            .ewait(&launched)
+            .enq()
+            .map_err(|e| format!("Failed to read 'wall shear' buffer: {}", e))?;
+        for (&n, tau) in self.wall_layer.iter().zip(out) {
+            shear[n as usize] = tau;
+        }
+        Ok(shear)
+    }
+}
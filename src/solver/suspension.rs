@@ -0,0 +1,214 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_FLUID;
+use crate::solver::rigid::cross;
+
+use std::error::Error;
+
+// A small freely moving sphere of a suspension
+#[derive(Debug, Clone)]
+pub struct Particle {
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+    pub radius: f32,
+    pub mass: f32,
+    pub volume: f32,
+    pub inertia: f32,
+    pub cells: Vec<u32>,
+}
+
+impl Particle {
+    fn velocity_at(&self, p: [f32; 3]) -> [f32; 3] {
+        let r = [0, 1, 2].map(|d| p[d] - self.position[d]);
+        let w = cross(self.angular_velocity, r);
+        [0, 1, 2].map(|d| self.velocity[d] + w[d])
+    }
+}
+
+// Many resolved spheres sharing one body ID. Loads of all particles are
+// evaluated in a single momentum-exchange launch and collisions are handled
+// with a short-range repulsive force (Glowinski et al. 2001).
+pub struct Suspension {
+    pub name: String,
+    pub body_id: u16,
+    pub particles: Vec<Particle>,
+    pub gravity: [f32; 3],
+    pub stiffness: f32,   // Repulsion at full overlap of the safety zone
+    pub range: f32,       // Gap below which the repulsion acts (lattice units)
+    pub walls: [bool; 3], // Domain faces along x, y, z act as walls for collisions
+}
+
+impl Suspension {
+    // Pairwise and wall repulsion forces on every particle
+    fn collision_forces(&self, dims: [usize; 3]) -> Vec<[f32; 3]> {
+        let mut forces = vec![[0.0f32; 3]; self.particles.len()];
+        let repulsion = |gap: f32| {
+            let overlap = ((self.range - gap) / self.range).max(0.0);
+            self.stiffness * overlap * overlap
+        };
+        for i in 0..self.particles.len() {
+            let a = &self.particles[i];
+            for j in i + 1..self.particles.len() {
+                let b = &self.particles[j];
+                let d = [0, 1, 2].map(|k| a.position[k] - b.position[k]);
+                let distance = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                let gap = distance - a.radius - b.radius;
+                if gap >= self.range || distance <= f32::EPSILON {
+                    continue;
+                }
+                let magnitude = repulsion(gap);
+                for k in 0..3 {
+                    forces[i][k] += magnitude * d[k] / distance;
+                    forces[j][k] -= magnitude * d[k] / distance;
+                }
+            }
+            for k in 0..3 {
+                if !self.walls[k] {
+                    continue;
+                }
+                // Wall cells occupy the outermost layer of the domain
+                let lower = a.position[k] - 0.5 - a.radius;
+                let upper = dims[k] as f32 - 1.5 - a.position[k] - a.radius;
+                forces[i][k] += repulsion(lower) - repulsion(upper);
+            }
+        }
+        forces
+    }
+}
+
+impl LBM {
+    // Create an empty suspension; particles are added with add_particle.
+    // Call after set_conditions. Returns the suspension index.
+    pub fn add_suspension(&mut self, name: &str, gravity: [f32; 3]) -> usize {
+        let body_id = self.register_body(name);
+        self.enable_moving_walls();
+        self.suspensions.push(Suspension {
+            name: name.to_string(),
+            body_id,
+            particles: vec![],
+            gravity,
+            stiffness: 0.01,
+            range: 2.0,
+            walls: [false; 3],
+        });
+        self.suspensions.len() - 1
+    }
+
+    // Insert a sphere of relative density `density` at rest. Returns its index
+    // within the suspension.
+    pub fn add_particle(
+        &mut self,
+        suspension: usize,
+        position: [f32; 3],
+        radius: f32,
+        density: f32,
+    ) -> usize {
+        let volume = 4.0 / 3.0 * std::f32::consts::PI * radius.powi(3);
+        let mass = density * volume;
+        let mut particle = Particle {
+            position,
+            velocity: [0.0; 3],
+            angular_velocity: [0.0; 3],
+            radius,
+            mass,
+            volume,
+            inertia: 0.4 * mass * radius * radius,
+            cells: vec![],
+        };
+        let body_id = self.suspensions[suspension].body_id;
+        let (covered, _) = self.rasterize_solid(
+            body_id,
+            &[],
+            (position, radius),
+            &|p| sphere_contains(position, radius, p),
+            &|_| [0.0; 3],
+        );
+        particle.cells = covered;
+        let particles = &mut self.suspensions[suspension].particles;
+        particles.push(particle);
+        particles.len() - 1
+    }
+
+    // Advance all suspensions by one step: batched momentum exchange, collision
+    // forces, explicit integration, re-rasterization and refill
+    pub fn update_suspensions(&mut self) -> Result<(), Box<dyn Error>> {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let mut suspensions = std::mem::take(&mut self.suspensions);
+        let mut fresh = Vec::new();
+        let mut result = Ok(());
+        for suspension in suspensions.iter_mut() {
+            let cells: Vec<u32> = suspension
+                .particles
+                .iter()
+                .flat_map(|p| p.cells.iter().copied())
+                .collect();
+            // Torques about the origin, shifted to each particle center below
+            let loads = match self.momentum_exchange_cells(&cells, [0.0; 3]) {
+                Ok(loads) => loads,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            };
+            let collisions = suspension.collision_forces(dims);
+
+            let mut offset = 0;
+            for (particle, collision) in suspension.particles.iter_mut().zip(collisions) {
+                let mut force = [0.0f32; 3];
+                let mut torque = [0.0f32; 3];
+                for cell in loads[offset * 6..(offset + particle.cells.len()) * 6].chunks_exact(6) {
+                    for d in 0..3 {
+                        force[d] += cell[d];
+                        torque[d] += cell[3 + d];
+                    }
+                }
+                offset += particle.cells.len();
+                let shift = cross(particle.position, force);
+                let net_mass = particle.mass - particle.volume;
+                for d in 0..3 {
+                    torque[d] -= shift[d];
+                    particle.velocity[d] +=
+                        (force[d] + collision[d] + net_mass * suspension.gravity[d])
+                            / particle.mass;
+                    particle.angular_velocity[d] += torque[d] / particle.inertia;
+                    particle.position[d] += particle.velocity[d];
+                }
+            }
+
+            // Release all cells first so particles never free each other's cells
+            for particle in suspension.particles.iter_mut() {
+                for &n in &particle.cells {
+                    self.flags[n as usize] = FLAG_FLUID;
+                    self.body_ids[n as usize] = 0;
+                }
+                fresh.append(&mut particle.cells);
+            }
+            for particle in suspension.particles.iter_mut() {
+                let (center, radius) = (particle.position, particle.radius);
+                let (covered, _) = self.rasterize_solid(
+                    suspension.body_id,
+                    &[],
+                    (center, radius),
+                    &|p| sphere_contains(center, radius, p),
+                    &|p| particle.velocity_at(p),
+                );
+                particle.cells = covered;
+            }
+        }
+        self.suspensions = suspensions;
+        result?;
+
+        // Released cells not reclaimed by any particle
+        fresh.retain(|&n| self.flags[n as usize] == FLAG_FLUID);
+        self.write_u_to_gpu()?;
+        self.refill_cells(&fresh)
+    }
+}
+
+fn sphere_contains(center: [f32; 3], radius: f32, p: [f32; 3]) -> bool {
+    let d2: f32 = (0..3).map(|d| (p[d] - center[d]).powi(2)).sum();
+    d2 <= radius * radius
+}
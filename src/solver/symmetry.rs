@@ -0,0 +1,37 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::transforms::Axis;
+
+const AXES: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+impl LBM {
+    // Make the lower or upper domain face normal to `axis` a symmetry
+    // (free-slip) plane, so only half of a symmetric problem is simulated.
+    // Must be called before run(), as the kernel is specialized for it.
+    pub fn set_symmetry_plane(&mut self, axis: Axis, upper: bool) {
+        self.symmetry_planes |= 1 << (2 * axis.index() + upper as usize);
+    }
+
+    // Write CSV/VTK output for the full domain reconstructed by mirroring
+    // the simulated part across every symmetry plane
+    pub fn set_mirror_output(&mut self, state: bool) {
+        self.mirror_output = state;
+    }
+
+    // Full-domain copy of the current fields, or None without symmetry planes
+    pub fn mirrored_output(&self) -> Option<LBM> {
+        let mut view: Option<LBM> = None;
+        for axis in AXES {
+            for upper in [false, true] {
+                if self.symmetry_planes & (1 << (2 * axis.index() + upper as usize)) == 0 {
+                    continue;
+                }
+                let source = view.as_ref().unwrap_or(self);
+                view = Some(source.mirrored_about(axis, upper));
+            }
+        }
+        view
+    }
+}
@@ -0,0 +1,167 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::transforms::n_from_xyz;
+use crate::utils::npy;
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+
+// A grayscale heightmap with values normalized to [0, 1]
+#[derive(Debug, Clone)]
+pub struct Heightmap {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>, // Row-major, row 0 first
+}
+
+impl Heightmap {
+    // Load a binary (P5) or ASCII (P2) PGM image, or a 2D float32 .npy array.
+    // .npy values are used as-is; image gray levels are scaled to [0, 1].
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        if npy::is_npy_file(path) {
+            let (shape, values) = npy::read_npy::<f32>(path)?;
+            if shape.len() != 2 {
+                return Err(format!("Heightmap must be a 2D array, got shape {:?}.", shape).into());
+            }
+            return Ok(Heightmap {
+                width: shape[1],
+                height: shape[0],
+                values,
+            });
+        }
+        Self::load_pgm(path)
+    }
+
+    fn load_pgm(path: &str) -> Result<Self, Box<dyn Error>> {
+        let bytes = std::fs::read(path)?;
+        let mut pos = 0;
+
+        // Header tokens (magic, width, height, maxval), skipping '#' comments
+        let mut next_token = |bytes: &[u8]| -> Result<String, Box<dyn Error>> {
+            loop {
+                while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                    pos += 1;
+                }
+                if pos < bytes.len() && bytes[pos] == b'#' {
+                    while pos < bytes.len() && bytes[pos] != b'\n' {
+                        pos += 1;
+                    }
+                    continue;
+                }
+                break;
+            }
+            let start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if start == pos {
+                return Err("Unexpected end of PGM header.".into());
+            }
+            Ok(String::from_utf8_lossy(&bytes[start..pos]).into_owned())
+        };
+
+        let magic = next_token(&bytes)?;
+        let width: usize = next_token(&bytes)?.parse()?;
+        let height: usize = next_token(&bytes)?.parse()?;
+        let maxval: u32 = next_token(&bytes)?.parse()?;
+        if maxval == 0 || maxval > 65535 {
+            return Err(format!("Invalid PGM maxval {}.", maxval).into());
+        }
+
+        let count = width * height;
+        let values: Vec<f32> = match magic.as_str() {
+            "P2" => {
+                let mut values = Vec::with_capacity(count);
+                for _ in 0..count {
+                    values.push(next_token(&bytes)?.parse::<u32>()? as f32 / maxval as f32);
+                }
+                values
+            }
+            "P5" => {
+                // A single whitespace byte separates the header from the pixels
+                let data = bytes.get(pos + 1..).ok_or("PGM pixel data is truncated.")?;
+                let sample = if maxval < 256 { 1 } else { 2 };
+                if data.len() < count * sample {
+                    return Err("PGM pixel data is truncated.".into());
+                }
+                data.chunks_exact(sample)
+                    .take(count)
+                    .map(|p| {
+                        // 16-bit PGM samples are big-endian
+                        let v = if sample == 1 { p[0] as u32 } else { ((p[0] as u32) << 8) | p[1] as u32 };
+                        v as f32 / maxval as f32
+                    })
+                    .collect()
+            }
+            _ => return Err(format!("{} is not a PGM (P2/P5) image.", path).into()),
+        };
+
+        Ok(Heightmap { width, height, values })
+    }
+
+    // Bilinear sample at normalized coordinates (u, v) in [0, 1]
+    pub fn sample(&self, u: f32, v: f32) -> f32 {
+        let fx = u.clamp(0.0, 1.0) * (self.width - 1) as f32;
+        let fy = v.clamp(0.0, 1.0) * (self.height - 1) as f32;
+        let (x0, y0) = (fx.floor() as usize, fy.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+        let (tx, ty) = (fx - x0 as f32, fy - y0 as f32);
+        let at = |x: usize, y: usize| self.values[y * self.width + x];
+        let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+        let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+impl LBM {
+    // Mark every cell below the terrain surface as solid. The heightmap is
+    // stretched over the x-y plane and its values map to a surface height of
+    // `base_height + value * vertical_scale` cells along z. In 2D domains
+    // (Nz == 1) the first heightmap row is a profile along x, with the
+    // surface height measured along y.
+    pub fn import_terrain(
+        &mut self,
+        path: &str,
+        vertical_scale: f32,
+        base_height: f32,
+    ) -> Result<(), Box<dyn Error>> {
+        let map = Heightmap::load(path)?;
+        if map.width < 2 || map.height < 1 {
+            return Err("Heightmap is too small.".into());
+        }
+        let is_2d = self.Nz == 1;
+        let mut solid = 0usize;
+
+        for y in 0..self.Ny {
+            for x in 0..self.Nx {
+                let u = x as f32 / (self.Nx.max(2) - 1) as f32;
+                let v = y as f32 / (self.Ny.max(2) - 1) as f32;
+                if is_2d {
+                    let surface = base_height + map.sample(u, 0.0) * vertical_scale;
+                    if (y as f32) < surface {
+                        self.flags[n_from_xyz(&x, &y, &0, &self.Nx, &self.Ny)] = FLAG_SOLID;
+                        solid += 1;
+                    }
+                    continue;
+                }
+                let surface = base_height + map.sample(u, v) * vertical_scale;
+                for z in 0..self.Nz {
+                    if (z as f32) >= surface {
+                        break;
+                    }
+                    self.flags[n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny)] = FLAG_SOLID;
+                    solid += 1;
+                }
+            }
+        }
+
+        terminal_utils::print_log(&format!(
+            "Imported terrain {}x{} from {} ({} solid cells)",
+            map.width, map.height, path, solid
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::averages::AVERAGE_FIELDS;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n, Axis};
+use crate::utils::terminal_utils;
+
+use std::error::Error;
+
+// A derived field for a remapped copy; a failure only drops it from the copy
+fn carried<T>(field: Option<Result<T, Box<dyn Error>>>, name: &str) -> Option<T> {
+    match field? {
+        Ok(values) => Some(values),
+        Err(err) => {
+            terminal_utils::print_warning(&format!("{} is not written for this output view: {}", name, err));
+            None
+        }
+    }
+}
+
+impl LBM {
+    // New simulation of size (new_nx, new_ny, new_nz) whose cells are copied
+    // from this one through `source`, which maps a destination cell to a
+    // source cell and the velocity components that must change sign.
+    // Only lattice fields (flags, density, velocity, time averages) and solver
+    // parameters are carried over; immersed boundaries and outputs must be set
+    // up again. Stress and wall shear need the populations, which the copy has
+    // no device buffer for, so they are computed here and remapped.
+    fn remapped<F>(&self, new_nx: usize, new_ny: usize, new_nz: usize, source: F) -> LBM
+    where
+        F: Fn(usize, usize, usize) -> ((usize, usize, usize), [bool; 3]),
+    {
+        let mut lbm = LBM::new_silent(
+            new_nx,
+            new_ny,
+            new_nz,
+            self.model,
+            self.viscosity,
+            self.precision_mode,
+        );
+        if let Some(force) = &self.constant_force {
+            lbm.set_constant_force(force.clone());
+        }
+        lbm.output_interval = self.output_interval;
+        lbm.output_csv = self.output_csv;
+        lbm.output_vtk = self.output_vtk;
+        lbm.output_vti = self.output_vti;
+        lbm.output_hdf5 = self.output_hdf5;
+        lbm.output_flags = self.output_flags;
+        lbm.output_dissipation = self.output_dissipation;
+        let stress = carried(self.output_stress.then(|| self.calculate_stress_tensor()), "Stress");
+        let wall_shear = carried(
+            self.output_wall_shear.then(|| self.calculate_wall_shear_stress()),
+            "Wall shear",
+        );
+        lbm.output_stress = stress.is_some();
+        lbm.output_wall_shear = wall_shear.is_some();
+        if stress.is_some() {
+            lbm.remapped_stress = vec![[0.0; 6]; lbm.N];
+        }
+        if wall_shear.is_some() {
+            lbm.remapped_wall_shear = vec![0.0; lbm.N];
+        }
+        lbm.output_arrays = self.output_arrays.clone();
+        lbm.output_spacing = self.output_spacing;
+
+        lbm.body_names.clone_from(&self.body_names);
+        let has_bodies = self.body_ids.len() == self.N;
+        if has_bodies {
+            lbm.body_ids = vec![0; lbm.N];
+        }
+
+        // Before set_conditions the Velocity array is authoritative, afterwards u is
+        let has_velocity = self.velocity.len() == self.N;
+        if !has_velocity {
+            lbm.velocity = vec![];
+        }
+        let has_averages = self.has_averages();
+        if has_averages {
+            lbm.averages = vec![0.0; lbm.N * AVERAGE_FIELDS];
+        }
+
+        for n in 0..lbm.N {
+            let (x, y, z) = xyz_from_n(&n, &lbm.Nx, &lbm.Ny);
+            let ((sx, sy, sz), flip) = source(x, y, z);
+            let s = n_from_xyz(&sx, &sy, &sz, &self.Nx, &self.Ny);
+            let sign = |d: usize| if flip[d] { -1.0 } else { 1.0 };
+
+            lbm.flags[n] = self.flags[s];
+            if has_bodies {
+                lbm.body_ids[n] = self.body_ids[s];
+            }
+            lbm.density[n] = self.density[s];
+            if let Some(stress) = &stress {
+                // Off-diagonal components change sign with one flip
+                let signs = [1.0, 1.0, 1.0, sign(0) * sign(1), sign(0) * sign(2), sign(1) * sign(2)];
+                for (k, sign) in signs.iter().enumerate() {
+                    lbm.remapped_stress[n][k] = sign * stress[s][k];
+                }
+            }
+            if let Some(wall_shear) = &wall_shear {
+                lbm.remapped_wall_shear[n] = wall_shear[s];
+            }
+            for d in 0..3 {
+                lbm.u[n * 3 + d] = sign(d) * self.u[s * 3 + d];
+            }
+            if has_velocity {
+                lbm.velocity[n].x = sign(0) * self.velocity[s].x;
+                lbm.velocity[n].y = sign(1) * self.velocity[s].y;
+                lbm.velocity[n].z = sign(2) * self.velocity[s].z;
+            }
+            if has_averages {
+                // Mean velocity and the cross products change sign with one flip
+                let signs = [
+                    1.0,
+                    sign(0),
+                    sign(1),
+                    sign(2),
+                    1.0,
+                    1.0,
+                    1.0,
+                    sign(0) * sign(1),
+                    sign(0) * sign(2),
+                    sign(1) * sign(2),
+                ];
+                for (k, sign) in signs.iter().enumerate() {
+                    lbm.averages[n * AVERAGE_FIELDS + k] =
+                        sign * self.averages[s * AVERAGE_FIELDS + k];
+                }
+            }
+        }
+        lbm
+    }
+
+    // Repeat the current setup tiles.0 x tiles.1 x tiles.2 times (cylinder/pin arrays)
+    pub fn tiled(&self, tiles: (usize, usize, usize)) -> LBM {
+        let (tx, ty, tz) = (tiles.0.max(1), tiles.1.max(1), tiles.2.max(1));
+        self.remapped(self.Nx * tx, self.Ny * ty, self.Nz * tz, |x, y, z| {
+            ((x % self.Nx, y % self.Ny, z % self.Nz), [false; 3])
+        })
+    }
+
+    // Every `stride`-th cell along each axis, for quick-look output of large
+    // domains; None when there is nothing to drop
+    pub fn decimated(&self, stride: usize) -> Option<LBM> {
+        if stride <= 1 {
+            return None;
+        }
+        let size = |n: usize| n.div_ceil(stride);
+        let mut lbm = self.remapped(size(self.Nx), size(self.Ny), size(self.Nz), |x, y, z| {
+            ((x * stride, y * stride, z * stride), [false; 3])
+        });
+        lbm.output_spacing = self.output_spacing * stride;
+        Some(lbm)
+    }
+
+    // Double the domain along `axis` by appending its mirror image; the velocity
+    // component normal to the mirror plane changes sign in the reflected half
+    pub fn mirrored(&self, axis: Axis) -> LBM {
+        self.mirrored_about(axis, true)
+    }
+
+    // Double the domain along `axis` by reflecting it about its upper (`upper`)
+    // or lower boundary, i.e. the image is appended after or prepended before it
+    pub fn mirrored_about(&self, axis: Axis, upper: bool) -> LBM {
+        let dims = [self.Nx, self.Ny, self.Nz];
+        let a = axis.index();
+        let mut new_dims = dims;
+        new_dims[a] *= 2;
+        self.remapped(new_dims[0], new_dims[1], new_dims[2], |x, y, z| {
+            let mut p = [x, y, z];
+            let mut flip = [false; 3];
+            if upper && p[a] >= dims[a] {
+                p[a] = 2 * dims[a] - 1 - p[a];
+                flip[a] = true;
+            } else if !upper && p[a] < dims[a] {
+                p[a] = dims[a] - 1 - p[a];
+                flip[a] = true;
+            } else if !upper {
+                p[a] -= dims[a];
+            }
+            ((p[0], p[1], p[2]), flip)
+        })
+    }
+}
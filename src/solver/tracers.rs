@@ -0,0 +1,286 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::injection::ParticleSource;
+use crate::solver::sparse::CellForces;
+
+use ocl::{flags::MEM_READ_WRITE, Buffer, Kernel};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Drag law of inertial particles
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DragModel {
+    #[default]
+    Stokes,
+    SchillerNaumann, // Finite particle Reynolds number correction
+}
+
+// Lagrangian particles advected on the device: passive tracers (diameter 0)
+// or one-way coupled inertial point particles. The host copy is only current
+// after read_tracers_from_gpu.
+#[derive(Default)]
+pub struct Tracers {
+    pub positions: Vec<[f32; 3]>,
+    pub velocities: Vec<[f32; 3]>,
+    pub travel: Vec<[f32; 3]>,   // Unwrapped displacement since release
+    pub released_at: Vec<usize>, // Release step
+    pub diameters: Vec<f32>,     // Lattice units, 0 for passive tracers
+    pub densities: Vec<f32>,     // Relative to the fluid
+    pub ids: Vec<u32>,           // Stable identifiers for output
+    pub next_id: u32,
+    pub gravity: [f32; 3],
+    pub drag: DragModel,
+    pub two_way: bool,      // Particle drag is returned to the fluid
+    pub reaction: CellForces, // Force density last added to lbm.force_field
+    pub open_faces: u8,     // Bit d: domain faces normal to axis d remove particles
+    pub sources: Vec<ParticleSource>,
+    pub exited: usize,
+    pub deposition: Vec<usize>, // Deposited particles per body ID (0: unnamed walls)
+    pub exit_times: Vec<usize>, // Age of every particle that left the domain
+    pub buffer: Option<Buffer<f32>>, // Positions
+    pub velocity_buffer: Option<Buffer<f32>>,
+    pub travel_buffer: Option<Buffer<f32>>,
+    pub property_buffer: Option<Buffer<f32>>,
+    pub status_buffer: Option<Buffer<i32>>,
+    pub removed_buffer: Option<Buffer<i32>>,
+}
+
+impl Tracers {
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    // Drop the device copies so the next advection re-uploads the host state
+    pub fn invalidate(&mut self) {
+        self.buffer = None;
+        self.velocity_buffer = None;
+        self.travel_buffer = None;
+        self.property_buffer = None;
+        self.status_buffer = None;
+        self.removed_buffer = None;
+    }
+
+    // Append particles on the host; returns their IDs
+    pub fn push(
+        &mut self,
+        positions: &[[f32; 3]],
+        velocity: [f32; 3],
+        diameter: f32,
+        density: f32,
+        step: usize,
+    ) -> Vec<u32> {
+        let first = self.next_id;
+        let ids: Vec<u32> = (first..first + positions.len() as u32).collect();
+        self.positions.extend_from_slice(positions);
+        self.velocities.extend(positions.iter().map(|_| velocity));
+        self.travel.extend(positions.iter().map(|_| [0.0; 3]));
+        self.released_at.extend(positions.iter().map(|_| step));
+        self.diameters
+            .extend(positions.iter().map(|_| diameter.max(0.0)));
+        self.densities.extend(positions.iter().map(|_| density));
+        self.ids.extend_from_slice(&ids);
+        self.next_id += positions.len() as u32;
+        ids
+    }
+
+    // Keep only the particles whose entry in `keep` is true
+    pub fn retain(&mut self, keep: &[bool]) {
+        fn filter<T>(values: &mut Vec<T>, keep: &[bool]) {
+            let mut flags = keep.iter();
+            values.retain(|_| *flags.next().unwrap());
+        }
+        filter(&mut self.positions, keep);
+        filter(&mut self.velocities, keep);
+        filter(&mut self.travel, keep);
+        filter(&mut self.released_at, keep);
+        filter(&mut self.diameters, keep);
+        filter(&mut self.densities, keep);
+        filter(&mut self.ids, keep);
+    }
+}
+
+fn flat(values: &[[f32; 3]]) -> Vec<f32> {
+    values.iter().flatten().copied().collect()
+}
+
+impl LBM {
+    // Release passive tracers at the given lattice positions. Returns their IDs.
+    pub fn add_tracers(&mut self, positions: &[[f32; 3]]) -> Result<Vec<u32>, Box<dyn Error>> {
+        self.add_particles(positions, 0.0, 1.0)
+    }
+
+    // Release inertial point particles of `diameter` (lattice units) and
+    // relative `density` at rest. Returns their IDs.
+    pub fn add_particles(
+        &mut self,
+        positions: &[[f32; 3]],
+        diameter: f32,
+        density: f32,
+    ) -> Result<Vec<u32>, Box<dyn Error>> {
+        // Pick up the device state before the buffers are rebuilt
+        self.read_tracers_from_gpu()?;
+        self.tracers.invalidate();
+        let step = self.time_step;
+        Ok(self
+            .tracers
+            .push(positions, [0.0; 3], diameter, density, step))
+    }
+
+    // Evenly spaced tracers on the segment from `a` to `b`, e.g. a dye rake
+    pub fn add_tracer_line(
+        &mut self,
+        a: [f32; 3],
+        b: [f32; 3],
+        count: usize,
+    ) -> Result<Vec<u32>, Box<dyn Error>> {
+        let count = count.max(2);
+        let points: Vec<[f32; 3]> = (0..count)
+            .map(|i| {
+                let s = i as f32 / (count - 1) as f32;
+                [0, 1, 2].map(|d| a[d] + s * (b[d] - a[d]))
+            })
+            .collect();
+        self.add_tracers(&points)
+    }
+
+    // Gravity and drag law applied to inertial particles
+    pub fn set_particle_physics(&mut self, gravity: [f32; 3], drag: DragModel) {
+        self.tracers.gravity = gravity;
+        self.tracers.drag = drag;
+    }
+
+    // Advance all tracers with the latest velocity field
+    pub fn advect_tracers(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.tracers.is_empty() {
+            return Ok(());
+        }
+        let queue = self.queue.as_ref().ok_or("OpenCL queue is None")?;
+        if self.tracers.buffer.is_none() {
+            let build = |values: &[f32], name: &str| {
+                Buffer::<f32>::builder()
+                    .queue(queue.clone())
+                    .flags(MEM_READ_WRITE)
+                    .len(values.len())
+                    .copy_host_slice(values)
+                    .build()
+                    .map_err(|e| format!("Failed to build '{}' buffer: {}", name, e))
+            };
+            let tracers = &self.tracers;
+            let properties: Vec<f32> = tracers
+                .diameters
+                .iter()
+                .zip(tracers.densities.iter())
+                .flat_map(|(&d, &rho)| [d, rho])
+                .collect();
+            self.tracers.buffer = Some(build(&flat(&tracers.positions), "tracers")?);
+            self.tracers.velocity_buffer =
+                Some(build(&flat(&self.tracers.velocities), "tracer velocities")?);
+            self.tracers.travel_buffer = Some(build(&flat(&self.tracers.travel), "tracer travel")?);
+            self.tracers.property_buffer = Some(build(&properties, "tracer properties")?);
+            let build_int = |len: usize, name: &str| {
+                Buffer::<i32>::builder()
+                    .queue(queue.clone())
+                    .flags(MEM_READ_WRITE)
+                    .len(len)
+                    .fill_val(0i32)
+                    .build()
+                    .map_err(|e| format!("Failed to build '{}' buffer: {}", name, e))
+            };
+            self.tracers.status_buffer = Some(build_int(self.tracers.len(), "tracer status")?);
+            self.tracers.removed_buffer = Some(build_int(1, "removed tracers")?);
+        }
+
+        let [gx, gy, gz] = self.tracers.gravity;
+        let kernel = Kernel::builder()
+            .program(self.program.as_ref().ok_or("OpenCL program is None")?)
+            .name("advect_tracers_kernel")
+            .queue(queue.clone())
+            .global_work_size(self.tracers.len())
+            .arg(self.u_buffer.as_ref().ok_or("Velocity buffer is None")?)
+            .arg(self.flags_buffer.as_ref().ok_or("Flags buffer is None")?)
+            .arg(self.tracers.buffer.as_ref().unwrap())
+            .arg(self.tracers.velocity_buffer.as_ref().unwrap())
+            .arg(self.tracers.travel_buffer.as_ref().unwrap())
+            .arg(self.tracers.property_buffer.as_ref().unwrap())
+            .arg(self.tracers.len() as i32)
+            .arg(self.viscosity)
+            .arg(gx)
+            .arg(gy)
+            .arg(gz)
+            .arg((self.tracers.drag == DragModel::SchillerNaumann) as i32)
+            .arg(self.tracers.status_buffer.as_ref().unwrap())
+            .arg(self.tracers.open_faces as i32)
+            .arg(self.tracers.removed_buffer.as_ref().unwrap())
+            .build()
+            .map_err(|e| format!("Failed to build 'advect_tracers_kernel': {}", e))?;
+        unsafe {
+            kernel
+                .enq()
+                .map_err(|e| format!("Failed to enqueue 'advect_tracers_kernel': {}", e))?;
+        }
+        self.enqueue_barrier()
+    }
+
+    // Copy the device tracer positions, velocities and travel to the host
+    pub fn read_tracers_from_gpu(&mut self) -> Result<(), Box<dyn Error>> {
+        let (Some(buffer), Some(velocity_buffer), Some(travel_buffer)) = (
+            self.tracers.buffer.as_ref(),
+            self.tracers.velocity_buffer.as_ref(),
+            self.tracers.travel_buffer.as_ref(),
+        ) else {
+            return Ok(());
+        };
+        let mut positions = vec![0.0f32; self.tracers.len() * 3];
+        buffer
+            .read(&mut positions)
+            .enq()
+            .map_err(|e| format!("Failed to read 'tracers' buffer: {}", e))?;
+        let mut velocities = vec![0.0f32; self.tracers.len() * 3];
+        velocity_buffer
+            .read(&mut velocities)
+            .enq()
+            .map_err(|e| format!("Failed to read 'tracer velocities' buffer: {}", e))?;
+        let mut travel = vec![0.0f32; self.tracers.len() * 3];
+        travel_buffer
+            .read(&mut travel)
+            .enq()
+            .map_err(|e| format!("Failed to read 'tracer travel' buffer: {}", e))?;
+        for k in 0..self.tracers.len() {
+            let r = k * 3..k * 3 + 3;
+            self.tracers.positions[k] = [
+                positions[r.start],
+                positions[r.start + 1],
+                positions[r.start + 2],
+            ];
+            self.tracers.velocities[k] = [
+                velocities[r.start],
+                velocities[r.start + 1],
+                velocities[r.start + 2],
+            ];
+            self.tracers.travel[k] = [travel[r.start], travel[r.start + 1], travel[r.start + 2]];
+        }
+        Ok(())
+    }
+
+    pub fn export_tracers_csv(&self, filename: &str) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writeln!(writer, "id,x,y,z,vx,vy,vz,diameter")?;
+        let t = &self.tracers;
+        for k in 0..t.len() {
+            let (p, v) = (t.positions[k], t.velocities[k]);
+            writeln!(
+                writer,
+                "{},{:.6},{:.6},{:.6},{:.6e},{:.6e},{:.6e},{}",
+                t.ids[k], p[0], p[1], p[2], v[0], v[1], v[2], t.diameters[k]
+            )?;
+        }
+        writer.flush()
+    }
+}
@@ -2,15 +2,47 @@
 #![allow(clippy::upper_case_acronyms)]
 
 use super::lbm::LBM;
+use serde::{Deserialize, Serialize};
 
 impl LBM {
     pub fn velocity_to_u(&self) -> Vec<f32> {
         self.velocity
             .iter()
-            .flat_map(|v| vec![v.x, v.y, v.z])
+            .flat_map(|v| [v.x, v.y, v.z])
             .collect()
     }
 
+    // Linear index of cell (x, y, z); x varies fastest, then y, then z
+    pub fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        assert!(
+            x < self.Nx && y < self.Ny && z < self.Nz,
+            "Cell ({}, {}, {}) is outside the {}x{}x{} lattice",
+            x, y, z, self.Nx, self.Ny, self.Nz
+        );
+        n_from_xyz(&x, &y, &z, &self.Nx, &self.Ny)
+    }
+
+    // Host copies of the macroscopic fields, valid after read_from_gpu()
+    pub fn density_at(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.density[self.index(x, y, z)]
+    }
+
+    pub fn density_at_mut(&mut self, x: usize, y: usize, z: usize) -> &mut f32 {
+        let n = self.index(x, y, z);
+        &mut self.density[n]
+    }
+
+    pub fn velocity_at(&self, x: usize, y: usize, z: usize) -> [f32; 3] {
+        let n = self.index(x, y, z);
+        [self.u[n * 3], self.u[n * 3 + 1], self.u[n * 3 + 2]]
+    }
+
+    // Changes reach the device with write_u_to_gpu()
+    pub fn velocity_at_mut(&mut self, x: usize, y: usize, z: usize) -> &mut [f32; 3] {
+        let n = self.index(x, y, z);
+        (&mut self.u[n * 3..n * 3 + 3]).try_into().unwrap()
+    }
+
     // pub fn u_to_velocity(&mut self, flat_velocity_data: Vec<f32>) {
     //     self.velocity = flat_velocity_data
     //         .chunks(3)
@@ -32,3 +64,23 @@ pub fn xyz_from_n(n: &usize, Nx: &usize, Ny: &usize) -> (usize, usize, usize) {
     let z = *n / (Ny * Nx);
     (x, y, z)
 }
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    // Component index (0, 1, 2) of the axis
+    pub fn index(&self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+}
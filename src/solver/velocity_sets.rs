@@ -0,0 +1,156 @@
+// src/solver/velocity_sets.rs
+// Host-side copy of the lattice velocity sets defined in kernel_velocity_sets.cl.
+// The ordering of directions must match the OpenCL tables exactly.
+
+use serde::{Deserialize, Serialize};
+
+pub const D2Q9_C: [[i32; 3]; 9] = [
+    [0, 0, 0], [1, 0, 0], [-1, 0, 0], [0, 1, 0], [0, -1, 0],
+    [1, 1, 0], [-1, -1, 0], [1, -1, 0], [-1, 1, 0],
+];
+pub const D2Q9_W: [f32; 9] = [
+    4.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0,
+    1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0,
+];
+
+pub const D3Q7_C: [[i32; 3]; 7] = [
+    [0, 0, 0], [1, 0, 0], [-1, 0, 0], [0, 1, 0],
+    [0, -1, 0], [0, 0, 1], [0, 0, -1],
+];
+pub const D3Q7_W: [f32; 7] = [
+    1.0 / 4.0, 1.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0, 1.0 / 8.0,
+];
+
+pub const D3Q15_C: [[i32; 3]; 15] = [
+    [0, 0, 0], [1, 0, 0], [-1, 0, 0], [0, 1, 0],
+    [0, -1, 0], [0, 0, 1], [0, 0, -1], [1, 1, 1],
+    [-1, -1, -1], [1, 1, -1], [-1, -1, 1], [1, -1, 1],
+    [-1, 1, -1], [-1, 1, 1], [1, -1, -1],
+];
+pub const D3Q15_W: [f32; 15] = [
+    2.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0, 1.0 / 9.0,
+    1.0 / 72.0, 1.0 / 72.0, 1.0 / 72.0, 1.0 / 72.0,
+    1.0 / 72.0, 1.0 / 72.0, 1.0 / 72.0, 1.0 / 72.0,
+];
+
+pub const D3Q19_C: [[i32; 3]; 19] = [
+    [0, 0, 0], [1, 0, 0], [-1, 0, 0], [0, 1, 0],
+    [0, -1, 0], [0, 0, 1], [0, 0, -1], [1, 1, 0],
+    [-1, -1, 0], [1, 0, 1], [-1, 0, -1], [0, 1, 1],
+    [0, -1, -1], [1, -1, 0], [-1, 1, 0], [1, 0, -1],
+    [-1, 0, 1], [0, 1, -1], [0, -1, 1],
+];
+pub const D3Q19_W: [f32; 19] = [
+    1.0 / 3.0,
+    1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0, 1.0 / 18.0,
+    1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0,
+    1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0, 1.0 / 36.0,
+];
+
+pub const D3Q27_C: [[i32; 3]; 27] = [
+    [0, 0, 0], [1, 0, 0], [-1, 0, 0], [0, 1, 0],
+    [0, -1, 0], [0, 0, 1], [0, 0, -1], [1, 1, 0],
+    [-1, -1, 0], [1, 0, 1], [-1, 0, -1], [0, 1, 1],
+    [0, -1, -1], [1, -1, 0], [-1, 1, 0], [1, 0, -1],
+    [-1, 0, 1], [0, 1, -1], [0, -1, 1], [1, 1, 1],
+    [-1, -1, -1], [1, 1, -1], [-1, -1, 1], [1, -1, 1],
+    [-1, 1, -1], [-1, 1, 1], [1, -1, -1],
+];
+pub const D3Q27_W: [f32; 27] = [
+    8.0 / 27.0,
+    2.0 / 27.0, 2.0 / 27.0, 2.0 / 27.0, 2.0 / 27.0, 2.0 / 27.0, 2.0 / 27.0,
+    1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0,
+    1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0, 1.0 / 54.0,
+    1.0 / 216.0, 1.0 / 216.0, 1.0 / 216.0, 1.0 / 216.0,
+    1.0 / 216.0, 1.0 / 216.0, 1.0 / 216.0, 1.0 / 216.0,
+];
+
+// Velocity set of a lattice
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VelocitySet {
+    D2Q9,
+    D3Q7,
+    D3Q15,
+    D3Q19,
+    D3Q27,
+}
+
+impl VelocitySet {
+    // Name used in the kernel defines, checkpoints and metadata
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VelocitySet::D2Q9 => "D2Q9",
+            VelocitySet::D3Q7 => "D3Q7",
+            VelocitySet::D3Q15 => "D3Q15",
+            VelocitySet::D3Q19 => "D3Q19",
+            VelocitySet::D3Q27 => "D3Q27",
+        }
+    }
+
+    // Number of discrete velocities
+    pub fn q(&self) -> usize {
+        self.weights().len()
+    }
+
+    // Number of spatial dimensions
+    pub fn dimensions(&self) -> usize {
+        match self {
+            VelocitySet::D2Q9 => 2,
+            _ => 3,
+        }
+    }
+
+    // Lattice vectors, in the same order as the OpenCL tables
+    pub fn vectors(&self) -> &'static [[i32; 3]] {
+        match self {
+            VelocitySet::D2Q9 => &D2Q9_C,
+            VelocitySet::D3Q7 => &D3Q7_C,
+            VelocitySet::D3Q15 => &D3Q15_C,
+            VelocitySet::D3Q19 => &D3Q19_C,
+            VelocitySet::D3Q27 => &D3Q27_C,
+        }
+    }
+
+    // Lattice weights, in the same order as the OpenCL tables
+    pub fn weights(&self) -> &'static [f32] {
+        match self {
+            VelocitySet::D2Q9 => &D2Q9_W,
+            VelocitySet::D3Q7 => &D3Q7_W,
+            VelocitySet::D3Q15 => &D3Q15_W,
+            VelocitySet::D3Q19 => &D3Q19_W,
+            VelocitySet::D3Q27 => &D3Q27_W,
+        }
+    }
+}
+
+impl std::fmt::Display for VelocitySet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for VelocitySet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "D2Q9" => Ok(VelocitySet::D2Q9),
+            "D3Q7" => Ok(VelocitySet::D3Q7),
+            "D3Q15" => Ok(VelocitySet::D3Q15),
+            "D3Q19" => Ok(VelocitySet::D3Q19),
+            "D3Q27" => Ok(VelocitySet::D3Q27),
+            _ => Err(format!("Unsupported model: {}", s)),
+        }
+    }
+}
+
+// Opposite direction index; every set stores opposite pairs next to each other
+pub fn opposite(q: usize) -> usize {
+    if q == 0 {
+        0
+    } else if q % 2 == 1 {
+        q + 1
+    } else {
+        q - 1
+    }
+}
@@ -0,0 +1,414 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// VTK XML ImageData (.vti) output with appended, zlib-compressed raw binary
+// arrays. Much smaller and faster to load than the legacy ASCII files, and
+// the written arrays can be restricted with set_output_arrays.
+
+use super::lbm::LBM;
+use crate::solver::transforms::xyz_from_n;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Uncompressed bytes per zlib block
+const VTI_BLOCK_SIZE: usize = 1 << 16;
+
+// Arrays available in .vti and HDF5 output
+pub const OUTPUT_ARRAYS: [&str; 13] = [
+    "density",
+    "velocity",
+    "q_criterion",
+    "lambda2",
+    "vorticity",
+    "strain_rate",
+    "dissipation",
+    "stress",
+    "wall_shear",
+    "flags",
+    "mean_density",
+    "mean_velocity",
+    "reynolds_stress",
+];
+
+pub enum ArrayData {
+    Float32(Vec<f32>),
+    UInt8(Vec<u8>),
+    UInt16(Vec<u16>),
+}
+
+// One point-data array of the binary output formats, x fastest
+pub struct OutputArray {
+    pub name: &'static str,
+    pub components: usize,
+    pub data: ArrayData,
+}
+
+impl OutputArray {
+    pub fn vtk_type(&self) -> &'static str {
+        match self.data {
+            ArrayData::Float32(_) => "Float32",
+            ArrayData::UInt8(_) => "UInt8",
+            ArrayData::UInt16(_) => "UInt16",
+        }
+    }
+
+    pub fn to_le_bytes(&self) -> Vec<u8> {
+        match &self.data {
+            ArrayData::Float32(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ArrayData::UInt8(values) => values.clone(),
+            ArrayData::UInt16(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+        }
+    }
+}
+
+// Compressed block layout of vtkZLibDataCompressor with a UInt64 header:
+// [block count, block size, last block size, compressed sizes...] + blocks
+fn compress(data: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut blocks = Vec::new();
+    for chunk in data.chunks(VTI_BLOCK_SIZE) {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(chunk)?;
+        blocks.push(encoder.finish()?);
+    }
+    let last = match data.len() % VTI_BLOCK_SIZE {
+        0 if !data.is_empty() => VTI_BLOCK_SIZE,
+        r => r,
+    };
+    let mut out = Vec::new();
+    out.extend_from_slice(&(blocks.len() as u64).to_le_bytes());
+    out.extend_from_slice(&(VTI_BLOCK_SIZE as u64).to_le_bytes());
+    out.extend_from_slice(&(last as u64).to_le_bytes());
+    for block in &blocks {
+        out.extend_from_slice(&(block.len() as u64).to_le_bytes());
+    }
+    for block in &blocks {
+        out.extend_from_slice(block);
+    }
+    Ok(out)
+}
+
+// Write `arrays` on a grid of `size` points starting at cell `origin` with
+// `spacing` cells between points, so subvolumes and decimated views line up
+// with full-domain files
+pub fn write_vti(
+    filename: &str,
+    origin: [usize; 3],
+    spacing: usize,
+    size: [usize; 3],
+    arrays: &[OutputArray],
+) -> std::io::Result<()> {
+    write_vti_piece(filename, origin, spacing, [0, 0, 0], size, arrays)
+}
+
+// write_vti() of a piece whose extent starts at point `start` of the whole
+// image, for the pieces indexed by a .pvti file
+pub fn write_vti_piece(
+    filename: &str,
+    origin: [usize; 3],
+    spacing: usize,
+    start: [usize; 3],
+    size: [usize; 3],
+    arrays: &[OutputArray],
+) -> std::io::Result<()> {
+    let mut compressed = Vec::with_capacity(arrays.len());
+    for array in arrays {
+        compressed.push(compress(&array.to_le_bytes())?);
+    }
+
+    let mut writer = BufWriter::new(File::create(filename)?);
+    let extent = vti_extent(start, size);
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(
+        writer,
+        "<VTKFile type=\"ImageData\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\" compressor=\"vtkZLibDataCompressor\">"
+    )?;
+    writeln!(
+        writer,
+        "  <ImageData WholeExtent=\"{}\" Origin=\"{} {} {}\" Spacing=\"{} {} {}\">",
+        extent, origin[0], origin[1], origin[2], spacing, spacing, spacing
+    )?;
+    writeln!(writer, "    <Piece Extent=\"{}\">", extent)?;
+    writeln!(writer, "      <PointData>")?;
+    let mut offset = 0;
+    for (array, data) in arrays.iter().zip(compressed.iter()) {
+        writeln!(
+            writer,
+            "        <DataArray type=\"{}\" Name=\"{}\" NumberOfComponents=\"{}\" format=\"appended\" offset=\"{}\"/>",
+            array.vtk_type(),
+            array.name, array.components, offset
+        )?;
+        offset += data.len();
+    }
+    writeln!(writer, "      </PointData>")?;
+    writeln!(writer, "    </Piece>")?;
+    writeln!(writer, "  </ImageData>")?;
+    writeln!(writer, "  <AppendedData encoding=\"raw\">")?;
+    write!(writer, "_")?;
+    for data in &compressed {
+        writer.write_all(data)?;
+    }
+    writeln!(writer)?;
+    writeln!(writer, "  </AppendedData>")?;
+    writeln!(writer, "</VTKFile>")?;
+    writer.flush()
+}
+
+fn vti_extent(start: [usize; 3], size: [usize; 3]) -> String {
+    format!(
+        "{} {} {} {} {} {}",
+        start[0],
+        start[0] + size[0] - 1,
+        start[1],
+        start[1] + size[1] - 1,
+        start[2],
+        start[2] + size[2] - 1
+    )
+}
+
+// Parallel ImageData index of one output step: the array layout of `arrays`
+// and the (file, start, size) of every piece, file names relative to the index
+pub fn write_pvti(
+    filename: &str,
+    spacing: usize,
+    whole: [usize; 3],
+    pieces: &[(String, [usize; 3], [usize; 3])],
+    arrays: &[OutputArray],
+) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writeln!(writer, "<?xml version=\"1.0\"?>")?;
+    writeln!(
+        writer,
+        "<VTKFile type=\"PImageData\" version=\"1.0\" byte_order=\"LittleEndian\" header_type=\"UInt64\">"
+    )?;
+    writeln!(
+        writer,
+        "  <PImageData WholeExtent=\"{}\" GhostLevel=\"0\" Origin=\"0 0 0\" Spacing=\"{} {} {}\">",
+        vti_extent([0, 0, 0], whole),
+        spacing,
+        spacing,
+        spacing
+    )?;
+    writeln!(writer, "    <PPointData>")?;
+    for array in arrays {
+        writeln!(
+            writer,
+            "      <PDataArray type=\"{}\" Name=\"{}\" NumberOfComponents=\"{}\"/>",
+            array.vtk_type(),
+            array.name,
+            array.components
+        )?;
+    }
+    writeln!(writer, "    </PPointData>")?;
+    for (source, start, size) in pieces {
+        writeln!(
+            writer,
+            "    <Piece Extent=\"{}\" Source=\"{}\"/>",
+            vti_extent(*start, *size),
+            source
+        )?;
+    }
+    writeln!(writer, "  </PImageData>")?;
+    writeln!(writer, "</VTKFile>")?;
+    writer.flush()
+}
+
+impl LBM {
+    // Restrict .vti and HDF5 output to the named arrays (see OUTPUT_ARRAYS);
+    // empty writes all
+    pub fn set_output_arrays(&mut self, arrays: &[&str]) {
+        self.output_arrays = arrays.iter().map(|a| a.to_string()).collect();
+    }
+
+    fn wants_array(&self, name: &str) -> bool {
+        self.output_arrays.is_empty() || self.output_arrays.iter().any(|a| a == name)
+    }
+
+    pub fn output_arrays(&self) -> std::io::Result<Vec<OutputArray>> {
+        self.arrays_matching(|name| {
+            let enabled = match name {
+                "stress" => self.output_stress,
+                "wall_shear" => self.output_wall_shear,
+                "strain_rate" | "dissipation" => self.output_dissipation,
+                // Only written when named in set_output_arrays()
+                "lambda2" => !self.output_arrays.is_empty(),
+                _ => true,
+            };
+            enabled && self.wants_array(name)
+        })
+    }
+
+    // The arrays of OUTPUT_ARRAYS accepted by `wants`, from the host fields
+    pub fn arrays_matching<F>(&self, wants: F) -> std::io::Result<Vec<OutputArray>>
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut arrays = Vec::new();
+        if wants("density") {
+            arrays.push(OutputArray {
+                name: "density",
+                components: 1,
+                data: ArrayData::Float32(self.density.clone()),
+            });
+        }
+        if wants("velocity") {
+            arrays.push(OutputArray {
+                name: "velocity",
+                components: 3,
+                data: ArrayData::Float32(self.u.clone()),
+            });
+        }
+        let field = self.velocity_field();
+        if wants("q_criterion") {
+            let values = (0..self.N).map(|n| {
+                let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+                field.q_criterion(x, y, z)
+            });
+            arrays.push(OutputArray {
+                name: "q_criterion",
+                components: 1,
+                data: ArrayData::Float32(values.collect()),
+            });
+        }
+        if wants("lambda2") {
+            let values = (0..self.N).map(|n| {
+                let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+                field.lambda2(x, y, z)
+            });
+            arrays.push(OutputArray {
+                name: "lambda2",
+                components: 1,
+                data: ArrayData::Float32(values.collect()),
+            });
+        }
+        if wants("vorticity") {
+            let values = (0..self.N).flat_map(|n| {
+                let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+                field.vorticity_vector(x, y, z)
+            });
+            arrays.push(OutputArray {
+                name: "vorticity",
+                components: 3,
+                data: ArrayData::Float32(values.collect()),
+            });
+        }
+        if wants("strain_rate") || wants("dissipation") {
+            let rates: Vec<f32> = (0..self.N)
+                .map(|n| {
+                    let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+                    field.strain_rate(x, y, z)
+                })
+                .collect();
+            let dissipation = rates.iter().map(|r| self.viscosity * r * r).collect();
+            if wants("strain_rate") {
+                arrays.push(OutputArray {
+                    name: "strain_rate",
+                    components: 1,
+                    data: ArrayData::Float32(rates),
+                });
+            }
+            if wants("dissipation") {
+                arrays.push(OutputArray {
+                    name: "dissipation",
+                    components: 1,
+                    data: ArrayData::Float32(dissipation),
+                });
+            }
+        }
+        if wants("stress") {
+            let stress = self
+                .calculate_stress_tensor()
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+            let values = stress
+                .iter()
+                .flat_map(|s| [s[0], s[3], s[4], s[3], s[1], s[5], s[4], s[5], s[2]]);
+            arrays.push(OutputArray {
+                name: "stress",
+                components: 9,
+                data: ArrayData::Float32(values.collect()),
+            });
+        }
+        if wants("wall_shear") {
+            let shear = self
+                .calculate_wall_shear_stress()
+                .map_err(|e| std::io::Error::other(e.to_string()))?;
+            arrays.push(OutputArray {
+                name: "wall_shear",
+                components: 1,
+                data: ArrayData::Float32(shear),
+            });
+        }
+        if wants("flags") && self.flags.len() == self.N {
+            arrays.push(OutputArray {
+                name: "flags",
+                components: 1,
+                data: ArrayData::UInt8(self.flags.clone()),
+            });
+        }
+        if self.has_averages() {
+            if wants("mean_density") {
+                arrays.push(OutputArray {
+                    name: "mean_density",
+                    components: 1,
+                    data: ArrayData::Float32(self.mean_density()),
+                });
+            }
+            if wants("mean_velocity") {
+                arrays.push(OutputArray {
+                    name: "mean_velocity",
+                    components: 3,
+                    data: ArrayData::Float32(self.mean_velocity()),
+                });
+            }
+            if wants("reynolds_stress") {
+                let values = self
+                    .reynolds_stress()
+                    .into_iter()
+                    .flat_map(|r| [r[0], r[3], r[4], r[3], r[1], r[5], r[4], r[5], r[2]]);
+                arrays.push(OutputArray {
+                    name: "reynolds_stress",
+                    components: 9,
+                    data: ArrayData::Float32(values.collect()),
+                });
+            }
+        }
+        Ok(arrays)
+    }
+
+    pub fn export_to_vti(&self, filename: &str) -> std::io::Result<()> {
+        let arrays = self.output_arrays()?;
+        write_vti(
+            filename,
+            [0, 0, 0],
+            self.output_spacing,
+            [self.Nx, self.Ny, self.Nz],
+            &arrays,
+        )
+    }
+
+    // Cell flags, plus body tags when bodies were added, for overlaying the
+    // geometry on results
+    pub fn export_flags_vti(&self, filename: &str) -> std::io::Result<()> {
+        let mut arrays = vec![OutputArray {
+            name: "flags",
+            components: 1,
+            data: ArrayData::UInt8(self.flags.clone()),
+        }];
+        if self.body_ids.len() == self.N {
+            arrays.push(OutputArray {
+                name: "body_id",
+                components: 1,
+                data: ArrayData::UInt16(self.body_ids.clone()),
+            });
+        }
+        write_vti(
+            filename,
+            [0, 0, 0],
+            self.output_spacing,
+            [self.Nx, self.Ny, self.Nz],
+            &arrays,
+        )
+    }
+}
@@ -0,0 +1,54 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+use super::lbm::LBM;
+use crate::solver::flags::FLAG_SOLID;
+use crate::solver::transforms::{n_from_xyz, xyz_from_n};
+
+use ocl::{flags::MEM_READ_ONLY, Buffer};
+use std::error::Error;
+
+impl LBM {
+    // Collect the non-solid cells that have at least one solid neighbour along
+    // the lattice directions (periodic wrap, like the kernel). The sorted index
+    // list is stored in `wall_layer` and, once OpenCL is initialized, uploaded to
+    // `wall_layer_buffer` for the wall shear and wall momentum exchange kernels.
+    // initialize() and fill_flags() rebuild it; call again after other flag
+    // edits. Returns the number of wall-layer cells.
+    pub fn compute_wall_layer(&mut self) -> Result<usize, Box<dyn Error>> {
+        let c = self.model.vectors();
+        let dims = [self.Nx as i64, self.Ny as i64, self.Nz as i64];
+
+        self.wall_layer = (0..self.N)
+            .filter(|&n| self.flags[n] != FLAG_SOLID)
+            .filter(|&n| {
+                let (x, y, z) = xyz_from_n(&n, &self.Nx, &self.Ny);
+                let p = [x as i64, y as i64, z as i64];
+                c.iter().skip(1).any(|ci| {
+                    let q: [usize; 3] =
+                        [0, 1, 2].map(|d| (p[d] + ci[d] as i64).rem_euclid(dims[d]) as usize);
+                    self.flags[n_from_xyz(&q[0], &q[1], &q[2], &self.Nx, &self.Ny)] == FLAG_SOLID
+                })
+            })
+            .map(|n| n as u32)
+            .collect();
+
+        if let Some(queue) = &self.queue {
+            // Zero-length buffers are invalid in OpenCL
+            let buffer = Buffer::<u32>::builder()
+                .queue(queue.clone())
+                .flags(MEM_READ_ONLY)
+                .len(self.wall_layer.len().max(1))
+                .build()
+                .map_err(|e| format!("Failed to build 'wall_layer' buffer: {}", e))?;
+            if !self.wall_layer.is_empty() {
+                buffer
+                    .write(&self.wall_layer)
+                    .enq()
+                    .map_err(|e| format!("Failed to write 'wall_layer' buffer: {}", e))?;
+            }
+            self.wall_layer_buffer = Some(buffer);
+        }
+        Ok(self.wall_layer.len())
+    }
+}
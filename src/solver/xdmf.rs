@@ -0,0 +1,185 @@
+#![allow(non_snake_case)] // Allow non-snake_case naming convention
+#![allow(clippy::upper_case_acronyms)] // Allow uppercase acronyms
+
+// HDF5 output backend: every output step goes into one file as a group of
+// chunked, deflate-compressed datasets (/step_<t>/<array>, shape [z, y, x, c]),
+// indexed by an XDMF file that ParaView opens as a time series. The HDF5
+// writer needs the C library and is only built with the "hdf5" feature; the
+// XDMF index is plain XML.
+
+use super::lbm::LBM;
+#[cfg(feature = "hdf5")]
+use crate::solver::vti::ArrayData;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+// Target uncompressed chunk size in values
+#[cfg(feature = "hdf5")]
+const HDF5_CHUNK_VALUES: usize = 1 << 18;
+
+// A step stored in the HDF5 file: domain size, cell spacing and (name,
+// components, XDMF number type, precision) of each dataset
+#[derive(Debug, Clone)]
+pub struct Hdf5Step {
+    pub step: usize,
+    pub dims: [usize; 3],
+    pub spacing: usize,
+    pub arrays: Vec<(String, usize, &'static str, usize)>,
+}
+
+impl LBM {
+    // Write all outputs to output/simulation.h5 with an XDMF index
+    pub fn set_output_hdf5(&mut self, state: bool) {
+        self.output_hdf5 = state;
+    }
+
+    // Store the selected arrays as group /step_<step> of `filename`. The file
+    // is truncated on the first call of a run and appended to afterwards.
+    #[cfg(feature = "hdf5")]
+    pub fn export_to_hdf5(
+        &self,
+        filename: &str,
+        step: usize,
+        truncate: bool,
+    ) -> Result<Hdf5Step, Box<dyn Error>> {
+        let file = if truncate {
+            hdf5::File::create(filename)?
+        } else {
+            hdf5::File::append(filename)?
+        };
+        let name = format!("step_{}", step);
+        if file.link_exists(&name) {
+            file.unlink(&name)?;
+        }
+        let group = file.create_group(&name)?;
+
+        let mut arrays = Vec::new();
+        for array in self.output_arrays()? {
+            let c = array.components;
+            let mut shape = vec![self.Nz, self.Ny, self.Nx];
+            if c > 1 {
+                shape.push(c);
+            }
+            // Slabs of whole xy-planes, about HDF5_CHUNK_VALUES values each
+            let mut chunk = shape.clone();
+            chunk[0] = (HDF5_CHUNK_VALUES / (self.Nx * self.Ny * c)).clamp(1, self.Nz);
+            match &array.data {
+                ArrayData::Float32(values) => {
+                    let dataset = group
+                        .new_dataset::<f32>()
+                        .shape(shape)
+                        .chunk(chunk)
+                        .deflate(4)
+                        .create(array.name)?;
+                    dataset.write_raw(values.as_slice())?;
+                    arrays.push((array.name.to_string(), c, "Float", 4));
+                }
+                ArrayData::UInt8(values) => {
+                    let dataset = group
+                        .new_dataset::<u8>()
+                        .shape(shape)
+                        .chunk(chunk)
+                        .deflate(4)
+                        .create(array.name)?;
+                    dataset.write_raw(values.as_slice())?;
+                    arrays.push((array.name.to_string(), c, "UChar", 1));
+                }
+                ArrayData::UInt16(values) => {
+                    let dataset = group
+                        .new_dataset::<u16>()
+                        .shape(shape)
+                        .chunk(chunk)
+                        .deflate(4)
+                        .create(array.name)?;
+                    dataset.write_raw(values.as_slice())?;
+                    arrays.push((array.name.to_string(), c, "UInt", 2));
+                }
+            }
+        }
+        Ok(Hdf5Step {
+            step,
+            dims: [self.Nx, self.Ny, self.Nz],
+            spacing: self.output_spacing,
+            arrays,
+        })
+    }
+
+    #[cfg(not(feature = "hdf5"))]
+    pub fn export_to_hdf5(
+        &self,
+        _filename: &str,
+        _step: usize,
+        _truncate: bool,
+    ) -> Result<Hdf5Step, Box<dyn Error>> {
+        Err("HDF5 output requires building with the 'hdf5' feature".into())
+    }
+
+    // XDMF temporal collection over the steps recorded in `hdf5_steps`,
+    // referencing the datasets of `h5_filename` relative to the index
+    pub fn write_xdmf(&self, filename: &str, h5_filename: &str) -> std::io::Result<()> {
+        let h5_name = Path::new(h5_filename)
+            .file_name()
+            .map_or(h5_filename.to_string(), |f| {
+                f.to_string_lossy().into_owned()
+            });
+        let mut writer = BufWriter::new(File::create(filename)?);
+        writeln!(writer, "<?xml version=\"1.0\" ?>")?;
+        writeln!(writer, "<Xdmf Version=\"3.0\">")?;
+        writeln!(writer, "  <Domain>")?;
+        writeln!(
+            writer,
+            "    <Grid Name=\"simulation\" GridType=\"Collection\" CollectionType=\"Temporal\">"
+        )?;
+        for entry in &self.hdf5_steps {
+            let [nx, ny, nz] = entry.dims;
+            writeln!(
+                writer,
+                "      <Grid Name=\"step_{}\" GridType=\"Uniform\">",
+                entry.step
+            )?;
+            writeln!(writer, "        <Time Value=\"{}\"/>", entry.step)?;
+            writeln!(
+                writer,
+                "        <Topology TopologyType=\"3DCoRectMesh\" Dimensions=\"{} {} {}\"/>",
+                nz, ny, nx
+            )?;
+            writeln!(writer, "        <Geometry GeometryType=\"ORIGIN_DXDYDZ\">")?;
+            writeln!(
+                writer,
+                "          <DataItem Dimensions=\"3\" Format=\"XML\">0 0 0</DataItem>"
+            )?;
+            writeln!(
+                writer,
+                "          <DataItem Dimensions=\"3\" Format=\"XML\">{} {} {}</DataItem>",
+                entry.spacing, entry.spacing, entry.spacing
+            )?;
+            writeln!(writer, "        </Geometry>")?;
+            for (name, components, number_type, precision) in &entry.arrays {
+                let (kind, dims) = match components {
+                    1 => ("Scalar", format!("{} {} {}", nz, ny, nx)),
+                    3 => ("Vector", format!("{} {} {} 3", nz, ny, nx)),
+                    c => ("Tensor", format!("{} {} {} {}", nz, ny, nx, c)),
+                };
+                writeln!(
+                    writer,
+                    "        <Attribute Name=\"{}\" AttributeType=\"{}\" Center=\"Node\">",
+                    name, kind
+                )?;
+                writeln!(
+                    writer,
+                    "          <DataItem Dimensions=\"{}\" NumberType=\"{}\" Precision=\"{}\" Format=\"HDF\">{}:/step_{}/{}</DataItem>",
+                    dims, number_type, precision, h5_name, entry.step, name
+                )?;
+                writeln!(writer, "        </Attribute>")?;
+            }
+            writeln!(writer, "      </Grid>")?;
+        }
+        writeln!(writer, "    </Grid>")?;
+        writeln!(writer, "  </Domain>")?;
+        writeln!(writer, "</Xdmf>")?;
+        writer.flush()
+    }
+}
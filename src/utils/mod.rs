@@ -1,4 +1,6 @@
 // src/utils/mod.rs
 
+pub mod npy;
+pub mod random;
 pub mod terminal_utils;
 pub mod velocity;
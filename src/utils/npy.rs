@@ -0,0 +1,175 @@
+/// Minimal reader/writer for NumPy `.npy` files (format version 1.0).
+///
+/// Only C-ordered, little-endian arrays of the element types used by the
+/// solver are supported, which is all that is needed to exchange fields and
+/// geometry with Python post-processing scripts.
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+// Element types that can be stored in a .npy file.
+pub trait NpyElement: Copy + Default {
+    const DESCR: &'static str;
+    const SIZE: usize;
+    fn write_le(&self, out: &mut Vec<u8>);
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+impl NpyElement for u8 {
+    const DESCR: &'static str = "|u1";
+    const SIZE: usize = 1;
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+    fn read_le(bytes: &[u8]) -> Self {
+        bytes[0]
+    }
+}
+
+impl NpyElement for u16 {
+    const DESCR: &'static str = "<u2";
+    const SIZE: usize = 2;
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+    fn read_le(bytes: &[u8]) -> Self {
+        u16::from_le_bytes([bytes[0], bytes[1]])
+    }
+}
+
+impl NpyElement for f32 {
+    const DESCR: &'static str = "<f4";
+    const SIZE: usize = 4;
+    fn write_le(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+    fn read_le(bytes: &[u8]) -> Self {
+        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+}
+
+// Returns true if the file starts with the .npy magic string.
+pub fn is_npy_file(path: &str) -> bool {
+    let mut magic = [0u8; 6];
+    match File::open(path) {
+        Ok(mut file) => file.read_exact(&mut magic).is_ok() && magic == NPY_MAGIC,
+        Err(_) => false,
+    }
+}
+
+// Write `data` as a C-ordered array with the given `shape`.
+pub fn write_npy<T: NpyElement>(path: &str, shape: &[usize], data: &[T]) -> Result<(), Box<dyn Error>> {
+    let expected: usize = shape.iter().product();
+    if expected != data.len() {
+        return Err(format!(
+            "Shape {:?} does not match data length {}.",
+            shape,
+            data.len()
+        )
+        .into());
+    }
+
+    let shape_str = match shape.len() {
+        1 => format!("({},)", shape[0]),
+        _ => format!(
+            "({})",
+            shape.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        T::DESCR,
+        shape_str
+    );
+    // Pad so that magic + version + length + header is a multiple of 64 bytes
+    let preamble = NPY_MAGIC.len() + 2 + 2;
+    let padding = (64 - (preamble + header.len() + 1) % 64) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    let file = File::create(path)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1u8, 0u8])?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+
+    let mut bytes = Vec::with_capacity(data.len() * T::SIZE);
+    for value in data {
+        value.write_le(&mut bytes);
+    }
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Read a C-ordered array, returning its shape and flattened data.
+pub fn read_npy<T: NpyElement>(path: &str) -> Result<(Vec<usize>, Vec<T>), Box<dyn Error>> {
+    let file = File::open(path)?;
+    let mut reader = BufReader::new(file);
+
+    let mut magic = [0u8; 6];
+    reader.read_exact(&mut magic)?;
+    if magic != NPY_MAGIC {
+        return Err(format!("{} is not a .npy file.", path).into());
+    }
+    let mut version = [0u8; 2];
+    reader.read_exact(&mut version)?;
+    let header_len = match version[0] {
+        1 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0u8; 4];
+            reader.read_exact(&mut len)?;
+            u32::from_le_bytes(len) as usize
+        }
+        v => return Err(format!("Unsupported .npy version: {}.", v).into()),
+    };
+    let mut header = vec![0u8; header_len];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8(header)?;
+
+    let descr = header_value(&header, "descr").ok_or("Missing 'descr' in .npy header.")?;
+    let descr = descr.trim_matches(|c| c == '\'' || c == '"');
+    if descr.is_empty() {
+        return Err("Empty 'descr' in .npy header.".into());
+    }
+    // Single-byte types may be written with either '|' or '<' byte order
+    if descr != T::DESCR && !(T::SIZE == 1 && descr.get(1..) == T::DESCR.get(1..)) {
+        return Err(format!("Unexpected dtype {} (expected {}).", descr, T::DESCR).into());
+    }
+    if header_value(&header, "fortran_order").map(|v| v.starts_with("True")) == Some(true) {
+        return Err("Fortran-ordered .npy arrays are not supported.".into());
+    }
+    let shape_str = header_value(&header, "shape").ok_or("Missing 'shape' in .npy header.")?;
+    let shape = shape_str
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let count: usize = shape.iter().product();
+    let mut bytes = vec![0u8; count * T::SIZE];
+    reader.read_exact(&mut bytes)?;
+    let data = bytes.chunks_exact(T::SIZE).map(T::read_le).collect();
+    Ok((shape, data))
+}
+
+// Extract the raw value string of a key in the header dictionary.
+fn header_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header.find(&format!("'{}'", key))? + key.len() + 2;
+    let rest = header[start..].trim_start().strip_prefix(':')?.trim_start();
+    let end = if rest.starts_with('(') {
+        rest.find(')')? + 1
+    } else {
+        rest.find(',')?
+    };
+    Some(&rest[..end])
+}
@@ -3,32 +3,129 @@
 /// This module provides functions to print styled messages to the terminal,
 /// including warnings, logs, success messages, errors, and other formatted outputs.
 use colored::*;
+use indicatif::ProgressBar;
+use tracing::subscriber::NoSubscriber;
+use tracing::Level;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// Amount of console output. Silent keeps only errors, Verbose adds details
+// such as the per-buffer memory breakdown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Silent,
+    Normal,
+    Verbose,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+// Set the process-wide verbosity used by every function of this module
+pub fn set_verbosity(level: Verbosity) {
+    VERBOSITY.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Silent,
+        1 => Verbosity::Normal,
+        _ => Verbosity::Verbose,
+    }
+}
+
+// True when messages of the given level are printed
+pub fn enabled(level: Verbosity) -> bool {
+    verbosity() >= level
+}
+
+// True when the application installed a `tracing` subscriber, globally or
+// for the current thread
+fn subscribed() -> bool {
+    tracing::dispatcher::get_default(|dispatch| !dispatch.is::<NoSubscriber>())
+}
+
+// With a subscriber installed, messages become tracing events with target
+// "cappusim" instead of console output, so they can be routed and filtered by
+// the application and carry the enclosing init/run/output spans. Returns true
+// if the message was emitted as an event.
+fn routed(level: Level, message: &str) -> bool {
+    if !subscribed() {
+        return false;
+    }
+    match level {
+        Level::ERROR => tracing::error!(target: "cappusim", "{}", message),
+        Level::WARN => tracing::warn!(target: "cappusim", "{}", message),
+        Level::INFO => tracing::info!(target: "cappusim", "{}", message),
+        Level::DEBUG => tracing::debug!(target: "cappusim", "{}", message),
+        _ => tracing::trace!(target: "cappusim", "{}", message),
+    }
+    true
+}
+
+// Progress bar that is hidden when silent or when stdout is not a terminal,
+// so redirected logs do not fill up with redraws
+pub fn progress_bar(len: u64) -> ProgressBar {
+    if enabled(Verbosity::Normal) && std::io::stdout().is_terminal() {
+        ProgressBar::new(len)
+    } else {
+        ProgressBar::hidden()
+    }
+}
+
+// Print a plain message without prefix.
+pub fn print_info(message: &str) {
+    if !routed(Level::INFO, message) && enabled(Verbosity::Normal) {
+        println!("{}", message);
+    }
+}
+
+// Print a plain message only in verbose mode.
+pub fn print_detail(message: &str) {
+    if !routed(Level::DEBUG, message) && enabled(Verbosity::Verbose) {
+        println!("{}", message);
+    }
+}
 
 // Print a WARNING message with a prefix in bold yellow.
 #[allow(dead_code)]
 pub fn print_warning(message: &str) {
+    if routed(Level::WARN, message) || !enabled(Verbosity::Normal) {
+        return;
+    }
     println!("{}: {}", "[WARNING]".yellow().bold(), message);
 }
 
 // Print a simple LOG message with a prefix in bold white.
 #[allow(dead_code)]
 pub fn print_log(message: &str) {
+    if routed(Level::INFO, message) || !enabled(Verbosity::Normal) {
+        return;
+    }
     println!("{}: {}", "[LOG]".white().bold(), message);
 }
 
 // Print a SUCCESS message with a prefix in bold green.
 pub fn print_success(message: &str) {
+    if routed(Level::INFO, message) || !enabled(Verbosity::Normal) {
+        return;
+    }
     println!("{}: {}", "[SUCCESS]".green().bold(), message);
 }
 
-// Print an ERROR message with a prefix in bold red.
+// Print an ERROR message with a prefix in bold red. Errors are never silenced.
 #[allow(dead_code)]
 pub fn print_error(message: &str) {
+    if routed(Level::ERROR, message) {
+        return;
+    }
     println!("{}: {}", "[ERROR]".red().bold(), message);
 }
 
 // Print welcome message
 pub fn print_welcome_message() {
+    if subscribed() || !enabled(Verbosity::Normal) {
+        return;
+    }
     println!("{}", "-".repeat(72));
     println!(
         "{}",
@@ -54,6 +151,13 @@ pub fn print_welcome_message() {
 }
 
 pub fn print_metrics(time_steps: u64, elapsed_time: f64, mlups: f64) {
+    let summary = format!(
+        "Simulation finished: {} time steps in {:.3} s, {:.2} MLUps",
+        time_steps, elapsed_time, mlups
+    );
+    if routed(Level::INFO, &summary) || !enabled(Verbosity::Normal) {
+        return;
+    }
     let seconds = elapsed_time;
     let days = seconds as i64 / 86400;
     let hours = (seconds as i64 % 86400) / 3600;
@@ -71,5 +175,8 @@ pub fn print_metrics(time_steps: u64, elapsed_time: f64, mlups: f64) {
 }
 
 pub fn print_name() {
+    if subscribed() || !enabled(Verbosity::Normal) {
+        return;
+    }
     println!("\n{}", "CappuSim".bold().blue());
 }